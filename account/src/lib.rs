@@ -22,6 +22,7 @@ Options
 
     --testnet  Use the Ethereum "Rinkeby" testnet (default: false)
 "#,
+    ..Help::DEFAULT
 };
 
 /// Work with Ethereum accounts.
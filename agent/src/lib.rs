@@ -0,0 +1,162 @@
+use std::ffi::OsString;
+
+use anyhow::anyhow;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "agent",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad agent [start] [--foreground]
+    rad agent stop
+    rad agent status
+
+    Starts an agent that holds your unlocked signing key in memory, so that
+    you aren't asked for your passphrase by every command. This is an
+    alternative to ssh-agent, for systems where it isn't available.
+
+    The agent listens on a socket in your profile's keys directory, or on
+    the path set with the `RAD_AGENT_SOCK` environment variable.
+
+Options
+
+    --foreground    Run the agent in the foreground, eg. under a service manager
+    --help          Print help
+"#,
+    ..Help::DEFAULT
+};
+
+#[derive(Debug)]
+pub enum Operation {
+    Start { foreground: bool },
+    Stop,
+    Status,
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<String> = None;
+        let mut foreground = false;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("foreground") => {
+                    foreground = true;
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if op.is_none() => {
+                    op = Some(val.to_string_lossy().to_string());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let op = match op.as_deref() {
+            None | Some("start") => Operation::Start { foreground },
+            Some("stop") => Operation::Stop,
+            Some("status") => Operation::Status,
+            Some(unknown) => anyhow::bail!("unknown operation '{}'", unknown),
+        };
+        if foreground && !matches!(op, Operation::Start { .. }) {
+            return Err(Error::Usage.into());
+        }
+
+        Ok((Options { op }, vec![]))
+    }
+}
+
+#[cfg(unix)]
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    use std::io::Write as _;
+    use std::process::{Command, Stdio};
+    use std::{env, thread, time};
+
+    use radicle_common::keys::{self, agent};
+
+    let profile = ctx.profile()?;
+    let path = agent::socket_path(&profile);
+
+    match options.op {
+        Operation::Start { foreground: true } => {
+            let key = term::secret_key(&profile)?;
+
+            term::info!(
+                "Agent listening on {}",
+                term::format::highlight(path.display())
+            );
+            agent::serve(&path, key)?;
+        }
+        Operation::Start { foreground: false } => {
+            if agent::AgentSigner::connect(&path).is_ok() {
+                anyhow::bail!("an agent is already running on {}", path.display());
+            }
+            let passphrase = term::secret_input();
+            let spinner = term::spinner("Unsealing key...");
+            // Nb. Check the passphrase before handing it to the agent.
+            keys::load_secret_key(&profile, passphrase.clone())?;
+
+            let mut child = Command::new(env::current_exe()?)
+                .args(["agent", "start", "--foreground"])
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()?;
+
+            if let Some(mut stdin) = child.stdin.take() {
+                writeln!(stdin, "{}", passphrase.unsecure())?;
+            }
+            for _ in 0..50 {
+                if agent::AgentSigner::connect(&path).is_ok() {
+                    spinner.finish();
+                    term::success!(
+                        "Agent started on {}",
+                        term::format::highlight(path.display())
+                    );
+                    return Ok(());
+                }
+                if child.try_wait()?.is_some() {
+                    break;
+                }
+                thread::sleep(time::Duration::from_millis(100));
+            }
+            spinner.failed();
+            anyhow::bail!("agent failed to start, try running it with `--foreground`");
+        }
+        Operation::Stop => {
+            agent::stop(&path).map_err(|_| anyhow!("no agent is running on {}", path.display()))?;
+            term::success!("Agent stopped");
+        }
+        Operation::Status => {
+            if agent::AgentSigner::connect(&path).is_ok() {
+                term::success!(
+                    "Agent is running on {}",
+                    term::format::highlight(path.display())
+                );
+            } else {
+                term::info!("No agent is running on {}", path.display());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn run(_options: Options, _ctx: impl term::Context) -> anyhow::Result<()> {
+    anyhow::bail!("`rad agent` is not supported on this platform")
+}
@@ -4,10 +4,11 @@ use std::str::FromStr;
 
 use anyhow::Context as _;
 use radicle_common::signer::ToSigner;
-use zeroize::Zeroizing;
 
+use librad::crypto::keystore::crypto::Pwhash;
 use librad::crypto::keystore::pinentry::SecUtf8;
-use librad::profile::ProfileId;
+use librad::profile::{LnkHome, Profile, ProfileId};
+use librad::{PeerId, PublicKey};
 
 use radicle_common::args::{Args, Error, Help};
 use radicle_common::{config, git, keys, person, profile};
@@ -21,18 +22,42 @@ pub const HELP: Help = Help {
 Usage
 
     rad auth [--init | --active] [<options>...] [<profile>]
+    rad auth --add-key <peer-id>
+    rad auth --revoke-key <peer-id>
+    rad auth --pin [<profile>]
+    rad auth --unpin
 
     If `--init` is used, name and passphrase may be given via the `--name`
     and `--passphrase` option. Using these disables the respective input prompt.
 
+    Your identity can have more than one device key, so that it isn't lost with
+    a single device. To add the key of another device, run `rad self --peer`
+    on that device and pass its Peer ID to `--add-key`.
+
+    Note that once an identity has several keys, updates to it must be signed
+    by a majority of them. Since updates can only be signed by the device
+    making them, such an identity can't be updated anymore: keys can't be
+    added or revoked, and `rad self --edit` fails. `--revoke-key` is therefore
+    only usable once updates can be co-signed by other devices.
+
+    With `--pin`, the selected profile is pinned to the working copy of the
+    current directory instead of being activated. Commands run in that working
+    copy then use the pinned profile, eg. to keep work and personal identities
+    apart. The profile is stored as `rad.profile` in the git configuration.
+
 Options
 
     --init                  Initialize a new identity
     --active                Authenticate with the currently active profile
     --name <name>           Use given name (default: none)
     --passphrase <phrase>   Use given passphrase (default: none)
+    --add-key <peer-id>     Add a device key to your identity
+    --revoke-key <peer-id>  Revoke a device key of your identity
+    --pin                   Pin the selected profile to the current working copy
+    --unpin                 Unpin the current working copy from its profile
     --help                  Print help
 "#,
+    ..Help::DEFAULT
 };
 
 #[derive(Debug)]
@@ -42,6 +67,10 @@ pub struct Options {
     pub name: Option<String>,
     pub passphrase: Option<String>,
     pub profile: Option<ProfileId>,
+    pub add_key: Option<PublicKey>,
+    pub revoke_key: Option<PublicKey>,
+    pub pin: bool,
+    pub unpin: bool,
 }
 
 impl Args for Options {
@@ -53,6 +82,10 @@ impl Args for Options {
         let mut name = None;
         let mut passphrase = None;
         let mut profile = None;
+        let mut add_key = None;
+        let mut revoke_key = None;
+        let mut pin = false;
+        let mut unpin = false;
         let mut parser = lexopt::Parser::from_args(args);
 
         while let Some(arg) = parser.next()? {
@@ -88,6 +121,24 @@ impl Args for Options {
 
                     passphrase = Some(val);
                 }
+                Long("add-key") if !init && add_key.is_none() && revoke_key.is_none() => {
+                    let val = parser.value()?;
+                    let key = keys::parse_key(&val.to_string_lossy())?;
+
+                    add_key = Some(key);
+                }
+                Long("revoke-key") if !init && add_key.is_none() && revoke_key.is_none() => {
+                    let val = parser.value()?;
+                    let key = keys::parse_key(&val.to_string_lossy())?;
+
+                    revoke_key = Some(key);
+                }
+                Long("pin") if !init && !unpin => {
+                    pin = true;
+                }
+                Long("unpin") if !init && !pin => {
+                    unpin = true;
+                }
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
@@ -110,6 +161,10 @@ impl Args for Options {
                 name,
                 passphrase,
                 profile,
+                add_key,
+                revoke_key,
+                pin,
+                unpin,
             },
             vec![],
         ))
@@ -117,11 +172,23 @@ impl Args for Options {
 }
 
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    if options.add_key.is_some() || options.revoke_key.is_some() {
+        return update_keys(options, ctx);
+    }
+    if options.unpin {
+        let repo = git::repository()?;
+
+        if profile::unpin(&repo)? {
+            term::success!("Working copy unpinned, the active profile will be used");
+        } else {
+            term::info!("Working copy is not pinned to a profile");
+        }
+        return Ok(());
+    }
     let profiles = match profile::list() {
         Ok(profiles) => profiles,
         _ => vec![],
     };
-
     if options.init || profiles.is_empty() {
         if options.profile.is_some() {
             anyhow::bail!("you may not specify a profile id when initializing a new identity");
@@ -145,11 +212,16 @@ pub fn init(options: Options) -> anyhow::Result<()> {
         term::blank();
     }
 
-    let name = sanitize_name(
-        options
-            .name
-            .unwrap_or_else(|| term::text_input("Name", None).unwrap()),
-    )?;
+    let name = match options.name {
+        Some(name) => sanitize_name(name, false)?,
+        None => {
+            let name = term::text_input("Name", None).map_err(|err| Error::WithHint {
+                err,
+                hint: "Use `--name` to specify your name.",
+            })?;
+            sanitize_name(name, true)?
+        }
+    };
     let passphrase = options
         .passphrase
         .map_or_else(term::secret_input_with_confirmation, |passphrase| {
@@ -157,15 +229,81 @@ pub fn init(options: Options) -> anyhow::Result<()> {
         });
     let pwhash = keys::pwhash(passphrase.clone());
     let home = profile::home();
+    let mut rollback = Rollback::default();
 
+    match create(name, passphrase, pwhash, home, sock, &mut rollback) {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            rollback.run();
+            Err(err)
+        }
+    }
+}
+
+/// The steps of `--init` to undo if it fails halfway, so that no broken profile is
+/// left behind. Nb. The new profile is only made active once all steps succeeded, so
+/// the active profile never has to be restored.
+#[derive(Default)]
+struct Rollback {
+    /// The profile that was created.
+    profile: Option<Profile>,
+    /// The ssh-agent the signing key was added to.
+    agent: Option<(Pwhash<SecUtf8>, keys::SshAuthSock)>,
+}
+
+impl Rollback {
+    fn run(self) {
+        let profile = match self.profile {
+            Some(profile) => profile,
+            None => return,
+        };
+        term::blank();
+        term::warning("Initialization failed, rolling back...");
+
+        if let Some((pwhash, sock)) = self.agent {
+            match keys::remove(&profile, pwhash, sock) {
+                Ok(()) => term::info!("Removed signing key from ssh-agent"),
+                Err(err) => term::warning(&format!(
+                    "Could not remove signing key from ssh-agent: {}",
+                    err
+                )),
+            }
+        }
+        match profile::delete(&profile) {
+            Ok(()) => term::info!(
+                "Removed profile {}",
+                term::format::highlight(&profile.id().to_string())
+            ),
+            Err(err) => term::warning(&format!(
+                "Could not remove profile {}: {}",
+                profile.id(),
+                err
+            )),
+        }
+        term::blank();
+    }
+}
+
+fn create(
+    name: String,
+    passphrase: SecUtf8,
+    pwhash: Pwhash<SecUtf8>,
+    home: LnkHome,
+    sock: anyhow::Result<keys::SshAuthSock>,
+    rollback: &mut Rollback,
+) -> anyhow::Result<()> {
     let mut spinner = term::spinner("Creating your 🌱 Ed25519 keypair...");
-    let (profile, peer_id) = profile::create(home, pwhash.clone())?;
+    let (profile, peer_id) = profile::new(&home, pwhash.clone())?;
+
+    rollback.profile = Some(profile.clone());
 
     let signer = if let Ok(sock) = sock {
         spinner.finish();
         spinner = term::spinner("Adding to ssh-agent...");
 
-        keys::add(&profile, pwhash, sock.clone())?;
+        keys::add(&profile, pwhash.clone(), sock.clone())?;
+        rollback.agent = Some((pwhash, sock.clone()));
+
         let signer = sock.to_signer(&profile)?;
 
         spinner.finish();
@@ -185,6 +323,7 @@ pub fn init(options: Options) -> anyhow::Result<()> {
     let person = person::create(&profile, &name, signer, &storage)
         .context("could not create identity document")?;
     person::set_local(&storage, &person)?;
+    Profile::set(&home, profile.id().clone())?;
 
     term::success!(
         "Profile {} created.",
@@ -247,20 +386,26 @@ pub fn authenticate(
         &profile
     };
 
-    let read_only = profile::read_only(selection)?;
-    let config = read_only.config()?;
-
-    if let Some(user) = config.user()? {
-        let username = config.user_name()?;
+    // Nb. Only the selected profile's identity is loaded.
+    let info = profile::info(selection)?;
 
+    if let Some(user) = info.urn {
         term::headline(&format!(
             "🌱 Authenticating as {} {}",
             term::format::highlight(user),
-            term::format::dim(format!("({})", username))
+            term::format::dim(format!("({})", info.name.unwrap_or_default()))
         ));
     }
 
-    if selection.id() != profile.id() {
+    if options.pin {
+        let repo = git::repository()?;
+        profile::pin(&repo, selection.id())?;
+
+        term::success!(
+            "Profile {} pinned to this working copy",
+            term::format::highlight(selection.id())
+        );
+    } else if selection.id() != profile.id() {
         let id = selection.id();
         profile::set(id)?;
 
@@ -274,14 +419,7 @@ pub fn authenticate(
 
             // TODO: We should show the spinner on the passphrase prompt,
             // otherwise it seems like the passphrase is valid even if it isn't.
-            let secret_input: SecUtf8 = if atty::is(atty::Stream::Stdin) {
-                term::secret_input()
-            } else {
-                let mut input: Zeroizing<String> = Zeroizing::new(Default::default());
-                std::io::stdin().read_line(&mut input)?;
-                SecUtf8::from(input.trim_end())
-            };
-            let pass = keys::pwhash(secret_input);
+            let pass = keys::pwhash(term::secret_input());
             let spinner = term::spinner("Unlocking...");
 
             keys::add(profile, pass, sock).context("invalid passphrase supplied")?;
@@ -296,11 +434,99 @@ pub fn authenticate(
     Ok(())
 }
 
-fn sanitize_name(name: String) -> anyhow::Result<String> {
-    if name.contains(char::is_whitespace) {
-        anyhow::bail!("Name cannot contain whitespaces");
+/// Add or revoke a device key of the local identity.
+pub fn update_keys(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let signer = term::signer(&profile)?;
+    let storage = keys::storage(&profile, signer)?;
+
+    if let Some(key) = options.add_key {
+        let peer = PeerId::from(key);
+
+        if !term::confirm(format!(
+            "Add key {}? Your identity can no longer be updated afterwards, since updates will need the signatures of a majority of its devices",
+            peer
+        )) {
+            return Ok(());
+        }
+        person::add_key(key, &storage)?;
+        term::success!(
+            "Key {} added to your identity",
+            term::format::highlight(peer)
+        );
+    }
+    if let Some(key) = options.revoke_key {
+        let peer = PeerId::from(key);
+
+        if !term::confirm(format!(
+            "Revoke key {}? It will no longer be able to sign on behalf of your identity",
+            peer
+        )) {
+            return Ok(());
+        }
+        person::revoke_key(&key, &storage)?;
+        term::success!(
+            "Key {} revoked from your identity",
+            term::format::highlight(peer)
+        );
+    }
+
+    let keys = person::keys(&storage)?;
+    let count = keys.len();
+    let this = storage.peer_id();
+
+    term::blank();
+    term::info!("Your identity has {} key(s):", count);
+
+    for key in keys {
+        let peer = PeerId::from(key);
+
+        if peer == *this {
+            term::info!(
+                "{} {}",
+                term::format::tertiary(peer),
+                term::format::badge_secondary("this device")
+            );
+        } else {
+            term::info!("{}", term::format::tertiary(peer));
+        }
+    }
+    if count == 2 {
+        term::blank();
+        term::tip!(
+            "With two keys, both are needed to update your identity. \
+            Consider adding a third key, so that you can recover from losing one."
+        );
+    }
+
+    Ok(())
+}
+
+/// Validate a name for the identity document. If it's invalid and a valid name close to
+/// it exists, the user is offered to use it when `interactive`, otherwise it is suggested.
+fn sanitize_name(name: String, interactive: bool) -> anyhow::Result<String> {
+    let err = match person::validate_name(&name) {
+        Ok(()) => return Ok(name),
+        Err(err) => err,
+    };
+    match person::suggest_name(&name) {
+        Some(suggestion) if interactive => {
+            term::warning(&format!("Invalid name '{}': {}", name, err));
+
+            if term::confirm(format!(
+                "Use {} instead?",
+                term::format::highlight(&suggestion)
+            )) {
+                Ok(suggestion)
+            } else {
+                anyhow::bail!("invalid name '{}': {}", name, err)
+            }
+        }
+        Some(suggestion) => {
+            anyhow::bail!("invalid name '{}': {}, try '{}'", name, err, suggestion)
+        }
+        None => anyhow::bail!("invalid name '{}': {}", name, err),
     }
-    Ok(name)
 }
 
 #[cfg(test)]
@@ -319,6 +545,10 @@ mod tests {
             name: Some(name.to_owned()),
             passphrase: Some(test::USER_PASS.to_owned()),
             profile: None,
+            add_key: None,
+            revoke_key: None,
+            pin: false,
+            unpin: false,
         }
     }
 
@@ -343,4 +573,38 @@ mod tests {
 
         assert!(init(options).is_err());
     }
+
+    #[test]
+    fn invalid_name_is_suggested() {
+        let err = sanitize_name("user A".to_owned(), false).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "invalid name 'user A': name cannot contain whitespace, try 'user-A'"
+        );
+        assert_eq!(sanitize_name("user".to_owned(), false).unwrap(), "user");
+    }
+
+    #[assay(
+        setup = test::setup::lnk_home()?,
+        teardown = test::teardown::profiles()?,
+    )]
+    fn rollback_keeps_active_profile() {
+        init(create_auth_options("user")).unwrap();
+
+        let active = profile::default().unwrap();
+        let pwhash = keys::pwhash(SecUtf8::from(test::USER_PASS));
+        let (created, _) = profile::new(&profile::home(), pwhash).unwrap();
+
+        assert_eq!(profile::count().unwrap(), 2);
+
+        Rollback {
+            profile: Some(created),
+            agent: None,
+        }
+        .run();
+
+        assert_eq!(profile::count().unwrap(), 1);
+        assert_eq!(profile::default().unwrap().id(), active.id());
+    }
 }
@@ -4,11 +4,14 @@ use std::path::PathBuf;
 use anyhow::anyhow;
 use anyhow::Context as _;
 
+use librad::crypto::BoxedSigner;
+use librad::git::storage::Storage;
 use librad::git::Urn;
+use librad::PeerId;
 
 use radicle_common::args::{Args, Error, Help};
 use radicle_common::Interactive;
-use radicle_common::{fmt, keys, profile, project};
+use radicle_common::{fmt, git, keys, profile, project};
 use radicle_terminal as term;
 
 pub const HELP: Help = Help {
@@ -20,16 +23,32 @@ Usage
 
     rad checkout <urn> [<option>...]
 
+    With `--peer`, the working copy is checked out from the given peer's view of
+    the project, instead of your own. The peer must be tracked, and its refs
+    fetched, eg. with `rad track` and `rad sync --fetch`.
+
+    If `--peer` is used from an existing working copy of the project, a remote is
+    setup for the peer, and its default branch is checked out as
+    `peers/<name>/<branch>`.
+
 Options
 
-    --no-confirm    Don't ask for confirmation during checkout
-    --help          Print help
+    --peer <peer-id>    Check out the given peer's view of the project
+    --no-confirm        Don't ask for confirmation during checkout
+    --help              Print help
 "#,
+    ..Help::DEFAULT
 };
 
 pub struct Options {
     pub urn: Urn,
     pub interactive: Interactive,
+    /// Directory under which to checkout the project. Defaults to the current directory.
+    pub directory: Option<PathBuf>,
+    /// Path of the working copy. Defaults to the project name, under `directory`.
+    pub path: Option<PathBuf>,
+    /// Peer whose view of the project to checkout. Defaults to our own, or a delegate's.
+    pub peer: Option<PeerId>,
 }
 
 impl Args for Options {
@@ -39,12 +58,19 @@ impl Args for Options {
 
         let mut parser = lexopt::Parser::from_args(args);
         let mut urn = None;
-        let mut interactive = Interactive::Yes;
+        let interactive = term::interactive();
+        let mut peer = None;
 
         while let Some(arg) = parser.next()? {
             match arg {
-                Long("no-confirm") => {
-                    interactive = Interactive::No;
+                Long("peer") if peer.is_none() => {
+                    let val = parser.value()?;
+                    let val = val
+                        .to_string_lossy()
+                        .parse()
+                        .context("invalid value specified for peer")?;
+
+                    peer = Some(val);
                 }
                 Long("help") => return Err(Error::Help.into()),
                 Value(val) if urn.is_none() => {
@@ -61,6 +87,9 @@ impl Args for Options {
             Options {
                 urn: urn.ok_or_else(|| anyhow!("a project URN to checkout must be provided"))?,
                 interactive,
+                directory: None,
+                path: None,
+                peer,
             },
             vec![],
         ))
@@ -84,9 +113,40 @@ pub fn execute(options: Options, profile: &profile::Profile) -> anyhow::Result<P
     let storage = keys::storage(profile, signer.clone())?;
     let project = project::get(&storage, &options.urn)?
         .context("project could not be found in local storage")?;
-    let path = PathBuf::from(project.name.clone());
+    let path = match options.path {
+        Some(path) => path,
+        None => options
+            .directory
+            .unwrap_or_default()
+            .join(project.name.clone()),
+    };
     let interactive = options.interactive;
 
+    if let Some(peer) = &options.peer {
+        if peer != storage.peer_id()
+            && project::get_remote_head(&storage, &options.urn, peer, &project.default_branch)
+                .ok()
+                .flatten()
+                .is_none()
+        {
+            return Err(Error::WithHint {
+                err: anyhow!(
+                    "{} branch of peer {} was not found in local storage",
+                    project.default_branch,
+                    peer
+                ),
+                hint: "Track the peer with `rad track` and fetch its refs with `rad sync --fetch`.",
+            }
+            .into());
+        }
+        // From a working copy of the project, setup the peer's branch in it.
+        if let Ok((urn, repo)) = project::cwd() {
+            if urn == options.urn {
+                return switch(&project, &repo, peer, &storage, signer, profile);
+            }
+        }
+    }
+
     if path.exists() {
         anyhow::bail!("the local path {:?} already exists", path.as_path());
     }
@@ -101,9 +161,14 @@ pub fn execute(options: Options, profile: &profile::Profile) -> anyhow::Result<P
     // a peer.
     // If we *don't* have a local head, we have to checkout a delegate's head. If there is
     // only one delegate, the choice is easy.
-    let peer = if project::get_local_head(&storage, &options.urn, &project.default_branch)?
-        .is_some()
-    {
+    let peer = if let Some(peer) = options.peer {
+        term::success!(
+            "Remote {} branch found via {}...",
+            project.default_branch,
+            term::format::highlight(peer)
+        );
+        Some(peer).filter(|p| p != storage.peer_id())
+    } else if project::get_local_head(&storage, &options.urn, &project.default_branch)?.is_some() {
         term::success!("Local {} branch found...", project.default_branch);
         None
     } else if project.remotes.len() > 1 {
@@ -141,6 +206,9 @@ pub fn execute(options: Options, profile: &profile::Profile) -> anyhow::Result<P
             if let Err(err) = rad_init::setup_signing(storage.peer_id(), &repo, interactive) {
                 term::warning(&format!("Could not setup signing: {:#}", err));
             }
+            if let Err(err) = rad_init::setup_profile(profile, &repo) {
+                term::warning(&format!("Could not pin profile: {:#}", err));
+            }
 
             // Setup a remote and tracking branch for all project delegates except yourself.
             let setup = project::SetupRemote {
@@ -152,13 +220,7 @@ pub fn execute(options: Options, profile: &profile::Profile) -> anyhow::Result<P
             };
             for peer in &project.remotes {
                 if peer != storage.peer_id() {
-                    let name = if let Some(person) =
-                        project::person(&storage, project.urn.clone(), peer)?
-                    {
-                        person.subject().name.to_string()
-                    } else {
-                        peer.default_encoding()
-                    };
+                    let name = peer_name(&project, peer, &storage)?;
 
                     if let Some((remote, branch)) = setup.run(peer, &name, profile)? {
                         term::success!("Remote {} set", term::format::highlight(remote.name),);
@@ -175,3 +237,66 @@ pub fn execute(options: Options, profile: &profile::Profile) -> anyhow::Result<P
 
     Ok(path)
 }
+
+/// Setup a remote and tracking branch for a peer in an existing working copy, and
+/// check it out.
+fn switch(
+    project: &project::Metadata,
+    repo: &git::Repository,
+    peer: &PeerId,
+    storage: &Storage,
+    signer: BoxedSigner,
+    profile: &profile::Profile,
+) -> anyhow::Result<PathBuf> {
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| anyhow!("cannot checkout in bare repository"))?;
+    let name = peer_name(project, peer, storage)?;
+    let remote = format!("{}/{}/rad", project::PEER_PREFIX, name);
+
+    // Re-create the remote, so that its refspecs are up to date.
+    if repo.find_remote(&remote).is_ok() {
+        repo.remote_delete(&remote)?;
+    }
+    let setup = project::SetupRemote {
+        project,
+        repo,
+        signer,
+        fetch: true,
+        upstream: true,
+    };
+    let spinner = term::spinner("Fetching peer refs...");
+    let (remote, branch) = match setup.run(peer, &name, profile) {
+        Ok(Some(result)) => result,
+        Ok(None) => unreachable!(),
+        Err(err) => {
+            spinner.failed();
+            return Err(err);
+        }
+    };
+    spinner.finish();
+
+    term::success!("Remote {} set", term::format::highlight(remote.name));
+    git::git(workdir, ["checkout", branch.as_str()])?;
+    term::success!(
+        "Switched to branch {} of {}",
+        term::format::highlight(&branch),
+        term::format::tertiary(fmt::peer(peer))
+    );
+
+    Ok(workdir.to_path_buf())
+}
+
+/// Get the name of a peer, from its personal identity if it's known.
+fn peer_name(
+    project: &project::Metadata,
+    peer: &PeerId,
+    storage: &Storage,
+) -> anyhow::Result<String> {
+    let name = if let Some(person) = project::person(storage, project.urn.clone(), peer)? {
+        person.subject().name.to_string()
+    } else {
+        peer.default_encoding()
+    };
+    Ok(name)
+}
@@ -0,0 +1,81 @@
+//! Dynamic completion of argument values, for shell completion scripts.
+//!
+//! Completion scripts call `rad __complete <kind> [<prefix>]`, which prints the
+//! candidates of the given kind that start with the prefix, one per line. The kinds are:
+//!
+//! * `projects`: names of projects in local storage.
+//! * `urns`: URNs of projects in local storage.
+//! * `profiles`: ids of local profiles.
+//! * `peers`: ids of tracked peers, for the project of the working copy if any,
+//!   otherwise for all local projects.
+//!
+//! Since completion must never get in the way of the user, errors are ignored, and
+//! only lead to fewer candidates.
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+
+use radicle_common::{profile, project};
+
+/// Print the completion candidates for the given arguments.
+pub fn run(args: &[OsString]) {
+    let mut args = args.iter().map(|a| a.to_string_lossy());
+    let kind = args.next().unwrap_or_default();
+    let prefix = args.next().unwrap_or_default();
+
+    let candidates = match kind.as_ref() {
+        "projects" => projects().map(|ps| ps.into_iter().map(|(_, name)| name).collect()),
+        "urns" => projects().map(|ps| ps.into_iter().map(|(urn, _)| urn).collect()),
+        "profiles" => profiles(),
+        "peers" => peers(),
+        _ => Ok(BTreeSet::new()),
+    };
+
+    for candidate in candidates.unwrap_or_default() {
+        if candidate.starts_with(prefix.as_ref()) {
+            println!("{}", candidate);
+        }
+    }
+}
+
+/// Get the URNs and names of projects in local storage.
+fn projects() -> anyhow::Result<Vec<(String, String)>> {
+    let profile = profile::default()?;
+    let storage = profile::read_only(&profile)?;
+    let projects = project::list(&storage)?
+        .into_iter()
+        .map(|(urn, meta, _)| (urn.to_string(), meta.name))
+        .collect();
+
+    Ok(projects)
+}
+
+/// Get the ids of local profiles.
+fn profiles() -> anyhow::Result<BTreeSet<String>> {
+    let ids = profile::list()?
+        .iter()
+        .map(|p| p.id().to_string())
+        .collect();
+
+    Ok(ids)
+}
+
+/// Get the ids of tracked peers.
+fn peers() -> anyhow::Result<BTreeSet<String>> {
+    let profile = profile::default()?;
+    let storage = profile::read_only(&profile)?;
+    let projects = match project::cwd() {
+        Ok((urn, _)) => project::get(&storage, &urn)?.into_iter().collect(),
+        Err(_) => project::list(&storage)?
+            .into_iter()
+            .map(|(_, meta, _)| meta)
+            .collect::<Vec<_>>(),
+    };
+    let mut peers = BTreeSet::new();
+
+    for meta in projects {
+        if let Ok(tracked) = project::tracked(&meta, &storage) {
+            peers.extend(tracked.keys().map(|peer| peer.to_string()));
+        }
+    }
+    Ok(peers)
+}
@@ -1,5 +1,4 @@
 #![allow(clippy::extra_unused_lifetimes)]
-use librad::crypto::keystore::pinentry::SecUtf8;
 #[cfg(not(feature = "ethereum"))]
 use librad::git::local::url::LocalUrl;
 use librad::profile::{LnkHome, LNK_HOME};
@@ -124,11 +123,15 @@ fn run(remote: Remote) -> anyhow::Result<()> {
             use std::process::Command;
             use std::process::Stdio;
 
+            radicle_common::offline::check("fetch from org")?;
+
             let domain = future::block_on(ethereum::resolve(org))?;
             let http_url = format!("https://{}/{}", domain, urn.encode_id());
 
             // TODO: Use `exec` here.
             let mut child = Command::new("git")
+                .args(radicle_common::proxy::git_args(&http_url)?)
+                .args(radicle_common::seed::credentials::git_args())
                 .arg("remote-https")
                 .arg(http_url)
                 .stdout(Stdio::inherit())
@@ -142,12 +145,19 @@ fn run(remote: Remote) -> anyhow::Result<()> {
         }
         Remote::Project { urn: _urn } => {
             let profile = profile::default()?;
-            let signer = if let Ok(sock) = keys::ssh_auth_sock() {
+            let sock = keys::ssh_auth_sock()
+                .ok()
+                .filter(|sock| keys::is_ready(&profile, sock.clone()).unwrap_or(false));
+            let signer = if let Some(sock) = sock {
                 sock.to_signer(&profile)?
-            } else if let Ok(pass) = env::var(keys::RAD_PASSPHRASE) {
-                keys::load_secret_key(&profile, SecUtf8::from(pass))?.to_signer(&profile)?
+            } else if let Some(signer) = keys::agent_signer(&profile) {
+                signer
+            } else if let Some(pass) = radicle_common::env::passphrase() {
+                keys::load_secret_key(&profile, pass)?.to_signer(&profile)?
             } else {
-                fatal(anyhow!("no signers found: ssh-agent is not running"));
+                fatal(anyhow!(
+                    "no signers found: neither ssh-agent nor `rad agent` is running"
+                ));
             };
             let config = remote_helper::Config {
                 signer: Some(signer),
@@ -1,11 +1,15 @@
 use std::ffi::OsString;
-use std::{io::ErrorKind, iter, process};
+use std::{env, iter, process};
 
 use anyhow::anyhow;
 use rad_help::*;
-use radicle_common::profile;
+use radicle_common::error::{self, Error, Kind};
+use radicle_common::{plugin, profile};
 use radicle_terminal as term;
 
+mod complete;
+mod suggest;
+
 pub const NAME: &str = "rad";
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 pub const DESCRIPTION: &str = env!("CARGO_PKG_DESCRIPTION");
@@ -18,22 +22,36 @@ enum Command {
 }
 
 fn main() {
-    match parse_args().map_err(Some).and_then(run) {
+    term::init_colors();
+
+    let command = match parse_args() {
+        Ok(command) => command,
+        Err(err) => exit(Error::new(Kind::Usage, err).into()),
+    };
+    match run(command) {
         Ok(_) => process::exit(0),
-        Err(err) => {
-            if let Some(err) = err {
-                radicle_terminal::error(&format!("Error: rad: {}", err));
-            }
-            process::exit(1);
-        }
+        Err(Some(err)) => exit(err),
+        Err(None) => process::exit(1),
     }
 }
 
+fn exit(err: anyhow::Error) -> ! {
+    let kind = error::kind(&err);
+
+    if term::is_json() {
+        term::error_json(&err, kind);
+    } else {
+        term::error(&format!("Error: rad: {}", err));
+    }
+    process::exit(kind.exit_code());
+}
+
 fn parse_args() -> anyhow::Result<Command> {
     use lexopt::prelude::*;
 
     let mut parser = lexopt::Parser::from_env();
     let mut command = None;
+    let mut verbosity = term::Verbosity::Normal;
 
     while let Some(arg) = parser.next()? {
         match arg {
@@ -43,6 +61,38 @@ fn parse_args() -> anyhow::Result<Command> {
             Long("version") => {
                 command = Some(Command::Version);
             }
+            Long("verbose") | Short('v') => {
+                verbosity = match verbosity {
+                    term::Verbosity::Quiet | term::Verbosity::Normal => term::Verbosity::Verbose,
+                    _ => term::Verbosity::Debug,
+                };
+            }
+            Long("quiet") | Short('q') => {
+                verbosity = term::Verbosity::Quiet;
+            }
+            Long("yes") | Long("no-confirm") => {
+                term::set_assume_yes(true);
+            }
+            Long("json") => {
+                term::set_json(true);
+            }
+            Long("no-color") => {
+                // Nb. Set in the environment so that external commands also pick it up.
+                env::set_var(term::NO_COLOR, "1");
+                term::set_colors(false);
+            }
+            Long("no-emoji") => {
+                env::set_var(term::RAD_NO_EMOJI, "1");
+                term::set_ascii(true);
+            }
+            Long("plain") => {
+                env::set_var(term::RAD_PLAIN, "1");
+                term::set_plain(true);
+            }
+            Long("offline") => {
+                // Nb. Set in the environment so that the git remote helper also picks it up.
+                env::set_var(radicle_common::env::RAD_OFFLINE, "true");
+            }
             Value(val) if command.is_none() => {
                 if val == *"." {
                     command = Some(Command::Other(vec![OsString::from("inspect")]));
@@ -58,6 +108,9 @@ fn parse_args() -> anyhow::Result<Command> {
         }
     }
 
+    term::set_verbosity(verbosity);
+    radicle_common::logger::init(verbosity.level())?;
+
     Ok(command.unwrap_or_else(|| Command::Other(vec![])))
 }
 
@@ -97,6 +150,10 @@ fn run(command: Command) -> Result<(), Option<anyhow::Error>> {
 
 fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>> {
     match exe {
+        // Nb. Hidden command, used by shell completion scripts.
+        "__complete" => {
+            complete::run(args);
+        }
         #[cfg(feature = "ethereum")]
         "account" => {
             term::run_command_args::<rad_account::Options, _>(
@@ -106,6 +163,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "agent" => {
+            term::run_command_args::<rad_agent::Options, _>(
+                rad_agent::HELP,
+                "Agent",
+                rad_agent::run,
+                args.to_vec(),
+            );
+        }
         "auth" => {
             term::run_command_args::<rad_auth::Options, _>(
                 rad_auth::HELP,
@@ -138,6 +203,46 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "config" => {
+            term::run_command_args::<rad_config::Options, _>(
+                rad_config::HELP,
+                "Config",
+                rad_config::run,
+                args.to_vec(),
+            );
+        }
+        "daemon" => {
+            term::run_command_args::<rad_daemon::Options, _>(
+                rad_daemon::HELP,
+                "Daemon",
+                rad_daemon::run,
+                args.to_vec(),
+            );
+        }
+        "delegate" => {
+            term::run_command_args::<rad_delegate::Options, _>(
+                rad_delegate::HELP,
+                "Delegate",
+                rad_delegate::run,
+                args.to_vec(),
+            );
+        }
+        "diff" => {
+            term::run_command_args::<rad_diff::Options, _>(
+                rad_diff::HELP,
+                "Diff",
+                rad_diff::run,
+                args.to_vec(),
+            );
+        }
+        "doctor" => {
+            term::run_command_args::<rad_doctor::Options, _>(
+                rad_doctor::HELP,
+                "Doctor",
+                rad_doctor::run,
+                args.to_vec(),
+            );
+        }
         "edit" => {
             term::run_command_args::<rad_edit::Options, _>(
                 rad_edit::HELP,
@@ -155,6 +260,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "gc" => {
+            term::run_command_args::<rad_gc::Options, _>(
+                rad_gc::HELP,
+                "GC",
+                rad_gc::run,
+                args.to_vec(),
+            );
+        }
         #[cfg(feature = "ethereum")]
         "gov" => {
             term::run_command_args::<rad_gov::Options, _>(
@@ -172,6 +285,30 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "export" => {
+            term::run_command_args::<rad_export::Options, _>(
+                rad_export::HELP,
+                "Export",
+                rad_export::run,
+                args.to_vec(),
+            );
+        }
+        "fork" => {
+            term::run_command_args::<rad_fork::Options, _>(
+                rad_fork::HELP,
+                "Fork",
+                rad_fork::run,
+                args.to_vec(),
+            );
+        }
+        "import" => {
+            term::run_command_args::<rad_import::Options, _>(
+                rad_import::HELP,
+                "Import",
+                rad_import::run,
+                args.to_vec(),
+            );
+        }
         "init" => {
             term::run_command_args::<rad_init::Options, _>(
                 rad_init::HELP,
@@ -196,6 +333,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "log" => {
+            term::run_command_args::<rad_log::Options, _>(
+                rad_log::HELP,
+                "Log",
+                rad_log::run,
+                args.to_vec(),
+            );
+        }
         "ls" => {
             term::run_command_args::<rad_ls::Options, _>(
                 rad_ls::HELP,
@@ -212,6 +357,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "notifications" => {
+            term::run_command_args::<rad_notifications::Options, _>(
+                rad_notifications::HELP,
+                "Notifications",
+                rad_notifications::run,
+                args.to_vec(),
+            );
+        }
         "patch" => {
             term::run_command_args::<rad_patch::Options, _>(
                 rad_patch::HELP,
@@ -244,6 +397,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "react" => {
+            term::run_command_args::<rad_react::Options, _>(
+                rad_react::HELP,
+                "React",
+                rad_react::run,
+                args.to_vec(),
+            );
+        }
         "remote" => {
             term::run_command_args::<rad_remote::Options, _>(
                 rad_remote::HELP,
@@ -268,6 +429,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "seed" => {
+            term::run_command_args::<rad_seed::Options, _>(
+                rad_seed::HELP,
+                "Seed",
+                rad_seed::run,
+                args.to_vec(),
+            );
+        }
         "self" => {
             term::run_command_args::<rad_self::Options, _>(
                 rad_self::HELP,
@@ -276,6 +445,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "status" => {
+            term::run_command_args::<rad_status::Options, _>(
+                rad_status::HELP,
+                "Status",
+                rad_status::run,
+                args.to_vec(),
+            );
+        }
         "sync" => {
             term::run_command_args::<rad_sync::Options, _>(
                 rad_sync::HELP,
@@ -284,6 +461,14 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "tag" => {
+            term::run_command_args::<rad_tag::Options, _>(
+                rad_tag::HELP,
+                "Tag",
+                rad_tag::run,
+                args.to_vec(),
+            );
+        }
         "track" => {
             term::run_command_args::<rad_track::Options, _>(
                 rad_track::HELP,
@@ -300,25 +485,79 @@ fn run_other(exe: &str, args: &[OsString]) -> Result<(), Option<anyhow::Error>>
                 args.to_vec(),
             );
         }
+        "verify" => {
+            term::run_command_args::<rad_verify::Options, _>(
+                rad_verify::HELP,
+                "Verify",
+                rad_verify::run,
+                args.to_vec(),
+            );
+        }
+        "web" => {
+            term::run_command_args::<rad_web::Options, _>(
+                rad_web::HELP,
+                "Web",
+                rad_web::run,
+                args.to_vec(),
+            );
+        }
         _ => {
-            let exe = format!("{}-{}", NAME, exe);
-            let status = process::Command::new(exe.clone()).args(args).status();
+            let path = plugin::find(exe).ok_or_else(|| not_found(exe))?;
+            let mut cmd = process::Command::new(path);
 
-            match status {
-                Ok(status) => {
-                    if !status.success() {
-                        return Err(None);
-                    }
-                }
-                Err(err) => {
-                    if let ErrorKind::NotFound = err.kind() {
-                        return Err(Some(anyhow!("command `{}` not found", exe)));
-                    } else {
-                        return Err(Some(err.into()));
-                    }
+            // Nb. Pass on the profile we resolved, so that external commands use the same
+            // one, eg. when the working copy is pinned to a profile.
+            if env::var_os(radicle_common::env::RAD_PROFILE).is_none() {
+                if let Ok(profile) = profile::default() {
+                    cmd.env(radicle_common::env::RAD_PROFILE, profile.id().to_string());
                 }
             }
+            let status = cmd.args(args).status().map_err(anyhow::Error::from)?;
+
+            if !status.success() {
+                process::exit(exit_code(&status));
+            }
         }
     }
     Ok(())
 }
+
+/// Error for an unknown command, suggesting the commands it may be a typo of.
+fn not_found(exe: &str) -> anyhow::Error {
+    let external = plugin::list();
+    let mut candidates = rad_help::commands()
+        .map(|help| help.name)
+        .collect::<Vec<&str>>();
+
+    candidates.push(rad_help::HELP.name);
+    candidates.extend(external.keys().map(String::as_str));
+
+    let err = match suggest::suggest(exe, candidates).as_slice() {
+        [] => anyhow!("unknown command `{}`", exe),
+        [name] => anyhow!("unknown command `{}`, did you mean `rad {}`?", exe, name),
+        names => anyhow!(
+            "unknown command `{}`, did you mean one of {}?",
+            exe,
+            names
+                .iter()
+                .map(|name| format!("`rad {}`", name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    Error::new(Kind::Usage, err).into()
+}
+
+/// Get the exit code of an external command. When it was killed by a signal, the
+/// code is `128` plus the signal number, as shells report it.
+fn exit_code(status: &process::ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(1)
+}
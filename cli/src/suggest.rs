@@ -0,0 +1,85 @@
+//! Suggestions for mistyped commands.
+//!
+//! Candidates are ranked by their edit distance to the given name, ie. the number of
+//! characters that must be inserted, removed, substituted or swapped to go from one
+//! to the other. Only candidates close enough to be a likely typo are suggested.
+
+/// Get the candidates closest to a name, if any are close enough to be suggested.
+pub fn suggest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    // Nb. Allow one edit for short names, and more for longer ones, so that eg. `sc`
+    // doesn't suggest every two-letter command.
+    let max = 1.max(name.chars().count() / 3);
+    let mut best = Vec::new();
+    let mut min = max;
+
+    for candidate in candidates {
+        let d = distance(name, candidate);
+
+        if d < min {
+            min = d;
+            best.clear();
+        }
+        if d == min && !best.contains(&candidate) {
+            best.push(candidate);
+        }
+    }
+    best.sort_unstable();
+    best
+}
+
+/// Compute the edit distance between two strings, counting the transposition of two
+/// adjacent characters as a single edit.
+fn distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    let mut rows = vec![(0..=b.len()).collect::<Vec<_>>()];
+
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+
+            row[j] = (rows[i - 1][j] + 1)
+                .min(row[j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance("sync", "sync"), 0);
+        assert_eq!(distance("snyc", "sync"), 1);
+        assert_eq!(distance("syn", "sync"), 1);
+        assert_eq!(distance("issues", "issue"), 1);
+        assert_eq!(distance("pach", "patch"), 1);
+        assert_eq!(distance("", "ls"), 2);
+    }
+
+    #[test]
+    fn test_suggest() {
+        let commands = [
+            "sync", "seed", "self", "issue", "patch", "push", "pull", "ls",
+        ];
+
+        assert_eq!(suggest("snyc", commands), vec!["sync"]);
+        assert_eq!(suggest("issues", commands), vec!["issue"]);
+        assert_eq!(suggest("puhs", commands), vec!["push"]);
+        assert_eq!(suggest("pul", commands), vec!["pull"]);
+        assert_eq!(suggest("sef", commands), vec!["self"]);
+        assert_eq!(suggest("pusl", commands), vec!["pull", "push"]);
+        assert!(suggest("frobnicate", commands).is_empty());
+        assert!(suggest("xy", commands).is_empty());
+    }
+}
@@ -1,6 +1,7 @@
 #![allow(clippy::or_fun_call)]
 use std::convert::TryFrom;
 use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use anyhow::anyhow;
@@ -9,9 +10,10 @@ use librad::git::Urn;
 use url::Url;
 
 use radicle_common::args::{Args, Error, Help};
+use radicle_common::hooks::{self, Hook};
 use radicle_common::seed;
 use radicle_common::Interactive;
-use radicle_common::{git, identity, keys, profile, project, sync};
+use radicle_common::{config, git, identity, keys, profile, project, sync};
 use radicle_terminal as term;
 
 pub const HELP: Help = Help {
@@ -21,15 +23,22 @@ pub const HELP: Help = Help {
     usage: r#"
 Usage
 
-    rad clone <urn | url> [--seed <addr>] [<option>...]
+    rad clone <urn | url> [<directory>] [--seed <addr>] [<option>...]
+
+    Clones a project into local storage, and checks it out into a working copy.
+    The working copy is created in the given directory, or in a directory named
+    after the project, under the configured `clone.directory` or the current
+    directory.
 
 Options
 
     --no-confirm    Don't ask for confirmation during clone
+    --no-checkout   Only clone into storage, without creating a working copy
     --seed <addr>   Seed to clone from
     --help          Print help
 
 "#,
+    ..Help::DEFAULT
 };
 
 #[derive(Debug)]
@@ -41,6 +50,8 @@ enum Origin {
 #[derive(Debug)]
 pub struct Options {
     origin: Origin,
+    path: Option<PathBuf>,
+    checkout: bool,
     interactive: Interactive,
 }
 
@@ -50,7 +61,9 @@ impl Args for Options {
 
         let mut parser = lexopt::Parser::from_args(args);
         let mut origin: Option<Origin> = None;
-        let mut interactive = Interactive::Yes;
+        let mut path: Option<PathBuf> = None;
+        let mut checkout = true;
+        let interactive = term::interactive();
         let mut seed = None;
 
         while let Some(arg) = parser.next()? {
@@ -58,8 +71,8 @@ impl Args for Options {
                 Long("seed") if seed.is_none() => {
                     seed = Some(seed::parse_value(&mut parser)?);
                 }
-                Long("no-confirm") => {
-                    interactive = Interactive::No;
+                Long("no-checkout") => {
+                    checkout = false;
                 }
                 Long("help") => {
                     return Err(Error::Help.into());
@@ -89,6 +102,9 @@ impl Args for Options {
                         }
                     }
                 }
+                Value(val) if path.is_none() => {
+                    path = Some(PathBuf::from(val));
+                }
                 _ => return Err(anyhow!(arg.unexpected())),
             }
         }
@@ -96,6 +112,15 @@ impl Args for Options {
             anyhow!("to clone, a URN or URL must be provided; see `rad clone --help`")
         })?;
 
+        if !checkout {
+            if path.is_some() {
+                anyhow::bail!("a directory cannot be specified with `--no-checkout`");
+            }
+            if let Origin::Git(_) = origin {
+                anyhow::bail!("`--no-checkout` can only be used to clone radicle projects");
+            }
+        }
+
         let origin = if let Origin::Radicle(identity::Origin { urn, seed: None }) = origin {
             Origin::Radicle(identity::Origin { urn, seed })
         } else {
@@ -105,6 +130,8 @@ impl Args for Options {
         Ok((
             Options {
                 origin,
+                path,
+                checkout,
                 interactive,
             },
             vec![],
@@ -115,11 +142,18 @@ impl Args for Options {
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     match options.origin {
         Origin::Radicle(origin) => {
-            clone_project(origin.urn, origin.seed, options.interactive, ctx)?;
+            clone_project(
+                origin.urn,
+                origin.seed,
+                options.path,
+                options.checkout,
+                options.interactive,
+                ctx,
+            )?;
         }
         Origin::Git(url) => {
             let profile = ctx.profile()?;
-            clone_repository(url, &profile)?;
+            clone_repository(url, options.path, &profile)?;
         }
     }
     Ok(())
@@ -128,6 +162,8 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 pub fn clone_project(
     urn: Urn,
     seed: Option<sync::Seed<String>>,
+    path: Option<PathBuf>,
+    checkout: bool,
     interactive: Interactive,
     ctx: impl term::Context,
 ) -> anyhow::Result<()> {
@@ -140,17 +176,26 @@ pub fn clone_project(
                 seed,
             }),
             verbose: true,
+            wait: true,
             ..rad_sync::Options::default()
         },
         profile.clone(),
     )?;
-    let path = rad_checkout::execute(
-        rad_checkout::Options {
-            urn: urn.clone(),
-            interactive,
-        },
-        &profile,
-    )?;
+    let path = if checkout {
+        let path = rad_checkout::execute(
+            rad_checkout::Options {
+                urn: urn.clone(),
+                interactive,
+                directory: config::get_path("clone.directory")?,
+                path,
+                peer: None,
+            },
+            &profile,
+        )?;
+        Some(path)
+    } else {
+        None
+    };
 
     let signer = term::signer(&profile)?;
     let storage = keys::storage(&profile, signer)?;
@@ -170,22 +215,50 @@ pub fn clone_project(
     }
     term::success!("Tracking for project delegates configured");
 
-    term::headline(&format!(
-        "🌱 Project clone successful under ./{}",
-        term::format::highlight(path.file_name().unwrap_or_default().to_string_lossy())
-    ));
+    let context = hooks::PostClone {
+        project: urn.to_string(),
+        name: project.name.clone(),
+        path: path.clone(),
+    };
+    if let Err(err) = hooks::run(&profile, Hook::PostClone, &context) {
+        term::warning(&err.to_string());
+    }
+
+    if let Some(path) = path {
+        term::headline(&format!(
+            "🌱 Project clone successful under {}",
+            term::format::highlight(display_path(&path))
+        ));
+    } else {
+        term::headline(&format!(
+            "🌱 Project {} cloned into storage",
+            term::format::highlight(&project.name)
+        ));
+        term::tip!(
+            "To create a working copy, run {}",
+            term::format::secondary(format!("`rad checkout {}`", urn))
+        );
+    }
 
     Ok(())
 }
 
-pub fn clone_repository(url: Url, profile: &profile::Profile) -> anyhow::Result<()> {
+pub fn clone_repository(
+    url: Url,
+    path: Option<PathBuf>,
+    profile: &profile::Profile,
+) -> anyhow::Result<()> {
     let proj = url
         .path_segments()
         .ok_or(anyhow!("couldn't get segments of URL"))?
         .last()
         .ok_or(anyhow!("couldn't get last segment of URL"))?;
     let proj = proj.strip_suffix(".git").unwrap_or(proj);
-    let destination = std::env::current_dir()?.join(proj);
+    let destination = match (path, config::get_path("clone.directory")?) {
+        (Some(path), _) => path,
+        (None, Some(dir)) => dir.join(proj),
+        (None, None) => std::env::current_dir()?.join(proj),
+    };
 
     let spinner = term::spinner(&format!(
         "Cloning git repository {}...",
@@ -207,6 +280,20 @@ pub fn clone_repository(url: Url, profile: &profile::Profile) -> anyhow::Result<
     Ok(())
 }
 
+/// Display a checkout path relative to the current directory, if it's under it.
+fn display_path(path: &Path) -> String {
+    if path.is_relative() {
+        return format!("./{}", path.display());
+    }
+    match std::env::current_dir() {
+        Ok(cwd) => match path.strip_prefix(cwd) {
+            Ok(relative) => format!("./{}", relative.display()),
+            Err(_) => path.display().to_string(),
+        },
+        Err(_) => path.display().to_string(),
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -251,6 +338,26 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_args_path() {
+        let args = vec!["rad:git:hnrkfbrd7y9674d8ow8uioki16fniwcyoz67y", "acme"];
+        let args = args.into_iter().map(|a| a.into()).collect();
+        let (opts, _) = Options::from_args(args).unwrap();
+
+        assert_eq!(opts.path, Some(PathBuf::from("acme")));
+        assert!(opts.checkout);
+
+        let args = vec![
+            "rad:git:hnrkfbrd7y9674d8ow8uioki16fniwcyoz67y",
+            "--no-checkout",
+        ];
+        let args = args.into_iter().map(|a| a.into()).collect();
+        let (opts, _) = Options::from_args(args).unwrap();
+
+        assert_eq!(opts.path, None);
+        assert!(!opts.checkout);
+    }
+
     #[test]
     fn test_args_error() {
         let tests = vec![
@@ -264,6 +371,11 @@ mod test {
                 "--seed",
                 "whyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa@illow.radicle.garden",
             ],
+            vec![
+                "rad:git:hnrkfbrd7y9674d8ow8uioki16fniwcyoz67y",
+                "acme",
+                "--no-checkout",
+            ],
         ];
 
         for args in tests {
@@ -1,17 +1,29 @@
 #![allow(clippy::or_fun_call)]
 use std::ffi::OsString;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 use anyhow::anyhow;
 
+use radicle_common as common;
 use radicle_common::args::{Args, Error, Help};
 use radicle_common::{
     cobs::{self, issue, patch, CommentId},
-    keys, project,
+    git, keys, project,
 };
 use radicle_terminal as term;
 use radicle_terminal::patch::Comment;
 
+/// Comment help message, shown in the editor.
+pub const COMMENT_MSG: &str = r#"
+<!--
+Please enter a comment message. An empty message aborts the comment.
+
+Markdown supported.
+-->
+"#;
+
 pub const HELP: Help = Help {
     name: "comment",
     description: env!("CARGO_PKG_DESCRIPTION"),
@@ -20,13 +32,27 @@ pub const HELP: Help = Help {
 Usage
 
     rad comment <id> [-m <text>] [--reply-to <index>]
+    rad comment <patch-id> --file <path> --line <line> [-m <text>]
+
+    Comments on an issue or patch, by id or an unambiguous prefix of it. Without
+    `--message`, the comment is written in your editor. The comment is signed by
+    your identity. To reply to a comment, pass its index, as shown by
+    `rad issue show`, with `--reply-to`.
+
+    Comments on patches are added to a revision of the patch, by default the latest.
+    With `--file` and `--line`, the comment is anchored to lines of a file of the
+    revision, and is shown with the patch diff in `rad patch show --diff`.
 
 Options
 
     -m, --message               Comment message
         --reply-to <index>      Index of comment writing a reply for
+    -r, --revision <number>     Patch revision to comment on (default: latest)
+        --file <path>           File to comment on, relative to the repository root
+        --line <line>           Line to comment on, or range of lines, eg. `40-42`
         --help                  Print help
 "#,
+    ..Help::DEFAULT
 };
 
 #[derive(Debug)]
@@ -34,6 +60,9 @@ pub struct Options {
     pub id: cobs::Identifier,
     pub message: Comment,
     pub reply_index: Option<CommentId>,
+    pub revision: Option<patch::RevisionIx>,
+    pub file: Option<PathBuf>,
+    pub lines: Option<RangeInclusive<usize>>,
 }
 
 impl Args for Options {
@@ -44,6 +73,9 @@ impl Args for Options {
         let mut id: Option<cobs::Identifier> = None;
         let mut message = Comment::default();
         let mut reply_index: Option<CommentId> = None;
+        let mut revision: Option<patch::RevisionIx> = None;
+        let mut file: Option<PathBuf> = None;
+        let mut lines: Option<RangeInclusive<usize>> = None;
 
         while let Some(arg) = parser.next()? {
             match arg {
@@ -62,6 +94,28 @@ impl Args for Options {
 
                     reply_index = Some(CommentId::from(idx));
                 }
+                Long("revision") | Short('r') => {
+                    let value = parser.value()?;
+                    let ix = value.to_str().unwrap_or_default().parse().map_err(|_| {
+                        anyhow!("invalid revision number `{}`", value.to_string_lossy())
+                    })?;
+
+                    revision = Some(ix);
+                }
+                Long("file") => {
+                    let path = PathBuf::from(parser.value()?);
+                    let path = path.strip_prefix("./").unwrap_or(&path).to_path_buf();
+
+                    file = Some(path);
+                }
+                Long("line") => {
+                    let value = parser.value()?;
+                    let value = value.to_string_lossy();
+
+                    lines = Some(parse_lines(&value).ok_or_else(|| {
+                        anyhow!("invalid line `{}`, expected eg. `42` or `40-42`", value)
+                    })?);
+                }
                 Value(val) if id.is_none() => {
                     let val = val
                         .to_str()
@@ -83,12 +137,27 @@ impl Args for Options {
                 id: id.ok_or_else(|| anyhow!("an object id must be provided"))?,
                 message,
                 reply_index,
+                revision,
+                file,
+                lines,
             },
             vec![],
         ))
     }
 }
 
+/// Parse a line number, or an inclusive range of line numbers, eg. `40-42`.
+fn parse_lines(value: &str) -> Option<RangeInclusive<usize>> {
+    let (start, end) = value.split_once('-').unwrap_or((value, value));
+    let start = start.trim().parse::<usize>().ok()?;
+    let end = end.trim().parse::<usize>().ok()?;
+
+    if start == 0 || start > end {
+        return None;
+    }
+    Some(start..=end)
+}
+
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profile = ctx.profile()?;
     let signer = term::signer(&profile)?;
@@ -96,30 +165,101 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let (project, _) = project::cwd()?;
     let cobs = cobs::store(&profile, &storage)?;
     let cob_id = options.id;
+    let location = match (options.file, options.lines) {
+        (Some(file), Some(lines)) => Some((file, lines)),
+        (None, None) => None,
+        _ => anyhow::bail!("`--file` and `--line` must be used together"),
+    };
 
-    let message = options.message.get("Enter a comment message...");
-    if message.is_empty() {
-        return Ok(());
-    }
+    // Nb. The object is loaded, and not only its id resolved, as a full id resolves to an
+    // object of any type.
+    if let Some((id, issue)) = cobs.resolve::<issue::Issue>(&project, &cob_id)? {
+        if location.is_some() {
+            anyhow::bail!("code comments can only be added to patches");
+        }
+        check_reply(options.reply_index, issue.comments().len())?;
 
-    if let Some(id) = cobs.resolve_id::<issue::Issue>(&project, &cob_id)? {
+        let message = get_message(options.message)?;
         if let Some(reply_to_index) = options.reply_index {
             cobs.issues()
                 .reply(&project, &id, reply_to_index, &message)?;
         } else {
             cobs.issues().comment(&project, &id, &message)?;
         }
+        term::success!(
+            "Comment added to issue {}",
+            term::format::highlight(common::fmt::cob(&id))
+        );
     } else if let Some((id, patch)) = cobs.resolve::<patch::Patch>(&project, &cob_id)? {
-        if let Some(reply_to_index) = options.reply_index {
+        let revision_ix = options.revision.unwrap_or_else(|| patch.version());
+        let revision = patch
+            .revisions
+            .get(revision_ix)
+            .ok_or_else(|| anyhow!("revision R{} does not exist", revision_ix))?;
+
+        if location.is_none() {
+            check_reply(options.reply_index, revision.discussion.len())?;
+        }
+        let message = get_message(options.message)?;
+
+        if let Some((path, lines)) = location {
+            let repo = git::Repository::open_bare(profile.paths().git_dir())?;
+            let tree = repo.find_commit(*revision.oid)?.tree()?;
+            let blob = tree
+                .get_path(&path)
+                .map_err(|_| {
+                    anyhow!(
+                        "file '{}' not found in revision R{}",
+                        path.display(),
+                        revision_ix
+                    )
+                })?
+                .id();
+            let location = patch::CodeLocation {
+                path,
+                lines,
+                commit: revision.oid,
+                blob: blob.into(),
+            };
+
+            cobs.patches()
+                .code_comment(&project, &id, revision_ix, location, &message)?;
+        } else if let Some(reply_to_index) = options.reply_index {
             cobs.patches()
-                .reply(&project, &id, patch.version(), reply_to_index, &message)?;
+                .reply(&project, &id, revision_ix, reply_to_index, &message)?;
         } else {
             cobs.patches()
-                .comment(&project, &id, patch.version(), &message)?;
+                .comment(&project, &id, revision_ix, &message)?;
         }
+        term::success!(
+            "Comment added to patch {} {}",
+            term::format::highlight(common::fmt::cob(&id)),
+            term::format::dim(format!("R{}", revision_ix))
+        );
     } else {
         anyhow::bail!("Couldn't find issue or patch {}", cob_id);
     }
 
     Ok(())
 }
+
+/// Get the comment message, from the options or the editor.
+fn get_message(message: Comment) -> anyhow::Result<String> {
+    let message = message.get(COMMENT_MSG);
+    if message.is_empty() {
+        anyhow::bail!("comment message is empty, aborting");
+    }
+    Ok(message)
+}
+
+/// Check that the comment replied to exists, in a discussion of the given length.
+fn check_reply(reply_to: Option<CommentId>, comments: usize) -> anyhow::Result<()> {
+    match reply_to {
+        Some(ix) if usize::from(ix) >= comments => Err(anyhow!(
+            "comment #{} does not exist, there are {} comment(s)",
+            usize::from(ix),
+            comments
+        )),
+        _ => Ok(()),
+    }
+}
@@ -19,11 +19,35 @@ pub enum Error {
     },
 }
 
+/// Flags accepted by all commands, that answer "yes" to all confirmation prompts.
+pub const ASSUME_YES: &[&str] = &["--yes", "--no-confirm"];
+
 pub struct Help {
     pub name: &'static str,
     pub description: &'static str,
     pub version: &'static str,
     pub usage: &'static str,
+    /// Usage examples, as a description and a command line. Only shown in man pages.
+    pub examples: &'static [(&'static str, &'static str)],
+    /// Environment variables specific to this command, with their description.
+    /// Variables read by all commands are documented in [`crate::man`].
+    pub environment: &'static [(&'static str, &'static str)],
+    /// Exit codes specific to this command, with their meaning.
+    pub exit_codes: &'static [(i32, &'static str)],
+}
+
+impl Help {
+    /// Help without any content, to fill in the optional sections of a command's help,
+    /// eg. `Help { name: "foo", ..Help::DEFAULT }`.
+    pub const DEFAULT: Help = Help {
+        name: "",
+        description: "",
+        version: "",
+        usage: "",
+        examples: &[],
+        environment: &[],
+        exit_codes: &[],
+    };
 }
 
 pub trait Args: Sized {
@@ -71,3 +95,43 @@ pub fn finish(unparsed: Vec<OsString>) -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+/// Remove the flags accepted by all commands from the given arguments, and return whether
+/// confirmation prompts should be answered with "yes". Arguments after `--` are kept as-is.
+pub fn assume_yes(args: &mut Vec<OsString>) -> bool {
+    let mut yes = false;
+    let mut rest = false;
+
+    args.retain(|arg| {
+        if rest {
+            return true;
+        }
+        if arg == "--" {
+            rest = true;
+        } else if ASSUME_YES.iter().any(|flag| arg == *flag) {
+            yes = true;
+            return false;
+        }
+        true
+    });
+    yes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assume_yes() {
+        let mut args: Vec<OsString> = vec!["rad:git:123".into(), "--yes".into(), "-i".into()];
+        assert!(assume_yes(&mut args));
+        assert_eq!(
+            args,
+            vec![OsString::from("rad:git:123"), OsString::from("-i")]
+        );
+
+        let mut args: Vec<OsString> = vec!["--".into(), "--no-confirm".into()];
+        assert!(!assume_yes(&mut args));
+        assert_eq!(args.len(), 2);
+    }
+}
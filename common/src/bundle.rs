@@ -0,0 +1,265 @@
+//! Project bundles, for moving projects between devices without a network.
+//!
+//! A bundle is a regular `git bundle` of a project's namespace in the monorepo. Every
+//! peer's view of the project is stored under `refs/remotes/<peer>`, including the
+//! view of the peer that exported it, so that importing a bundle never overwrites the
+//! importing peer's own refs. The project's identity, and the identities of its
+//! delegates, are included so that the project can be verified on import.
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context as _};
+
+use librad::git::identities;
+use librad::git::storage::{ReadOnly, Storage};
+use librad::git::Urn;
+use librad::PeerId;
+
+use crate::git;
+
+/// A project imported from a bundle.
+#[derive(Debug)]
+pub struct Imported {
+    /// The project URN.
+    pub urn: Urn,
+    /// Peers whose views of the project were imported.
+    pub peers: BTreeSet<PeerId>,
+    /// Whether the project was new to the local storage.
+    pub new: bool,
+}
+
+/// Export a project to a bundle file. Returns the number of refs in the bundle.
+pub fn export<S>(storage: &S, urn: &Urn, path: &Path) -> anyhow::Result<usize>
+where
+    S: AsRef<ReadOnly>,
+{
+    let storage = storage.as_ref();
+    let monorepo = git::Repository::open_bare(storage.path())?;
+    let namespace = format!("refs/namespaces/{}/refs/", urn.encode_id());
+    let self_prefix = format!("{}remotes/{}/", namespace, storage.peer_id());
+    let mut refs = BTreeMap::new();
+
+    for reference in monorepo.references_glob(&format!("{}*", namespace))? {
+        let reference = reference?;
+        let name = match reference.name() {
+            Some(name) => name.to_owned(),
+            None => continue,
+        };
+        let oid = match reference.resolve()?.target() {
+            Some(oid) => oid,
+            None => continue,
+        };
+        let rest = &name[namespace.len()..];
+
+        if rest.starts_with("remotes/") {
+            refs.insert(name, oid);
+            continue;
+        }
+        // Identities of the delegates, so that the project can be verified.
+        if let Some(id) = rest.strip_prefix("rad/ids/") {
+            refs.insert(format!("refs/namespaces/{}/refs/rad/id", id), oid);
+        }
+        // The project identity, so that the project can be found on import.
+        if rest == "rad/id" {
+            refs.insert(name.clone(), oid);
+        }
+        refs.insert(format!("{}{}", self_prefix, rest), oid);
+    }
+    if !refs.contains_key(&format!("{}rad/id", namespace)) {
+        anyhow::bail!("project {} was not found in local storage", urn);
+    }
+
+    // Create the bundle from a scratch repository that borrows the monorepo objects,
+    // so that refs can be laid out without touching the storage.
+    let bundle = absolute(path)?.display().to_string();
+    let result = (|| {
+        let dir = tempfile::Builder::new().prefix("rad-export-").tempdir()?;
+        let scratch = dir.path();
+        let repo = git::Repository::init_bare(scratch)?;
+        fs::write(
+            scratch.join("objects").join("info").join("alternates"),
+            format!("{}\n", storage.path().join("objects").display()),
+        )?;
+        for (name, oid) in &refs {
+            repo.reference(name, *oid, true, "rad export")?;
+        }
+        git::git(scratch, ["bundle", "create", bundle.as_str(), "--all"])
+    })();
+
+    result.context("failed to create bundle")?;
+
+    Ok(refs.len())
+}
+
+/// Import a project from a bundle file into storage. Other peers' views of the
+/// project are updated, and identities are only created if they are missing. If the
+/// imported project doesn't verify, the storage is restored to how it was.
+pub fn import(storage: &Storage, path: &Path) -> anyhow::Result<Imported> {
+    let read_only: &ReadOnly = storage.as_ref();
+    let monorepo = git::Repository::open_bare(read_only.path())?;
+    let bundle = absolute(path)?.display().to_string();
+
+    git::git(read_only.path(), ["bundle", "verify", bundle.as_str()]).context("invalid bundle")?;
+
+    let heads = git::git(read_only.path(), ["bundle", "list-heads", bundle.as_str()])?;
+    let mut specs = Vec::new();
+    let mut project: Option<Urn> = None;
+    let mut persons = BTreeSet::new();
+    let mut peers = BTreeSet::new();
+
+    for line in heads.lines() {
+        let name = match line.split_whitespace().nth(1) {
+            Some(name) => name,
+            None => continue,
+        };
+        let (id, rest) = match name
+            .strip_prefix("refs/namespaces/")
+            .and_then(|n| n.split_once("/refs/"))
+        {
+            Some(parts) => parts,
+            None => continue,
+        };
+        let urn =
+            Urn::try_from_id(id).map_err(|_| anyhow!("invalid namespace '{}' in bundle", id))?;
+
+        if rest == "rad/id" {
+            // Identities are only created, never updated, since their history is
+            // verified against the existing one.
+            if monorepo.find_reference(name).is_err() {
+                specs.push(format!("{}:{}", name, name));
+            }
+            persons.insert(urn);
+        } else if let Some(remote) = rest.strip_prefix("remotes/") {
+            let peer = remote
+                .split('/')
+                .next()
+                .and_then(|p| PeerId::from_str(p).ok())
+                .ok_or_else(|| anyhow!("invalid ref '{}' in bundle", name))?;
+
+            if project.get_or_insert_with(|| urn.clone()) != &urn {
+                anyhow::bail!("bundle contains more than one project");
+            }
+            if &peer != read_only.peer_id() {
+                specs.push(format!("+{}:{}", name, name));
+                peers.insert(peer);
+            }
+        }
+    }
+    let urn = project.ok_or_else(|| anyhow!("bundle doesn't contain a project"))?;
+    persons.remove(&urn);
+
+    let namespace = format!("refs/namespaces/{}/refs/", urn.encode_id());
+    let new = monorepo
+        .find_reference(&format!("{}rad/id", namespace))
+        .is_err();
+    let mut targets = specs
+        .iter()
+        .filter_map(|s| s.split_once(':').map(|(_, dst)| dst.to_owned()))
+        .collect::<Vec<_>>();
+    for person in &persons {
+        let name = format!("{}rad/ids/{}", namespace, person.encode_id());
+        if monorepo.find_reference(&name).is_err() {
+            targets.push(name);
+        }
+    }
+
+    // Remember what the refs pointed to, in case we have to restore them.
+    let snapshot = targets
+        .iter()
+        .map(|name| {
+            let oid = monorepo.find_reference(name).ok().and_then(|r| r.target());
+            (name.clone(), oid)
+        })
+        .collect::<Vec<_>>();
+
+    let result = (|| {
+        if !specs.is_empty() {
+            let mut args = vec!["fetch", "--quiet", bundle.as_str()];
+            args.extend(specs.iter().map(|s| s.as_str()));
+            git::git(read_only.path(), args)?;
+        }
+        for person in &persons {
+            let name = format!("{}rad/ids/{}", namespace, person.encode_id());
+            if monorepo.find_reference(&name).is_err() {
+                monorepo.reference_symbolic(
+                    &name,
+                    &format!("refs/namespaces/{}/refs/rad/id", person.encode_id()),
+                    false,
+                    "rad import",
+                )?;
+            }
+        }
+        identities::project::verify(storage, &urn)?
+            .ok_or_else(|| anyhow!("project {} was not found in bundle", urn))?;
+
+        Ok::<_, anyhow::Error>(())
+    })();
+
+    if let Err(err) = result {
+        for (name, oid) in snapshot {
+            if let Some(oid) = oid {
+                monorepo.reference(&name, oid, true, "rad import").ok();
+            } else if let Ok(mut r) = monorepo.find_reference(&name) {
+                r.delete().ok();
+            }
+        }
+        return Err(err.context("failed to import bundle"));
+    }
+
+    Ok(Imported { urn, peers, new })
+}
+
+/// Get the absolute path of a file, since git commands are run from the storage.
+fn absolute(path: &Path) -> anyhow::Result<PathBuf> {
+    if path.is_absolute() {
+        Ok(path.to_path_buf())
+    } else {
+        Ok(std::env::current_dir()?.join(path))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test;
+
+    #[test]
+    fn test_export_import() {
+        let (storage, _, _, project) = test::setup::profile();
+        let (other, _, _, _) = test::setup::profile();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nakamoto.bundle");
+        let urn = project.urn();
+
+        assert!(export(&other, &urn, &path).is_err());
+
+        let refs = export(&storage, &urn, &path).unwrap();
+        assert!(refs > 0);
+
+        let imported = import(&other, &path).unwrap();
+        assert_eq!(imported.urn, urn);
+        assert!(imported.new);
+        assert!(imported.peers.contains(storage.peer_id()));
+        assert!(identities::project::verify(&other, &urn).unwrap().is_some());
+
+        let imported = import(&other, &path).unwrap();
+        assert!(!imported.new);
+
+        // A peer's own view of the project is never imported.
+        let imported = import(&storage, &path).unwrap();
+        assert!(!imported.new);
+        assert!(imported.peers.is_empty());
+    }
+
+    #[test]
+    fn test_import_invalid() {
+        let (storage, _, _, _) = test::setup::profile();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("invalid.bundle");
+
+        fs::write(&path, "not a bundle").unwrap();
+        assert!(import(&storage, &path).is_err());
+    }
+}
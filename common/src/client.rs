@@ -0,0 +1,351 @@
+//! A client of a profile's storage, for programs using radicle as a library.
+//!
+//! Unlike commands, the client never reads from or writes to the terminal: operations
+//! take plain data and return types that can be serialized, eg. to JSON. The storage
+//! is kept open, so that it is reused across operations, and the client can be shared
+//! between threads.
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+use std::time;
+
+use anyhow::anyhow;
+use serde::Serialize;
+
+use librad::crypto::keystore::pinentry::SecUtf8;
+use librad::crypto::BoxedSigner;
+use librad::git::storage::Storage;
+use librad::git::Urn;
+use librad::profile::Profile;
+
+use crate::cobs::issue::Issue;
+use crate::cobs::patch::{Patch, PatchId};
+use crate::cobs::{self, StateFilter};
+use crate::hooks::{self, SeedResult};
+use crate::nonempty::NonEmpty;
+use crate::signer::ToSigner;
+use crate::sync::{self, Mode, Seed, Seeds};
+use crate::{env, git, keys, lock, patch, project};
+
+/// How long to wait for a seed to respond when syncing.
+pub const SYNC_TIMEOUT: time::Duration = time::Duration::from_secs(9);
+
+/// A project in local storage.
+#[derive(Debug, Serialize)]
+pub struct Project {
+    #[serde(flatten)]
+    pub metadata: project::Metadata,
+    /// Local head of the default branch.
+    pub head: Option<String>,
+}
+
+/// A collaborative object, with its id.
+#[derive(Debug, Serialize)]
+pub struct Object<T> {
+    pub id: String,
+    #[serde(flatten)]
+    pub object: T,
+}
+
+/// A patch to propose.
+#[derive(Debug, Clone)]
+pub struct NewPatch {
+    pub title: String,
+    pub description: String,
+    /// Commit to propose. Must be in storage, eg. pushed with `git push rad`.
+    pub head: git::Oid,
+    /// Commit the patch is based on. Found from the project's tracked peers if not set.
+    pub base: Option<git::Oid>,
+}
+
+/// Get the signer of a profile without prompting: the key in ssh-agent or in
+/// `rad agent`, or else the key unsealed with the given passphrase, or the one set
+/// with [`env::RAD_PASSPHRASE`].
+pub fn signer(profile: &Profile, passphrase: Option<SecUtf8>) -> anyhow::Result<BoxedSigner> {
+    if let Ok(sock) = keys::ssh_auth_sock() {
+        if keys::is_ready(profile, sock)? {
+            return Ok(keys::ssh_auth_sock()?.to_signer(profile)?);
+        }
+    }
+    if let Some(signer) = keys::agent_signer(profile) {
+        return Ok(signer);
+    }
+    let passphrase = passphrase
+        .or_else(env::passphrase)
+        .ok_or_else(|| anyhow!("the signing key is sealed, and no passphrase was given"))?;
+
+    Ok(keys::load_secret_key(profile, passphrase)?.to_signer(profile)?)
+}
+
+/// A client of a profile's storage.
+pub struct Client {
+    profile: Profile,
+    signer: BoxedSigner,
+    storage: Mutex<Storage>,
+    rt: tokio::runtime::Runtime,
+}
+
+impl Client {
+    /// Open the storage of a profile.
+    pub fn new(profile: Profile, signer: BoxedSigner) -> anyhow::Result<Self> {
+        let storage = keys::storage(&profile, signer.clone())?;
+        let rt = tokio::runtime::Runtime::new()?;
+
+        Ok(Self {
+            profile,
+            signer,
+            storage: Mutex::new(storage),
+            rt,
+        })
+    }
+
+    /// Get the profile of the client.
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    /// Get the seeds to sync with by default.
+    pub fn seeds(&self) -> anyhow::Result<NonEmpty<Seed<String>>> {
+        sync::seeds(&self.profile)
+    }
+
+    fn storage(&self) -> MutexGuard<'_, Storage> {
+        // Nb. An operation that panicked while holding the storage doesn't leave it
+        // in an invalid state, since it is only modified through git.
+        self.storage
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Lock the storage against modification by other processes, eg. `rad` commands.
+    fn lock(&self) -> anyhow::Result<lock::StorageLock> {
+        lock::try_storage(&self.profile)?
+            .ok_or_else(|| anyhow!("storage is busy, another command is modifying it"))
+    }
+
+    /// List the projects in local storage.
+    pub fn projects(&self) -> anyhow::Result<Vec<Project>> {
+        let storage = self.storage();
+        let projects = project::list(&*storage)?
+            .into_iter()
+            .map(|(_, metadata, head)| Project {
+                metadata,
+                head: head.map(|h| h.to_string()),
+            })
+            .collect();
+
+        Ok(projects)
+    }
+
+    /// Sync a project with the given seeds, and get the outcome for every seed. Runs
+    /// the `post-sync` and `patch-received` hooks, as `rad sync` does.
+    pub fn sync(
+        &self,
+        urn: &Urn,
+        mode: Mode,
+        seeds: NonEmpty<Seed<String>>,
+    ) -> anyhow::Result<Vec<SeedResult>> {
+        let lock = self.lock()?;
+        let patch_heads = hooks::before_sync(&self.profile, &self.storage(), urn, mode)?;
+        let results = self.rt.block_on(async {
+            let (seeds, _errors) = Seeds::resolve(seeds.iter()).await;
+            let client = sync::client(self.signer.clone(), &self.profile).await?;
+            let results = sync::sync(
+                &client,
+                urn.clone(),
+                seeds,
+                mode,
+                SYNC_TIMEOUT,
+                sync::Retry::from_config(),
+                |_, _| {},
+            )
+            .await;
+
+            Ok::<_, anyhow::Error>(results)
+        })?;
+        let storage = self.storage();
+        let unverified = if mode.is_fetch() {
+            sync::verify_peers(&*storage, urn)?
+        } else {
+            BTreeMap::new()
+        };
+
+        // Nb. Hooks may run `rad` commands that lock the storage themselves.
+        drop(lock);
+
+        let context = hooks::PostSync::new(urn, mode, &results);
+
+        for err in hooks::after_sync(
+            &self.profile,
+            &storage,
+            urn,
+            patch_heads,
+            &unverified,
+            &context,
+        )? {
+            log::warn!("{}", err);
+        }
+        Ok(context.seeds)
+    }
+
+    /// List the issues of a project, oldest first.
+    pub fn issues(&self, urn: &Urn, filter: StateFilter) -> anyhow::Result<Vec<Object<Issue>>> {
+        let storage = self.storage();
+        let cobs = cobs::store(&self.profile, &storage)?;
+        let mut issues = cobs.issues().all(urn)?;
+
+        issues.retain(|(_, issue)| issue.matches(filter));
+        issues.sort_by_key(|(_, issue)| issue.timestamp);
+
+        let issues = issues
+            .into_iter()
+            .map(|(id, mut issue)| {
+                // Nb. Authors are shown by name when their identity is found locally.
+                issue.resolve(&*storage).ok();
+
+                Object {
+                    id: id.to_string(),
+                    object: issue,
+                }
+            })
+            .collect();
+
+        Ok(issues)
+    }
+
+    /// List the patches of a project, oldest first.
+    pub fn patches(&self, urn: &Urn, filter: StateFilter) -> anyhow::Result<Vec<Object<Patch>>> {
+        let storage = self.storage();
+        let cobs = cobs::store(&self.profile, &storage)?;
+        let mut patches = cobs.patches().all(urn)?;
+
+        patches.retain(|(_, patch)| patch.matches(filter));
+        patches.sort_by_key(|(_, patch)| patch.timestamp);
+
+        let patches = patches
+            .into_iter()
+            .map(|(id, mut patch)| {
+                patch.resolve(&*storage).ok();
+
+                Object {
+                    id: id.to_string(),
+                    object: patch,
+                }
+            })
+            .collect();
+
+        Ok(patches)
+    }
+
+    /// Propose a patch to a project.
+    pub fn create_patch(&self, urn: &Urn, new: NewPatch) -> anyhow::Result<PatchId> {
+        let _lock = self.lock()?;
+        let storage = self.storage();
+        let metadata =
+            project::get(&*storage, urn)?.ok_or_else(|| anyhow!("project {} not found", urn))?;
+        let base = match new.base {
+            Some(base) => base,
+            None => match patch::find_merge_target(&new.head, &*storage, &metadata)? {
+                Some((_, target)) => {
+                    let repo = git::Repository::open_bare(self.profile.paths().git_dir())?;
+                    repo.merge_base(target.into(), new.head)?
+                }
+                None => anyhow::bail!("all tracked peers are up to date"),
+            },
+        };
+        let cobs = cobs::store(&self.profile, &storage)?;
+
+        patch::create(
+            &cobs.patches(),
+            &storage,
+            urn,
+            &new.title,
+            &new.description,
+            base,
+            new.head,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use librad::crypto::keystore::crypto::{Pwhash, KDF_PARAMS_TEST};
+
+    use super::*;
+    use crate::test;
+
+    fn client() -> (Client, Urn) {
+        let (_, profile, _, project) = test::setup::profile();
+        let pass = Pwhash::new(SecUtf8::from(test::USER_PASS), *KDF_PARAMS_TEST);
+        let signer = test::signer(&profile, pass).unwrap();
+
+        (Client::new(profile, signer).unwrap(), project.urn())
+    }
+
+    #[test]
+    fn test_projects() {
+        let (client, urn) = client();
+        let head = test::commit(&client.storage(), &urn, "master").unwrap();
+        let projects = client.projects().unwrap();
+        let project = projects.iter().find(|p| p.metadata.urn == urn).unwrap();
+
+        assert_eq!(project.metadata.name, "nakamoto");
+        assert_eq!(project.head, Some(head.to_string()));
+    }
+
+    #[test]
+    fn test_issues() {
+        let (client, urn) = client();
+        assert!(client.issues(&urn, StateFilter::All).unwrap().is_empty());
+
+        let id = {
+            let storage = client.storage();
+            let cobs = cobs::store(client.profile(), &storage).unwrap();
+
+            cobs.issues()
+                .create(&urn, "Flux capacitor", "It's broken", &[])
+                .unwrap()
+        };
+        let issues = client.issues(&urn, StateFilter::Open).unwrap();
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].id, id.to_string());
+        assert_eq!(issues[0].object.title, "Flux capacitor");
+        assert!(client.issues(&urn, StateFilter::Closed).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_create_patch() {
+        let (client, urn) = client();
+        let base = test::commit(&client.storage(), &urn, "master").unwrap();
+        let head = test::commit(&client.storage(), &urn, "master").unwrap();
+        let patch = |title: &str, head: git::Oid| NewPatch {
+            title: title.to_owned(),
+            description: String::from("Implements the flux capacitor"),
+            head,
+            base: Some(base),
+        };
+
+        assert!(client.create_patch(&urn, patch(" ", head)).is_err());
+
+        let missing = git::Oid::from_str("d2c5a5ff8d5b2ed0ebdf1a1fb7e14c26fbbd1f1d").unwrap();
+        let err = client
+            .create_patch(&urn, patch("Flux capacitor", missing))
+            .unwrap_err();
+        assert!(err.to_string().contains("not found in storage"));
+
+        let id = client
+            .create_patch(&urn, patch("Flux capacitor", head))
+            .unwrap();
+        let patches = client.patches(&urn, StateFilter::Open).unwrap();
+
+        assert_eq!(patches.len(), 1);
+        assert_eq!(patches[0].id, id.to_string());
+        assert_eq!(patches[0].object.title, "Flux capacitor");
+        assert!(client
+            .patches(&urn, StateFilter::Merged)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -1,3 +1,4 @@
+pub mod export;
 pub mod issue;
 pub mod label;
 pub mod patch;
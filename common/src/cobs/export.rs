@@ -0,0 +1,390 @@
+//! Export of collaborative objects as JSON.
+//!
+//! The exported documents follow a schema of their own, versioned with [`VERSION`],
+//! so that tools consuming them, eg. for migrations, backups or dashboards, aren't
+//! affected by changes to how objects are stored. Each object also lists the changes
+//! it was built from, with their signatures, so that its history can be verified.
+use std::collections::{BTreeMap, HashMap};
+use std::ops::ControlFlow;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use serde::Serialize;
+
+use librad::collaborative_objects::ObjectId;
+use librad::git::storage::ReadOnly;
+use librad::git::Urn;
+
+use crate::cobs::issue::{self, Issue};
+use crate::cobs::patch::{self, CodeComment, Merge, MergeTarget, Patch, Review, Revision, Verdict};
+use crate::cobs::shared::{Author, Cob, Comment, Reaction, Replies, Store, Timestamp};
+
+/// Version of the export schema. Bumped on incompatible changes.
+pub const VERSION: u64 = 1;
+
+/// Commit trailer holding a change signature, as `<key> <signature>`.
+pub const SIGNATURE_TRAILER: &str = "X-Rad-Signature";
+
+/// Exported issues of a project.
+#[derive(Debug, Serialize)]
+pub struct Issues {
+    pub version: u64,
+    pub project: String,
+    pub issues: Vec<IssueExport>,
+}
+
+/// Exported patches of a project.
+#[derive(Debug, Serialize)]
+pub struct Patches {
+    pub version: u64,
+    pub project: String,
+    pub patches: Vec<PatchExport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IssueExport {
+    pub id: String,
+    pub title: String,
+    pub author: AuthorExport,
+    pub state: issue::State,
+    pub labels: Vec<String>,
+    pub description: String,
+    pub reactions: BTreeMap<String, usize>,
+    pub comments: Vec<CommentExport>,
+    pub timestamp: Timestamp,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PatchExport {
+    pub id: String,
+    pub title: String,
+    pub author: AuthorExport,
+    pub state: patch::State,
+    pub merged: bool,
+    pub target: MergeTarget,
+    pub labels: Vec<String>,
+    pub revisions: Vec<RevisionExport>,
+    pub timestamp: Timestamp,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevisionExport {
+    pub id: String,
+    pub peer: String,
+    pub base: String,
+    pub oid: String,
+    pub description: String,
+    pub reactions: BTreeMap<String, usize>,
+    pub comments: Vec<CommentExport>,
+    pub reviews: Vec<ReviewExport>,
+    pub merges: Vec<MergeExport>,
+    pub timestamp: Timestamp,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReviewExport {
+    pub author: AuthorExport,
+    pub verdict: Option<Verdict>,
+    pub comment: CommentExport,
+    pub inline: Vec<CodeCommentExport>,
+    pub timestamp: Timestamp,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CodeCommentExport {
+    pub path: String,
+    pub start: usize,
+    pub end: usize,
+    pub commit: String,
+    pub blob: String,
+    pub comment: CommentExport,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MergeExport {
+    pub peer: String,
+    pub commit: String,
+    pub timestamp: Timestamp,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommentExport {
+    pub author: AuthorExport,
+    pub body: String,
+    pub reactions: BTreeMap<String, usize>,
+    pub replies: Vec<CommentExport>,
+    pub timestamp: Timestamp,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuthorExport {
+    pub urn: String,
+    pub peer: String,
+    /// Name of the author, if their identity was found locally.
+    pub name: Option<String>,
+}
+
+/// A change an object was built from, ie. a commit in its history.
+#[derive(Debug, Serialize)]
+pub struct Change {
+    pub id: String,
+    pub signatures: Vec<Signature>,
+}
+
+/// A change signature.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Signature {
+    pub key: String,
+    pub signature: String,
+}
+
+/// Export all issues of a project.
+pub fn issues<S: AsRef<ReadOnly>>(
+    store: &Store,
+    storage: &S,
+    project: &Urn,
+) -> anyhow::Result<Issues> {
+    let repo = git2::Repository::open_bare(storage.as_ref().path())?;
+    let mut issues = store.issues().all(project)?;
+    let mut exported = Vec::with_capacity(issues.len());
+
+    issues.sort_by_key(|(_, i)| i.timestamp);
+
+    for (id, mut issue) in issues {
+        issue.resolve(storage).ok();
+
+        exported.push(IssueExport {
+            id: id.to_string(),
+            title: issue.title.clone(),
+            author: author(&issue.author),
+            state: issue.state,
+            labels: labels(issue.labels.iter().map(|l| l.name())),
+            description: issue.comment.body.clone(),
+            reactions: reactions(&issue.comment.reactions),
+            comments: issue.discussion.iter().map(discussion_comment).collect(),
+            timestamp: issue.timestamp,
+            changes: changes::<Issue>(store, &repo, project, &id)?,
+        });
+    }
+
+    Ok(Issues {
+        version: VERSION,
+        project: project.to_string(),
+        issues: exported,
+    })
+}
+
+/// Export all patches of a project.
+pub fn patches<S: AsRef<ReadOnly>>(
+    store: &Store,
+    storage: &S,
+    project: &Urn,
+) -> anyhow::Result<Patches> {
+    let repo = git2::Repository::open_bare(storage.as_ref().path())?;
+    let mut patches = store.patches().all(project)?;
+    let mut exported = Vec::with_capacity(patches.len());
+
+    patches.sort_by_key(|(_, p)| p.timestamp);
+
+    for (id, mut patch) in patches {
+        patch.resolve(storage).ok();
+
+        exported.push(PatchExport {
+            id: id.to_string(),
+            title: patch.title.clone(),
+            author: author(&patch.author),
+            state: patch.state,
+            merged: patch.is_merged(),
+            target: patch.target,
+            labels: labels(patch.labels.iter().map(|l| l.name())),
+            revisions: patch.revisions.iter().map(revision).collect(),
+            timestamp: patch.timestamp,
+            changes: changes::<Patch>(store, &repo, project, &id)?,
+        });
+    }
+
+    Ok(Patches {
+        version: VERSION,
+        project: project.to_string(),
+        patches: exported,
+    })
+}
+
+/// Get the changes of an object, in the order they were applied, with their signatures.
+fn changes<T: Cob>(
+    store: &Store,
+    repo: &git2::Repository,
+    project: &Urn,
+    id: &ObjectId,
+) -> anyhow::Result<Vec<Change>> {
+    let cob = store
+        .retrieve(project, T::type_name(), id)?
+        .ok_or_else(|| anyhow!("object {} not found", id))?;
+    let ids = cob.history().traverse(Vec::new(), |mut ids, entry| {
+        ids.push(entry.id().to_string());
+        ControlFlow::Continue(ids)
+    });
+
+    ids.into_iter()
+        .map(|id| -> anyhow::Result<Change> {
+            let commit = repo.find_commit(git2::Oid::from_str(&id)?)?;
+            let signatures = signatures(commit.message().unwrap_or_default());
+
+            Ok(Change { id, signatures })
+        })
+        .collect()
+}
+
+/// Get the signatures from the trailers of a change commit message.
+fn signatures(message: &str) -> Vec<Signature> {
+    message
+        .lines()
+        .filter_map(|line| line.strip_prefix(SIGNATURE_TRAILER)?.strip_prefix(':'))
+        .filter_map(|value| {
+            let (key, signature) = value.trim().split_once(' ')?;
+
+            Some(Signature {
+                key: key.to_owned(),
+                signature: signature.trim().to_owned(),
+            })
+        })
+        .collect()
+}
+
+fn revision(revision: &Revision) -> RevisionExport {
+    let mut reviews = revision.reviews.values().collect::<Vec<_>>();
+    reviews.sort_by_key(|r| r.timestamp);
+
+    RevisionExport {
+        id: revision.id.to_string(),
+        peer: revision.peer.to_string(),
+        base: revision.base.to_string(),
+        oid: revision.oid.to_string(),
+        description: revision.comment.body.clone(),
+        reactions: reactions(&revision.comment.reactions),
+        comments: revision.discussion.iter().map(discussion_comment).collect(),
+        reviews: reviews.into_iter().map(review).collect(),
+        merges: revision.merges.iter().map(merge).collect(),
+        timestamp: revision.timestamp,
+    }
+}
+
+fn review(review: &Review) -> ReviewExport {
+    ReviewExport {
+        author: author(&review.author),
+        verdict: review.verdict,
+        comment: discussion_comment(&review.comment),
+        inline: review.inline.iter().map(code_comment).collect(),
+        timestamp: review.timestamp,
+    }
+}
+
+fn code_comment(comment: &CodeComment) -> CodeCommentExport {
+    let location = &comment.location;
+
+    CodeCommentExport {
+        path: location.path.to_string_lossy().into_owned(),
+        start: *location.lines.start(),
+        end: *location.lines.end(),
+        commit: location.commit.to_string(),
+        blob: location.blob.to_string(),
+        comment: self::comment(&comment.comment),
+    }
+}
+
+fn merge(merge: &Merge) -> MergeExport {
+    MergeExport {
+        peer: merge.peer.to_string(),
+        commit: merge.commit.to_string(),
+        timestamp: merge.timestamp,
+    }
+}
+
+fn discussion_comment(comment: &Comment<Replies>) -> CommentExport {
+    CommentExport {
+        author: author(&comment.author),
+        body: comment.body.clone(),
+        reactions: reactions(&comment.reactions),
+        replies: comment.replies.iter().map(self::comment).collect(),
+        timestamp: comment.timestamp,
+    }
+}
+
+fn comment(comment: &Comment) -> CommentExport {
+    CommentExport {
+        author: author(&comment.author),
+        body: comment.body.clone(),
+        reactions: reactions(&comment.reactions),
+        replies: vec![],
+        timestamp: comment.timestamp,
+    }
+}
+
+fn author(author: &Author) -> AuthorExport {
+    AuthorExport {
+        urn: author.urn.to_string(),
+        peer: author.peer.to_string(),
+        name: author.profile.as_ref().map(|p| p.name.clone()),
+    }
+}
+
+fn reactions(reactions: &HashMap<Reaction, usize>) -> BTreeMap<String, usize> {
+    reactions
+        .iter()
+        .map(|(r, count)| (r.emoji.to_string(), *count))
+        .collect()
+}
+
+fn labels<'a>(labels: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut labels = labels.map(ToOwned::to_owned).collect::<Vec<_>>();
+    labels.sort();
+    labels
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test;
+
+    #[test]
+    fn test_signatures() {
+        let message = "Update\n\nX-Rad-Signature: hyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa abc123\n";
+
+        assert_eq!(
+            signatures(message),
+            vec![Signature {
+                key: "hyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa".to_owned(),
+                signature: "abc123".to_owned(),
+            }]
+        );
+        assert!(signatures("Update").is_empty());
+    }
+
+    #[test]
+    fn test_export_issues() {
+        let (storage, profile, whoami, project) = test::setup::profile();
+        let cobs = Store::new(whoami, profile.paths(), &storage);
+        let issues = cobs.issues();
+        let issue_id = issues
+            .create(&project.urn(), "My first issue", "Blah blah blah.", &[])
+            .unwrap();
+
+        issues
+            .comment(&project.urn(), &issue_id, "Ho ho ho.")
+            .unwrap();
+
+        let export = super::issues(&cobs, &storage, &project.urn()).unwrap();
+        let json = serde_json::to_value(&export).unwrap();
+
+        assert_eq!(json["version"], VERSION);
+        assert_eq!(json["project"], project.urn().to_string());
+        assert_eq!(json["issues"][0]["id"], issue_id.to_string());
+        assert_eq!(json["issues"][0]["title"], "My first issue");
+        assert_eq!(json["issues"][0]["state"]["status"], "open");
+        assert_eq!(json["issues"][0]["description"], "Blah blah blah.");
+        assert_eq!(json["issues"][0]["comments"][0]["body"], "Ho ho ho.");
+        assert_eq!(json["issues"][0]["changes"].as_array().unwrap().len(), 2);
+    }
+}
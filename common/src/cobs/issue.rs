@@ -125,6 +125,16 @@ impl Issue {
         self.timestamp
     }
 
+    /// Check whether the issue matches a state filter. Issues are never merged.
+    pub fn matches(&self, filter: StateFilter) -> bool {
+        match filter {
+            StateFilter::Open => matches!(self.state, State::Open),
+            StateFilter::Closed => matches!(self.state, State::Closed { .. }),
+            StateFilter::Merged => false,
+            StateFilter::All => true,
+        }
+    }
+
     pub fn resolve<S: AsRef<ReadOnly>>(&mut self, storage: &S) -> Result<(), ResolveError> {
         self.author.resolve(storage)?;
         self.comment.resolve(storage)?;
@@ -319,7 +329,7 @@ impl<'a> IssueStore<'a> {
                 UpdateObjectSpec {
                     object_id: *issue_id,
                     typename: TYPENAME.clone(),
-                    message: Some("Add comment".to_owned()),
+                    message: Some("React".to_owned()),
                     changes,
                 },
             )
@@ -722,9 +732,23 @@ mod test {
         let issue = issues.get(&project, &issue_id).unwrap().unwrap();
         let count = issue.reactions()[&reaction];
 
-        // TODO: Test multiple reactions from same author and different authors
+        // TODO: Test multiple reactions from different authors
 
         assert_eq!(count, 1);
+
+        // Reacting again with the same reaction isn't counted twice.
+        let other = Reaction::new('👍').unwrap();
+        issues
+            .react(&project, &issue_id, CommentId::root(), reaction)
+            .unwrap();
+        issues
+            .react(&project, &issue_id, CommentId::root(), other)
+            .unwrap();
+
+        let issue = issues.get(&project, &issue_id).unwrap().unwrap();
+        assert_eq!(issue.reactions()[&reaction], 1);
+        assert_eq!(issue.reactions()[&other], 1);
+        assert_eq!(issue.reactions().len(), 2);
     }
 
     #[test]
@@ -3,6 +3,7 @@ use std::collections::{HashMap, HashSet};
 use std::convert::{TryFrom, TryInto};
 use std::fmt;
 use std::ops::{ControlFlow, Deref, RangeInclusive};
+use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -125,6 +126,58 @@ impl Patch {
         self.latest().1.description()
     }
 
+    /// Check whether any revision of the patch was merged.
+    pub fn is_merged(&self) -> bool {
+        self.revisions.iter().any(|r| !r.merges.is_empty())
+    }
+
+    /// Check whether the patch matches a state filter. A merged patch is only matched
+    /// as merged, even if it is also archived.
+    pub fn matches(&self, filter: StateFilter) -> bool {
+        match filter {
+            StateFilter::Open => !self.is_merged() && !self.is_archived(),
+            StateFilter::Closed => !self.is_merged() && self.is_archived(),
+            StateFilter::Merged => self.is_merged(),
+            StateFilter::All => true,
+        }
+    }
+
+    /// Get the objects referenced from the revision descriptions and discussions of this
+    /// patch, eg. with `fixes #<id>`, once per object. Only references from the patch
+    /// author can close the referenced objects; others are merely mentions.
+    pub fn references(&self) -> Vec<Reference> {
+        let mut refs: Vec<Reference> = Vec::new();
+
+        for revision in self.revisions.iter() {
+            let comments = std::iter::once((&revision.comment.author, &revision.comment.body))
+                .chain(revision.discussion.iter().map(|c| (&c.author, &c.body)))
+                .chain(
+                    revision
+                        .discussion
+                        .iter()
+                        .flat_map(|c| c.replies.iter().map(|r| (&r.author, &r.body))),
+                );
+
+            for (author, body) in comments {
+                let authored = author.urn == self.author.urn;
+
+                for mut reference in Reference::parse(body) {
+                    reference.closes &= authored;
+
+                    if let Some(r) = refs
+                        .iter_mut()
+                        .find(|r| r.id.to_string() == reference.id.to_string())
+                    {
+                        r.closes |= reference.closes;
+                    } else {
+                        refs.push(reference);
+                    }
+                }
+            }
+        }
+        refs
+    }
+
     pub fn resolve<S: AsRef<ReadOnly>>(&mut self, storage: &S) -> Result<(), ResolveError> {
         self.author.resolve(storage)?;
 
@@ -375,6 +428,64 @@ impl<'a> PatchStore<'a> {
         Ok(())
     }
 
+    /// React to a comment of a revision. The root comment is the revision's own
+    /// comment, eg. the patch description, and other comments are in its discussion.
+    pub fn react(
+        &self,
+        project: &Urn,
+        patch_id: &PatchId,
+        revision_ix: RevisionIx,
+        comment_id: CommentId,
+        reaction: Reaction,
+    ) -> Result<(), Error> {
+        let author = self.whoami.urn();
+        let mut patch = self.get_raw(project, patch_id)?.unwrap();
+        let changes = events::react(&mut patch, revision_ix, comment_id, &author, &[reaction])?;
+
+        cobs::update(
+            *patch_id,
+            project,
+            "React",
+            changes,
+            &self.whoami,
+            self.store,
+        )?;
+
+        Ok(())
+    }
+
+    /// Comment on lines of a file of a revision. The comment is added to the inline
+    /// comments of the author's review of the revision.
+    pub fn code_comment(
+        &self,
+        project: &Urn,
+        patch_id: &PatchId,
+        revision_ix: RevisionIx,
+        location: CodeLocation,
+        body: &str,
+    ) -> Result<(), Error> {
+        let author = self.author();
+        let timestamp = Timestamp::now();
+        let comment = CodeComment::new(
+            location,
+            Comment::new(author.clone(), body.trim().to_owned(), timestamp),
+        );
+
+        let mut patch = self.get_raw(project, patch_id)?.unwrap();
+        let changes = events::code_comment(&mut patch, revision_ix, &author, &comment, timestamp)?;
+
+        cobs::update(
+            *patch_id,
+            project,
+            "Add code comment",
+            changes,
+            &self.whoami,
+            self.store,
+        )?;
+
+        Ok(())
+    }
+
     pub fn get(&self, namespace: &Urn, id: &ObjectId) -> anyhow::Result<Option<Patch>> {
         self.store.get::<Patch>(namespace, id)
     }
@@ -643,6 +754,8 @@ impl<'a> FromValue<'a> for Verdict {
 /// Code location, used for attaching comments.
 #[derive(Debug, Clone, Serialize)]
 pub struct CodeLocation {
+    /// Path of the file commented on, relative to the repository root.
+    pub path: PathBuf,
     /// Line number commented on.
     pub lines: RangeInclusive<usize>,
     /// Commit commented on.
@@ -651,13 +764,54 @@ pub struct CodeLocation {
     pub blob: git::Oid,
 }
 
+impl CodeLocation {
+    /// Put this object into an automerge document.
+    fn put(
+        &self,
+        tx: &mut automerge::transaction::Transaction,
+        id: &automerge::ObjId,
+    ) -> Result<(), AutomergeError> {
+        let location_id = tx.put_object(&id, "location", ObjType::Map)?;
+
+        tx.put(
+            &location_id,
+            "path",
+            self.path.to_string_lossy().into_owned(),
+        )?;
+        tx.put(&location_id, "start", *self.lines.start() as u64)?;
+        tx.put(&location_id, "end", *self.lines.end() as u64)?;
+        tx.put(&location_id, "commit", self.commit.to_string())?;
+        tx.put(&location_id, "blob", self.blob.to_string())?;
+
+        Ok(())
+    }
+}
+
 /// Comment on code.
 #[derive(Debug, Clone, Serialize)]
 pub struct CodeComment {
     /// Code location of the comment.
-    location: CodeLocation,
+    pub location: CodeLocation,
     /// Comment.
-    comment: Comment,
+    pub comment: Comment,
+}
+
+impl CodeComment {
+    pub fn new(location: CodeLocation, comment: Comment) -> Self {
+        Self { location, comment }
+    }
+
+    /// Put this object into an automerge document.
+    fn put(
+        &self,
+        tx: &mut automerge::transaction::Transaction,
+        id: &automerge::ObjId,
+    ) -> Result<(), AutomergeError> {
+        self.location.put(tx, id)?;
+        self.comment.put(tx, id)?;
+
+        Ok(())
+    }
 }
 
 /// A patch review on a revision.
@@ -695,16 +849,14 @@ impl Review {
     }
 
     /// Put this object into an automerge document.
+    ///
+    /// If the review is put in place of an existing review, the inline comments of the
+    /// existing review are kept, and the inline comments of this review are added.
     fn put(
         &self,
         tx: &mut automerge::transaction::Transaction,
         id: &automerge::ObjId,
     ) -> Result<(), AutomergeError> {
-        assert!(
-            self.inline.is_empty(),
-            "Cannot put review with non-empty inline comments"
-        );
-
         tx.put(&id, "author", self.author.urn().to_string())?;
         tx.put(&id, "peer", self.author.peer.default_encoding())?;
         tx.put(
@@ -719,7 +871,16 @@ impl Review {
 
         self.comment.put(tx, id)?;
 
-        tx.put_object(&id, "inline", ObjType::List)?;
+        let inline_id = match tx.get(&id, "inline")? {
+            Some((_, inline_id)) => inline_id,
+            None => tx.put_object(&id, "inline", ObjType::List)?,
+        };
+        for comment in &self.inline {
+            let length = tx.length(&inline_id);
+            let comment_id = tx.insert_object(&inline_id, length, ObjType::Map)?;
+
+            comment.put(tx, &comment_id)?;
+        }
         tx.put(&id, "timestamp", self.timestamp)?;
 
         Ok(())
@@ -729,6 +890,9 @@ impl Review {
         self.author.resolve(storage)?;
         self.comment.resolve(storage)?;
 
+        for inline in &mut self.inline {
+            inline.comment.resolve(storage)?;
+        }
         Ok(())
     }
 }
@@ -794,7 +958,7 @@ mod lookup {
         let verdict = doc.val(&obj_id, "verdict")?;
         let timestamp = doc.val(&obj_id, "timestamp")?;
         let comment = doc.lookup(&obj_id, "comment", shared::lookup::thread)?;
-        let inline = vec![];
+        let inline = doc.list(&obj_id, "inline", self::code_comment)?;
 
         Ok(Review {
             author: Author::new(author, peer),
@@ -804,6 +968,34 @@ mod lookup {
             timestamp,
         })
     }
+
+    pub fn code_comment(
+        doc: Document,
+        obj_id: &automerge::ObjId,
+    ) -> Result<CodeComment, DocumentError> {
+        let location = doc.lookup(&obj_id, "location", self::code_location)?;
+        let comment = doc.lookup(&obj_id, "comment", shared::lookup::comment)?;
+
+        Ok(CodeComment { location, comment })
+    }
+
+    pub fn code_location(
+        doc: Document,
+        obj_id: &automerge::ObjId,
+    ) -> Result<CodeLocation, DocumentError> {
+        let path: String = doc.val(&obj_id, "path")?;
+        let start = doc.val(&obj_id, "start")?;
+        let end = doc.val(&obj_id, "end")?;
+        let commit = doc.val(&obj_id, "commit")?;
+        let blob = doc.val(&obj_id, "blob")?;
+
+        Ok(CodeLocation {
+            path: PathBuf::from(path),
+            lines: start..=end,
+            commit,
+            blob,
+        })
+    }
 }
 
 mod cobs {
@@ -1022,8 +1214,12 @@ mod events {
                     let (_, revision_id) = tx.get(&revisions_id, revision_ix)?.unwrap();
                     let (_, reviews_id) = tx.get(&revision_id, "reviews")?.unwrap();
 
-                    let review_id =
-                        tx.put_object(&reviews_id, review.author.urn().to_string(), ObjType::Map)?;
+                    let key = review.author.urn().to_string();
+                    // Nb. An existing review is updated in place, to keep its inline comments.
+                    let review_id = match tx.get(&reviews_id, key.as_str())? {
+                        Some((_, review_id)) => review_id,
+                        None => tx.put_object(&reviews_id, key, ObjType::Map)?,
+                    };
 
                     review.put(tx, &review_id)?;
 
@@ -1037,6 +1233,94 @@ mod events {
         Ok(((), EntryContents::Automerge(change)))
     }
 
+    pub fn react(
+        patch: &mut Automerge,
+        revision_ix: RevisionIx,
+        comment_id: CommentId,
+        author: &Urn,
+        reactions: &[Reaction],
+    ) -> Result<EntryContents, AutomergeError> {
+        patch
+            .transact_with::<_, _, AutomergeError, _, ()>(
+                |_| CommitOptions::default().with_message("React".to_owned()),
+                |tx| {
+                    let (_, obj_id) = tx.get(ObjId::Root, "patch")?.unwrap();
+                    let (_, revisions_id) = tx.get(&obj_id, "revisions")?.unwrap();
+                    let (_, revision_id) = tx.get(&revisions_id, revision_ix)?.unwrap();
+                    let (_, discussion_id) = tx.get(&revision_id, "discussion")?.unwrap();
+                    let (_, comment_id) = if comment_id == CommentId::root() {
+                        tx.get(&revision_id, "comment")?.unwrap()
+                    } else {
+                        tx.get(&discussion_id, usize::from(comment_id) - 1)?
+                            .unwrap()
+                    };
+                    let (_, reactions_id) = tx.get(&comment_id, "reactions")?.unwrap();
+
+                    for reaction in reactions {
+                        let key = reaction.emoji.to_string();
+                        let reaction_id = match tx.get(&reactions_id, key.as_str())? {
+                            Some((_, reaction_id)) => reaction_id,
+                            None => tx.put_object(&reactions_id, key, ObjType::Map)?,
+                        };
+                        tx.put(&reaction_id, author.to_string(), true)?;
+                    }
+
+                    Ok(())
+                },
+            )
+            .map_err(|failure| failure.error)?;
+
+        let change = patch.get_last_local_change().unwrap().raw_bytes().to_vec();
+
+        Ok(EntryContents::Automerge(change))
+    }
+
+    pub fn code_comment(
+        patch: &mut Automerge,
+        revision_ix: RevisionIx,
+        author: &Author,
+        comment: &CodeComment,
+        timestamp: Timestamp,
+    ) -> Result<EntryContents, AutomergeError> {
+        patch
+            .transact_with::<_, _, AutomergeError, _, ()>(
+                |_| CommitOptions::default().with_message("Add code comment".to_owned()),
+                |tx| {
+                    let (_, obj_id) = tx.get(ObjId::Root, "patch")?.unwrap();
+                    let (_, revisions_id) = tx.get(&obj_id, "revisions")?.unwrap();
+                    let (_, revision_id) = tx.get(&revisions_id, revision_ix)?.unwrap();
+                    let (_, reviews_id) = tx.get(&revision_id, "reviews")?.unwrap();
+
+                    let key = author.urn().to_string();
+                    let review_id = match tx.get(&reviews_id, key.as_str())? {
+                        Some((_, review_id)) => review_id,
+                        None => {
+                            // Code comments are part of a review, so one without a verdict
+                            // is created if the author hasn't reviewed the revision yet.
+                            let review = Review::new(author.clone(), None, "", vec![], timestamp);
+                            let review_id = tx.put_object(&reviews_id, key, ObjType::Map)?;
+
+                            review.put(tx, &review_id)?;
+                            review_id
+                        }
+                    };
+                    let (_, inline_id) = tx.get(&review_id, "inline")?.unwrap();
+
+                    let length = tx.length(&inline_id);
+                    let comment_id = tx.insert_object(&inline_id, length, ObjType::Map)?;
+
+                    comment.put(tx, &comment_id)?;
+
+                    Ok(())
+                },
+            )
+            .map_err(|failure| failure.error)?;
+
+        let change = patch.get_last_local_change().unwrap().raw_bytes().to_vec();
+
+        Ok(EntryContents::Automerge(change))
+    }
+
     pub fn merge(
         patch: &mut Automerge,
         revision_ix: RevisionIx,
@@ -1114,6 +1398,45 @@ mod test {
         assert!(revision.merges.is_empty());
     }
 
+    #[test]
+    fn test_patch_references() {
+        let (storage, profile, whoami, project) = test::setup::profile();
+        let cobs = Store::new(whoami, profile.paths(), &storage);
+        let patches = cobs.patches();
+        let target = MergeTarget::Upstream;
+        let oid = git::Oid::from(git2::Oid::zero());
+        let base = git::Oid::from_str("cb18e95ada2bb38aadd8e6cef0963ce37a87add3").unwrap();
+        let patch_id = patches
+            .create(
+                &project.urn(),
+                "My first patch",
+                "Blah blah blah.\n\nFixes #a1b2c3d4e5f6.",
+                target,
+                base,
+                oid,
+                &[],
+            )
+            .unwrap();
+
+        patches
+            .comment(
+                &project.urn(),
+                &patch_id,
+                0,
+                "Also see #f6e5d4c3b2a1, and closes #a1b2c3d4e5f6",
+            )
+            .unwrap();
+
+        let patch = patches.get(&project.urn(), &patch_id).unwrap().unwrap();
+        let refs = patch.references();
+
+        assert_eq!(refs.len(), 2);
+        assert_eq!(refs[0].id.to_string(), "a1b2c3d4e5f6");
+        assert!(refs[0].closes);
+        assert_eq!(refs[1].id.to_string(), "f6e5d4c3b2a1");
+        assert!(!refs[1].closes);
+    }
+
     #[test]
     fn test_patch_merge() {
         let (storage, profile, whoami, project) = test::setup::profile();
@@ -1134,8 +1457,16 @@ mod test {
             )
             .unwrap();
 
+        let patch = patches.get(&project.urn(), &patch_id).unwrap().unwrap();
+        assert!(patch.matches(StateFilter::Open));
+        assert!(!patch.matches(StateFilter::Merged));
+
         let _merge = patches.merge(&project.urn(), &patch_id, 0, base).unwrap();
         let patch = patches.get(&project.urn(), &patch_id).unwrap().unwrap();
+        assert!(patch.is_merged());
+        assert!(patch.matches(StateFilter::Merged));
+        assert!(!patch.matches(StateFilter::Open));
+
         let merges = patch.revisions.head.merges;
 
         assert_eq!(merges.len(), 1);
@@ -1177,6 +1508,93 @@ mod test {
         assert_eq!(review.comment.body.as_str(), "LGTM");
     }
 
+    #[test]
+    fn test_patch_code_comment() {
+        let (storage, profile, whoami, project) = test::setup::profile();
+        let cobs = Store::new(whoami.clone(), profile.paths(), &storage);
+        let patches = cobs.patches();
+        let target = MergeTarget::Upstream;
+        let base = git::Oid::from_str("cb18e95ada2bb38aadd8e6cef0963ce37a87add3").unwrap();
+        let rev_oid = git::Oid::from_str("518d5069f94c03427f694bb494ac1cd7d1339380").unwrap();
+        let blob = git::Oid::from_str("af08e95ada2bb38aadd8e6cef0963ce37a87add3").unwrap();
+        let project = &project.urn();
+        let patch_id = patches
+            .create(
+                project,
+                "My first patch",
+                "Blah blah blah.",
+                target,
+                base,
+                rev_oid,
+                &[],
+            )
+            .unwrap();
+        let location = CodeLocation {
+            path: PathBuf::from("src/lib.rs"),
+            lines: 40..=42,
+            commit: rev_oid,
+            blob,
+        };
+
+        patches
+            .code_comment(project, &patch_id, 0, location, "Off by one?")
+            .unwrap();
+        // Reviewing after commenting keeps the comment.
+        patches
+            .review(project, &patch_id, 0, Some(Verdict::Reject), "", vec![])
+            .unwrap();
+
+        let patch = patches.get(project, &patch_id).unwrap().unwrap();
+        let review = patch.revisions.head.reviews.get(&whoami.urn()).unwrap();
+        assert_eq!(review.verdict, Some(Verdict::Reject));
+        assert_eq!(review.inline.len(), 1);
+
+        let inline = &review.inline[0];
+        assert_eq!(inline.location.path, PathBuf::from("src/lib.rs"));
+        assert_eq!(inline.location.lines, 40..=42);
+        assert_eq!(inline.location.commit, rev_oid);
+        assert_eq!(inline.location.blob, blob);
+        assert_eq!(inline.comment.body, "Off by one?");
+        assert_eq!(inline.comment.author.urn(), &whoami.urn());
+    }
+
+    #[test]
+    fn test_patch_react() {
+        let (storage, profile, whoami, project) = test::setup::profile();
+        let cobs = Store::new(whoami, profile.paths(), &storage);
+        let patches = cobs.patches();
+        let target = MergeTarget::Upstream;
+        let base = git::Oid::from_str("cb18e95ada2bb38aadd8e6cef0963ce37a87add3").unwrap();
+        let rev_oid = git::Oid::from_str("518d5069f94c03427f694bb494ac1cd7d1339380").unwrap();
+        let project = &project.urn();
+        let patch_id = patches
+            .create(
+                project,
+                "My first patch",
+                "Blah blah blah.",
+                target,
+                base,
+                rev_oid,
+                &[],
+            )
+            .unwrap();
+        patches.comment(project, &patch_id, 0, "Ho ho ho.").unwrap();
+
+        let reaction = Reaction::new('🚀').unwrap();
+        patches
+            .react(project, &patch_id, 0, CommentId::root(), reaction)
+            .unwrap();
+        patches
+            .react(project, &patch_id, 0, CommentId::from(1), reaction)
+            .unwrap();
+
+        let patch = patches.get(project, &patch_id).unwrap().unwrap();
+        let revision = patch.revisions.head;
+
+        assert_eq!(revision.comment.reactions[&reaction], 1);
+        assert_eq!(revision.discussion[0].reactions[&reaction], 1);
+    }
+
     #[test]
     fn test_patch_update() {
         let (storage, profile, whoami, project) = test::setup::profile();
@@ -90,6 +90,60 @@ impl fmt::Display for Identifier {
     }
 }
 
+impl Identifier {
+    /// Check whether this identifier identifies the given object.
+    pub fn matches(&self, id: &ObjectId) -> bool {
+        match self {
+            Self::Full(full) => full == id,
+            Self::Prefix(prefix) => id.to_string().starts_with(prefix.as_str()),
+        }
+    }
+}
+
+/// Words which, when preceding a reference, mark it as closing the referenced issue,
+/// eg. `fixes #<id>`.
+pub const CLOSING_KEYWORDS: &[&str] = &[
+    "close", "closes", "closed", "fix", "fixes", "fixed", "resolve", "resolves", "resolved",
+];
+
+/// A reference to an object from some text, eg. `#<id>` or `fixes #<id>`.
+#[derive(Debug, Clone)]
+pub struct Reference {
+    /// Referenced object.
+    pub id: Identifier,
+    /// Whether the reference closes the referenced object, eg. `fixes #<id>`.
+    pub closes: bool,
+}
+
+impl Reference {
+    /// Parse the references in some text, in order. An object is referenced with a `#`
+    /// followed by its id, or an unambiguous prefix of at least seven characters.
+    pub fn parse(text: &str) -> Vec<Self> {
+        let mut refs = Vec::new();
+        let mut previous: Option<&str> = None;
+
+        for word in text.split_whitespace() {
+            let id = word
+                .strip_prefix('#')
+                .map(|id| id.trim_end_matches(|c: char| !c.is_ascii_alphanumeric()))
+                .filter(|id| id.len() >= 7 && id.chars().all(|c| c.is_ascii_hexdigit()));
+
+            if let Some(id) = id {
+                let closes = previous
+                    .map(|w| w.trim_end_matches(':').to_lowercase())
+                    .map_or(false, |w| CLOSING_KEYWORDS.contains(&w.as_str()));
+                let id = match Identifier::from_str(id) {
+                    Ok(id) => id,
+                    Err(e) => match e {},
+                };
+                refs.push(Self { id, closes });
+            }
+            previous = Some(word);
+        }
+        refs
+    }
+}
+
 /// A collaborative object. Objects of this type can be turned into rust types.
 pub trait Cob: Sized {
     /// The object type name.
@@ -277,6 +331,43 @@ impl From<Label> for String {
     }
 }
 
+#[derive(thiserror::Error, Debug)]
+#[error("invalid state `{0}`, expected one of: open, closed, merged, all")]
+pub struct StateFilterError(String);
+
+/// Filter of objects by state, eg. when listing issues or patches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateFilter {
+    /// Issues that are open, and patches that are neither merged nor archived.
+    Open,
+    /// Issues that are closed, and patches that are archived.
+    Closed,
+    /// Patches that are merged.
+    Merged,
+    /// All objects.
+    All,
+}
+
+impl Default for StateFilter {
+    fn default() -> Self {
+        Self::Open
+    }
+}
+
+impl FromStr for StateFilter {
+    type Err = StateFilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "open" => Ok(Self::Open),
+            "closed" => Ok(Self::Closed),
+            "merged" => Ok(Self::Merged),
+            "all" => Ok(Self::All),
+            _ => Err(StateFilterError(s.to_owned())),
+        }
+    }
+}
+
 /// RGB color.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub struct Color(u32);
@@ -527,13 +618,26 @@ impl Timestamp {
     pub fn to_rfc2822(&self) -> String {
         chrono::Utc.timestamp(self.as_secs() as i64, 0).to_rfc2822()
     }
+
+    /// Format the timestamp in the local time zone, with a `strftime`-style format.
+    /// Returns `None` if the format is invalid.
+    pub fn format(&self, fmt: &str) -> Option<String> {
+        let items = chrono::format::StrftimeItems::new(fmt);
+
+        if items.clone().any(|i| i == chrono::format::Item::Error) {
+            return None;
+        }
+        let time = chrono::Local.timestamp(self.as_secs() as i64, 0);
+
+        Some(time.format_with_items(items).to_string())
+    }
 }
 
 impl fmt::Display for Timestamp {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let fmt = timeago::Formatter::new();
         let now = Timestamp::now();
-        let duration = time::Duration::from_secs(now.seconds - self.seconds);
+        let duration = time::Duration::from_secs(now.seconds.saturating_sub(self.seconds));
 
         write!(f, "{}", fmt.convert(duration))
     }
@@ -621,6 +725,19 @@ impl<'a> FromValue<'a> for git::OneLevel {
     }
 }
 
+impl<'a> FromValue<'a> for usize {
+    fn from_value(val: Value<'a>) -> Result<usize, ValueError> {
+        if let Value::Scalar(scalar) = &val {
+            match scalar.borrow() {
+                ScalarValue::Uint(n) => return Ok(*n as usize),
+                ScalarValue::Int(n) if *n >= 0 => return Ok(*n as usize),
+                _ => {}
+            }
+        }
+        Err(ValueError::InvalidValue(val.to_string()))
+    }
+}
+
 impl<'a> FromValue<'a> for String {
     fn from_value(val: Value) -> Result<String, ValueError> {
         val.into_string().map_err(|_| ValueError::InvalidType)
@@ -815,7 +932,7 @@ pub enum DocumentError {
 }
 
 pub mod lookup {
-    use super::{Author, Comment, HashMap, Reaction, Replies};
+    use super::{Author, Comment, FromStr, HashMap, Reaction, Replies};
     use super::{Document, DocumentError};
 
     pub fn comment(doc: Document, obj_id: &automerge::ObjId) -> Result<Comment<()>, DocumentError> {
@@ -825,7 +942,7 @@ pub mod lookup {
             .map(|urn| Author::new(urn, peer))?;
         let body = doc.val(&obj_id, "body")?;
         let timestamp = doc.val(&obj_id, "timestamp")?;
-        let reactions: HashMap<Reaction, usize> = doc.map(&obj_id, "reactions", |v| *v += 1)?;
+        let reactions = self::reactions(doc, obj_id)?;
 
         Ok(Comment {
             author,
@@ -836,6 +953,27 @@ pub mod lookup {
         })
     }
 
+    /// Get the reactions to a comment, with the number of authors of each reaction.
+    pub fn reactions(
+        doc: Document,
+        obj_id: &automerge::ObjId,
+    ) -> Result<HashMap<Reaction, usize>, DocumentError> {
+        let (_, reactions_id) = doc.get(&obj_id, "reactions")?;
+        let mut reactions = HashMap::new();
+
+        // Nb. Each reaction is a map of the authors who reacted.
+        for key in (*doc).keys(&reactions_id) {
+            let reaction = Reaction::from_str(&key).map_err(|_| DocumentError::Property)?;
+            let (_, authors_id) = doc.get(&reactions_id, key.as_str())?;
+            let count = (*doc).keys(&authors_id).count();
+
+            if count > 0 {
+                reactions.insert(reaction, count);
+            }
+        }
+        Ok(reactions)
+    }
+
     pub fn thread(
         doc: Document,
         obj_id: &automerge::ObjId,
@@ -883,4 +1021,19 @@ mod test {
         Color::from_str("#aa00").unwrap_err();
         Color::from_str("#abc").unwrap_err();
     }
+
+    #[test]
+    fn test_references() {
+        let refs = Reference::parse(
+            "Fixes #1f2e3d4c5b, see also #a1b2c3d4e5f6.\n\n# Heading\nCloses: #4d3c2b1a9f #ffccaa #12",
+        );
+
+        assert_eq!(refs.len(), 3);
+        assert_eq!(refs[0].id.to_string(), "1f2e3d4c5b");
+        assert!(refs[0].closes);
+        assert_eq!(refs[1].id.to_string(), "a1b2c3d4e5f6");
+        assert!(!refs[1].closes);
+        assert_eq!(refs[2].id.to_string(), "4d3c2b1a9f");
+        assert!(refs[2].closes);
+    }
 }
@@ -1,10 +1,14 @@
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::convert::TryInto;
+use std::env;
+use std::fmt;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+use anyhow::anyhow;
 use librad::profile::Profile;
 use librad::PeerId;
 use serde::{Deserialize, Serialize};
@@ -34,6 +38,12 @@ pub const DEFAULT_SEEDS: &[(&str, &str)] = &[
 pub const FILE_NAME_LOCAL: &str = "Radicle.toml";
 /// Configuration file name for the profile scope.
 pub const FILE_NAME_PROFILE: &str = "config.toml";
+/// Configuration directory for the user scope, under the user's configuration directory.
+pub const USER_CONFIG_DIR: &str = "radicle";
+/// Git configuration section for settings of the working copy, eg. `rad.seed`.
+pub const GIT_CONFIG_SECTION: &str = "rad";
+/// Table of named seeds in the user configuration file, as managed by `rad seed`.
+pub const SEEDS_TABLE: &str = "seeds";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SeedConfig {
@@ -58,9 +68,43 @@ impl TryFrom<SeedConfig> for Seed<String> {
     }
 }
 
+/// Terminal output settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TerminalConfig {
+    /// Whether to output emoji and unicode glyphs. If `false`, ASCII equivalents are used.
+    #[serde(default = "TerminalConfig::default_emoji")]
+    pub emoji: bool,
+    /// Whether to output simple status lines instead of spinners and other animated
+    /// output, eg. for screen readers.
+    #[serde(default)]
+    pub plain: bool,
+    /// How to format timestamps: `relative` (the default), `absolute`, or a custom
+    /// `strftime`-style format, eg. `%d.%m.%Y %H:%M`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamps: Option<String>,
+}
+
+impl TerminalConfig {
+    fn default_emoji() -> bool {
+        true
+    }
+}
+
+impl Default for TerminalConfig {
+    fn default() -> Self {
+        Self {
+            emoji: Self::default_emoji(),
+            plain: false,
+            timestamps: None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Config {
     pub seed: Vec<SeedConfig>,
+    #[serde(default)]
+    pub terminal: TerminalConfig,
 }
 
 impl Default for Config {
@@ -93,6 +137,7 @@ impl Default for Config {
                     }
                 })
                 .collect(),
+            terminal: TerminalConfig::default(),
         }
     }
 }
@@ -109,6 +154,14 @@ impl Config {
         Self::local().or_else(|_| Self::profile(profile))
     }
 
+    /// Load the configuration of the working copy, or else of the default profile, if any.
+    pub fn current() -> Option<Self> {
+        Self::local().ok().or_else(|| {
+            let profile = crate::profile::default().ok()?;
+            Self::profile(&profile).ok()
+        })
+    }
+
     pub fn local() -> Result<Self, io::Error> {
         Self::read(Path::new(FILE_NAME_LOCAL))
     }
@@ -146,3 +199,586 @@ impl Config {
         self.seed.iter()
     }
 }
+
+/// Type of a setting's value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyType {
+    /// `true` or `false`.
+    Bool,
+    /// A seed address, eg. `<peer-id>@<host>:<port>`.
+    Seed,
+    /// A file system path.
+    Path,
+    /// A non-negative integer.
+    Number,
+    /// Any string.
+    String,
+}
+
+/// A user setting, as read and written with `rad config`.
+#[derive(Debug)]
+pub struct Key {
+    /// Name of the setting, eg. `terminal.emoji`.
+    pub name: &'static str,
+    /// Environment variable that overrides the setting, if any.
+    pub env: Option<&'static str>,
+    /// Type of the setting's value.
+    pub ty: KeyType,
+    /// Value used when the setting isn't set, if any.
+    pub default: Option<&'static str>,
+    /// Short description of the setting.
+    pub description: &'static str,
+}
+
+/// Known user settings.
+pub const KEYS: &[Key] = &[
+    Key {
+        name: "seed",
+        env: Some(crate::env::RAD_SEED),
+        ty: KeyType::Seed,
+        default: None,
+        description: "Default seed, used instead of the profile's seeds",
+    },
+    Key {
+        name: "clone.directory",
+        env: Some("RAD_CLONE_DIR"),
+        ty: KeyType::Path,
+        default: None,
+        description: "Directory under which projects are cloned",
+    },
+    Key {
+        name: "terminal.color",
+        env: None,
+        ty: KeyType::Bool,
+        default: Some("true"),
+        description: "Whether to output colors",
+    },
+    Key {
+        name: "terminal.emoji",
+        env: None,
+        ty: KeyType::Bool,
+        default: Some("true"),
+        description: "Whether to output emoji and unicode glyphs",
+    },
+    Key {
+        name: "terminal.plain",
+        env: None,
+        ty: KeyType::Bool,
+        default: Some("false"),
+        description: "Whether to output simple status lines instead of animations",
+    },
+    Key {
+        name: "terminal.timestamps",
+        env: None,
+        ty: KeyType::String,
+        default: Some("relative"),
+        description: "Timestamp format: `relative`, `absolute` or a `strftime` format",
+    },
+    Key {
+        name: "terminal.pager",
+        env: Some("RAD_PAGER"),
+        ty: KeyType::String,
+        default: None,
+        description: "Pager for long output, eg. `less -R`",
+    },
+    Key {
+        name: "network.offline",
+        env: Some(crate::env::RAD_OFFLINE),
+        ty: KeyType::Bool,
+        default: Some("false"),
+        description: "Whether to fail right away instead of using the network",
+    },
+    Key {
+        name: "network.proxy",
+        env: None,
+        ty: KeyType::String,
+        default: None,
+        description: "Proxy for HTTP connections to seeds, eg. `socks5h://localhost:9050`",
+    },
+    Key {
+        name: "mirror.remote",
+        env: None,
+        ty: KeyType::String,
+        default: None,
+        description: "Git remote or URL that `rad push --mirror-external` mirrors to",
+    },
+    Key {
+        name: "sync.retries",
+        env: None,
+        ty: KeyType::Number,
+        default: Some("2"),
+        description: "How many times to retry syncing with a seed after a network failure",
+    },
+    Key {
+        name: "sync.backoff",
+        env: None,
+        ty: KeyType::Number,
+        default: Some("1"),
+        description: "Seconds to wait before the first sync retry, doubled on every retry",
+    },
+    Key {
+        name: "web.host",
+        env: None,
+        ty: KeyType::String,
+        default: Some("app.radicle.network"),
+        description: "Host of the web client that `rad web` opens projects in",
+    },
+];
+
+impl Key {
+    /// Look up a known setting by name.
+    pub fn find(name: &str) -> anyhow::Result<&'static Key> {
+        KEYS.iter().find(|k| k.name == name).ok_or_else(|| {
+            let known = KEYS.iter().map(|k| k.name).collect::<Vec<_>>().join(", ");
+            anyhow!("unknown setting '{}', expected one of: {}", name, known)
+        })
+    }
+
+    /// Check that a value is valid for this setting.
+    pub fn validate(&self, value: &str) -> anyhow::Result<()> {
+        match self.ty {
+            KeyType::Bool => {
+                value.parse::<bool>().map_err(|_| {
+                    anyhow!(
+                        "invalid value '{}' for {}, expected 'true' or 'false'",
+                        value,
+                        self.name
+                    )
+                })?;
+            }
+            KeyType::Seed => {
+                Seed::<String>::from_str(value).map_err(|_| {
+                    anyhow!(
+                        "invalid value '{}' for {}, expected a seed address of the form <peer-id>@<host>:<port>",
+                        value,
+                        self.name
+                    )
+                })?;
+            }
+            KeyType::Number => {
+                value.parse::<u64>().map_err(|_| {
+                    anyhow!(
+                        "invalid value '{}' for {}, expected a non-negative integer",
+                        value,
+                        self.name
+                    )
+                })?;
+            }
+            KeyType::Path | KeyType::String => {
+                if value.is_empty() {
+                    anyhow::bail!("value for {} must not be empty", self.name);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where the value of a setting comes from, from highest to lowest precedence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// An environment variable.
+    Env(&'static str),
+    /// The git configuration of the working copy, under the `rad` section.
+    Git,
+    /// The user configuration file.
+    User(PathBuf),
+    /// The working copy's `Radicle.toml`, or the profile configuration.
+    Config,
+    /// The setting's default value.
+    Default,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Env(var) => write!(f, "env: {}", var),
+            Self::Git => write!(f, "git config"),
+            Self::User(path) => write!(f, "{}", path.display()),
+            Self::Config => write!(f, "radicle config"),
+            Self::Default => write!(f, "default"),
+        }
+    }
+}
+
+/// The value of a setting, and where it comes from.
+#[derive(Debug, Clone)]
+pub struct Setting {
+    pub key: &'static Key,
+    pub value: String,
+    pub source: Source,
+}
+
+/// Get the path of the user configuration file. This is `radicle/config.toml` under
+/// `$XDG_CONFIG_HOME`, or else under `~/.config`.
+pub fn user_path() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .map(|dir| dir.join(USER_CONFIG_DIR).join(FILE_NAME_PROFILE))
+}
+
+/// Read the user configuration file. Returns an empty table if it doesn't exist.
+fn read_user(path: &Path) -> anyhow::Result<toml::value::Table> {
+    match fs::read_to_string(path) {
+        Ok(content) => Ok(toml::from_str(&content)?),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(toml::value::Table::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Look up a dotted setting name in a table.
+fn lookup(table: &toml::value::Table, name: &str) -> Option<String> {
+    let mut parts = name.split('.');
+    let mut value = table.get(parts.next()?)?;
+
+    for part in parts {
+        value = value.as_table()?.get(part)?;
+    }
+    match value {
+        toml::Value::String(s) => Some(s.clone()),
+        toml::Value::Table(_) | toml::Value::Array(_) => None,
+        other => Some(other.to_string()),
+    }
+}
+
+/// Look up a setting in the git configuration of the working copy, if any.
+fn lookup_git(name: &str) -> Option<String> {
+    let repo = git2::Repository::open_from_env().ok()?;
+    let config = repo
+        .config()
+        .ok()?
+        .open_level(git2::ConfigLevel::Local)
+        .ok()?;
+
+    config
+        .get_string(&format!("{}.{}", GIT_CONFIG_SECTION, name))
+        .ok()
+}
+
+/// Get the value of a setting. Settings are looked up, in order, in the setting's
+/// environment variable, the git configuration of the working copy, the user
+/// configuration file, and the `Radicle.toml` or profile configuration. If none of
+/// these set it, its default value is returned, if any.
+pub fn get(name: &str) -> anyhow::Result<Option<Setting>> {
+    let key = Key::find(name)?;
+    let setting = |value: String, source: Source| Ok(Some(Setting { key, value, source }));
+
+    if let Some(var) = key.env {
+        if let Some(value) = crate::env::var(var) {
+            return setting(value, Source::Env(var));
+        }
+    }
+    if let Some(value) = lookup_git(name) {
+        return setting(value, Source::Git);
+    }
+    if let Some(path) = user_path() {
+        if let Some(value) = lookup(&read_user(&path)?, name) {
+            return setting(value, Source::User(path));
+        }
+    }
+    if let Some(toml::Value::Table(table)) =
+        Config::current().and_then(|c| toml::Value::try_from(c).ok())
+    {
+        if let Some(value) = lookup(&table, name) {
+            return setting(value, Source::Config);
+        }
+    }
+    if let Some(value) = key.default {
+        return setting(value.to_owned(), Source::Default);
+    }
+    Ok(None)
+}
+
+/// Get the value of a setting from the git configuration of the working copy only, for
+/// settings that only make sense per project.
+pub fn get_project(name: &str) -> anyhow::Result<Option<Setting>> {
+    let key = Key::find(name)?;
+
+    Ok(lookup_git(name).map(|value| Setting {
+        key,
+        value,
+        source: Source::Git,
+    }))
+}
+
+/// Get the value of a boolean setting. Invalid values are ignored.
+pub fn get_bool(name: &str) -> Option<bool> {
+    get(name).ok()??.value.parse().ok()
+}
+
+/// Get the value of a number setting. Invalid values are ignored.
+pub fn get_number(name: &str) -> Option<u64> {
+    get(name).ok()??.value.parse().ok()
+}
+
+/// Get the value of a path setting. A leading `~` is expanded to the home directory.
+pub fn get_path(name: &str) -> anyhow::Result<Option<PathBuf>> {
+    let setting = match get(name)? {
+        Some(setting) => setting,
+        None => return Ok(None),
+    };
+    let path = match (setting.value.strip_prefix('~'), env::var_os("HOME")) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            PathBuf::from(home).join(rest.trim_start_matches('/'))
+        }
+        _ => PathBuf::from(setting.value),
+    };
+    Ok(Some(path))
+}
+
+/// Set a setting in the user configuration file. Returns the path of the file.
+pub fn set(name: &str, value: &str) -> anyhow::Result<PathBuf> {
+    let key = Key::find(name)?;
+    key.validate(value)?;
+
+    let path = user_path().ok_or_else(|| anyhow!("couldn't find user configuration directory"))?;
+    let mut config = read_user(&path)?;
+    let value = match key.ty {
+        KeyType::Bool => toml::Value::Boolean(value.parse()?),
+        KeyType::Number => toml::Value::Integer(value.parse()?),
+        _ => toml::Value::String(value.to_owned()),
+    };
+
+    let mut parts = name.split('.').collect::<Vec<_>>();
+    let last = parts.pop().unwrap_or(name);
+    let mut table = &mut config;
+    for part in parts {
+        table = table
+            .entry(part)
+            .or_insert_with(|| toml::Value::Table(toml::value::Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| anyhow!("invalid user configuration: '{}' is not a table", part))?;
+    }
+    table.insert(last.to_owned(), value);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, toml::to_string_pretty(&config)?)?;
+
+    Ok(path)
+}
+
+/// Remove a setting from the user configuration file. Returns whether it was set.
+pub fn unset(name: &str) -> anyhow::Result<bool> {
+    Key::find(name)?;
+
+    let path = match user_path() {
+        Some(path) if path.exists() => path,
+        _ => return Ok(false),
+    };
+    let mut config = read_user(&path)?;
+    let mut parts = name.split('.').collect::<Vec<_>>();
+    let last = parts.pop().unwrap_or(name);
+    let mut table = Some(&mut config);
+    for part in parts {
+        table = table
+            .and_then(|t| t.get_mut(part))
+            .and_then(|v| v.as_table_mut());
+    }
+    let removed = table.and_then(|t| t.remove(last)).is_some();
+
+    if removed {
+        fs::write(&path, toml::to_string_pretty(&config)?)?;
+    }
+    Ok(removed)
+}
+
+/// Open the local git configuration of the working copy.
+fn open_git() -> anyhow::Result<(PathBuf, git2::Config)> {
+    let repo = git2::Repository::open_from_env()
+        .map_err(|_| anyhow!("the current working directory is not a git repository"))?;
+    let config = repo.config()?.open_level(git2::ConfigLevel::Local)?;
+
+    Ok((repo.path().join("config"), config))
+}
+
+/// Set a setting in the git configuration of the working copy, under the `rad`
+/// section, so that it only applies to that project. Returns the path of the file.
+pub fn set_project(name: &str, value: &str) -> anyhow::Result<PathBuf> {
+    let key = Key::find(name)?;
+    key.validate(value)?;
+
+    let (path, mut config) = open_git()?;
+    config.set_str(&format!("{}.{}", GIT_CONFIG_SECTION, name), value)?;
+
+    Ok(path)
+}
+
+/// Remove a setting from the git configuration of the working copy. Returns whether
+/// it was set.
+pub fn unset_project(name: &str) -> anyhow::Result<bool> {
+    Key::find(name)?;
+
+    let (_, mut config) = open_git()?;
+    match config.remove(&format!("{}.{}", GIT_CONFIG_SECTION, name)) {
+        Ok(()) => Ok(true),
+        Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Get the named seeds of the user configuration file. Each seed is labeled with its
+/// name.
+pub fn seeds() -> anyhow::Result<BTreeMap<String, Seed<String>>> {
+    let path = match user_path() {
+        Some(path) => path,
+        None => return Ok(BTreeMap::new()),
+    };
+    named_seeds(&read_user(&path)?)
+}
+
+/// Add a named seed to the user configuration file, replacing any seed with the same
+/// name. Returns the path of the file.
+pub fn add_seed(name: &str, seed: &Seed<String>) -> anyhow::Result<PathBuf> {
+    let path = user_path().ok_or_else(|| anyhow!("couldn't find user configuration directory"))?;
+    let mut config = read_user(&path)?;
+    let seed = Seed {
+        label: None,
+        ..seed.clone()
+    };
+
+    config
+        .entry(SEEDS_TABLE)
+        .or_insert_with(|| toml::Value::Table(toml::value::Table::new()))
+        .as_table_mut()
+        .ok_or_else(|| {
+            anyhow!(
+                "invalid user configuration: '{}' is not a table",
+                SEEDS_TABLE
+            )
+        })?
+        .insert(name.to_owned(), toml::Value::String(seed.to_string()));
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, toml::to_string_pretty(&config)?)?;
+
+    Ok(path)
+}
+
+/// Remove a named seed from the user configuration file. Returns the seed, if it was
+/// there.
+pub fn remove_seed(name: &str) -> anyhow::Result<Option<Seed<String>>> {
+    let path = match user_path() {
+        Some(path) if path.exists() => path,
+        _ => return Ok(None),
+    };
+    let mut config = read_user(&path)?;
+    let seed = named_seeds(&config)?.remove(name);
+
+    if seed.is_some() {
+        if let Some(table) = config.get_mut(SEEDS_TABLE).and_then(|t| t.as_table_mut()) {
+            table.remove(name);
+        }
+        fs::write(&path, toml::to_string_pretty(&config)?)?;
+    }
+    Ok(seed)
+}
+
+/// Parse the named seeds of a configuration table.
+fn named_seeds(config: &toml::value::Table) -> anyhow::Result<BTreeMap<String, Seed<String>>> {
+    let table = match config.get(SEEDS_TABLE) {
+        Some(toml::Value::Table(table)) => table,
+        Some(_) => anyhow::bail!(
+            "invalid user configuration: '{}' is not a table",
+            SEEDS_TABLE
+        ),
+        None => return Ok(BTreeMap::new()),
+    };
+    let mut seeds = BTreeMap::new();
+
+    for (name, value) in table {
+        let seed = value
+            .as_str()
+            .and_then(|v| Seed::<String>::from_str(v).ok())
+            .ok_or_else(|| {
+                anyhow!(
+                    "invalid address for seed '{}' in user configuration: {}",
+                    name,
+                    value
+                )
+            })?;
+        seeds.insert(
+            name.clone(),
+            Seed {
+                label: Some(name.clone()),
+                ..seed
+            },
+        );
+    }
+    Ok(seeds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let table: toml::value::Table = toml::from_str(
+            r#"
+            seed = "hyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa@pine.radicle.garden:8776"
+
+            [terminal]
+            emoji = false
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            lookup(&table, "terminal.emoji"),
+            Some(String::from("false"))
+        );
+        assert_eq!(lookup(&table, "terminal.plain"), None);
+        assert_eq!(lookup(&table, "terminal"), None);
+        assert!(lookup(&table, "seed")
+            .unwrap()
+            .ends_with("pine.radicle.garden:8776"));
+    }
+
+    #[test]
+    fn test_named_seeds() {
+        let table: toml::value::Table = toml::from_str(
+            r#"
+            [seeds]
+            pine = "hyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa@pine.radicle.garden:8776"
+            "#,
+        )
+        .unwrap();
+        let seeds = named_seeds(&table).unwrap();
+        let seed = &seeds["pine"];
+
+        assert_eq!(seed.label.as_deref(), Some("pine"));
+        assert_eq!(seed.addrs, "pine.radicle.garden:8776");
+        assert!(named_seeds(&toml::value::Table::new()).unwrap().is_empty());
+
+        let table: toml::value::Table = toml::from_str(
+            r#"
+            [seeds]
+            pine = "pine.radicle.garden"
+            "#,
+        )
+        .unwrap();
+        assert!(named_seeds(&table).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let key = Key::find("terminal.emoji").unwrap();
+
+        assert!(key.validate("false").is_ok());
+        assert!(key.validate("no").is_err());
+        assert!(Key::find("terminal.emojis").is_err());
+
+        let key = Key::find("sync.retries").unwrap();
+
+        assert!(key.validate("3").is_ok());
+        assert!(key.validate("-1").is_err());
+        assert!(key.validate("three").is_err());
+    }
+}
@@ -0,0 +1,473 @@
+//! A long-running daemon, exposing radicle operations to editors and other apps.
+//!
+//! The daemon keeps the profile's storage open, and answers [JSON-RPC 2.0] requests
+//! on its Unix socket, one JSON object per line, so that clients can integrate without
+//! running `rad` for every operation:
+//!
+//! ```text
+//! > {"jsonrpc":"2.0","id":1,"method":"issues.list","params":{"project":"rad:git:..."}}
+//! < {"jsonrpc":"2.0","id":1,"result":[{"id":"...","title":"...",...}]}
+//! ```
+//!
+//! The following methods are supported:
+//!
+//! * `projects.list`: list the projects in local storage.
+//! * `projects.sync`: sync a `project` with seeds. Optionally takes a `mode`, one of
+//!   `fetch`, `push` or `all`, and a `seed` to sync with instead of the configured ones.
+//! * `issues.list`: list the issues of a `project`, optionally filtered by `state`.
+//! * `patches.create`: propose the `head` commit as a patch to a `project`, with the
+//!   given `title` and `description`. The `base` commit is found from the project's
+//!   tracked peers, unless given.
+//! * `daemon.stop`: stop the daemon.
+//!
+//! Requests without an `id` are notifications, and aren't answered.
+//!
+//! [JSON-RPC 2.0]: https://www.jsonrpc.org/specification
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::{env, fs, io, thread};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use librad::crypto::BoxedSigner;
+use librad::git::Urn;
+use librad::profile::Profile;
+
+use crate::client::{Client, NewPatch, Object};
+use crate::cobs::issue::Issue;
+use crate::cobs::StateFilter;
+use crate::hooks::SeedResult;
+use crate::nonempty::NonEmpty;
+use crate::sync::{Mode, Seed};
+use crate::{git, keys, project};
+
+/// Environment variable that sets the daemon's socket path.
+pub const RAD_DAEMON_SOCK: &str = "RAD_DAEMON_SOCK";
+
+/// File name of the daemon's socket, in the profile directory.
+pub const SOCKET_FILE: &str = "daemon.sock";
+
+/// Version of the JSON-RPC protocol.
+pub const VERSION: &str = "2.0";
+
+/// A request to the daemon.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Request {
+    pub jsonrpc: String,
+    /// Request id, echoed in the response. Absent for notifications.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Value>,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub params: Value,
+}
+
+/// A response from the daemon. Holds either a result or an error.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Response {
+    pub jsonrpc: String,
+    pub id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<Error>,
+}
+
+impl Response {
+    fn new(id: Value, result: Result<Value, Error>) -> Self {
+        let (result, error) = match result {
+            Ok(result) => (Some(result), None),
+            Err(err) => (None, Some(err)),
+        };
+
+        Self {
+            jsonrpc: VERSION.to_owned(),
+            id,
+            result,
+            error,
+        }
+    }
+}
+
+/// An error answered by the daemon.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+#[error("{message}")]
+pub struct Error {
+    pub code: i64,
+    pub message: String,
+}
+
+impl Error {
+    /// The request isn't valid JSON.
+    pub const PARSE_ERROR: i64 = -32700;
+    /// The request isn't a valid JSON-RPC request.
+    pub const INVALID_REQUEST: i64 = -32600;
+    /// The requested method doesn't exist.
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    /// The request parameters are invalid.
+    pub const INVALID_PARAMS: i64 = -32602;
+    /// The operation failed.
+    pub const OPERATION_FAILED: i64 = -32000;
+
+    pub fn new(code: i64, message: impl ToString) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(err: anyhow::Error) -> Self {
+        Self::new(Self::OPERATION_FAILED, err)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SyncParams {
+    #[serde(deserialize_with = "project::deserialize_urn")]
+    project: Urn,
+    #[serde(default)]
+    mode: Option<String>,
+    #[serde(default)]
+    seed: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IssuesParams {
+    #[serde(deserialize_with = "project::deserialize_urn")]
+    project: Urn,
+    #[serde(default)]
+    state: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreatePatchParams {
+    #[serde(deserialize_with = "project::deserialize_urn")]
+    project: Urn,
+    title: String,
+    #[serde(default)]
+    description: String,
+    head: String,
+    #[serde(default)]
+    base: Option<String>,
+}
+
+/// Get the daemon's socket path for a profile. Can be overridden with [`RAD_DAEMON_SOCK`].
+pub fn socket_path(profile: &Profile) -> PathBuf {
+    // Nb. As for hooks, the profile directory is found from one of its files.
+    env::var_os(RAD_DAEMON_SOCK)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| profile.paths().seeds_file().with_file_name(SOCKET_FILE))
+}
+
+/// The state of a running daemon, shared by its connections.
+pub struct Daemon {
+    client: Client,
+}
+
+impl Daemon {
+    /// Open the storage of a profile, for the daemon to serve.
+    pub fn new(profile: Profile, signer: BoxedSigner) -> anyhow::Result<Self> {
+        Ok(Self {
+            client: Client::new(profile, signer)?,
+        })
+    }
+
+    /// Handle a request, and return its result.
+    pub fn handle(&self, method: &str, params: Value) -> Result<Value, Error> {
+        match method {
+            "projects.list" => to_value(self.client.projects()?),
+            "projects.sync" => to_value(self.sync(self::params(params)?)?),
+            "issues.list" => to_value(self.issues(self::params(params)?)?),
+            "patches.create" => to_value(self.create_patch(self::params(params)?)?),
+            _ => Err(Error::new(
+                Error::METHOD_NOT_FOUND,
+                format!("unknown method '{}'", method),
+            )),
+        }
+    }
+
+    fn sync(&self, params: SyncParams) -> Result<Vec<SeedResult>, Error> {
+        let mode = match params.mode.as_deref() {
+            None | Some("all") => Mode::default(),
+            Some("fetch") => Mode::Fetch,
+            Some("push") => Mode::Push,
+            Some(other) => return Err(invalid_params(format!("invalid mode '{}'", other))),
+        };
+        let seeds = match params.seed {
+            Some(seed) => NonEmpty::new(
+                Seed::from_str(&seed)
+                    .map_err(|_| invalid_params(format!("invalid seed address '{}'", seed)))?,
+            ),
+            None => self.client.seeds()?,
+        };
+
+        Ok(self.client.sync(&params.project, mode, seeds)?)
+    }
+
+    fn issues(&self, params: IssuesParams) -> Result<Vec<Object<Issue>>, Error> {
+        let filter = match params.state.as_deref() {
+            Some(state) => StateFilter::from_str(state).map_err(invalid_params)?,
+            None => StateFilter::default(),
+        };
+
+        Ok(self.client.issues(&params.project, filter)?)
+    }
+
+    fn create_patch(&self, params: CreatePatchParams) -> Result<Object<()>, Error> {
+        if params.title.trim().is_empty() {
+            return Err(invalid_params("a title must be given"));
+        }
+        let head = git::Oid::from_str(&params.head)
+            .map_err(|_| invalid_params(format!("invalid head '{}'", params.head)))?;
+        let base = params
+            .base
+            .as_deref()
+            .map(|base| {
+                git::Oid::from_str(base)
+                    .map_err(|_| invalid_params(format!("invalid base '{}'", base)))
+            })
+            .transpose()?;
+        let id = self.client.create_patch(
+            &params.project,
+            NewPatch {
+                title: params.title,
+                description: params.description,
+                head,
+                base,
+            },
+        )?;
+
+        Ok(Object {
+            id: id.to_string(),
+            object: (),
+        })
+    }
+}
+
+fn params<T: DeserializeOwned>(params: Value) -> Result<T, Error> {
+    // Nb. Methods whose parameters are all optional may be called without any.
+    let params = if params.is_null() {
+        Value::Object(Default::default())
+    } else {
+        params
+    };
+    serde_json::from_value(params).map_err(invalid_params)
+}
+
+fn invalid_params(err: impl ToString) -> Error {
+    Error::new(Error::INVALID_PARAMS, err)
+}
+
+fn to_value(result: impl Serialize) -> Result<Value, Error> {
+    serde_json::to_value(result).map_err(|err| anyhow::Error::from(err).into())
+}
+
+/// Run the daemon on the given socket path until it is stopped. Connections are
+/// served concurrently. The socket is only accessible by the current user.
+pub fn serve(path: &Path, daemon: Daemon) -> io::Result<()> {
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("a daemon is already running on {}", path.display()),
+            ));
+        }
+        // Nb. A previous daemon didn't shut down cleanly.
+        fs::remove_file(path)?;
+    }
+    let listener = keys::agent::bind(path)?;
+
+    let daemon = Arc::new(daemon);
+    let stopped = Arc::new(AtomicBool::new(false));
+
+    for stream in listener.incoming() {
+        if stopped.load(Ordering::SeqCst) {
+            break;
+        }
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                log::warn!("Daemon connection failed: {}", err);
+                continue;
+            }
+        };
+        let daemon = daemon.clone();
+        let stopped = stopped.clone();
+        let path = path.to_owned();
+
+        thread::spawn(move || match handle(stream, &daemon) {
+            Ok(true) => {
+                stopped.store(true, Ordering::SeqCst);
+                // Nb. Wake up the listener, so that it notices it was stopped.
+                UnixStream::connect(&path).ok();
+            }
+            Ok(false) => {}
+            Err(err) => log::warn!("Daemon connection error: {}", err),
+        });
+    }
+    fs::remove_file(path)?;
+
+    Ok(())
+}
+
+/// Handle requests on a connection. Returns `true` if the daemon should stop.
+fn handle(stream: UnixStream, daemon: &Daemon) -> io::Result<bool> {
+    let mut writer = stream.try_clone()?;
+
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value: Value = match serde_json::from_str(&line) {
+            Ok(value) => value,
+            Err(err) => {
+                let err = Error::new(Error::PARSE_ERROR, format!("invalid JSON: {}", err));
+                send(&mut writer, &Response::new(Value::Null, Err(err)))?;
+
+                continue;
+            }
+        };
+        let request: Request = match serde_json::from_value(value.clone()) {
+            Ok(request) => request,
+            Err(err) => {
+                let id = value.get("id").cloned().unwrap_or(Value::Null);
+                let err = Error::new(Error::INVALID_REQUEST, format!("invalid request: {}", err));
+                send(&mut writer, &Response::new(id, Err(err)))?;
+
+                continue;
+            }
+        };
+        if request.method == "daemon.stop" {
+            if let Some(id) = request.id {
+                send(&mut writer, &Response::new(id, Ok(Value::Null)))?;
+            }
+            return Ok(true);
+        }
+        let result = if request.jsonrpc == VERSION {
+            daemon.handle(&request.method, request.params)
+        } else {
+            Err(Error::new(
+                Error::INVALID_REQUEST,
+                format!("unsupported JSON-RPC version '{}'", request.jsonrpc),
+            ))
+        };
+        if let Some(id) = request.id {
+            send(&mut writer, &Response::new(id, result))?;
+        }
+    }
+    Ok(false)
+}
+
+fn send(writer: &mut impl Write, response: &Response) -> io::Result<()> {
+    let mut line = serde_json::to_vec(response)?;
+    line.push(b'\n');
+
+    writer.write_all(&line)
+}
+
+/// Call a method of the daemon running on the given socket path, and return its result.
+pub fn call(path: &Path, method: &str, params: Value) -> io::Result<Value> {
+    let mut stream = UnixStream::connect(path)?;
+    let request = Request {
+        jsonrpc: VERSION.to_owned(),
+        id: Some(Value::from(1)),
+        method: method.to_owned(),
+        params,
+    };
+    let mut line = serde_json::to_vec(&request)?;
+    line.push(b'\n');
+    stream.write_all(&line)?;
+
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response)?;
+
+    match serde_json::from_str(&response)? {
+        Response {
+            error: Some(err), ..
+        } => Err(io::Error::new(io::ErrorKind::Other, err)),
+        Response { result, .. } => Ok(result.unwrap_or_default()),
+    }
+}
+
+/// Check whether a daemon is running on the given socket path.
+pub fn is_running(path: &Path) -> bool {
+    UnixStream::connect(path).is_ok()
+}
+
+/// Stop the daemon running on the given socket path.
+pub fn stop(path: &Path) -> io::Result<()> {
+    call(path, "daemon.stop", Value::Null).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test;
+
+    use std::time;
+
+    use librad::crypto::keystore::crypto::{Pwhash, KDF_PARAMS_TEST};
+    use librad::crypto::keystore::pinentry::SecUtf8;
+    use serde_json::json;
+
+    #[test]
+    fn test_protocol_encoding() {
+        let req: Request =
+            serde_json::from_str(r#"{"jsonrpc":"2.0","method":"projects.list"}"#).unwrap();
+        assert!(req.id.is_none());
+        assert!(req.params.is_null());
+
+        let res = Response::new(json!(7), Err(Error::new(Error::METHOD_NOT_FOUND, "nope")));
+        assert_eq!(
+            serde_json::to_string(&res).unwrap(),
+            r#"{"jsonrpc":"2.0","id":7,"error":{"code":-32601,"message":"nope"}}"#
+        );
+    }
+
+    #[test]
+    fn test_serve() {
+        let (_, profile, _, project) = test::setup::profile();
+        let pass = Pwhash::new(SecUtf8::from(test::USER_PASS), *KDF_PARAMS_TEST);
+        let signer = test::signer(&profile, pass).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daemon.sock");
+        let daemon = Daemon::new(profile, signer).unwrap();
+        let server = {
+            let path = path.clone();
+            thread::spawn(move || serve(&path, daemon))
+        };
+        while !is_running(&path) {
+            thread::sleep(time::Duration::from_millis(10));
+        }
+        let urn = project.urn().to_string();
+
+        let projects = call(&path, "projects.list", Value::Null).unwrap();
+        assert!(projects
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|p| p["urn"] == urn && p["name"] == "nakamoto"));
+
+        let issues = call(&path, "issues.list", json!({ "project": urn })).unwrap();
+        assert_eq!(issues, json!([]));
+
+        let err = call(&path, "issues.list", json!({ "state": "open" })).unwrap_err();
+        assert!(err.to_string().contains("project"));
+
+        let err = call(&path, "frobnicate", Value::Null).unwrap_err();
+        assert_eq!(err.to_string(), "unknown method 'frobnicate'");
+
+        stop(&path).unwrap();
+        server.join().unwrap().unwrap();
+        assert!(!path.exists());
+    }
+}
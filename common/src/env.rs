@@ -0,0 +1,75 @@
+//! Environment variables that override defaults in every command.
+//!
+//! All `RAD_*` overrides are parsed here, so that commands behave the same way
+//! regardless of how they are invoked:
+//!
+//! * [`RAD_HOME`]: the radicle home directory, instead of the default location.
+//! * [`RAD_PROFILE`]: the ID of the profile to use, instead of the active profile.
+//! * [`RAD_SEED`]: the seed to sync with, instead of the configured seeds.
+//! * [`RAD_PASSPHRASE`]: the passphrase of the signing key, instead of prompting for it.
+//! * [`RAD_OFFLINE`]: enables offline mode, in which commands don't use the network.
+//!
+//! Variables that are set to an empty value are treated as unset.
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+
+use librad::crypto::keystore::pinentry::SecUtf8;
+use librad::profile::ProfileId;
+
+use crate::sync::Seed;
+
+/// Environment variable that sets the radicle home directory.
+pub const RAD_HOME: &str = "RAD_HOME";
+/// Environment variable that sets the profile to use.
+pub const RAD_PROFILE: &str = "RAD_PROFILE";
+/// Environment variable that sets the seed to use, eg. `<peer-id>@<host>:<port>`.
+pub const RAD_SEED: &str = "RAD_SEED";
+/// Environment variable that sets the passphrase of the signing key. Used by the
+/// git remote helper and non-interactive sessions when no key agent is running.
+pub const RAD_PASSPHRASE: &str = "RAD_PASSPHRASE";
+/// Environment variable that enables offline mode when set to `true`.
+pub const RAD_OFFLINE: &str = "RAD_OFFLINE";
+
+/// Get the value of an environment variable, if it is set and not empty.
+pub fn var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+/// Get the radicle home directory set with [`RAD_HOME`].
+pub fn home() -> Option<PathBuf> {
+    var(RAD_HOME).map(PathBuf::from)
+}
+
+/// Get the profile set with [`RAD_PROFILE`]. Fails if the variable isn't a valid
+/// profile ID.
+pub fn profile() -> Result<Option<ProfileId>> {
+    var(RAD_PROFILE)
+        .map(|id| {
+            ProfileId::from_str(&id)
+                .map_err(|_| anyhow!("invalid profile ID '{}' in `{}`", id, RAD_PROFILE))
+        })
+        .transpose()
+}
+
+/// Get the seed set with [`RAD_SEED`]. Fails if the variable isn't a valid seed
+/// address.
+pub fn seed() -> Result<Option<Seed<String>>> {
+    var(RAD_SEED)
+        .map(|addr| {
+            Seed::from_str(&addr).map_err(|_| {
+                anyhow!(
+                    "invalid seed address '{}' in `{}`, expected <peer-id>@<host>:<port>",
+                    addr,
+                    RAD_SEED
+                )
+            })
+        })
+        .transpose()
+}
+
+/// Get the passphrase set with [`RAD_PASSPHRASE`].
+pub fn passphrase() -> Option<SecUtf8> {
+    var(RAD_PASSPHRASE).map(SecUtf8::from)
+}
@@ -0,0 +1,219 @@
+//! Error taxonomy shared by all commands.
+//!
+//! Each error kind maps to a stable process exit code, so that scripts can tell
+//! failures apart without parsing error messages.
+use std::fmt;
+
+use crate::args;
+
+/// The kind of an error.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Kind {
+    /// An error that doesn't fit any other kind.
+    Other,
+    /// Invalid command-line usage.
+    Usage,
+    /// No usable profile, or the signing key is not available.
+    Auth,
+    /// Error reading from or writing to local storage.
+    Storage,
+    /// Error communicating with a seed or peer.
+    Network,
+    /// The requested object doesn't exist.
+    NotFound,
+    /// The operation conflicts with existing state.
+    Conflict,
+}
+
+impl Kind {
+    /// The process exit code for this kind of error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Other => 1,
+            Self::Usage => 2,
+            Self::Auth => 3,
+            Self::Storage => 4,
+            Self::Network => 5,
+            Self::NotFound => 6,
+            Self::Conflict => 7,
+        }
+    }
+
+    /// Machine-readable name of this kind of error.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Other => "other",
+            Self::Usage => "usage",
+            Self::Auth => "auth",
+            Self::Storage => "storage",
+            Self::Network => "network",
+            Self::NotFound => "not-found",
+            Self::Conflict => "conflict",
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// An error tagged with a [`Kind`].
+#[derive(Debug)]
+pub struct Error {
+    pub kind: Kind,
+    pub err: anyhow::Error,
+}
+
+impl Error {
+    pub fn new(kind: Kind, err: impl Into<anyhow::Error>) -> Self {
+        Self {
+            kind,
+            err: err.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.err)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.err.source()
+    }
+}
+
+/// Tag the error of a result with a [`Kind`].
+pub trait WithKind<T> {
+    fn with_kind(self, kind: Kind) -> anyhow::Result<T>;
+}
+
+impl<T, E: Into<anyhow::Error>> WithKind<T> for Result<T, E> {
+    fn with_kind(self, kind: Kind) -> anyhow::Result<T> {
+        self.map_err(|e| Error::new(kind, e).into())
+    }
+}
+
+/// Get the kind of an error. Errors tagged with a [`Kind`] take precedence, otherwise
+/// the kind is derived from well-known error types in the error chain.
+pub fn kind(err: &anyhow::Error) -> Kind {
+    for cause in err.chain() {
+        if let Some(e) = cause.downcast_ref::<Error>() {
+            return e.kind;
+        }
+        if let Some(e) = cause.downcast_ref::<args::Error>() {
+            match e {
+                args::Error::Help | args::Error::Usage => return Kind::Usage,
+                args::Error::WithHint { err, .. } => match kind(err) {
+                    Kind::Other => continue,
+                    k => return k,
+                },
+            }
+        }
+        if cause.downcast_ref::<lexopt::Error>().is_some() {
+            return Kind::Usage;
+        }
+        if let Some(e) = cause.downcast_ref::<git2::Error>() {
+            match e.code() {
+                git2::ErrorCode::NotFound => return Kind::NotFound,
+                git2::ErrorCode::Exists
+                | git2::ErrorCode::Conflict
+                | git2::ErrorCode::NotFastForward => return Kind::Conflict,
+                git2::ErrorCode::Auth => return Kind::Auth,
+                _ => {}
+            }
+            if let git2::ErrorClass::Net | git2::ErrorClass::Http | git2::ErrorClass::Ssh =
+                e.class()
+            {
+                return Kind::Network;
+            }
+        }
+        if cause.downcast_ref::<ureq::Error>().is_some() {
+            return Kind::Network;
+        }
+        if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            use std::io::ErrorKind;
+
+            match e.kind() {
+                ErrorKind::NotFound => return Kind::NotFound,
+                ErrorKind::AlreadyExists => return Kind::Conflict,
+                ErrorKind::ConnectionRefused
+                | ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::NotConnected
+                | ErrorKind::AddrNotAvailable
+                | ErrorKind::TimedOut => return Kind::Network,
+                _ => {}
+            }
+        }
+    }
+    Kind::Other
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_kind_tagged() {
+        let err: anyhow::Error = Error::new(Kind::NotFound, anyhow!("project not found")).into();
+
+        assert_eq!(kind(&err), Kind::NotFound);
+        assert_eq!(err.to_string(), "project not found");
+    }
+
+    #[test]
+    fn test_kind_with_context() {
+        let result: Result<(), _> = Err(anyhow!("connection closed"));
+        let err = result
+            .with_kind(Kind::Network)
+            .map_err(|e| e.context("sync failed"))
+            .unwrap_err();
+
+        assert_eq!(kind(&err), Kind::Network);
+    }
+
+    #[test]
+    fn test_kind_with_hint() {
+        let err: anyhow::Error = args::Error::WithHint {
+            err: Error::new(Kind::Auth, anyhow!("no profile")).into(),
+            hint: "run `rad auth`",
+        }
+        .into();
+
+        assert_eq!(kind(&err), Kind::Auth);
+        assert_eq!(kind(&args::Error::Usage.into()), Kind::Usage);
+    }
+
+    #[test]
+    fn test_kind_io() {
+        let err: anyhow::Error =
+            std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "refused").into();
+
+        assert_eq!(kind(&err), Kind::Network);
+        assert_eq!(kind(&anyhow!("something else")), Kind::Other);
+    }
+
+    #[test]
+    fn test_exit_codes_distinct() {
+        let kinds = [
+            Kind::Other,
+            Kind::Usage,
+            Kind::Auth,
+            Kind::Storage,
+            Kind::Network,
+            Kind::NotFound,
+            Kind::Conflict,
+        ];
+        let mut codes = kinds.iter().map(|k| k.exit_code()).collect::<Vec<_>>();
+        codes.sort_unstable();
+        codes.dedup();
+
+        assert_eq!(codes.len(), kinds.len());
+    }
+}
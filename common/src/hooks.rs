@@ -0,0 +1,348 @@
+//! Hook scripts, run around radicle operations.
+//!
+//! Hooks are executables in the `hooks` directory of the profile, named after the
+//! operation they run around, eg. `hooks/post-sync`. The context of the operation is
+//! written to their standard input as a JSON object, and the name of the hook is set
+//! in [`RAD_HOOK`]. This lets users wire up notifications, CI triggers or policies.
+//!
+//! A `pre-` hook that fails, ie. exits with a non-zero code, aborts its operation.
+//! Other hooks run after the fact, and their failure doesn't fail the operation.
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::anyhow;
+use serde::Serialize;
+
+use librad::git::storage::Storage;
+use librad::git::Urn;
+use librad::profile::Profile;
+use librad::PeerId;
+
+use crate::cobs::patch::PatchId;
+use crate::cobs::{self, Store};
+use crate::plugin;
+use crate::sync::{self, Mode, SyncResult};
+
+/// Directory of the hooks, under the profile directory.
+pub const HOOKS_DIR: &str = "hooks";
+/// Environment variable set to the name of the hook being run.
+pub const RAD_HOOK: &str = "RAD_HOOK";
+
+/// A hook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    /// Before branches are pushed with `rad push`. Fails the push if it fails.
+    PrePush,
+    /// After a project is synced with seeds.
+    PostSync,
+    /// After a project is cloned with `rad clone`.
+    PostClone,
+    /// When a new patch, or a new revision of a patch, is fetched from another peer.
+    PatchReceived,
+}
+
+impl Hook {
+    /// Name of the hook, which is also the file name of its executable.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::PrePush => "pre-push",
+            Self::PostSync => "post-sync",
+            Self::PostClone => "post-clone",
+            Self::PatchReceived => "patch-received",
+        }
+    }
+}
+
+impl fmt::Display for Hook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Context of the `pre-push` hook.
+#[derive(Debug, Serialize)]
+pub struct PrePush {
+    pub project: String,
+    /// Branches to push. Empty if only the current branch is pushed.
+    pub branches: Vec<String>,
+    /// Whether all branches are pushed.
+    pub all: bool,
+    pub force: bool,
+}
+
+/// Context of the `post-sync` hook.
+#[derive(Debug, Serialize)]
+pub struct PostSync {
+    pub project: String,
+    /// What was synced: `fetch`, `push` or `all`.
+    pub mode: &'static str,
+    pub seeds: Vec<SeedResult>,
+}
+
+/// Result of syncing with a seed.
+#[derive(Debug, Serialize)]
+pub struct SeedResult {
+    pub peer: String,
+    pub label: Option<String>,
+    /// `synced`, `failed` or `timed-out`.
+    pub status: &'static str,
+    /// Error, if the sync failed.
+    pub error: Option<String>,
+}
+
+impl PostSync {
+    /// Get the context of the hook from the results of syncing a project.
+    pub fn new<'a>(
+        project: &Urn,
+        mode: Mode,
+        results: impl IntoIterator<Item = &'a SyncResult>,
+    ) -> Self {
+        let mode = match (mode.is_fetch(), mode.is_push()) {
+            (true, true) => "all",
+            (true, false) => "fetch",
+            _ => "push",
+        };
+
+        Self {
+            project: project.to_string(),
+            mode,
+            seeds: results.into_iter().map(SeedResult::from).collect(),
+        }
+    }
+}
+
+impl From<&SyncResult> for SeedResult {
+    fn from(result: &SyncResult) -> Self {
+        let (status, error) = match result.status() {
+            sync::Status::Synced => ("synced", None),
+            sync::Status::Failed(err) => ("failed", Some(err)),
+            sync::Status::TimedOut => ("timed-out", None),
+        };
+
+        Self {
+            peer: result.seed.peer.to_string(),
+            label: result.seed.label.clone(),
+            status,
+            error,
+        }
+    }
+}
+
+/// Context of the `post-clone` hook.
+#[derive(Debug, Serialize)]
+pub struct PostClone {
+    pub project: String,
+    pub name: String,
+    /// Path of the working copy, if one was created.
+    pub path: Option<PathBuf>,
+}
+
+/// Context of the `patch-received` hook.
+#[derive(Debug, Serialize)]
+pub struct PatchReceived {
+    pub project: String,
+    pub id: String,
+    pub title: String,
+    /// URN of the patch author.
+    pub author: String,
+    /// Peer who published the revision.
+    pub peer: String,
+    /// Revision number, starting at zero.
+    pub revision: usize,
+    /// Commit of the revision.
+    pub oid: String,
+}
+
+/// Get the hooks directory of a profile.
+pub fn dir(profile: &Profile) -> PathBuf {
+    // Nb. As for the profile configuration, the profile directory is found from one of
+    // its files.
+    profile.paths().seeds_file().with_file_name(HOOKS_DIR)
+}
+
+/// Get the executable of a hook, if it is installed.
+pub fn find(profile: &Profile, hook: Hook) -> Option<PathBuf> {
+    let path = dir(profile).join(hook.name());
+
+    if plugin::is_executable(&path) {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// Run a hook, if it is installed, with the given context. Fails if the hook can't be
+/// run, or exits with a non-zero code.
+pub fn run<C: Serialize>(profile: &Profile, hook: Hook, context: &C) -> anyhow::Result<()> {
+    match find(profile, hook) {
+        Some(path) => exec(&path, profile, hook, context),
+        None => Ok(()),
+    }
+}
+
+fn exec<C: Serialize>(
+    path: &Path,
+    profile: &Profile,
+    hook: Hook,
+    context: &C,
+) -> anyhow::Result<()> {
+    let input = serde_json::to_vec(context)?;
+    let mut child = Command::new(path)
+        .env(RAD_HOOK, hook.name())
+        .env(crate::env::RAD_PROFILE, profile.id().to_string())
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|err| anyhow!("couldn't run `{}` hook: {}", hook, err))?;
+
+    // Nb. Hooks don't have to read their input, in which case writing to it fails.
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&input).ok();
+    }
+    let status = child.wait()?;
+
+    if !status.success() {
+        anyhow::bail!("`{}` hook failed with {}", hook, status);
+    }
+    Ok(())
+}
+
+/// Get the head of every patch of a project, to find the patches received when
+/// fetching, with [`received`].
+pub fn patch_heads(store: &Store, project: &Urn) -> anyhow::Result<HashMap<PatchId, String>> {
+    let heads = store
+        .patches()
+        .all(project)?
+        .into_iter()
+        .map(|(id, patch)| (id, patch.head().to_string()))
+        .collect();
+
+    Ok(heads)
+}
+
+/// Get the head of every patch of a project before it is synced, if the `patch-received`
+/// hook is to be run after the sync, with [`after_sync`].
+pub fn before_sync(
+    profile: &Profile,
+    storage: &Storage,
+    project: &Urn,
+    mode: Mode,
+) -> anyhow::Result<Option<HashMap<PatchId, String>>> {
+    // Nb. Patch heads are only needed to find received patches, if there's a hook.
+    if !mode.is_fetch() || find(profile, Hook::PatchReceived).is_none() {
+        return Ok(None);
+    }
+    let cobs = cobs::store(profile, storage)?;
+
+    Ok(Some(patch_heads(&cobs, project)?))
+}
+
+/// Run the hooks after a project was synced: `patch-received` for every patch received
+/// since the heads returned by [`before_sync`], and `post-sync`. Since these hooks
+/// don't fail the sync, their errors are returned instead, to be reported as warnings.
+pub fn after_sync(
+    profile: &Profile,
+    storage: &Storage,
+    project: &Urn,
+    heads: Option<HashMap<PatchId, String>>,
+    unverified: &BTreeMap<PeerId, sync::Verification>,
+    context: &PostSync,
+) -> anyhow::Result<Vec<anyhow::Error>> {
+    let mut errors = Vec::new();
+
+    if let Some(heads) = heads {
+        let cobs = cobs::store(profile, storage)?;
+
+        for patch in received(&cobs, project, &heads, storage.peer_id(), unverified)? {
+            if let Err(err) = run(profile, Hook::PatchReceived, &patch) {
+                errors.push(err);
+            }
+        }
+    }
+    if let Err(err) = run(profile, Hook::PostSync, context) {
+        errors.push(err);
+    }
+    Ok(errors)
+}
+
+/// Get the patches and patch revisions published by other peers since the given heads
+/// were taken. Revisions of peers whose refs failed verification, as returned by
+/// [`sync::verify_all`], are skipped.
+pub fn received(
+    store: &Store,
+    project: &Urn,
+    heads: &HashMap<PatchId, String>,
+    local: &PeerId,
+    unverified: &BTreeMap<PeerId, sync::Verification>,
+) -> anyhow::Result<Vec<PatchReceived>> {
+    let mut received = Vec::new();
+
+    for (id, patch) in store.patches().all(project)? {
+        let (ix, revision) = patch.latest();
+
+        if &revision.peer == local
+            || unverified.contains_key(&revision.peer)
+            || heads.get(&id) == Some(&revision.oid.to_string())
+        {
+            continue;
+        }
+        received.push(PatchReceived {
+            project: project.to_string(),
+            id: id.to_string(),
+            title: patch.title.clone(),
+            author: patch.author.urn.to_string(),
+            peer: revision.peer.to_string(),
+            revision: ix,
+            oid: revision.oid.to_string(),
+        });
+    }
+    Ok(received)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_run() {
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+
+        let (_, profile, _, project) = test::setup::profile();
+        let dir = dir(&profile);
+        let output = dir.join("output.json");
+        let hook = dir.join(Hook::PostClone.name());
+
+        // Not installed.
+        run(&profile, Hook::PostClone, &()).unwrap();
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            &hook,
+            format!("#!/bin/sh\ncat > {}\n", output.display()).as_bytes(),
+        )
+        .unwrap();
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let context = PostClone {
+            project: project.urn().to_string(),
+            name: "acme".to_owned(),
+            path: None,
+        };
+        run(&profile, Hook::PostClone, &context).unwrap();
+
+        let json: serde_json::Value = serde_json::from_slice(&fs::read(&output).unwrap()).unwrap();
+        assert_eq!(json["project"], project.urn().to_string());
+        assert_eq!(json["name"], "acme");
+        assert!(json["path"].is_null());
+
+        fs::write(&hook, "#!/bin/sh\nexit 1\n").unwrap();
+        assert!(run(&profile, Hook::PostClone, &context).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,15 +1,23 @@
+use std::collections::{BTreeSet, HashMap};
 use std::convert::TryFrom;
 use std::str::FromStr;
 
 use anyhow::anyhow;
+use serde_json::Value;
 use url::Url;
 
+use librad::git::storage::ReadOnly;
 use librad::git::Urn;
 use librad::PeerId;
 
+use crate::cobs::Timestamp;
+use crate::git;
 use crate::project::URL_SCHEME;
 use crate::{seed, sync};
 
+/// Commit trailer holding a signature over an identity revision.
+pub const SIGNATURE_TRAILER: &str = "X-Rad-Signature";
+
 /// Identity origin.
 ///
 /// Represents a location from which an identity can be fetched.
@@ -80,6 +88,178 @@ impl TryFrom<Url> for Origin {
     }
 }
 
+/// A revision of an identity document.
+#[derive(Debug, Clone)]
+pub struct Revision {
+    /// Revision ID, ie. the tree the document is stored in.
+    pub id: git::Oid,
+    /// The first commit of this revision.
+    pub commit: git::Oid,
+    /// Time the revision was first committed.
+    pub timestamp: Timestamp,
+    /// Keys that signed this revision.
+    pub signers: BTreeSet<String>,
+    /// The identity document.
+    pub doc: Value,
+}
+
+/// A change between two revisions of an identity document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// A delegate was added.
+    DelegateAdded(String),
+    /// A delegate was removed.
+    DelegateRemoved(String),
+    /// A payload field was set, changed or unset. Fields are qualified with the
+    /// name of their payload namespace, eg. `project.name`.
+    Payload {
+        field: String,
+        old: Option<Value>,
+        new: Option<Value>,
+    },
+}
+
+/// Get the revision history of an identity, oldest first. Revisions that were signed
+/// more than once, eg. by several delegates, are listed once with all their signers.
+pub fn history<S>(storage: &S, urn: &Urn) -> anyhow::Result<Vec<Revision>>
+where
+    S: AsRef<ReadOnly>,
+{
+    let repo = git::Repository::open_bare(storage.as_ref().path())?;
+    let head = repo
+        .find_reference(&format!("refs/namespaces/{}/refs/rad/id", urn.encode_id()))
+        .map_err(|_| anyhow!("identity {} was not found in local storage", urn))?
+        .peel_to_commit()?;
+
+    let mut walk = repo.revwalk()?;
+    walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+    walk.push(head.id())?;
+
+    let mut revisions: Vec<Revision> = Vec::new();
+    let mut index: HashMap<git::Oid, usize> = HashMap::new();
+
+    for oid in walk {
+        let commit = repo.find_commit(oid?)?;
+        let signers = signers(commit.message().unwrap_or_default());
+        let tree = commit.tree()?;
+
+        if let Some(ix) = index.get(&tree.id()) {
+            revisions[*ix].signers.extend(signers);
+            continue;
+        }
+        let blob = tree
+            .get(0)
+            .ok_or_else(|| anyhow!("revision {} of {} is empty", tree.id(), urn))?
+            .to_object(&repo)?
+            .peel_to_blob()?;
+        let doc = serde_json::from_slice(blob.content())?;
+
+        index.insert(tree.id(), revisions.len());
+        revisions.push(Revision {
+            id: tree.id(),
+            commit: commit.id(),
+            timestamp: Timestamp::new(commit.time().seconds() as u64),
+            signers,
+            doc,
+        });
+    }
+    Ok(revisions)
+}
+
+/// Get the changes between two identity documents.
+pub fn changes(old: &Value, new: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let (old_delegates, new_delegates) = (delegations(old), delegations(new));
+
+    for d in new_delegates.difference(&old_delegates) {
+        changes.push(Change::DelegateAdded(d.clone()));
+    }
+    for d in old_delegates.difference(&new_delegates) {
+        changes.push(Change::DelegateRemoved(d.clone()));
+    }
+
+    let (old_fields, new_fields) = (payload(old), payload(new));
+    let fields = old_fields
+        .keys()
+        .chain(new_fields.keys())
+        .collect::<BTreeSet<_>>();
+
+    for field in fields {
+        let (o, n) = (old_fields.get(field), new_fields.get(field));
+        if o != n {
+            changes.push(Change::Payload {
+                field: field.clone(),
+                old: o.cloned(),
+                new: n.cloned(),
+            });
+        }
+    }
+    changes
+}
+
+/// Get the signing keys from the signature trailers of an identity commit message.
+fn signers(message: &str) -> BTreeSet<String> {
+    message
+        .lines()
+        .filter_map(|line| {
+            let (token, value) = line.split_once(':')?;
+            if !token.trim().eq_ignore_ascii_case(SIGNATURE_TRAILER) {
+                return None;
+            }
+            value.split_whitespace().next().map(ToOwned::to_owned)
+        })
+        .collect()
+}
+
+/// Get the delegations of an identity document, as strings.
+fn delegations(doc: &Value) -> BTreeSet<String> {
+    doc.get("delegations")
+        .and_then(Value::as_array)
+        .map(|ds| {
+            ds.iter()
+                .map(|d| match d {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Get the payload fields of an identity document, qualified by namespace.
+fn payload(doc: &Value) -> HashMap<String, Value> {
+    let mut fields = HashMap::new();
+
+    if let Some(namespaces) = doc.get("payload").and_then(Value::as_object) {
+        for (namespace, value) in namespaces {
+            let prefix = namespace_name(namespace);
+
+            match value.as_object() {
+                Some(obj) => {
+                    for (k, v) in obj {
+                        fields.insert(format!("{}.{}", prefix, k), v.clone());
+                    }
+                }
+                None => {
+                    fields.insert(prefix, value.clone());
+                }
+            }
+        }
+    }
+    fields
+}
+
+/// Get a short name for a payload namespace, eg. `license` for
+/// `https://radicle.xyz/project/license/v1`.
+fn namespace_name(namespace: &str) -> String {
+    namespace
+        .trim_end_matches('/')
+        .rsplit('/')
+        .find(|s| !(s.starts_with('v') && s[1..].chars().all(|c| c.is_ascii_digit())))
+        .unwrap_or(namespace)
+        .to_owned()
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -132,4 +312,59 @@ mod test {
         );
         assert!(origin.seed.is_none());
     }
+
+    #[test]
+    fn test_signers() {
+        let msg = "Update\n\nX-Rad-Signature: hyb1 sig1\nx-rad-signature: hyb2 sig2\nOther: x\n";
+        let signers = signers(msg);
+
+        assert_eq!(
+            signers.into_iter().collect::<Vec<_>>(),
+            vec![String::from("hyb1"), String::from("hyb2")]
+        );
+    }
+
+    #[test]
+    fn test_changes() {
+        let old = serde_json::json!({
+            "payload": {
+                "https://radicle.xyz/link/identities/project/v1": {
+                    "name": "acme",
+                    "default_branch": "master"
+                }
+            },
+            "delegations": ["hyb1", "hyb2"]
+        });
+        let new = serde_json::json!({
+            "payload": {
+                "https://radicle.xyz/link/identities/project/v1": {
+                    "name": "acme",
+                    "default_branch": "main"
+                },
+                "https://radicle.xyz/project/license/v1": {
+                    "id": "MIT"
+                }
+            },
+            "delegations": ["hyb2", "hyb3"]
+        });
+
+        assert_eq!(
+            changes(&old, &new),
+            vec![
+                Change::DelegateAdded(String::from("hyb3")),
+                Change::DelegateRemoved(String::from("hyb1")),
+                Change::Payload {
+                    field: String::from("license.id"),
+                    old: None,
+                    new: Some(Value::from("MIT")),
+                },
+                Change::Payload {
+                    field: String::from("project.default_branch"),
+                    old: Some(Value::from("master")),
+                    new: Some(Value::from("main")),
+                },
+            ]
+        );
+        assert!(changes(&new, &new).is_empty());
+    }
 }
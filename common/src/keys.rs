@@ -1,44 +1,99 @@
 //! SSH and key-related functions.
+#[cfg(unix)]
+pub mod agent;
+#[cfg(any(windows, test))]
+pub mod pipe;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+
 use anyhow::{Context as _, Error, Result};
 
 use librad::crypto::keystore::crypto;
 use librad::crypto::keystore::crypto::Pwhash;
 use librad::crypto::keystore::pinentry::{Pinentry, SecUtf8};
 use librad::crypto::keystore::{FileStorage, Keystore};
+use librad::crypto::BoxedSigner;
 use librad::git::storage::Storage;
-use librad::profile::Profile;
+use librad::profile::{Profile, ProfileId};
 use librad::{PeerId, PublicKey};
 
 use lnk_clib::keys;
-use lnk_clib::keys::ssh::SshAuthSock;
+pub use lnk_clib::keys::ssh::SshAuthSock;
 
 pub use lnk_clib::keys::LIBRAD_KEY_FILE as KEY_FILE;
 
+use crate::error::{self, WithKind as _};
 use crate::signer::{ToSigner, ZeroizingSecretKey};
 
-/// Env var used to pass down the passphrase to the git-remote-helper when
-/// ssh-agent isn't present.
-pub const RAD_PASSPHRASE: &str = "RAD_PASSPHRASE";
+pub use crate::env::RAD_PASSPHRASE;
 
 /// Get the radicle signer and storage.
 pub fn storage(profile: &Profile, signer: impl ToSigner) -> Result<Storage, Error> {
     let signer = match signer.to_signer(profile) {
         Ok(signer) => signer,
-        Err(keys::ssh::Error::NoSuchKey(_)) => {
-            anyhow::bail!("the radicle ssh key for this profile is not in ssh-agent")
+        // Nb. The key may be held by `rad agent` instead of ssh-agent.
+        Err(err)
+            if matches!(
+                err.downcast_ref::<keys::ssh::Error>(),
+                Some(keys::ssh::Error::NoSuchKey(_))
+            ) =>
+        {
+            match agent_signer(profile) {
+                Some(signer) => signer,
+                None => {
+                    return Err(error::Error::new(
+                        error::Kind::Auth,
+                        anyhow::anyhow!("the radicle ssh key for this profile is not in ssh-agent"),
+                    )
+                    .into());
+                }
+            }
         }
-        Err(err) => anyhow::bail!(err),
+        Err(err) => return Err(err),
     };
-    let storage = Storage::open(profile.paths(), signer)?;
+    let storage = Storage::open(profile.paths(), signer).with_kind(error::Kind::Storage)?;
 
     Ok(storage)
 }
 
+/// Get a signer from `rad agent`, if it is running.
+#[cfg(unix)]
+pub fn agent_signer(profile: &Profile) -> Option<BoxedSigner> {
+    let agent = agent::AgentSigner::connect(&agent::socket_path(profile)).ok()?;
+
+    agent.to_signer(profile).ok()
+}
+
+#[cfg(not(unix))]
+pub fn agent_signer(_profile: &Profile) -> Option<BoxedSigner> {
+    None
+}
+
 /// Add a profile's radicle signing key to ssh-agent.
 pub fn add<P: Pinentry>(profile: &Profile, pass: Pwhash<P>, sock: SshAuthSock) -> Result<(), Error>
 where
     <P as Pinentry>::Error: std::fmt::Debug + std::error::Error + Send + Sync + 'static,
 {
+    invalidate(profile);
+
+    #[cfg(windows)]
+    if let Some(path) = pipe_path(&sock) {
+        let keystore: FileStorage<_, PublicKey, librad::SecretKey, _> =
+            FileStorage::new(&profile.paths().keys_dir().join(KEY_FILE), pass);
+        let keypair = keystore.get_key()?;
+
+        return pipe::Agent::connect(&path)
+            .and_then(|mut agent| {
+                agent.add(
+                    keypair.public_key.as_ref(),
+                    keypair.secret_key.as_ref(),
+                    &format!("radicle profile {}", profile.id()),
+                )
+            })
+            .context("could not add ssh key");
+    }
     keys::ssh::add_signer(profile, sock, pass, vec![]).context("could not add ssh key")?;
 
     Ok(())
@@ -53,66 +108,190 @@ pub fn remove<P: Pinentry>(
 where
     <P as Pinentry>::Error: std::fmt::Debug + std::error::Error + Send + Sync + 'static,
 {
+    invalidate(profile);
+
+    #[cfg(windows)]
+    if let Some(path) = pipe_path(&sock) {
+        let peer_id = *crate::profile::read_only(profile)?.peer_id();
+
+        return pipe::Agent::connect(&path)
+            .and_then(|mut agent| agent.remove(peer_id.as_public_key().as_ref()))
+            .context("could not remove ssh key");
+    }
     keys::ssh::remove_signer(profile, sock, pass).context("could not remove ssh key")?;
 
     Ok(())
 }
 
+/// Named pipe of the Windows OpenSSH agent service.
+#[cfg(windows)]
+pub const OPENSSH_AGENT_PIPE: &str = r"\\.\pipe\openssh-ssh-agent";
+
 /// Get the SSH auth socket and error if ssh-agent is not running.
+#[cfg(not(windows))]
 pub fn ssh_auth_sock() -> Result<SshAuthSock, anyhow::Error> {
     if std::env::var("SSH_AGENT_PID").is_err() && std::env::var("SSH_AUTH_SOCK").is_err() {
-        anyhow::bail!("ssh-agent does not appear to be running");
+        return Err(error::Error::new(
+            error::Kind::Auth,
+            anyhow::anyhow!("ssh-agent does not appear to be running"),
+        )
+        .into());
     }
     Ok(SshAuthSock::Env)
 }
 
-/// Check whether the radicle signing key has been added to ssh-agent.
+/// Get the socket of the SSH agent set with `SSH_AUTH_SOCK`, or else the named pipe of
+/// the Windows OpenSSH agent service, and error if it is not running. Pageant is used
+/// by setting `SSH_AUTH_SOCK` to its named pipe.
+#[cfg(windows)]
+pub fn ssh_auth_sock() -> Result<SshAuthSock, anyhow::Error> {
+    use std::path::{Path, PathBuf};
+
+    let path = match std::env::var_os("SSH_AUTH_SOCK") {
+        Some(path) => PathBuf::from(path),
+        None if Path::new(OPENSSH_AGENT_PIPE).exists() => PathBuf::from(OPENSSH_AGENT_PIPE),
+        None => {
+            return Err(error::Error::new(
+                error::Kind::Auth,
+                anyhow::anyhow!("ssh-agent does not appear to be running"),
+            )
+            .into())
+        }
+    };
+    Ok(SshAuthSock::Uds(path))
+}
+
+/// Get the named pipe of an SSH agent, if it listens on one rather than on a Unix
+/// domain socket.
+#[cfg(windows)]
+pub(crate) fn pipe_path(sock: &SshAuthSock) -> Option<std::path::PathBuf> {
+    match sock {
+        SshAuthSock::Uds(path) if is_pipe(path) => Some(path.clone()),
+        SshAuthSock::Env => std::env::var_os("SSH_AUTH_SOCK")
+            .map(std::path::PathBuf::from)
+            .filter(|path| is_pipe(path)),
+        _ => None,
+    }
+}
+
+/// Check whether a path is a Windows named pipe, eg. `\\.\pipe\openssh-ssh-agent`.
+#[cfg(any(windows, test))]
+fn is_pipe(path: &std::path::Path) -> bool {
+    let path = path
+        .to_string_lossy()
+        .replace('/', "\\")
+        .to_ascii_lowercase();
+
+    path.starts_with(r"\\.\pipe\")
+}
+
+lazy_static::lazy_static! {
+    /// Whether a profile's signing key is in ssh-agent, as last checked by [`is_ready`].
+    /// Invalidated when a key is added or removed.
+    static ref READY: Mutex<HashMap<ProfileId, bool>> = Mutex::new(HashMap::new());
+}
+
+/// Check whether the radicle signing key has been added to ssh-agent. The result is
+/// cached for the rest of the process, so that checking it repeatedly is cheap.
 pub fn is_ready(profile: &Profile, sock: SshAuthSock) -> Result<bool, Error> {
-    keys::ssh::is_signer_present(profile, sock)
-        .context("could not lookup ssh key, is ssh-agent running?")
+    if let Some(ready) = READY.lock().unwrap().get(profile.id()) {
+        return Ok(*ready);
+    }
+    let ready =
+        is_present(profile, sock).context("could not lookup ssh key, is ssh-agent running?")?;
+
+    READY.lock().unwrap().insert(profile.id().clone(), ready);
+
+    Ok(ready)
 }
 
-/// Get the SSH long key from a peer id.
-/// This is the output of `ssh-add -L`.
-pub fn to_ssh_key(peer_id: &PeerId) -> Result<String, std::io::Error> {
+/// Check whether the radicle signing key is in the given ssh-agent.
+fn is_present(profile: &Profile, sock: SshAuthSock) -> Result<bool, Error> {
+    #[cfg(windows)]
+    if let Some(path) = pipe_path(&sock) {
+        let peer_id = *crate::profile::read_only(profile)?.peer_id();
+
+        return Ok(pipe::is_present(&path, peer_id.as_public_key().as_ref())?);
+    }
+    Ok(keys::ssh::is_signer_present(profile, sock)?)
+}
+
+/// Forget whether a profile's signing key is in ssh-agent.
+fn invalidate(profile: &Profile) {
+    READY.lock().unwrap().remove(profile.id());
+}
+
+/// Name of the Ed25519 key type, in the SSH wire format.
+const SSH_ED25519: &[u8] = b"ssh-ed25519";
+
+/// DER prefix of an Ed25519 `SubjectPublicKeyInfo`, as defined in RFC 8410.
+const ED25519_SPKI_PREFIX: &[u8] = &[
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+/// Encode an Ed25519 public key in the SSH wire format, as defined in RFC 8709.
+/// This is a length-prefixed key type name, followed by the length-prefixed key.
+fn ssh_wire_encode(key: &[u8]) -> Result<Vec<u8>, std::io::Error> {
     use byteorder::{BigEndian, WriteBytesExt};
 
     let mut buf = Vec::new();
-    let key = peer_id.as_public_key().as_ref();
-    let len = key.len();
 
-    buf.write_u32::<BigEndian>(len as u32)?;
+    buf.write_u32::<BigEndian>(SSH_ED25519.len() as u32)?;
+    buf.extend_from_slice(SSH_ED25519);
+    buf.write_u32::<BigEndian>(key.len() as u32)?;
     buf.extend_from_slice(key);
 
-    // Despite research, I have no idea what this string is, but it seems
-    // to be the same for all Ed25519 keys.
-    let mut encoded = String::from("ssh-ed25519 AAAAC3NzaC1lZDI1NTE5");
-    encoded.push_str(&base64::encode(buf));
+    Ok(buf)
+}
 
-    Ok(encoded)
+/// Get the SSH long key from a peer id.
+/// This is the output of `ssh-add -L`.
+pub fn to_ssh_key(peer_id: &PeerId) -> Result<String, std::io::Error> {
+    let buf = ssh_wire_encode(peer_id.as_public_key().as_ref())?;
+
+    Ok(format!("ssh-ed25519 {}", base64::encode(buf)))
 }
 
 /// Get the SSH key fingerprint from a peer id.
 /// This is the output of `ssh-add -l`.
 pub fn to_ssh_fingerprint(peer_id: &PeerId) -> Result<String, std::io::Error> {
-    use byteorder::{BigEndian, WriteBytesExt};
     use sha2::Digest;
 
-    let mut buf = Vec::new();
-    let name = b"ssh-ed25519";
-    let key = peer_id.as_public_key().as_ref();
-
-    buf.write_u32::<BigEndian>(name.len() as u32)?;
-    buf.extend_from_slice(name);
-    buf.write_u32::<BigEndian>(key.len() as u32)?;
-    buf.extend_from_slice(key);
-
+    let buf = ssh_wire_encode(peer_id.as_public_key().as_ref())?;
     let sha = sha2::Sha256::digest(&buf).to_vec();
     let encoded = base64::encode(sha);
 
     Ok(format!("SHA256:{}", encoded.trim_end_matches('=')))
 }
 
+/// Get the PEM-encoded public key from a peer id.
+/// This is the output of `openssl pkey -pubout`.
+pub fn to_pem(peer_id: &PeerId) -> String {
+    pem_encode(peer_id.as_public_key().as_ref())
+}
+
+fn pem_encode(key: &[u8]) -> String {
+    let mut der = ED25519_SPKI_PREFIX.to_vec();
+    der.extend_from_slice(key);
+
+    let encoded = base64::encode(der);
+    let mut pem = String::from("-----BEGIN PUBLIC KEY-----\n");
+
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(&String::from_utf8_lossy(line));
+        pem.push('\n');
+    }
+    pem.push_str("-----END PUBLIC KEY-----");
+    pem
+}
+
+/// Parse a device key, given as the Peer ID of the device.
+pub fn parse_key(s: &str) -> Result<PublicKey, Error> {
+    let peer = PeerId::from_str(s).context("invalid key, expected a Peer ID")?;
+
+    Ok(*peer.as_public_key())
+}
+
 /// Get a profile's secret key by providing a passphrase.
 pub fn load_secret_key(
     profile: &Profile,
@@ -135,3 +314,46 @@ pub fn pwhash(secret: SecUtf8) -> crypto::Pwhash<SecUtf8> {
 pub fn pwhash(secret: SecUtf8) -> crypto::Pwhash<SecUtf8> {
     crypto::Pwhash::new(secret, *crypto::KDF_PARAMS_TEST)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_ssh_wire_encode() {
+        let key = [0xab; 32];
+        let buf = ssh_wire_encode(&key).unwrap();
+
+        assert_eq!(&buf[..4], &[0, 0, 0, 11]);
+        assert_eq!(&buf[4..15], b"ssh-ed25519");
+        assert_eq!(&buf[15..19], &[0, 0, 0, 32]);
+        assert_eq!(&buf[19..], &key);
+        assert!(base64::encode(buf).starts_with("AAAAC3NzaC1lZDI1NTE5AAAAI"));
+    }
+
+    #[test]
+    fn test_is_pipe() {
+        assert!(is_pipe(Path::new(r"\\.\pipe\openssh-ssh-agent")));
+        assert!(is_pipe(Path::new("//./pipe/pageant.user")));
+        assert!(!is_pipe(Path::new(r"C:\Users\user\.ssh\agent.sock")));
+        assert!(!is_pipe(Path::new("/tmp/ssh-XXXX/agent.1234")));
+    }
+
+    #[test]
+    fn test_pem_encode() {
+        // Test vector from RFC 8410, section 10.1.
+        let key = [
+            0x19, 0xbf, 0x44, 0x09, 0x69, 0x84, 0xcd, 0xfe, 0x85, 0x41, 0xba, 0xc1, 0x67, 0xdc,
+            0x3b, 0x96, 0xc8, 0x50, 0x86, 0xaa, 0x30, 0xb6, 0xb6, 0xcb, 0x0c, 0x5c, 0x38, 0xad,
+            0x70, 0x31, 0x66, 0xe1,
+        ];
+
+        assert_eq!(
+            pem_encode(&key),
+            "-----BEGIN PUBLIC KEY-----\n\
+             MCowBQYDK2VwAyEAGb9ECWmEzf6FQbrBZ9w7lshQhqowtrbLDFw4rXAxZuE=\n\
+             -----END PUBLIC KEY-----"
+        );
+    }
+}
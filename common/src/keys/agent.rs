@@ -0,0 +1,333 @@
+//! A lightweight key agent, for systems without `ssh-agent`.
+//!
+//! The agent holds an unlocked signing key in memory and signs data on behalf of
+//! clients connecting to its Unix socket. Requests and responses are JSON objects,
+//! one per line:
+//!
+//! ```text
+//! > {"type":"public-key"}
+//! < {"type":"public-key","key":"<base64>"}
+//! > {"type":"sign","data":"<base64>"}
+//! < {"type":"signature","signature":"<base64>"}
+//! > {"type":"stop"}
+//! < {"type":"ok"}
+//! ```
+//!
+//! Failed requests are answered with `{"type":"error","message":"..."}`.
+use std::convert::TryInto;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::{env, fs, io, thread};
+
+use serde::{Deserialize, Serialize};
+
+use librad::crypto::keystore::sign::ed25519;
+use librad::crypto::BoxedSignError;
+use librad::crypto::BoxedSigner;
+use librad::profile::Profile;
+
+use crate::signer::ToSigner;
+
+/// Environment variable that sets the agent's socket path.
+pub const RAD_AGENT_SOCK: &str = "RAD_AGENT_SOCK";
+
+/// File name of the agent's socket, in the profile's keys directory.
+pub const SOCKET_FILE: &str = "agent.sock";
+
+/// A request to the agent.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Request {
+    /// Get the public key of the agent's signer.
+    PublicKey,
+    /// Sign the given base64-encoded data.
+    Sign { data: String },
+    /// Stop the agent.
+    Stop,
+}
+
+/// A response from the agent.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Response {
+    /// The base64-encoded public key of the agent's signer.
+    PublicKey { key: String },
+    /// The base64-encoded signature of the requested data.
+    Signature { signature: String },
+    /// The request succeeded.
+    Ok,
+    /// The request failed.
+    Error { message: String },
+}
+
+/// Get the agent's socket path for a profile. Can be overridden with [`RAD_AGENT_SOCK`].
+pub fn socket_path(profile: &Profile) -> PathBuf {
+    env::var_os(RAD_AGENT_SOCK)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| profile.paths().keys_dir().join(SOCKET_FILE))
+}
+
+/// Run the agent on the given socket path until it is stopped, signing requests with
+/// the given signer. Connections are served concurrently. The socket is only accessible
+/// by the current user.
+pub fn serve<S>(path: &Path, signer: S) -> io::Result<()>
+where
+    S: librad::Signer + ed25519::Signer<Error = BoxedSignError> + Send + Sync + 'static,
+{
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("an agent is already running on {}", path.display()),
+            ));
+        }
+        // Nb. A previous agent didn't shut down cleanly.
+        fs::remove_file(path)?;
+    }
+    let listener = bind(path)?;
+
+    let signer = Arc::new(signer);
+    let stopped = Arc::new(AtomicBool::new(false));
+
+    for stream in listener.incoming() {
+        if stopped.load(Ordering::SeqCst) {
+            break;
+        }
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                log::warn!("Agent connection failed: {}", err);
+                continue;
+            }
+        };
+        let signer = signer.clone();
+        let stopped = stopped.clone();
+        let path = path.to_owned();
+
+        thread::spawn(move || match handle(stream, &*signer) {
+            Ok(true) => {
+                stopped.store(true, Ordering::SeqCst);
+                // Nb. Wake up the listener, so that it notices it was stopped.
+                UnixStream::connect(&path).ok();
+            }
+            Ok(false) => {}
+            Err(err) => log::warn!("Agent connection error: {}", err),
+        });
+    }
+    fs::remove_file(path)?;
+
+    Ok(())
+}
+
+/// Bind a socket that is only accessible by the current user. The socket is bound in a
+/// private directory and only moved to the given path once its permissions are set, so
+/// that other users can't connect to it in the meantime.
+pub(crate) fn bind(path: &Path) -> io::Result<UnixListener> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(parent)?;
+
+    let dir = tempfile::Builder::new()
+        .prefix(".rad-")
+        .tempdir_in(parent)?;
+    let tmp = dir.path().join("sock");
+    let listener = UnixListener::bind(&tmp)?;
+
+    fs::set_permissions(&tmp, fs::Permissions::from_mode(0o600))?;
+    fs::rename(&tmp, path)?;
+
+    Ok(listener)
+}
+
+/// Handle requests on a connection. Returns `true` if the agent should stop.
+fn handle<S>(stream: UnixStream, signer: &S) -> io::Result<bool>
+where
+    S: librad::Signer + ed25519::Signer<Error = BoxedSignError>,
+{
+    let mut writer = stream.try_clone()?;
+
+    for line in BufReader::new(stream).lines() {
+        let response = match serde_json::from_str(&line?) {
+            Ok(Request::PublicKey) => Response::PublicKey {
+                key: base64::encode(signer.public_key().0),
+            },
+            Ok(Request::Sign { data }) => match base64::decode(data) {
+                Ok(data) => match signer.sign_blocking(&data) {
+                    Ok(sig) => Response::Signature {
+                        signature: base64::encode(sig.0),
+                    },
+                    Err(err) => Response::Error {
+                        message: err.to_string(),
+                    },
+                },
+                Err(err) => Response::Error {
+                    message: format!("invalid data: {}", err),
+                },
+            },
+            Ok(Request::Stop) => {
+                send(&mut writer, &Response::Ok)?;
+                return Ok(true);
+            }
+            Err(err) => Response::Error {
+                message: format!("invalid request: {}", err),
+            },
+        };
+        send(&mut writer, &response)?;
+    }
+    Ok(false)
+}
+
+fn send(writer: &mut impl Write, response: &Response) -> io::Result<()> {
+    let mut line = serde_json::to_vec(response)?;
+    line.push(b'\n');
+
+    writer.write_all(&line)
+}
+
+/// Send a single request to the agent, and return its response.
+pub fn request(path: &Path, request: &Request) -> io::Result<Response> {
+    let mut stream = UnixStream::connect(path)?;
+    let mut line = serde_json::to_vec(request)?;
+    line.push(b'\n');
+    stream.write_all(&line)?;
+
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response)?;
+
+    match serde_json::from_str(&response)? {
+        Response::Error { message } => Err(io::Error::new(io::ErrorKind::Other, message)),
+        response => Ok(response),
+    }
+}
+
+/// Stop the agent running on the given socket path.
+pub fn stop(path: &Path) -> io::Result<()> {
+    request(path, &Request::Stop).map(|_| ())
+}
+
+/// A signer backed by a running agent.
+#[derive(Debug, Clone)]
+pub struct AgentSigner {
+    path: PathBuf,
+    key: ed25519::PublicKey,
+}
+
+impl AgentSigner {
+    /// Connect to the agent on the given socket path.
+    pub fn connect(path: &Path) -> io::Result<Self> {
+        match request(path, &Request::PublicKey)? {
+            Response::PublicKey { key } => {
+                let key = base64::decode(key)
+                    .ok()
+                    .and_then(|k| k.as_slice().try_into().ok())
+                    .ok_or_else(|| invalid_data("invalid public key"))?;
+
+                Ok(Self {
+                    path: path.to_owned(),
+                    key: ed25519::PublicKey(key),
+                })
+            }
+            _ => Err(invalid_data("unexpected response")),
+        }
+    }
+
+    fn sign_data(&self, data: &[u8]) -> io::Result<ed25519::Signature> {
+        let data = base64::encode(data);
+
+        match request(&self.path, &Request::Sign { data })? {
+            Response::Signature { signature } => base64::decode(signature)
+                .ok()
+                .and_then(|s| s.as_slice().try_into().ok())
+                .map(ed25519::Signature)
+                .ok_or_else(|| invalid_data("invalid signature")),
+            _ => Err(invalid_data("unexpected response")),
+        }
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("agent: {}", msg))
+}
+
+#[async_trait::async_trait]
+impl ed25519::Signer for AgentSigner {
+    type Error = BoxedSignError;
+
+    fn public_key(&self) -> ed25519::PublicKey {
+        self.key
+    }
+
+    async fn sign(&self, data: &[u8]) -> Result<ed25519::Signature, Self::Error> {
+        self.sign_data(data).map_err(BoxedSignError::from_std_error)
+    }
+}
+
+impl librad::Signer for AgentSigner {
+    fn sign_blocking(
+        &self,
+        data: &[u8],
+    ) -> Result<librad::keystore::sign::Signature, <Self as ed25519::Signer>::Error> {
+        self.sign_data(data).map_err(BoxedSignError::from_std_error)
+    }
+}
+
+impl ToSigner for AgentSigner {
+    fn to_signer(self, _profile: &Profile) -> Result<BoxedSigner, anyhow::Error> {
+        Ok(BoxedSigner::new(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_encoding() {
+        let req = serde_json::to_string(&Request::Sign {
+            data: "aGVsbG8=".to_owned(),
+        })
+        .unwrap();
+        assert_eq!(req, r#"{"type":"sign","data":"aGVsbG8="}"#);
+
+        let res: Response = serde_json::from_str(r#"{"type":"public-key","key":"a2V5"}"#).unwrap();
+        assert!(matches!(res, Response::PublicKey { key } if key == "a2V5"));
+
+        let req: Request = serde_json::from_str(r#"{"type":"stop"}"#).unwrap();
+        assert!(matches!(req, Request::Stop));
+    }
+
+    #[test]
+    fn test_serve() {
+        use crate::signer::ZeroizingSecretKey;
+        use librad::SecretKey;
+
+        let key = ZeroizingSecretKey::new(SecretKey::new());
+        let public = ed25519::Signer::public_key(&key);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SOCKET_FILE);
+        let server = {
+            let path = path.clone();
+            thread::spawn(move || serve(&path, key))
+        };
+        while UnixStream::connect(&path).is_err() {
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        // An idle connection doesn't keep other clients waiting.
+        let _idle = UnixStream::connect(&path).unwrap();
+        let signer = AgentSigner::connect(&path).unwrap();
+
+        assert_eq!(signer.key, public);
+        assert!(signer.sign_data(b"data").is_ok());
+
+        stop(&path).unwrap();
+        server.join().unwrap().unwrap();
+        assert!(!path.exists());
+    }
+}
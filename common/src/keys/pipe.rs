@@ -0,0 +1,311 @@
+//! A client for SSH agents listening on a Windows named pipe, eg. the OpenSSH agent
+//! service or Pageant.
+//!
+//! Messages are framed as in the SSH agent protocol: a big-endian `u32` length
+//! followed by the message type and its contents. Only the messages needed to add,
+//! remove, list and sign with Ed25519 keys are supported.
+use std::convert::TryInto;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use zeroize::Zeroizing;
+
+use librad::crypto::keystore::sign::ed25519;
+use librad::crypto::BoxedSignError;
+
+use super::{ssh_wire_encode, SSH_ED25519};
+
+const FAILURE: u8 = 5;
+const SUCCESS: u8 = 6;
+const REQUEST_IDENTITIES: u8 = 11;
+const IDENTITIES_ANSWER: u8 = 12;
+const SIGN_REQUEST: u8 = 13;
+const SIGN_RESPONSE: u8 = 14;
+const ADD_IDENTITY: u8 = 17;
+const REMOVE_IDENTITY: u8 = 18;
+
+/// Maximum length of an agent response we are willing to read.
+const MAX_MESSAGE_LEN: u32 = 256 * 1024;
+
+/// A connection to an SSH agent.
+pub struct Agent<S> {
+    stream: S,
+}
+
+impl Agent<File> {
+    /// Connect to the agent on the given named pipe.
+    pub fn connect(path: &Path) -> io::Result<Self> {
+        let stream = OpenOptions::new().read(true).write(true).open(path)?;
+
+        Ok(Self::new(stream))
+    }
+}
+
+impl<S: Read + Write> Agent<S> {
+    pub fn new(stream: S) -> Self {
+        Self { stream }
+    }
+
+    /// Get the key blobs of all identities held by the agent.
+    pub fn identities(&mut self) -> io::Result<Vec<Vec<u8>>> {
+        let res = self.request(&[REQUEST_IDENTITIES])?;
+        let mut body = expect(&res, IDENTITIES_ANSWER)?;
+        let count = body.read_u32::<BigEndian>()?;
+        let mut keys = Vec::new();
+
+        for _ in 0..count {
+            keys.push(read_string(&mut body)?);
+            // Skip the comment.
+            read_string(&mut body)?;
+        }
+        Ok(keys)
+    }
+
+    /// Sign data with the given Ed25519 key.
+    pub fn sign(&mut self, key: &[u8], data: &[u8]) -> io::Result<ed25519::Signature> {
+        let mut req = vec![SIGN_REQUEST];
+        write_string(&mut req, &ssh_wire_encode(key)?)?;
+        write_string(&mut req, data)?;
+        req.write_u32::<BigEndian>(0)?;
+
+        let res = self.request(&req)?;
+        let mut body = expect(&res, SIGN_RESPONSE)?;
+        let blob = read_string(&mut body)?;
+        let mut blob = blob.as_slice();
+
+        if read_string(&mut blob)? != SSH_ED25519 {
+            return Err(invalid_data("unexpected signature type"));
+        }
+        read_string(&mut blob)?
+            .as_slice()
+            .try_into()
+            .map(ed25519::Signature)
+            .map_err(|_| invalid_data("invalid signature"))
+    }
+
+    /// Add an Ed25519 key to the agent. The secret key starts with its 32-byte seed.
+    pub fn add(&mut self, public: &[u8], secret: &[u8], comment: &str) -> io::Result<()> {
+        let seed = secret
+            .get(..32)
+            .ok_or_else(|| invalid_data("invalid secret key"))?;
+        let mut pair = Zeroizing::new(Vec::with_capacity(64));
+        pair.extend_from_slice(seed);
+        pair.extend_from_slice(public);
+
+        let mut req = Zeroizing::new(vec![ADD_IDENTITY]);
+        write_string(&mut req, SSH_ED25519)?;
+        write_string(&mut req, public)?;
+        write_string(&mut req, &pair)?;
+        write_string(&mut req, comment.as_bytes())?;
+
+        let res = self.request(&req)?;
+        expect(&res, SUCCESS).map(|_| ())
+    }
+
+    /// Remove an Ed25519 key from the agent.
+    pub fn remove(&mut self, public: &[u8]) -> io::Result<()> {
+        let mut req = vec![REMOVE_IDENTITY];
+        write_string(&mut req, &ssh_wire_encode(public)?)?;
+
+        let res = self.request(&req)?;
+        expect(&res, SUCCESS).map(|_| ())
+    }
+
+    /// Send a message to the agent, and return its response.
+    fn request(&mut self, msg: &[u8]) -> io::Result<Vec<u8>> {
+        let mut frame = Zeroizing::new(Vec::with_capacity(msg.len() + 4));
+        write_string(&mut frame, msg)?;
+
+        self.stream.write_all(&frame)?;
+        self.stream.flush()?;
+
+        let len = self.stream.read_u32::<BigEndian>()?;
+        if len == 0 || len > MAX_MESSAGE_LEN {
+            return Err(invalid_data("invalid message length"));
+        }
+        let mut res = vec![0; len as usize];
+        self.stream.read_exact(&mut res)?;
+
+        Ok(res)
+    }
+}
+
+/// Check whether the agent on the given named pipe holds the given key.
+pub fn is_present(path: &Path, key: &[u8]) -> io::Result<bool> {
+    let blob = ssh_wire_encode(key)?;
+    let keys = Agent::connect(path)?.identities()?;
+
+    Ok(keys.contains(&blob))
+}
+
+/// Check the type of a response, and return its contents.
+fn expect(res: &[u8], kind: u8) -> io::Result<&[u8]> {
+    match res.split_first() {
+        Some((k, body)) if *k == kind => Ok(body),
+        Some((&FAILURE, _)) => Err(io::Error::new(
+            io::ErrorKind::Other,
+            "ssh-agent: request failed",
+        )),
+        _ => Err(invalid_data("unexpected response")),
+    }
+}
+
+fn read_string(reader: &mut &[u8]) -> io::Result<Vec<u8>> {
+    let len = reader.read_u32::<BigEndian>()? as usize;
+    if len > reader.len() {
+        return Err(invalid_data("truncated message"));
+    }
+    let (s, rest) = reader.split_at(len);
+    let s = s.to_vec();
+    *reader = rest;
+
+    Ok(s)
+}
+
+fn write_string(buf: &mut Vec<u8>, s: &[u8]) -> io::Result<()> {
+    buf.write_u32::<BigEndian>(s.len() as u32)?;
+    buf.extend_from_slice(s);
+
+    Ok(())
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("ssh-agent: {}", msg))
+}
+
+/// A signer backed by an SSH agent on a named pipe.
+#[derive(Debug, Clone)]
+pub struct PipeSigner {
+    path: PathBuf,
+    key: ed25519::PublicKey,
+}
+
+impl PipeSigner {
+    pub fn new(path: PathBuf, key: ed25519::PublicKey) -> Self {
+        Self { path, key }
+    }
+
+    fn sign_data(&self, data: &[u8]) -> io::Result<ed25519::Signature> {
+        Agent::connect(&self.path)?.sign(&self.key.0, data)
+    }
+}
+
+#[async_trait::async_trait]
+impl ed25519::Signer for PipeSigner {
+    type Error = BoxedSignError;
+
+    fn public_key(&self) -> ed25519::PublicKey {
+        self.key
+    }
+
+    async fn sign(&self, data: &[u8]) -> Result<ed25519::Signature, Self::Error> {
+        self.sign_data(data).map_err(BoxedSignError::from_std_error)
+    }
+}
+
+impl librad::Signer for PipeSigner {
+    fn sign_blocking(
+        &self,
+        data: &[u8],
+    ) -> Result<librad::keystore::sign::Signature, <Self as ed25519::Signer>::Error> {
+        self.sign_data(data).map_err(BoxedSignError::from_std_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A stream that answers with a canned response, and captures the request.
+    struct Mock {
+        request: Vec<u8>,
+        response: io::Cursor<Vec<u8>>,
+    }
+
+    impl Mock {
+        fn new(response: &[u8]) -> Self {
+            let mut framed = Vec::new();
+            write_string(&mut framed, response).unwrap();
+
+            Self {
+                request: Vec::new(),
+                response: io::Cursor::new(framed),
+            }
+        }
+    }
+
+    impl Read for Mock {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.response.read(buf)
+        }
+    }
+
+    impl Write for Mock {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.request.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_identities() {
+        let blob = ssh_wire_encode(&[7; 32]).unwrap();
+        let mut res = vec![IDENTITIES_ANSWER];
+        res.write_u32::<BigEndian>(1).unwrap();
+        write_string(&mut res, &blob).unwrap();
+        write_string(&mut res, b"comment").unwrap();
+
+        let mut agent = Agent::new(Mock::new(&res));
+
+        assert_eq!(agent.identities().unwrap(), vec![blob]);
+        assert_eq!(agent.stream.request, [0, 0, 0, 1, REQUEST_IDENTITIES]);
+    }
+
+    #[test]
+    fn test_sign() {
+        let mut sig = Vec::new();
+        write_string(&mut sig, SSH_ED25519).unwrap();
+        write_string(&mut sig, &[9; 64]).unwrap();
+        let mut res = vec![SIGN_RESPONSE];
+        write_string(&mut res, &sig).unwrap();
+
+        let mut agent = Agent::new(Mock::new(&res));
+        let signature = agent.sign(&[7; 32], b"data").unwrap();
+
+        assert_eq!(signature.0, [9; 64]);
+
+        let req = &agent.stream.request;
+        let blob = ssh_wire_encode(&[7; 32]).unwrap();
+
+        assert_eq!(req[4], SIGN_REQUEST);
+        assert_eq!(&req[9..9 + blob.len()], blob.as_slice());
+        assert!(req.ends_with(&[0, 0, 0, 4, b'd', b'a', b't', b'a', 0, 0, 0, 0]));
+    }
+
+    #[test]
+    fn test_add() {
+        let mut agent = Agent::new(Mock::new(&[SUCCESS]));
+        agent.add(&[7; 32], &[1; 32], "rad").unwrap();
+
+        let mut expected = vec![ADD_IDENTITY];
+        write_string(&mut expected, SSH_ED25519).unwrap();
+        write_string(&mut expected, &[7; 32]).unwrap();
+        write_string(&mut expected, &[[1; 32], [7; 32]].concat()).unwrap();
+        write_string(&mut expected, b"rad").unwrap();
+
+        assert_eq!(&agent.stream.request[4..], expected.as_slice());
+    }
+
+    #[test]
+    fn test_failure() {
+        let mut agent = Agent::new(Mock::new(&[FAILURE]));
+
+        assert!(agent.remove(&[7; 32]).is_err());
+    }
+}
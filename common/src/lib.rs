@@ -1,16 +1,29 @@
 //! Common radicle utilities.
 #![allow(clippy::or_fun_call)]
 pub mod args;
+pub mod bundle;
+pub mod client;
 pub mod cobs;
 pub mod config;
+#[cfg(unix)]
+pub mod daemon;
+pub mod env;
+pub mod error;
 pub mod git;
+pub mod hooks;
 pub mod identity;
 pub mod keys;
+pub mod lock;
 pub mod logger;
+pub mod man;
+pub mod notifications;
+pub mod offline;
 pub mod patch;
 pub mod person;
+pub mod plugin;
 pub mod profile;
 pub mod project;
+pub mod proxy;
 pub mod seed;
 pub mod signer;
 pub mod sync;
@@ -0,0 +1,52 @@
+//! Advisory locking of a profile's storage.
+//!
+//! Commands that modify the storage, eg. `rad sync` and `rad track`, take the lock
+//! for their whole duration, so that they don't race when run concurrently. The lock
+//! is held by the operating system, and is released when the process exits, even if
+//! it crashes.
+use std::fs::{self, File};
+use std::io;
+
+use fs2::FileExt as _;
+use librad::profile::Profile;
+
+/// Lock file name, in the profile's git storage directory.
+pub const LOCK_FILE: &str = "rad.lock";
+
+/// A lock on a profile's storage. The lock is released when dropped.
+#[derive(Debug)]
+pub struct StorageLock {
+    file: File,
+}
+
+impl Drop for StorageLock {
+    fn drop(&mut self) {
+        self.file.unlock().ok();
+    }
+}
+
+fn open(profile: &Profile) -> io::Result<File> {
+    fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .open(profile.paths().git_dir().join(LOCK_FILE))
+}
+
+/// Lock a profile's storage, waiting for another process to release it if needed.
+pub fn storage(profile: &Profile) -> io::Result<StorageLock> {
+    let file = open(profile)?;
+    file.lock_exclusive()?;
+
+    Ok(StorageLock { file })
+}
+
+/// Try to lock a profile's storage. Returns `None` if another process holds the lock.
+pub fn try_storage(profile: &Profile) -> io::Result<Option<StorageLock>> {
+    let file = open(profile)?;
+
+    match file.try_lock_exclusive() {
+        Ok(()) => Ok(Some(StorageLock { file })),
+        Err(err) if err.kind() == fs2::lock_contended_error().kind() => Ok(None),
+        Err(err) => Err(err),
+    }
+}
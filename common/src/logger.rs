@@ -1,42 +1,82 @@
 //! Logging module.
-use std::io;
+use std::io::Write as _;
+use std::sync::Mutex;
+use std::{env, fs, io};
 
 pub use log::{Level, Log, Metadata, Record, SetLoggerError};
 
+/// Environment var that sets a file to write a trace of all log records to,
+/// regardless of the log level. Useful for bug reports.
+pub const RAD_LOG_FILE: &str = "RAD_LOG_FILE";
+
 struct Logger {
     level: Level,
+    file: Option<Mutex<fs::File>>,
 }
 
 impl Log for Logger {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= self.level
+        metadata.level() <= self.level || self.file.is_some()
     }
 
     fn log(&self, record: &Record) {
-        if self.enabled(record.metadata()) {
-            let module = record.module_path().unwrap_or_default();
+        let module = record.module_path().unwrap_or_default();
 
-            if record.level() == Level::Error {
-                write(record, module, io::stderr());
-            } else {
-                write(record, module, io::stdout());
+        if let Some(file) = &self.file {
+            if let Ok(mut file) = file.lock() {
+                writeln!(
+                    file,
+                    "{} {} {} {}",
+                    chrono::Utc::now().to_rfc3339(),
+                    record.level(),
+                    module,
+                    record.args()
+                )
+                .ok();
             }
+        }
 
-            fn write(record: &log::Record, module: &str, mut stream: impl io::Write) {
-                writeln!(stream, "{} {} {}", record.level(), module, record.args()).ok();
-            }
+        // Nb. Records are written to stderr, so that they don't mix with the output
+        // of commands, eg. with `--json`.
+        if record.level() <= self.level {
+            writeln!(
+                io::stderr(),
+                "{} {} {}",
+                record.level(),
+                module,
+                record.args()
+            )
+            .ok();
         }
     }
 
-    fn flush(&self) {}
+    fn flush(&self) {
+        if let Some(file) = &self.file {
+            if let Ok(mut file) = file.lock() {
+                file.flush().ok();
+            }
+        }
+    }
 }
 
-/// Initialize a new logger.
+/// Initialize a new logger. If [`RAD_LOG_FILE`] is set, all records are also
+/// appended to that file.
 pub fn init(level: Level) -> Result<(), SetLoggerError> {
-    let logger = Logger { level };
+    let file = env::var_os(RAD_LOG_FILE).and_then(|path| {
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .ok()
+    });
+    let max = if file.is_some() { Level::Trace } else { level };
+    let logger = Logger {
+        level,
+        file: file.map(Mutex::new),
+    };
 
     log::set_boxed_logger(Box::new(logger))?;
-    log::set_max_level(level.to_level_filter());
+    log::set_max_level(max.to_level_filter());
 
     Ok(())
 }
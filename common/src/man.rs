@@ -0,0 +1,218 @@
+//! Man page generation from command help.
+//!
+//! Pages are rendered as roff, from a command's [`Help`]. The usage text is split into
+//! sections at its unindented headings, eg. `Usage` or `Options`, and each section is
+//! kept as preformatted text, so that option tables stay aligned. The environment
+//! variables and exit codes that apply to all commands are added to every page.
+use std::fmt::Write as _;
+
+use crate::args::Help;
+use crate::env;
+use crate::error::Kind;
+
+/// Title of the manual the pages belong to.
+pub const MANUAL: &str = "Radicle CLI Manual";
+
+/// Environment variables read by all commands.
+pub const ENVIRONMENT: &[(&str, &str)] = &[
+    (
+        env::RAD_HOME,
+        "The radicle home directory, instead of the default location.",
+    ),
+    (
+        env::RAD_PROFILE,
+        "The ID of the profile to use, instead of the active profile.",
+    ),
+    (
+        env::RAD_PASSPHRASE,
+        "The passphrase of the signing key, instead of prompting for it.",
+    ),
+    (
+        env::RAD_OFFLINE,
+        "When set to `true`, commands don't use the network.",
+    ),
+    ("NO_COLOR", "When set, disables all colors."),
+    (
+        "RAD_NO_EMOJI",
+        "When set, replaces emoji and unicode glyphs with ASCII equivalents.",
+    ),
+    ("RAD_PLAIN", "When set, output is plain and line-oriented."),
+];
+
+/// Exit codes of all commands, by kind of error.
+pub const EXIT_CODES: &[(Kind, &str)] = &[
+    (Kind::Other, "An error that doesn't fit any other kind."),
+    (Kind::Usage, "Invalid command-line usage."),
+    (
+        Kind::Auth,
+        "No usable profile, or the signing key is not available.",
+    ),
+    (
+        Kind::Storage,
+        "Error reading from or writing to local storage.",
+    ),
+    (Kind::Network, "Error communicating with a seed or peer."),
+    (Kind::NotFound, "The requested object doesn't exist."),
+    (
+        Kind::Conflict,
+        "The operation conflicts with existing state.",
+    ),
+];
+
+/// Name of the man page of a command, eg. `rad-sync`.
+pub fn name(help: &Help) -> String {
+    format!("rad-{}", help.name)
+}
+
+/// Render the man page of a command, as roff.
+pub fn render(help: &Help) -> String {
+    let name = name(help);
+    let mut page = String::new();
+
+    writeln!(
+        page,
+        ".TH \"{}\" \"1\" \"\" \"rad {}\" \"{}\"",
+        name.to_uppercase(),
+        help.version,
+        MANUAL
+    )
+    .ok();
+    page.push_str(".SH NAME\n");
+    writeln!(page, "{} \\- {}", escape(&name), escape(help.description)).ok();
+
+    for (heading, body) in sections(help.usage) {
+        writeln!(page, ".SH {}", escape(&heading.to_uppercase())).ok();
+        page.push_str(".nf\n");
+        for line in body {
+            writeln!(page, "{}", escape(line)).ok();
+        }
+        page.push_str(".fi\n");
+    }
+
+    if !help.examples.is_empty() {
+        page.push_str(".SH EXAMPLES\n");
+        for (description, command) in help.examples {
+            writeln!(page, ".PP\n{}", escape(description)).ok();
+            writeln!(page, ".PP\n.RS 4\n.nf\n$ {}\n.fi\n.RE", escape(command)).ok();
+        }
+    }
+
+    page.push_str(".SH ENVIRONMENT\n");
+    for (var, description) in help.environment.iter().chain(ENVIRONMENT) {
+        writeln!(page, ".TP\n.B {}\n{}", var, escape(description)).ok();
+    }
+
+    page.push_str(".SH EXIT STATUS\n");
+    writeln!(page, ".TP\n.B 0\nSuccess.").ok();
+    for (code, description) in help.exit_codes {
+        writeln!(page, ".TP\n.B {}\n{}", code, escape(description)).ok();
+    }
+    for (kind, description) in EXIT_CODES {
+        writeln!(
+            page,
+            ".TP\n.B {}\n{}",
+            kind.exit_code(),
+            escape(description)
+        )
+        .ok();
+    }
+    page
+}
+
+/// Split a usage text into sections, at its unindented lines, which are the section
+/// headings. The section bodies are dedented, and stripped of leading and trailing
+/// blank lines.
+fn sections(usage: &str) -> Vec<(&str, Vec<&str>)> {
+    let mut sections: Vec<(&str, Vec<&str>)> = Vec::new();
+
+    for line in usage.lines() {
+        if line.trim().is_empty() {
+            if let Some((_, body)) = sections.last_mut() {
+                body.push("");
+            }
+        } else if !line.starts_with(' ') {
+            // Nb. Short usages are written on a single line, eg. `Usage: rad foo`.
+            match line.split_once(':') {
+                Some((heading, rest)) if !rest.trim().is_empty() => {
+                    sections.push((heading.trim(), vec![rest.trim()]));
+                }
+                _ => sections.push((line.trim().trim_end_matches(':'), Vec::new())),
+            }
+        } else if let Some((_, body)) = sections.last_mut() {
+            body.push(
+                line.strip_prefix("    ")
+                    .unwrap_or_else(|| line.trim_start()),
+            );
+        } else {
+            // Nb. Text before any heading is taken as the usage.
+            sections.push(("Usage", vec![line.trim_start()]));
+        }
+    }
+    for (_, body) in &mut sections {
+        while body.first().map_or(false, |l| l.is_empty()) {
+            body.remove(0);
+        }
+        while body.last().map_or(false, |l| l.is_empty()) {
+            body.pop();
+        }
+    }
+    sections
+}
+
+/// Escape text for roff.
+fn escape(text: &str) -> String {
+    let text = text.replace('\\', "\\e").replace('-', "\\-");
+
+    if text.starts_with('.') || text.starts_with('\'') {
+        format!("\\&{}", text)
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let help = Help {
+            name: "frob",
+            description: "Frobnicate things",
+            version: "0.1.0",
+            usage: r#"
+Usage
+
+    rad frob <thing> [--verbose]
+
+    Frobnicates a thing.
+
+Options
+
+    --verbose   Frobnicate verbosely
+"#,
+            examples: &[("Frobnicate the widget", "rad frob widget")],
+            ..Help::DEFAULT
+        };
+        let page = render(&help);
+
+        assert!(page.starts_with(".TH \"RAD-FROB\" \"1\" \"\" \"rad 0.1.0\""));
+        assert!(page.contains(".SH NAME\nrad\\-frob \\- Frobnicate things\n"));
+        assert!(page.contains(
+            ".SH USAGE\n.nf\nrad frob <thing> [\\-\\-verbose]\n\nFrobnicates a thing.\n.fi\n"
+        ));
+        assert!(page.contains(".SH OPTIONS\n.nf\n\\-\\-verbose   Frobnicate verbosely\n.fi\n"));
+        assert!(page.contains(".SH EXAMPLES\n.PP\nFrobnicate the widget\n"));
+        assert!(page.contains("$ rad frob widget\n"));
+        assert!(page.contains(".B RAD_HOME\n"));
+        assert!(page.contains(".B 2\nInvalid command\\-line usage.\n"));
+    }
+
+    #[test]
+    fn test_sections() {
+        assert_eq!(
+            sections("Usage: rad help [--help]"),
+            vec![("Usage", vec!["rad help [--help]"])]
+        );
+    }
+}
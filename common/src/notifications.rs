@@ -0,0 +1,335 @@
+//! Notifications about project activity since the last check.
+//!
+//! What has been seen is stored per project in the `notifications` file next to the
+//! user configuration file, as JSON. For each project, we store the time of the last
+//! check, along with the heads and identity refs of tracked peers at that time, so that
+//! fetched ref updates can be told apart from ones we've already seen.
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use librad::collaborative_objects::ObjectId;
+use librad::git::storage::ReadOnly;
+use librad::git::Urn;
+use librad::PeerId;
+
+use crate::cobs::patch::Verdict;
+use crate::cobs::{Author, Store, Timestamp};
+use crate::{config, git};
+
+/// Name of the notifications file, in the user configuration directory.
+pub const FILE_NAME: &str = "notifications";
+
+/// What was seen of a project at the last check.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Time of the check.
+    pub timestamp: Timestamp,
+    /// Refs of tracked peers, eg. `<peer>/heads/master`, and their targets.
+    pub refs: BTreeMap<String, String>,
+}
+
+/// Kind of collaborative object a notification is about.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Kind {
+    Issue,
+    Patch,
+}
+
+/// A notification.
+#[derive(Debug, Clone)]
+pub enum Notification {
+    /// A new issue was opened.
+    Issue {
+        id: ObjectId,
+        title: String,
+        author: Author,
+        timestamp: Timestamp,
+    },
+    /// A new patch was proposed.
+    Patch {
+        id: ObjectId,
+        title: String,
+        author: Author,
+        timestamp: Timestamp,
+    },
+    /// Someone commented on one of our issues or patches.
+    Comment {
+        kind: Kind,
+        id: ObjectId,
+        title: String,
+        author: Author,
+        timestamp: Timestamp,
+    },
+    /// Someone reviewed one of our patches.
+    Review {
+        id: ObjectId,
+        title: String,
+        author: Author,
+        verdict: Option<Verdict>,
+        timestamp: Timestamp,
+    },
+    /// Someone merged one of our patches.
+    Merge {
+        id: ObjectId,
+        title: String,
+        peer: PeerId,
+        timestamp: Timestamp,
+    },
+    /// A peer's identity ref changed, eg. the project identity was updated.
+    Identity { peer: PeerId, oid: String },
+    /// A peer's branch was created or updated.
+    Branch {
+        peer: PeerId,
+        name: String,
+        oid: String,
+    },
+}
+
+impl Notification {
+    /// Time of the event, if known. Ref updates aren't timestamped.
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        match self {
+            Self::Issue { timestamp, .. }
+            | Self::Patch { timestamp, .. }
+            | Self::Comment { timestamp, .. }
+            | Self::Review { timestamp, .. }
+            | Self::Merge { timestamp, .. } => Some(*timestamp),
+            Self::Identity { .. } | Self::Branch { .. } => None,
+        }
+    }
+}
+
+/// Get the path of the notifications file.
+pub fn path() -> Option<PathBuf> {
+    config::user_path().and_then(|p| p.parent().map(|dir| dir.join(FILE_NAME)))
+}
+
+/// Get the checkpoint of a project, if it was ever marked as read.
+pub fn checkpoint(urn: &Urn) -> anyhow::Result<Option<Checkpoint>> {
+    let path = match path() {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let mut checkpoints = read(&path)?;
+
+    Ok(checkpoints.remove(&urn.to_string()))
+}
+
+/// Mark everything in a project as read, by storing a new checkpoint. Returns the path
+/// of the notifications file.
+pub fn mark_read(urn: &Urn, checkpoint: Checkpoint) -> anyhow::Result<PathBuf> {
+    let path = path().ok_or_else(|| anyhow!("couldn't find user configuration directory"))?;
+    let mut checkpoints = read(&path)?;
+
+    checkpoints.insert(urn.to_string(), checkpoint);
+    write(&path, &checkpoints)?;
+
+    Ok(path)
+}
+
+/// Get the heads and identity refs of the tracked peers of a project, as fetched in
+/// local storage. Our own refs are not included.
+pub fn refs<S>(storage: &S, urn: &Urn) -> anyhow::Result<BTreeMap<String, String>>
+where
+    S: AsRef<ReadOnly>,
+{
+    let storage = storage.as_ref();
+    let repo = git::Repository::open_bare(storage.path())?;
+    let namespace = format!("refs/namespaces/{}/", urn.encode_id());
+    let mut refs = BTreeMap::new();
+
+    for reference in repo.references_glob(&format!("{}refs/remotes/*", namespace))? {
+        let reference = reference?;
+        let (name, oid) = match (reference.name(), reference.target()) {
+            (Some(name), Some(oid)) => (&name[namespace.len()..], oid),
+            _ => continue,
+        };
+
+        if let Some((peer, r)) = git::parse_remote(name) {
+            if r == "rad/id" || r.starts_with("heads/") {
+                refs.insert(format!("{}/{}", peer, r), oid.to_string());
+            }
+        }
+    }
+    Ok(refs)
+}
+
+/// Get the notifications for ref changes between two snapshots of peer refs, as
+/// returned by [`refs`]. Refs that were removed are not reported.
+pub fn ref_changes(
+    previous: &BTreeMap<String, String>,
+    current: &BTreeMap<String, String>,
+) -> Vec<Notification> {
+    let mut notifications = Vec::new();
+
+    for (name, oid) in current {
+        if previous.get(name) == Some(oid) {
+            continue;
+        }
+        let (peer, r) = match name
+            .split_once('/')
+            .and_then(|(peer, r)| peer.parse::<PeerId>().ok().map(|p| (p, r)))
+        {
+            Some(parsed) => parsed,
+            None => continue,
+        };
+        let oid = oid.clone();
+
+        if r == "rad/id" {
+            notifications.push(Notification::Identity { peer, oid });
+        } else if let Some(branch) = r.strip_prefix("heads/") {
+            notifications.push(Notification::Branch {
+                peer,
+                name: branch.to_owned(),
+                oid,
+            });
+        }
+    }
+    notifications
+}
+
+/// Get the notifications for issues and patches of a project, since the given time.
+/// This includes new issues and patches from others, as well as comments, reviews and
+/// merges by others on our own issues and patches.
+pub fn cobs(store: &Store, project: &Urn, since: Timestamp) -> anyhow::Result<Vec<Notification>> {
+    let whoami = store.whoami.urn();
+    let is_new = |author: &Author, timestamp: Timestamp| author.urn != whoami && timestamp > since;
+    let mut notifications = Vec::new();
+
+    for (id, issue) in store.issues().all(project)? {
+        if issue.author.urn != whoami {
+            if issue.timestamp > since {
+                notifications.push(Notification::Issue {
+                    id,
+                    title: issue.title.clone(),
+                    author: issue.author.clone(),
+                    timestamp: issue.timestamp,
+                });
+            }
+            continue;
+        }
+        for comment in &issue.discussion {
+            if is_new(&comment.author, comment.timestamp) {
+                notifications.push(Notification::Comment {
+                    kind: Kind::Issue,
+                    id,
+                    title: issue.title.clone(),
+                    author: comment.author.clone(),
+                    timestamp: comment.timestamp,
+                });
+            }
+        }
+    }
+
+    for (id, patch) in store.patches().all(project)? {
+        if patch.author.urn != whoami {
+            if patch.timestamp > since {
+                notifications.push(Notification::Patch {
+                    id,
+                    title: patch.title.clone(),
+                    author: patch.author.clone(),
+                    timestamp: patch.timestamp,
+                });
+            }
+            continue;
+        }
+        for revision in patch.revisions.iter() {
+            for comment in &revision.discussion {
+                if is_new(&comment.author, comment.timestamp) {
+                    notifications.push(Notification::Comment {
+                        kind: Kind::Patch,
+                        id,
+                        title: patch.title.clone(),
+                        author: comment.author.clone(),
+                        timestamp: comment.timestamp,
+                    });
+                }
+            }
+            for review in revision.reviews.values() {
+                if is_new(&review.author, review.timestamp) {
+                    notifications.push(Notification::Review {
+                        id,
+                        title: patch.title.clone(),
+                        author: review.author.clone(),
+                        verdict: review.verdict,
+                        timestamp: review.timestamp,
+                    });
+                }
+            }
+            for merge in &revision.merges {
+                if merge.peer != store.peer_id && merge.timestamp > since {
+                    notifications.push(Notification::Merge {
+                        id,
+                        title: patch.title.clone(),
+                        peer: merge.peer,
+                        timestamp: merge.timestamp,
+                    });
+                }
+            }
+        }
+    }
+    notifications.sort_by_key(|n| n.timestamp());
+
+    Ok(notifications)
+}
+
+/// Read the checkpoints of the notifications file, keyed by project URN.
+fn read(path: &Path) -> anyhow::Result<HashMap<String, Checkpoint>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(err) => return Err(err.into()),
+    };
+    let checkpoints = serde_json::from_str(&content)
+        .map_err(|e| anyhow!("invalid notifications file {:?}: {}", path, e))?;
+
+    Ok(checkpoints)
+}
+
+/// Write the checkpoints of the notifications file.
+fn write(path: &Path, checkpoints: &HashMap<String, Checkpoint>) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(checkpoints)?)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ref_changes() {
+        let peer = PeerId::from(librad::crypto::SecretKey::new());
+        let key = |r: &str| format!("{}/{}", peer, r);
+        let previous = BTreeMap::from([
+            (key("rad/id"), "a".to_owned()),
+            (key("heads/master"), "b".to_owned()),
+            (key("heads/stale"), "c".to_owned()),
+        ]);
+        let current = BTreeMap::from([
+            (key("rad/id"), "d".to_owned()),
+            (key("heads/master"), "b".to_owned()),
+            (key("heads/feature"), "e".to_owned()),
+        ]);
+        let changes = ref_changes(&previous, &current);
+
+        assert_eq!(changes.len(), 2);
+        assert!(changes.iter().any(|n| matches!(
+            n,
+            Notification::Identity { peer: p, oid } if *p == peer && oid == "d"
+        )));
+        assert!(changes.iter().any(|n| matches!(
+            n,
+            Notification::Branch { peer: p, name, oid } if *p == peer && name == "feature" && oid == "e"
+        )));
+        assert!(ref_changes(&current, &current).is_empty());
+    }
+}
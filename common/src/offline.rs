@@ -0,0 +1,32 @@
+//! Offline mode, in which commands that need the network fail right away, instead of
+//! waiting for connections to time out.
+//!
+//! Offline mode is enabled with `rad --offline`, the `network.offline` setting, or the
+//! [`RAD_OFFLINE`](crate::env::RAD_OFFLINE) environment variable.
+use anyhow::anyhow;
+
+use crate::args;
+use crate::config;
+use crate::error::{Error, Kind};
+
+/// Check whether offline mode is enabled.
+pub fn is_enabled() -> bool {
+    config::get_bool("network.offline").unwrap_or(false)
+}
+
+/// Fail if offline mode is enabled. The action is what needs the network, eg.
+/// `sync with seeds`.
+pub fn check(action: &str) -> anyhow::Result<()> {
+    if is_enabled() {
+        return Err(args::Error::WithHint {
+            err: Error::new(
+                Kind::Network,
+                anyhow!("cannot {}: offline mode is enabled", action),
+            )
+            .into(),
+            hint: "hint: offline mode is enabled by `--offline`, the `network.offline` setting or `RAD_OFFLINE`",
+        }
+        .into());
+    }
+    Ok(())
+}
@@ -244,6 +244,59 @@ where
     Ok(targets)
 }
 
+/// Find the merge target of a patch with the given head: the default branch of the one
+/// tracked peer that doesn't have the head yet. Returns `None` if every peer has it.
+pub fn find_merge_target<S>(
+    head: &git2::Oid,
+    storage: &S,
+    project: &project::Metadata,
+) -> anyhow::Result<Option<(project::PeerInfo, git::Oid)>>
+where
+    S: AsRef<ReadOnly>,
+{
+    let mut targets = find_merge_targets(head, storage, project)?;
+
+    match targets.not_merged.len() {
+        0 => Ok(None),
+        1 => Ok(targets.not_merged.pop()),
+        _ => anyhow::bail!("found more than one merge target, a base must be given"),
+    }
+}
+
+/// Create a patch proposing the given head. The head must be in storage, since there
+/// would otherwise be no way for anyone to merge the patch.
+pub fn create(
+    patches: &cob::PatchStore,
+    storage: &Storage,
+    project: &Urn,
+    title: &str,
+    description: &str,
+    base: git2::Oid,
+    head: git2::Oid,
+) -> anyhow::Result<cob::PatchId> {
+    let title = title.trim();
+    if title.is_empty() {
+        anyhow::bail!("a title must be given");
+    }
+    if storage.find_object(git::Oid::from(head))?.is_none() {
+        anyhow::bail!(
+            "commit {} was not found in storage, push it with `git push rad` first",
+            head
+        );
+    }
+    let id = patches.create(
+        project,
+        title,
+        description.trim(),
+        cob::MergeTarget::default(),
+        base,
+        head,
+        &[],
+    )?;
+
+    Ok(id)
+}
+
 pub fn patch_merge_target_oid(
     target: cob::MergeTarget,
     project: identities::VerifiedProject,
@@ -1,5 +1,5 @@
 //! Person-related functionality.
-use anyhow::{Context, Error, Result};
+use anyhow::{anyhow, Context, Error, Result};
 
 use librad::{canonical::Cstring, git::identities::local::LocalIdentity};
 
@@ -10,9 +10,12 @@ use librad::crypto::BoxedSigner;
 use librad::identities::payload;
 use librad::identities::payload::HasNamespace;
 use librad::profile::Profile;
+use librad::PublicKey;
 
 use lnk_identities::{self, local, person};
 
+use crate::error;
+
 pub use librad::git::identities::person::verify;
 pub use person::get;
 
@@ -34,6 +37,153 @@ impl HasNamespace for Ens {
     }
 }
 
+/// Maximum length of a person's name, in characters.
+pub const NAME_MAX_LENGTH: usize = 64;
+
+/// Why a name can't be used in a person's identity document.
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum NameError {
+    #[error("name cannot be empty")]
+    Empty,
+    #[error("name cannot be longer than {max} characters, it is {0}", max = NAME_MAX_LENGTH)]
+    TooLong(usize),
+    #[error("name cannot contain whitespace")]
+    Whitespace,
+    #[error("name cannot contain control or invisible characters, found {0:?}")]
+    Control(char),
+    #[error("name contains {0:?}, which can be confused with '{1}'")]
+    Confusable(char, char),
+    #[error("name must start and end with a letter or digit")]
+    Punctuation,
+}
+
+/// Check that a name is valid in a person's identity document.
+///
+/// Characters that look like ASCII, eg. Cyrillic 'а' or fullwidth 'ａ', are only allowed
+/// if the name isn't mixed with ASCII letters, so that names can't impersonate others.
+pub fn validate_name(name: &str) -> Result<(), NameError> {
+    let len = name.chars().count();
+
+    if len == 0 {
+        return Err(NameError::Empty);
+    }
+    if len > NAME_MAX_LENGTH {
+        return Err(NameError::TooLong(len));
+    }
+    if name.contains(char::is_whitespace) {
+        return Err(NameError::Whitespace);
+    }
+    if let Some(c) = name.chars().find(|c| is_invisible(*c)) {
+        return Err(NameError::Control(c));
+    }
+    let ascii = name.chars().any(|c| c.is_ascii_alphabetic());
+    for c in name.chars() {
+        if let Some(a) = fullwidth(c).or_else(|| confusable(c).filter(|_| ascii)) {
+            return Err(NameError::Confusable(c, a));
+        }
+    }
+    let first = name.chars().next().unwrap_or_default();
+    let last = name.chars().last().unwrap_or_default();
+    if !first.is_alphanumeric() || !last.is_alphanumeric() {
+        return Err(NameError::Punctuation);
+    }
+    Ok(())
+}
+
+/// Suggest a valid name that is close to the given invalid one, if there is any.
+pub fn suggest_name(name: &str) -> Option<String> {
+    let mapped: String = name.chars().map(|c| fullwidth(c).unwrap_or(c)).collect();
+    let ascii = mapped.chars().any(|c| c.is_ascii_alphabetic());
+    let mut suggestion = String::new();
+
+    for c in mapped.chars().filter(|c| !is_invisible(*c)) {
+        if c.is_whitespace() {
+            if !suggestion.ends_with('-') {
+                suggestion.push('-');
+            }
+        } else if ascii {
+            suggestion.push(confusable(c).unwrap_or(c));
+        } else {
+            suggestion.push(c);
+        }
+    }
+    let suggestion: String = suggestion
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .chars()
+        .take(NAME_MAX_LENGTH)
+        .collect();
+    let suggestion = suggestion.trim_end_matches(|c: char| !c.is_alphanumeric());
+
+    if suggestion != name && validate_name(suggestion).is_ok() {
+        Some(suggestion.to_owned())
+    } else {
+        None
+    }
+}
+
+/// Whether a character is a control character, or a formatting character that isn't
+/// visible, eg. a zero-width space or a bidirectional override.
+fn is_invisible(c: char) -> bool {
+    c.is_control()
+        || matches!(
+            c,
+            '\u{00AD}'
+                | '\u{034F}'
+                | '\u{061C}'
+                | '\u{180E}'
+                | '\u{200B}'..='\u{200F}'
+                | '\u{202A}'..='\u{202E}'
+                | '\u{2060}'..='\u{2064}'
+                | '\u{2066}'..='\u{206F}'
+                | '\u{FEFF}'
+        )
+}
+
+/// Get the ASCII character of a fullwidth character, eg. 'a' for 'ａ'.
+fn fullwidth(c: char) -> Option<char> {
+    match c {
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0),
+        _ => None,
+    }
+}
+
+/// Get the ASCII character that a Cyrillic or Greek letter looks like, if any.
+fn confusable(c: char) -> Option<char> {
+    let a = match c {
+        'а' | 'α' => 'a',
+        'с' | 'ϲ' => 'c',
+        'е' => 'e',
+        'һ' => 'h',
+        'і' | 'ι' => 'i',
+        'ј' => 'j',
+        'ο' | 'о' => 'o',
+        'р' | 'ρ' => 'p',
+        'ѕ' => 's',
+        'ν' => 'v',
+        'х' => 'x',
+        'у' => 'y',
+        'А' | 'Α' => 'A',
+        'В' | 'Β' => 'B',
+        'С' | 'Ϲ' => 'C',
+        'Е' | 'Ε' => 'E',
+        'Н' | 'Η' => 'H',
+        'І' | 'Ι' => 'I',
+        'Ј' => 'J',
+        'К' | 'Κ' => 'K',
+        'М' | 'Μ' => 'M',
+        'Ν' => 'N',
+        'О' | 'Ο' => 'O',
+        'Р' | 'Ρ' => 'P',
+        'Ѕ' => 'S',
+        'Т' | 'Τ' => 'T',
+        'Χ' | 'Х' => 'X',
+        'Υ' | 'Ү' => 'Y',
+        'Ζ' => 'Z',
+        _ => return None,
+    };
+    Some(a)
+}
+
 /// Create a personal identity.
 pub fn create(
     profile: &Profile,
@@ -80,12 +230,8 @@ pub fn local(storage: &Storage) -> Result<LocalIdentity, local::Error> {
 /// Returns the updated person.
 pub fn set_ens_payload(ens: Ens, storage: &Storage) -> Result<Person> {
     let id = local::default(storage)?;
-    let payload = id.payload();
-    let mut exts = payload
-        .exts()
-        .map(|(namespace, val)| (namespace.clone(), val.clone()))
-        .map(|(namespace, val)| payload::Ext { namespace, val })
-        .collect::<Vec<_>>();
+    ensure_quorum(&id)?;
+    let mut exts = exts(id.payload());
 
     let namespace = Ens::namespace().clone();
     let val = serde_json::to_value(ens)?;
@@ -104,3 +250,166 @@ pub fn set_ens_payload(ens: Ens, storage: &Storage) -> Result<Person> {
 
     Ok(new)
 }
+
+/// Set the payload of the local identity, eg. to change its name or extensions.
+/// Returns the updated person.
+pub fn set_payload(payload: payload::PersonPayload, storage: &Storage) -> Result<Person> {
+    let id = local::default(storage)?;
+    ensure_quorum(&id)?;
+    let delegations = id.delegations().iter().cloned();
+    let exts = exts(&payload);
+
+    let new = person::update(
+        storage,
+        &id.urn(),
+        Some(id.urn()),
+        Some(payload.subject),
+        exts,
+        Some(delegations),
+    )?;
+
+    Ok(new)
+}
+
+/// Get the device keys of the local identity. Any of these keys can sign on behalf
+/// of the identity.
+pub fn keys(storage: &Storage) -> Result<Vec<PublicKey>> {
+    let id = local::default(storage)?;
+
+    Ok(id.delegations().iter().cloned().collect())
+}
+
+/// Add a device key to the local identity, eg. the key of another device of the
+/// same person. Since updates are then signed by a majority of the keys, see
+/// [`ensure_quorum`], the identity can't be updated from this device alone anymore.
+/// Returns the updated person.
+pub fn add_key(key: PublicKey, storage: &Storage) -> Result<Person> {
+    let id = local::default(storage)?;
+    let mut keys = id.delegations().iter().cloned().collect::<Vec<_>>();
+
+    if keys.contains(&key) {
+        return Err(error::Error::new(
+            error::Kind::Conflict,
+            anyhow!("key is already part of the identity"),
+        )
+        .into());
+    }
+    ensure_quorum(&id)?;
+    keys.push(key);
+
+    set_keys(&id, keys, storage)
+}
+
+/// Revoke a device key of the local identity, eg. because the device was lost.
+/// The key of the current device, and the last remaining key can't be revoked.
+/// Nb. Since the identity has more than one key, the update must be signed by a
+/// majority of them, so it is rejected by [`ensure_quorum`] until updates can be
+/// co-signed by other devices.
+/// Returns the updated person.
+pub fn revoke_key(key: &PublicKey, storage: &Storage) -> Result<Person> {
+    let id = local::default(storage)?;
+    let keys = id.delegations().iter().cloned().collect::<Vec<_>>();
+
+    if !keys.contains(key) {
+        return Err(error::Error::new(
+            error::Kind::NotFound,
+            anyhow!("key is not part of the identity"),
+        )
+        .into());
+    }
+    if key == storage.peer_id().as_public_key() {
+        anyhow::bail!("the key of this device can't be revoked, revoke it from another device");
+    }
+    let keys = keys.into_iter().filter(|k| k != key).collect::<Vec<_>>();
+    if keys.is_empty() {
+        anyhow::bail!("the last key of an identity can't be revoked");
+    }
+    ensure_quorum(&id)?;
+
+    set_keys(&id, keys, storage)
+}
+
+/// Check that an update of the local identity can be verified. Updates must be signed
+/// by a majority of the identity's keys, but are only signed by this device: once an
+/// identity has more than one key, a majority can't be reached and it can't be updated.
+pub fn ensure_quorum(id: &LocalIdentity) -> Result<()> {
+    let keys = id.delegations().len();
+
+    if keys > 1 {
+        return Err(error::Error::new(
+            error::Kind::Auth,
+            anyhow!(
+                "the identity has {} keys, so updates must be signed by a majority of them, \
+                but can only be signed by this device",
+                keys
+            ),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Update the local identity's delegations, keeping its payload.
+fn set_keys(id: &LocalIdentity, keys: Vec<PublicKey>, storage: &Storage) -> Result<Person> {
+    let person = person::update(
+        storage,
+        &id.urn(),
+        Some(id.urn()),
+        None,
+        exts(id.payload()),
+        Some(keys.into_iter()),
+    )?;
+
+    Ok(person)
+}
+
+/// Get the extensions of a person payload.
+fn exts(payload: &payload::PersonPayload) -> Vec<payload::Ext<serde_json::Value>> {
+    payload
+        .exts()
+        .map(|(namespace, val)| payload::Ext {
+            namespace: namespace.clone(),
+            val: val.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_name() {
+        assert_eq!(validate_name("cloudhead"), Ok(()));
+        assert_eq!(validate_name("alice-2"), Ok(()));
+        assert_eq!(validate_name("Иван"), Ok(()));
+        assert_eq!(validate_name(""), Err(NameError::Empty));
+        assert_eq!(validate_name("user A"), Err(NameError::Whitespace));
+        assert_eq!(
+            validate_name(&"a".repeat(NAME_MAX_LENGTH + 1)),
+            Err(NameError::TooLong(NAME_MAX_LENGTH + 1))
+        );
+        assert_eq!(
+            validate_name("al\u{200B}ice"),
+            Err(NameError::Control('\u{200B}'))
+        );
+        assert_eq!(validate_name("аlice"), Err(NameError::Confusable('а', 'a')));
+        assert_eq!(
+            validate_name("ａlice"),
+            Err(NameError::Confusable('ａ', 'a'))
+        );
+        assert_eq!(validate_name("alice."), Err(NameError::Punctuation));
+        assert_eq!(validate_name("-alice"), Err(NameError::Punctuation));
+    }
+
+    #[test]
+    fn test_suggest_name() {
+        assert_eq!(suggest_name("user A").as_deref(), Some("user-A"));
+        assert_eq!(suggest_name("  alice  bob. ").as_deref(), Some("alice-bob"));
+        assert_eq!(suggest_name("аlice").as_deref(), Some("alice"));
+        assert_eq!(suggest_name("ａｌｉｃｅ").as_deref(), Some("alice"));
+        assert_eq!(suggest_name("al\u{200B}ice").as_deref(), Some("alice"));
+        assert_eq!(suggest_name("..."), None);
+        assert_eq!(suggest_name("alice"), None);
+    }
+}
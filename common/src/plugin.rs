@@ -0,0 +1,73 @@
+//! External commands, ie. `rad-<name>` executables found on `PATH`.
+//!
+//! Like `git`, `rad <name>` runs `rad-<name>` when `<name>` isn't a built-in command,
+//! so that third parties can ship commands of their own. External commands are
+//! given the same arguments and environment as built-in commands, with the profile
+//! resolved by `rad` set in [`crate::env::RAD_PROFILE`], so that they use the same
+//! profile, eg. when the working copy is pinned to one.
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Prefix of external command executables.
+pub const PREFIX: &str = "rad-";
+
+/// Find the executable of an external command on `PATH`.
+pub fn find(name: &str) -> Option<PathBuf> {
+    let exe = format!("{}{}", PREFIX, name);
+
+    paths()
+        .map(|dir| dir.join(&exe))
+        .find(|path| is_executable(path))
+}
+
+/// List the external commands found on `PATH`, by name. When a command is found
+/// more than once, the first one found is used, as when running it.
+pub fn list() -> BTreeMap<String, PathBuf> {
+    let mut commands = BTreeMap::new();
+
+    for dir in paths() {
+        let entries = match dir.read_dir() {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = match path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.strip_prefix(PREFIX))
+            {
+                Some(name) if !name.is_empty() => name.to_owned(),
+                _ => continue,
+            };
+            if is_executable(&path) {
+                commands.entry(name).or_insert(path);
+            }
+        }
+    }
+    commands
+}
+
+/// Directories of `PATH`, in order.
+fn paths() -> impl Iterator<Item = PathBuf> {
+    env::var_os("PATH")
+        .map(|path| env::split_paths(&path).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+}
+
+/// Check whether a path is an executable file.
+#[cfg(unix)]
+pub(crate) fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
@@ -1,5 +1,6 @@
 //! User profile related functions.
-use std::{env, fmt, path};
+use std::fmt;
+use std::str::FromStr;
 
 use anyhow::{anyhow, Error, Result};
 use serde::{de::DeserializeOwned, Serialize};
@@ -8,56 +9,105 @@ pub use librad::profile::{LnkHome, Profile, ProfileId};
 
 use librad::crypto::{
     keystore::{FileStorage, Keystore as _},
-    PublicKey, SecretKey,
+    BoxedSigner, PublicKey, SecretKey,
 };
+use librad::git::Urn;
 use librad::PeerId;
 use librad::{git::storage::ReadOnly, git::Storage, keystore::crypto::Crypto};
 
 use crate::args;
+use crate::error::{self, WithKind as _};
 use crate::keys;
+use crate::signer::PublicKeySigner;
 
-/// Environment var that sets the radicle home directory.
-pub const RAD_HOME: &str = "RAD_HOME";
+pub use crate::env::RAD_HOME;
 
-/// Create a new profile.
+/// Create a new profile, and make it the active profile.
 pub fn create<C: Crypto>(home: impl Into<LnkHome>, crypto: C) -> Result<(Profile, PeerId)>
 where
     C::Error: fmt::Debug + fmt::Display + Send + Sync + 'static,
     C::SecretBox: Serialize + DeserializeOwned,
 {
     let home = home.into();
-    let profile = Profile::new(&home)?;
+    let (profile, peer_id) = new(&home, crypto)?;
 
     Profile::set(&home, profile.id().clone())?;
 
+    Ok((profile, peer_id))
+}
+
+/// Create a new profile, without making it the active profile.
+pub fn new<C: Crypto>(home: &LnkHome, crypto: C) -> Result<(Profile, PeerId)>
+where
+    C::Error: fmt::Debug + fmt::Display + Send + Sync + 'static,
+    C::SecretBox: Serialize + DeserializeOwned,
+{
+    let profile = Profile::new(home)?;
     let key = SecretKey::new();
-    let mut store: FileStorage<C, PublicKey, SecretKey, _> =
-        FileStorage::new(&profile.paths().keys_dir().join(keys::KEY_FILE), crypto);
 
-    store.put_key(key.clone())?;
-    Storage::open(profile.paths(), key.clone())?;
+    // Don't leave an empty profile behind if the key or storage can't be created.
+    let result = (|| -> Result<()> {
+        let mut store: FileStorage<C, PublicKey, SecretKey, _> =
+            FileStorage::new(&profile.paths().keys_dir().join(keys::KEY_FILE), crypto);
 
+        store.put_key(key.clone())?;
+        Storage::open(profile.paths(), key.clone())?;
+
+        Ok(())
+    })();
+    if let Err(err) = result {
+        delete(&profile).ok();
+        return Err(err);
+    }
     Ok((profile, PeerId::from(key)))
 }
 
-/// Get the radicle home.
-pub fn home() -> LnkHome {
-    let home = env::var(RAD_HOME)
-        .map(|h| LnkHome::Root(path::Path::new(&h).to_path_buf()))
-        .ok();
+/// Delete a profile, including its keys and storage. The active profile is left as is.
+pub fn delete(profile: &Profile) -> Result<(), Error> {
+    let dir = profile
+        .paths()
+        .keys_dir()
+        .parent()
+        .ok_or_else(|| anyhow!("profile {} has no directory", profile.id()))?;
+
+    std::fs::remove_dir_all(dir)?;
+
+    Ok(())
+}
 
-    home.unwrap_or_default()
+/// Get the radicle home. Can be overridden with [`RAD_HOME`].
+pub fn home() -> LnkHome {
+    crate::env::home().map(LnkHome::Root).unwrap_or_default()
 }
 
-/// Get the default profile. Fails if there is no profile.
+/// Git configuration key that pins a working copy to a profile.
+pub const CONFIG_PROFILE_KEY: &str = "rad.profile";
+
+/// Get the default profile. This is the profile set with [`crate::env::RAD_PROFILE`] if
+/// any, or else the profile pinned by the working copy, or else the active profile.
+/// Fails if there is no profile.
 pub fn default() -> Result<Profile, Error> {
+    if let Some(id) = crate::env::profile()? {
+        return find(&id, "Unset `RAD_PROFILE` to use the active profile.");
+    }
+    if let Some(id) = pinned()? {
+        return find(
+            &id,
+            "This working copy is pinned to another profile, run `rad auth --unpin` to unpin it.",
+        );
+    }
+
     let error = args::Error::WithHint {
-        err: anyhow!("Could not load radicle profile"),
+        err: error::Error::new(error::Kind::Auth, anyhow!("Could not load radicle profile")).into(),
         hint: "To setup your radicle profile, run `rad auth`.",
     };
 
     let not_active_error = args::Error::WithHint {
-        err: anyhow!("Could not load active radicle profile"),
+        err: error::Error::new(
+            error::Kind::Auth,
+            anyhow!("Could not load active radicle profile"),
+        )
+        .into(),
         hint: "To setup your radicle profile, run `rad auth --init`.",
     };
 
@@ -68,16 +118,92 @@ pub fn default() -> Result<Profile, Error> {
     }
 }
 
+/// Find a profile by ID.
+fn find(id: &ProfileId, hint: &'static str) -> Result<Profile, Error> {
+    list()?.into_iter().find(|p| p.id() == id).ok_or_else(|| {
+        args::Error::WithHint {
+            err: error::Error::new(
+                error::Kind::Auth,
+                anyhow!("Could not find radicle profile {}", id),
+            )
+            .into(),
+            hint,
+        }
+        .into()
+    })
+}
+
+/// Get the profile pinned by the working copy of the current directory, if any.
+pub fn pinned() -> Result<Option<ProfileId>, Error> {
+    let repo = match git2::Repository::open_from_env() {
+        Ok(repo) => repo,
+        Err(_) => return Ok(None),
+    };
+    let id = match repo.config()?.get_string(CONFIG_PROFILE_KEY) {
+        Ok(id) => id,
+        Err(_) => return Ok(None),
+    };
+    let id = ProfileId::from_str(&id)
+        .map_err(|_| anyhow!("invalid profile ID '{}' in `{}`", id, CONFIG_PROFILE_KEY))?;
+
+    Ok(Some(id))
+}
+
+/// Pin a working copy to a profile, so that commands run in it use this profile
+/// instead of the active profile.
+pub fn pin(repo: &git2::Repository, id: &ProfileId) -> Result<(), Error> {
+    repo.config()?
+        .set_str(CONFIG_PROFILE_KEY, &id.to_string())?;
+
+    Ok(())
+}
+
+/// Unpin a working copy from its profile. Returns whether it was pinned.
+pub fn unpin(repo: &git2::Repository) -> Result<bool, Error> {
+    let mut config = repo.config()?.open_level(git2::ConfigLevel::Local)?;
+
+    match config.remove(CONFIG_PROFILE_KEY) {
+        Ok(()) => Ok(true),
+        Err(err) if err.code() == git2::ErrorCode::NotFound => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Identity details of a profile, loaded from its storage.
+#[derive(Debug, Clone)]
+pub struct Info {
+    /// Peer ID of the profile's device key.
+    pub peer: PeerId,
+    /// URN of the profile's personal identity, if any.
+    pub urn: Option<Urn>,
+    /// Name of the profile's personal identity, if any.
+    pub name: Option<String>,
+}
+
+/// Load the identity details of a profile. This opens the profile's storage, so
+/// it should only be called for the profiles that are shown, not every listed profile.
+pub fn info(profile: &Profile) -> Result<Info, Error> {
+    let storage = read_only(profile)?;
+    let config = storage.config()?;
+
+    Ok(Info {
+        peer: *storage.peer_id(),
+        urn: config.user()?,
+        name: config.user_name().ok(),
+    })
+}
+
 /// Get a profile's name. If none is given, get the default profile's name.
 pub fn name(profile: Option<&Profile>) -> Result<String, Error> {
-    let default = default()?;
-    let read_only = read_only(profile.unwrap_or(&default))?;
-    let config = read_only.config()?;
-
-    Ok(config.user_name()?)
+    let info = match profile {
+        Some(profile) => info(profile)?,
+        None => info(&default()?)?,
+    };
+    info.name.ok_or_else(|| anyhow!("profile has no user name"))
 }
 
-/// List all profiles.
+/// List all profiles. This is cheap, since profiles' storage isn't opened: use
+/// [`info`] to load the details of a profile.
 pub fn list() -> Result<Vec<Profile>, Error> {
     Profile::list(&home()).map_err(|e| e.into())
 }
@@ -98,7 +224,17 @@ pub fn set(id: &ProfileId) -> Result<(), Error> {
 
 /// Open read-only storage.
 pub fn read_only(profile: &Profile) -> Result<ReadOnly, Error> {
-    let storage = ReadOnly::open(profile.paths())?;
+    let storage = ReadOnly::open(profile.paths()).with_kind(error::Kind::Storage)?;
+
+    Ok(storage)
+}
+
+/// Open storage without the signing key, to read collaborative objects, which need
+/// [`Storage`]. Anything that has to sign fails.
+pub fn read_storage(profile: &Profile) -> Result<Storage, Error> {
+    let peer_id = *read_only(profile)?.peer_id();
+    let signer = BoxedSigner::new(PublicKeySigner::new(&peer_id));
+    let storage = Storage::open(profile.paths(), signer).with_kind(error::Kind::Storage)?;
 
     Ok(storage)
 }
@@ -2,8 +2,8 @@
 use std::collections::{HashMap, HashSet};
 use std::convert::{TryFrom, TryInto};
 use std::fmt;
-use std::iter;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 use anyhow::{anyhow, Result};
 use either::Either;
@@ -15,6 +15,7 @@ use librad::crypto::BoxedSigner;
 use librad::git::identities::{self, project, Project};
 use librad::git::local::transport;
 use librad::git::local::url::LocalUrl;
+use librad::git::refs::Refs;
 use librad::git::storage::{ReadOnly, Storage};
 use librad::git::tracking;
 use librad::git::types::remote::Remote;
@@ -26,14 +27,15 @@ use librad::identities::SomeIdentity;
 use librad::identities::{Person, VerifiedProject};
 use librad::paths::Paths;
 use librad::profile::Profile;
-use librad::PeerId;
+use librad::{PeerId, PublicKey};
 
 use lnk_identities;
 use lnk_identities::working_copy_dir::WorkingCopyDir;
 
 use crate as common;
 use crate::person::Ens;
-use crate::{git, person};
+use crate::seed::credentials;
+use crate::{error, git, offline, person, proxy};
 
 /// URL scheme for radicle resources.
 pub const URL_SCHEME: &str = "rad";
@@ -41,6 +43,42 @@ pub const URL_SCHEME: &str = "rad";
 /// Prefix for remote tracking branches from peers.
 pub const PEER_PREFIX: &str = "peers";
 
+lazy_static::lazy_static! {
+    static ref LICENSE_NAMESPACE: Url = "https://radicle.xyz/project/license/v1"
+        .parse()
+        .expect("static URL malformed");
+    static ref FORK_NAMESPACE: Url = "https://radicle.xyz/project/fork/v1"
+        .parse()
+        .expect("static URL malformed");
+}
+
+/// License payload, recording under which license a project is published.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct License {
+    /// SPDX identifier of the license, eg. `MIT`.
+    pub id: String,
+}
+
+impl payload::HasNamespace for License {
+    fn namespace() -> &'static Url {
+        &LICENSE_NAMESPACE
+    }
+}
+
+/// Fork payload, recording which project a project was forked from.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Fork {
+    /// The project that was forked.
+    #[serde(deserialize_with = "deserialize_urn")]
+    pub upstream: Urn,
+}
+
+impl payload::HasNamespace for Fork {
+    fn namespace() -> &'static Url {
+        &FORK_NAMESPACE
+    }
+}
+
 /// Project indirect contributor identity.
 #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct PeerIdentity {
@@ -140,6 +178,43 @@ impl Delegate {
             Self::Indirect { ids, .. } => ids.contains(other),
         }
     }
+
+    /// Get the name of the delegate's personal identity, if it is found in storage.
+    pub fn name<S: AsRef<ReadOnly>>(&self, storage: &S) -> Option<String> {
+        match self {
+            Self::Direct { .. } => None,
+            Self::Indirect { urn, .. } => identities::person::get(&storage, urn)
+                .ok()
+                .flatten()
+                .map(|p| p.subject().name.to_string()),
+        }
+    }
+
+    /// Get the keys of the delegate.
+    pub fn keys(&self) -> Vec<PeerId> {
+        match self {
+            Self::Direct { id } => vec![*id],
+            Self::Indirect { ids, .. } => ids.iter().copied().collect(),
+        }
+    }
+}
+
+/// Get the keys of a project's delegates, with the name to show them by: the name of the
+/// delegate's personal identity if it is known, or else the key itself.
+pub fn delegate_keys<S: AsRef<ReadOnly>>(
+    storage: &S,
+    project: &Metadata,
+) -> HashMap<PeerId, String> {
+    let mut keys = HashMap::new();
+
+    for delegate in &project.delegates {
+        let name = delegate.name(storage);
+
+        for id in delegate.keys() {
+            keys.insert(id, name.clone().unwrap_or_else(|| common::fmt::peer(&id)));
+        }
+    }
+    keys
 }
 
 /// Project metadata.
@@ -190,8 +265,13 @@ impl Metadata {
 
     /// Get a [`VerifiedProject`] from project metadata.
     pub fn verified(&self, storage: &Storage) -> anyhow::Result<VerifiedProject> {
-        identities::project::verify(storage, &self.urn)?
-            .ok_or_else(|| anyhow::anyhow!("project {} not found", self.urn))
+        identities::project::verify(storage, &self.urn)?.ok_or_else(|| {
+            error::Error::new(
+                error::Kind::NotFound,
+                anyhow!("project {} not found", self.urn),
+            )
+            .into()
+        })
     }
 }
 
@@ -263,26 +343,210 @@ impl TryFrom<librad::identities::Project> for Metadata {
 }
 
 /// Create a project payload.
-pub fn payload(name: String, description: String, default_branch: String) -> payload::Project {
-    payload::Project {
+pub fn payload(name: String, description: String, default_branch: String) -> ProjectPayload {
+    ProjectPayload::new(payload::Project {
         name: Cstring::from(name),
         description: Some(Cstring::from(description)),
         default_branch: Some(Cstring::from(default_branch)),
-    }
+    })
 }
 
-/// Create a new project identity.
-pub fn create(payload: payload::Project, storage: &Storage) -> anyhow::Result<Project> {
+/// Create a new project identity, delegated to the local identity and the given
+/// delegates. Delegates are either public keys, or personal identities that must be
+/// in local storage.
+pub fn create(
+    payload: ProjectPayload,
+    delegates: &[Either<PublicKey, Urn>],
+    storage: &Storage,
+) -> anyhow::Result<Project> {
     let whoami = person::local(storage)?;
-    let payload = ProjectPayload::new(payload);
-    let delegations = identities::IndirectDelegation::try_from_iter(iter::once(Either::Right(
-        whoami.clone().into_inner().into_inner(),
-    )))?;
+    let mut delegations = vec![Either::Right(whoami.clone().into_inner().into_inner())];
+
+    for delegate in delegates {
+        if delegate.as_ref().right() != Some(&whoami.urn()) {
+            delegations.push(resolve_delegate(storage, delegate)?);
+        }
+    }
+    let delegations = identities::IndirectDelegation::try_from_iter(delegations.into_iter())
+        .map_err(|err| anyhow!("invalid project delegates: {}", err))?;
     let project = project::create(storage, whoami, payload, delegations)?;
 
     Ok(project)
 }
 
+/// Parse a delegate, given by personal URN or Peer ID.
+pub fn parse_delegate(s: &str) -> anyhow::Result<Either<PublicKey, Urn>> {
+    if let Ok(urn) = Urn::from_str(s) {
+        Ok(Either::Right(urn))
+    } else if let Ok(peer) = PeerId::from_str(s) {
+        Ok(Either::Left(*peer.as_public_key()))
+    } else {
+        Err(anyhow!(
+            "invalid delegate '{}', expected a personal URN or Peer ID",
+            s
+        ))
+    }
+}
+
+/// Resolve a delegate given by key or personal URN. Personal identities must be in
+/// local storage.
+fn resolve_delegate(
+    storage: &Storage,
+    delegate: &Either<PublicKey, Urn>,
+) -> anyhow::Result<Either<PublicKey, Person>> {
+    match delegate {
+        Either::Left(key) => Ok(Either::Left(*key)),
+        Either::Right(urn) => {
+            let person = person::verify(storage, urn)?.ok_or_else(|| {
+                error::Error::new(
+                    error::Kind::NotFound,
+                    anyhow!(
+                        "delegate {} was not found in local storage, fetch it first",
+                        urn
+                    ),
+                )
+            })?;
+            Ok(Either::Right(person.into_inner()))
+        }
+    }
+}
+
+/// Whether a delegation is the given key or personal URN, or a personal identity
+/// with the given key.
+fn is_delegate(delegation: Either<&PublicKey, &Person>, delegate: &Either<PublicKey, Urn>) -> bool {
+    match (delegation, delegate) {
+        (Either::Left(pk), Either::Left(key)) => pk == key,
+        (Either::Right(person), Either::Left(key)) => person.delegations().contains(key),
+        (Either::Right(person), Either::Right(urn)) => &person.urn() == urn,
+        (Either::Left(_), Either::Right(_)) => false,
+    }
+}
+
+/// Number of delegate signatures needed to update an identity document with the given
+/// number of delegates. This is a majority of them.
+pub fn quorum(delegates: usize) -> usize {
+    delegates / 2 + 1
+}
+
+/// Update the delegates of a project, by adding or removing one. The update is signed
+/// by the local peer, which must be a delegate. If the project has other delegates,
+/// it only takes effect once a [`quorum`] of them have accepted it.
+pub fn update_delegates(
+    storage: &Storage,
+    urn: &Urn,
+    add: Option<&Either<PublicKey, Urn>>,
+    remove: Option<&Either<PublicKey, Urn>>,
+) -> anyhow::Result<Project> {
+    let current = project::get(storage, urn)?.ok_or_else(|| {
+        error::Error::new(
+            error::Kind::NotFound,
+            anyhow!("project {} was not found", urn),
+        )
+    })?;
+    let local = Either::Left(*storage.peer_id().as_public_key());
+
+    if !current.delegations().iter().any(|d| is_delegate(d, &local)) {
+        return Err(error::Error::new(
+            error::Kind::Auth,
+            anyhow!("you are not a delegate of project {}", urn),
+        )
+        .into());
+    }
+
+    let mut delegations: Vec<Either<PublicKey, Person>> = current
+        .delegations()
+        .iter()
+        .map(|d| d.map_left(|pk| *pk).map_right(|p| p.clone()))
+        .collect();
+
+    if let Some(delegate) = add {
+        if delegations
+            .iter()
+            .any(|d| is_delegate(d.as_ref(), delegate))
+        {
+            return Err(error::Error::new(
+                error::Kind::Conflict,
+                anyhow!("{} is already a delegate", fmt_delegate(delegate)),
+            )
+            .into());
+        }
+        delegations.push(resolve_delegate(storage, delegate)?);
+    }
+    if let Some(delegate) = remove {
+        let len = delegations.len();
+        delegations.retain(|d| !is_delegate(d.as_ref(), delegate));
+
+        if delegations.len() == len {
+            return Err(error::Error::new(
+                error::Kind::NotFound,
+                anyhow!("{} is not a delegate", fmt_delegate(delegate)),
+            )
+            .into());
+        }
+        if delegations.is_empty() {
+            anyhow::bail!("the last delegate of a project cannot be removed");
+        }
+    }
+    let delegations = identities::IndirectDelegation::try_from_iter(delegations.into_iter())
+        .map_err(|err| anyhow!("invalid project delegates: {}", err))?;
+    let project = project::update(
+        storage,
+        urn,
+        None,
+        current.payload().clone(),
+        Some(delegations),
+    )?;
+
+    Ok(project)
+}
+
+/// Accept an update to a project's identity document made by another delegate, by
+/// signing it with the local peer.
+pub fn accept_update(storage: &Storage, urn: &Urn, peer: PeerId) -> anyhow::Result<Project> {
+    let project = project::merge(storage, urn, peer)?;
+
+    Ok(project)
+}
+
+fn fmt_delegate(delegate: &Either<PublicKey, Urn>) -> String {
+    match delegate {
+        Either::Left(key) => PeerId::from(*key).default_encoding(),
+        Either::Right(urn) => urn.to_string(),
+    }
+}
+
+/// Fork a project. This creates a new project with the same metadata, delegated to the
+/// local identity, and records the project it was forked from. Its default branch
+/// starts at the given head, which must be in storage.
+pub fn fork(storage: &Storage, upstream: &Metadata, head: git::Oid) -> anyhow::Result<Project> {
+    let payload = self::payload(
+        upstream.name.clone(),
+        upstream.description.clone(),
+        upstream.default_branch.to_string(),
+    )
+    .with_ext(Fork {
+        upstream: upstream.urn.clone(),
+    })?;
+    let project = self::create(payload, &[], storage)?;
+    let urn = project.urn();
+
+    // Nb. the git2 crate doesn't handle namespaces properly, so we specify it manually.
+    let repo = git::Repository::open_bare(storage.as_ref().path())?;
+    repo.reference(
+        &format!(
+            "refs/namespaces/{}/refs/heads/{}",
+            urn.encode_id(),
+            upstream.default_branch
+        ),
+        head,
+        false,
+        &format!("fork of {}", upstream.urn),
+    )?;
+    Refs::update(storage, &urn)?;
+
+    Ok(project)
+}
+
 /// Initialize a repo as a project.
 pub fn init(
     project: &Project,
@@ -465,16 +729,75 @@ pub fn list_seed_heads(
     url: &Url,
     project: &Urn,
 ) -> anyhow::Result<HashMap<PeerId, Vec<(String, git::Oid)>>> {
+    let mut remotes = HashMap::new();
+
+    for (name, oid) in list_seed_refs(repo, url, project)? {
+        if let Some((peer, r)) = git::parse_remote(&name) {
+            if let Some(branch) = r.strip_prefix("heads/") {
+                let value = (branch.to_owned(), oid);
+                remotes.entry(peer).or_insert_with(Vec::new).push(value);
+            }
+        }
+    }
+    Ok(remotes)
+}
+
+/// List all refs of a project on a seed, given the URL of the seed's git server.
+pub fn list_seed_refs(
+    repo: &git::Repository,
+    url: &Url,
+    project: &Urn,
+) -> anyhow::Result<Vec<(String, git::Oid)>> {
+    offline::check("list refs on seed")?;
+
     let url = url.join(&project.encode_id())?;
     let mut remote = repo.remote_anonymous(url.as_str())?;
+    let proxy_options = proxy::git2_options(url.as_str())?;
+
+    remote.connect_auth(
+        git::Direction::Fetch,
+        Some(credentials::callbacks()),
+        Some(proxy_options),
+    )?;
+
+    let refs = remote
+        .list()?
+        .iter()
+        .map(|head| (head.name().to_owned(), head.oid()))
+        .collect();
+
+    Ok(refs)
+}
+
+/// List the heads of a project in local storage, for each peer, including the local peer.
+pub fn list_local_heads<S>(
+    storage: &S,
+    urn: &Urn,
+) -> anyhow::Result<HashMap<PeerId, Vec<(String, git::Oid)>>>
+where
+    S: AsRef<ReadOnly>,
+{
+    let storage = storage.as_ref();
+    let repo = git::Repository::open_bare(storage.path())?;
+    let namespace = format!("refs/namespaces/{}/", urn.encode_id());
     let mut remotes = HashMap::new();
 
-    remote.connect(git::Direction::Fetch)?;
+    for reference in repo.references_glob(&format!("{}refs/*", namespace))? {
+        let reference = reference?;
+        let (name, oid) = match (reference.name(), reference.target()) {
+            (Some(name), Some(oid)) => (&name[namespace.len()..], oid),
+            _ => continue,
+        };
 
-    for head in remote.list()? {
-        if let Some((peer, r)) = git::parse_remote(head.name()) {
+        if let Some(branch) = name.strip_prefix("refs/heads/") {
+            let value = (branch.to_owned(), oid);
+            remotes
+                .entry(*storage.peer_id())
+                .or_insert_with(Vec::new)
+                .push(value);
+        } else if let Some((peer, r)) = git::parse_remote(name) {
             if let Some(branch) = r.strip_prefix("heads/") {
-                let value = (branch.to_owned(), head.oid());
+                let value = (branch.to_owned(), oid);
                 remotes.entry(peer).or_insert_with(Vec::new).push(value);
             }
         }
@@ -0,0 +1,166 @@
+//! Proxy support for connections to seeds over HTTP.
+//!
+//! The proxy is set with the `network.proxy` setting, or else with the standard
+//! `HTTPS_PROXY` and `ALL_PROXY` environment variables. It is used for the seed API,
+//! and for git connections to a seed's git server, eg. when cloning from a git URL.
+//! Hosts listed in the standard `NO_PROXY` environment variable are connected to
+//! directly.
+//!
+//! Nb. Syncing with seeds uses the peer-to-peer protocol, which runs over QUIC, ie. UDP,
+//! and can't go through an HTTP or SOCKS5 proxy.
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use url::Url;
+
+use crate::{config, env, offline};
+
+/// Proxy URL schemes that are supported.
+pub const SCHEMES: &[&str] = &["http", "https", "socks5", "socks5h"];
+
+/// Standard environment variables that set a proxy, in order of precedence.
+pub const ENV: &[&str] = &["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"];
+
+/// Standard environment variables that set the hosts not to use a proxy for.
+pub const NO_PROXY_ENV: &[&str] = &["NO_PROXY", "no_proxy"];
+
+/// Get the proxy to connect to seeds through, if any.
+pub fn url() -> anyhow::Result<Option<Url>> {
+    let (value, source) = if let Some(setting) = config::get("network.proxy")? {
+        (setting.value, setting.source.to_string())
+    } else if let Some((var, value)) = ENV.iter().find_map(|v| env::var(v).map(|val| (v, val))) {
+        (value, format!("`{}`", var))
+    } else {
+        return Ok(None);
+    };
+    parse(&value)
+        .map(Some)
+        .map_err(|e| anyhow!("{} ({})", e, source))
+}
+
+/// Get the proxy to connect to the given URL through, if any. Hosts listed in
+/// `NO_PROXY` are connected to directly.
+pub fn url_for(target: &str) -> anyhow::Result<Option<Url>> {
+    let host = Url::parse(target)
+        .ok()
+        .and_then(|url| url.host_str().map(|h| h.to_owned()));
+
+    if let Some(host) = host {
+        if let Some(no_proxy) = NO_PROXY_ENV.iter().find_map(|v| env::var(v)) {
+            if is_excluded(&no_proxy, &host) {
+                return Ok(None);
+            }
+        }
+    }
+    url()
+}
+
+/// Check whether a host is listed in a `NO_PROXY` value, ie. a comma-separated list of
+/// domains, which also match their subdomains, or `*` for all hosts.
+fn is_excluded(no_proxy: &str, host: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+
+    no_proxy
+        .split(',')
+        .map(|entry| entry.trim().trim_start_matches('.'))
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| {
+            entry == "*"
+                || host.eq_ignore_ascii_case(entry)
+                || host
+                    .to_ascii_lowercase()
+                    .ends_with(&format!(".{}", entry.to_ascii_lowercase()))
+        })
+}
+
+/// Parse a proxy URL.
+pub fn parse(value: &str) -> anyhow::Result<Url> {
+    let url = Url::from_str(value).map_err(|e| anyhow!("invalid proxy '{}': {}", value, e))?;
+
+    if !SCHEMES.contains(&url.scheme()) {
+        anyhow::bail!(
+            "invalid proxy '{}': scheme must be one of {}",
+            value,
+            SCHEMES.join(", ")
+        );
+    }
+    if url.host().is_none() {
+        anyhow::bail!("invalid proxy '{}': host is missing", value);
+    }
+    Ok(url)
+}
+
+/// Get an HTTP agent to connect to the given URL, through the proxy if any. Fails in
+/// offline mode.
+pub fn agent(target: &str) -> anyhow::Result<ureq::Agent> {
+    offline::check("connect to seeds")?;
+
+    let mut builder = ureq::AgentBuilder::new();
+
+    if let Some(url) = url_for(target)? {
+        builder = builder.proxy(ureq::Proxy::new(url.as_str())?);
+    }
+    Ok(builder.build())
+}
+
+/// Get the git configuration arguments that make git connect to the given URL through
+/// the proxy, if any, eg. `-c http.proxy=socks5://localhost:9050`.
+pub fn git_args(target: &str) -> anyhow::Result<Vec<String>> {
+    Ok(url_for(target)?
+        .map(|url| vec![String::from("-c"), format!("http.proxy={}", url)])
+        .unwrap_or_default())
+}
+
+/// Remove the credentials of a URL, eg. of a proxy, so that it can be shown or logged.
+pub fn redact(url: &Url) -> Url {
+    let mut url = url.clone();
+
+    if !url.username().is_empty() || url.password().is_some() {
+        // Nb. This only fails for URLs that can't have credentials in the first place.
+        url.set_username("***").ok();
+        url.set_password(None).ok();
+    }
+    url
+}
+
+/// Get the proxy options of a libgit2 connection to the given URL. Fails if the proxy
+/// is a SOCKS5 proxy, since libgit2 only supports HTTP proxies.
+pub fn git2_options(target: &str) -> anyhow::Result<git2::ProxyOptions<'static>> {
+    let mut options = git2::ProxyOptions::new();
+
+    if let Some(url) = url_for(target)? {
+        if url.scheme().starts_with("socks5") {
+            anyhow::bail!(
+                "proxy '{}' can't be used to list refs on a seed, only HTTP proxies are supported",
+                redact(&url)
+            );
+        }
+        options.url(url.as_str());
+    }
+    Ok(options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert!(parse("socks5h://localhost:9050").is_ok());
+        assert!(parse("http://proxy.example.com:3128").is_ok());
+        assert!(parse("ftp://proxy.example.com").is_err());
+        assert!(parse("localhost:9050").is_err());
+    }
+
+    #[test]
+    fn test_is_excluded() {
+        assert!(is_excluded("*", "seed.radicle.xyz"));
+        assert!(is_excluded("localhost, radicle.xyz", "seed.radicle.xyz"));
+        assert!(is_excluded(".radicle.xyz", "seed.radicle.xyz"));
+        assert!(is_excluded("Radicle.xyz", "radicle.xyz"));
+        assert!(is_excluded("::1", "[::1]"));
+        assert!(!is_excluded("radicle.xyz", "notradicle.xyz"));
+        assert!(!is_excluded("localhost", "seed.radicle.xyz"));
+        assert!(!is_excluded("", "seed.radicle.xyz"));
+    }
+}
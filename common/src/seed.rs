@@ -1,4 +1,7 @@
 //! Seed-related functionality.
+pub mod credentials;
+pub mod discovery;
+
 use std::convert::TryFrom;
 use std::net;
 use std::path::Path;
@@ -11,7 +14,7 @@ use url::{Host, Url};
 
 use crate::args::Error;
 use crate::sync::Seed;
-use crate::{git, project};
+use crate::{config, git, project, proxy};
 
 pub const CONFIG_SEED_KEY: &str = "rad.seed";
 pub const CONFIG_PEER_KEY: &str = "rad.peer";
@@ -30,6 +33,25 @@ pub struct Commit {
     pub header: CommitHeader,
 }
 
+/// A seed's HTTP API, as described by its root endpoint.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default)]
+pub struct ApiInfo {
+    /// Name of the service, eg. `radicle-http-api`.
+    pub service: Option<String>,
+    /// Version of the service.
+    pub version: Option<String>,
+    /// Endpoints offered by the API.
+    pub links: Vec<ApiLink>,
+}
+
+/// An endpoint offered by a seed's HTTP API, eg. `projects`.
+#[derive(Debug, serde::Deserialize)]
+pub struct ApiLink {
+    pub rel: String,
+    pub href: String,
+}
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum Protocol {
     Link { peer: Option<PeerId> },
@@ -213,17 +235,87 @@ impl FromStr for Address {
     }
 }
 
-/// Parse a seed value from an options parser.
+/// Parse a seed value from an options parser. The value is either a seed address, the
+/// name of a seed added with `rad seed add`, or the domain name of a seed to discover.
 pub fn parse_value(parser: &mut lexopt::Parser) -> anyhow::Result<Seed<String>> {
     let value = parser.value()?;
     let value = value.to_string_lossy();
     let value = value.as_ref();
-    let seed = Seed::from_str(value).map_err(|_| Error::WithHint {
+
+    if let Ok(seed) = Seed::from_str(value) {
+        return Ok(seed);
+    }
+    if let Some(seed) = config::seeds()?.remove(value) {
+        return Ok(seed);
+    }
+    if discovery::is_domain(value) {
+        return Ok(discovery::discover(value)?.seed(value));
+    }
+    Err(Error::WithHint {
         err: anyhow!("invalid seed address specified: '{}'", value),
-        hint: "hint: valid seed addresses have the format <peer-id>@<addr>, eg. hyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa@pine.radicle.garden:8776",
-    })?;
+        hint: "hint: valid seed addresses have the format <peer-id>@<addr>, eg. hyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa@pine.radicle.garden:8776, or are names of seeds listed by `rad seed list`, or domain names of seeds",
+    }
+    .into())
+}
+
+/// Get the seeds of the working copy, set with `rad.seed` in its git configuration. The
+/// setting may be given more than once, for several seeds, and may name a seed added
+/// with `rad seed add`. Returns no seeds outside of a working copy.
+pub fn get_project_seeds() -> Result<Vec<Seed<String>>> {
+    let repo = match git2::Repository::open_from_env() {
+        Ok(repo) => repo,
+        Err(_) => return Ok(Vec::new()),
+    };
+    let config = repo.config()?;
+    let entries = config.multivar(CONFIG_SEED_KEY, None)?;
+    let mut named = None;
+    let mut seeds = Vec::new();
+
+    for entry in &entries {
+        let entry = entry?;
+        let value = entry.value().unwrap_or_default();
+
+        if let Ok(seed) = Seed::from_str(value) {
+            seeds.push(seed);
+            continue;
+        }
+        let named = match &mut named {
+            Some(named) => named,
+            None => named.insert(config::seeds()?),
+        };
+        let seed = named.get(value).cloned().ok_or_else(|| {
+            anyhow!(
+                "invalid seed '{}' in `{}` git configuration, expected a seed address or name",
+                value,
+                CONFIG_SEED_KEY
+            )
+        })?;
+        seeds.push(seed);
+    }
+    Ok(seeds)
+}
+
+/// Set the seeds of the working copy, replacing the ones that were set.
+pub fn set_project_seeds(seeds: &[Seed<String>]) -> Result<()> {
+    let path = Path::new(".");
+
+    // Fails if no seeds were set.
+    git::git(path, ["config", "--local", "--unset-all", CONFIG_SEED_KEY]).ok();
 
-    Ok(seed)
+    for seed in seeds {
+        let seed = Seed {
+            label: None,
+            ..seed.clone()
+        }
+        .to_string();
+
+        git::git(
+            path,
+            ["config", "--local", "--add", CONFIG_SEED_KEY, seed.as_str()],
+        )
+        .context("failed to save seed configuration")?;
+    }
+    Ok(())
 }
 
 /// Set the configured "peer" seed within the local repository.
@@ -250,12 +342,21 @@ pub fn get_peer_seed(peer_id: &PeerId) -> Result<Url, anyhow::Error> {
     Ok(url)
 }
 
+/// Get the URL of a seed's git server, from the seed's address.
+pub fn git_url(seed: &Seed<String>) -> Result<Url> {
+    let mut url = Url::from_str(&format!("https://{}", seed.addrs))
+        .map_err(|e| anyhow!("invalid seed address '{}': {}", seed.addrs, e))?;
+    url.set_port(None).ok();
+
+    Ok(url)
+}
+
 /// Query a seed node for its [`PeerId`].
 pub fn get_seed_id(mut seed: Url) -> Result<PeerId, anyhow::Error> {
     seed.set_port(Some(DEFAULT_SEED_API_PORT)).unwrap();
     seed = seed.join("/v1/peer")?;
 
-    let agent = ureq::Agent::new();
+    let agent = proxy::agent(seed.as_str())?;
     let obj: serde_json::Value = agent.get(seed.as_str()).call()?.into_json()?;
 
     let id = obj
@@ -268,6 +369,31 @@ pub fn get_seed_id(mut seed: Url) -> Result<PeerId, anyhow::Error> {
     Ok(id)
 }
 
+/// Query a seed node for information about its API, eg. its version.
+pub fn get_seed_info(mut seed: Url) -> Result<ApiInfo, anyhow::Error> {
+    seed.set_port(Some(DEFAULT_SEED_API_PORT)).unwrap();
+    seed = seed.join("/")?;
+
+    let agent = proxy::agent(seed.as_str())?;
+    let val: serde_json::Value = agent.get(seed.as_str()).call()?.into_json()?;
+    let info = serde_json::from_value(val)?;
+
+    Ok(info)
+}
+
+/// Query a seed node for whether it hosts a project.
+pub fn has_project(mut seed: Url, project: &Urn) -> Result<bool, anyhow::Error> {
+    seed.set_port(Some(DEFAULT_SEED_API_PORT)).unwrap();
+    seed = seed.join(&format!("/v1/projects/{}", project))?;
+
+    let agent = proxy::agent(seed.as_str())?;
+    match agent.get(seed.as_str()).call() {
+        Ok(_) => Ok(true),
+        Err(ureq::Error::Status(404, _)) => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
 /// Query a seed node for a project commit.
 pub fn get_commit(
     mut seed: Url,
@@ -277,7 +403,7 @@ pub fn get_commit(
     seed.set_port(Some(DEFAULT_SEED_API_PORT)).unwrap();
     seed = seed.join(&format!("/v1/projects/{}/commits/{}", project, commit))?;
 
-    let agent = ureq::Agent::new();
+    let agent = proxy::agent(seed.as_str())?;
     let val: serde_json::Value = agent.get(seed.as_str()).call()?.into_json()?;
     let commit = serde_json::from_value(val)?;
 
@@ -289,7 +415,7 @@ pub fn get_remotes(mut seed: Url, project: &Urn) -> Result<Vec<project::PeerInfo
     seed.set_port(Some(DEFAULT_SEED_API_PORT)).unwrap();
     seed = seed.join(&format!("/v1/projects/{}/remotes", project))?;
 
-    let agent = ureq::Agent::new();
+    let agent = proxy::agent(seed.as_str())?;
     let val: serde_json::Value = agent.get(seed.as_str()).call()?.into_json()?;
     let response = serde_json::from_value(val)?;
 
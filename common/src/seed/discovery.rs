@@ -0,0 +1,209 @@
+//! Discovery of a seed's peer ID and endpoints from its domain name.
+//!
+//! A seed is discovered, in order, from:
+//!
+//! 1. The `https://<domain>/.well-known/radicle` document, eg.
+//!    `{ "id": "<peer-id>", "p2p": "<host>:<port>", "git": "<url>", "api": "<url>" }`.
+//! 2. A DNS TXT record of `_radicle.<domain>`, eg.
+//!    `id=<peer-id> p2p=<host>:<port> git=<url> api=<url>`, unless a SOCKS proxy is set.
+//! 3. The seed API, on its default port, which only provides the peer ID.
+//!
+//! Endpoints that aren't given default to the domain, on the default ports.
+//!
+//! Nb. The DNS lookup goes to the system resolver, and not through the proxy, so it
+//! would reveal the domain, eg. when using Tor.
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context as _, Result};
+use librad::crypto::peer::PeerId;
+use serde::Deserialize;
+use url::Url;
+
+use crate::sync::Seed;
+use crate::{offline, proxy};
+
+use super::{DEFAULT_SEED_API_PORT, DEFAULT_SEED_P2P_PORT};
+
+/// Path of the well-known document describing a seed.
+pub const WELL_KNOWN_PATH: &str = "/.well-known/radicle";
+/// Prefix of the domain name holding a seed's DNS TXT record.
+pub const DNS_PREFIX: &str = "_radicle";
+
+/// A seed's peer ID and endpoints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Discovered {
+    /// Peer ID of the seed.
+    pub peer: PeerId,
+    /// Peer-to-peer address, eg. `pine.radicle.garden:8776`.
+    pub p2p: String,
+    /// Git server URL.
+    pub git: Url,
+    /// HTTP API URL.
+    pub api: Url,
+}
+
+impl Discovered {
+    /// Get the seed to sync with, labeled with the given name.
+    pub fn seed(&self, label: impl Into<String>) -> Seed<String> {
+        Seed {
+            peer: self.peer,
+            addrs: self.p2p.clone(),
+            label: Some(label.into()),
+        }
+    }
+}
+
+/// Endpoints as given in a well-known document or DNS record.
+#[derive(Debug, Default, Deserialize)]
+struct Record {
+    id: String,
+    p2p: Option<String>,
+    git: Option<Url>,
+    api: Option<Url>,
+}
+
+impl Record {
+    fn discovered(self, domain: &str) -> Result<Discovered> {
+        let peer = PeerId::from_str(&self.id)
+            .map_err(|_| anyhow!("invalid peer ID '{}' for seed {}", self.id, domain))?;
+
+        Ok(Discovered {
+            peer,
+            p2p: self
+                .p2p
+                .unwrap_or_else(|| format!("{}:{}", domain, DEFAULT_SEED_P2P_PORT)),
+            git: match self.git {
+                Some(git) => git,
+                None => Url::parse(&format!("https://{}", domain))?,
+            },
+            api: match self.api {
+                Some(api) => api,
+                None => Url::parse(&format!("https://{}:{}", domain, DEFAULT_SEED_API_PORT))?,
+            },
+        })
+    }
+}
+
+/// Discover a seed from its domain name, eg. `pine.radicle.garden`.
+pub fn discover(domain: &str) -> Result<Discovered> {
+    offline::check("discover seed")?;
+
+    if let Some(record) = well_known(domain)? {
+        return record.discovered(domain);
+    }
+    let socks = proxy::url_for(&format!("https://{}", domain))?
+        .map_or(false, |proxy| proxy.scheme().starts_with("socks5"));
+
+    if socks {
+        log::debug!("Skipping DNS lookup for {}, a SOCKS proxy is set", domain);
+    } else if let Some(record) = dns(domain)? {
+        return record.discovered(domain);
+    }
+    let api = Url::parse(&format!("https://{}", domain))?;
+    let id = super::get_seed_id(api)
+        .with_context(|| format!("couldn't discover a seed at {}", domain))?;
+
+    Record {
+        id: id.default_encoding(),
+        ..Record::default()
+    }
+    .discovered(domain)
+}
+
+/// Check whether a value looks like a bare domain name, rather than a seed address.
+pub fn is_domain(value: &str) -> bool {
+    !value.contains(['@', ':', '/']) && matches!(url::Host::parse(value), Ok(url::Host::Domain(_)))
+}
+
+/// Fetch the well-known document of a domain, if it has one.
+fn well_known(domain: &str) -> Result<Option<Record>> {
+    let url = format!("https://{}{}", domain, WELL_KNOWN_PATH);
+
+    match proxy::agent(&url)?.get(&url).call() {
+        Ok(response) => {
+            let record = response
+                .into_json()
+                .with_context(|| format!("invalid document at {}", url))?;
+            Ok(Some(record))
+        }
+        Err(err) => {
+            log::debug!("No well-known document for {}: {}", domain, err);
+            Ok(None)
+        }
+    }
+}
+
+/// Look up the DNS TXT record of a domain, if it has one.
+fn dns(domain: &str) -> Result<Option<Record>> {
+    let name = format!("{}.{}", DNS_PREFIX, domain);
+    let resolver = trust_dns_resolver::Resolver::from_system_conf()?;
+    let lookup = match resolver.txt_lookup(name.as_str()) {
+        Ok(lookup) => lookup,
+        Err(err) => {
+            log::debug!("No DNS record for {}: {}", name, err);
+            return Ok(None);
+        }
+    };
+
+    for txt in lookup.iter() {
+        let text = txt
+            .txt_data()
+            .iter()
+            .map(|data| String::from_utf8_lossy(data))
+            .collect::<String>();
+
+        if let Some(record) = parse_txt(&text) {
+            return Ok(Some(record));
+        }
+    }
+    Ok(None)
+}
+
+/// Parse a DNS TXT record, eg. `id=<peer-id> p2p=<host>:<port>`. Returns `None` if the
+/// record has no `id`, eg. because it's for something else.
+fn parse_txt(text: &str) -> Option<Record> {
+    let mut record = Record::default();
+
+    for (key, value) in text.split_whitespace().filter_map(|kv| kv.split_once('=')) {
+        match key {
+            "id" => record.id = value.to_owned(),
+            "p2p" => record.p2p = Some(value.to_owned()),
+            "git" => record.git = Url::parse(value).ok(),
+            "api" => record.api = Url::parse(value).ok(),
+            _ => {}
+        }
+    }
+    if record.id.is_empty() {
+        return None;
+    }
+    Some(record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_txt() {
+        let record = parse_txt(
+            "id=hyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa p2p=seed.example.com:9000",
+        )
+        .unwrap();
+        let discovered = record.discovered("example.com").unwrap();
+
+        assert_eq!(discovered.p2p, "seed.example.com:9000");
+        assert_eq!(discovered.git.as_str(), "https://example.com/");
+        assert_eq!(discovered.api.as_str(), "https://example.com:8777/");
+        assert!(parse_txt("v=spf1 -all").is_none());
+    }
+
+    #[test]
+    fn test_is_domain() {
+        assert!(is_domain("pine.radicle.garden"));
+        assert!(!is_domain("pine.radicle.garden:8776"));
+        assert!(!is_domain("127.0.0.1"));
+        assert!(!is_domain(
+            "hyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa@pine.radicle.garden:8776"
+        ));
+    }
+}
@@ -1,9 +1,13 @@
+use std::convert::TryInto as _;
+use std::io;
+
 use zeroize::Zeroizing;
 
 use librad::crypto::keystore::sign::ed25519;
 use librad::crypto::BoxedSignError;
 use librad::crypto::BoxedSigner;
 use librad::profile::Profile;
+use librad::PeerId;
 use librad::SecretKey;
 
 use lnk_clib::keys;
@@ -12,34 +16,53 @@ use lnk_clib::keys::ssh::SshAuthSock;
 /// A trait for types that can be converted to signers.
 pub trait ToSigner {
     /// Convert to a signer.
-    fn to_signer(self, profile: &Profile) -> Result<BoxedSigner, keys::ssh::Error>;
+    fn to_signer(self, profile: &Profile) -> Result<BoxedSigner, anyhow::Error>;
 }
 
 impl ToSigner for BoxedSigner {
-    fn to_signer(self, _profile: &Profile) -> Result<BoxedSigner, keys::ssh::Error> {
+    fn to_signer(self, _profile: &Profile) -> Result<BoxedSigner, anyhow::Error> {
         Ok(self)
     }
 }
 
 impl ToSigner for SshAuthSock {
-    fn to_signer(self, profile: &Profile) -> Result<BoxedSigner, keys::ssh::Error> {
+    fn to_signer(self, profile: &Profile) -> Result<BoxedSigner, anyhow::Error> {
+        // Nb. `lnk-clib` can only connect to agents on a Unix domain socket.
+        #[cfg(windows)]
+        if let Some(path) = crate::keys::pipe_path(&self) {
+            let peer_id = *crate::profile::read_only(profile)?.peer_id();
+            let signer = crate::keys::pipe::PipeSigner::new(path, public_key(&peer_id));
+
+            return Ok(BoxedSigner::new(signer));
+        }
         let signer = keys::ssh::signer(profile, self)?;
         Ok(signer)
     }
 }
 
 impl ToSigner for SecretKey {
-    fn to_signer(self, _profile: &Profile) -> Result<BoxedSigner, keys::ssh::Error> {
+    fn to_signer(self, _profile: &Profile) -> Result<BoxedSigner, anyhow::Error> {
         Ok(self.into())
     }
 }
 
 impl ToSigner for ZeroizingSecretKey {
-    fn to_signer(self, _profile: &Profile) -> Result<BoxedSigner, keys::ssh::Error> {
+    fn to_signer(self, _profile: &Profile) -> Result<BoxedSigner, anyhow::Error> {
         Ok(BoxedSigner::new(self))
     }
 }
 
+/// Get the public key of a peer, as used by signers.
+pub fn public_key(peer_id: &PeerId) -> ed25519::PublicKey {
+    let key = peer_id
+        .as_public_key()
+        .as_ref()
+        .try_into()
+        .expect("public_key: ed25519 public keys are 32 bytes");
+
+    ed25519::PublicKey(key)
+}
+
 /// Secret key that is zeroed when dropped.
 #[derive(Clone)]
 pub struct ZeroizingSecretKey {
@@ -79,3 +102,47 @@ impl librad::Signer for ZeroizingSecretKey {
             .map_err(BoxedSignError::from_std_error)
     }
 }
+
+/// Signer that only knows its public key, and fails to sign. Used to open storage
+/// for reading, without unsealing the secret key.
+#[derive(Clone)]
+pub struct PublicKeySigner {
+    key: ed25519::PublicKey,
+}
+
+impl PublicKeySigner {
+    pub fn new(peer_id: &PeerId) -> Self {
+        Self {
+            key: public_key(peer_id),
+        }
+    }
+
+    fn error() -> BoxedSignError {
+        BoxedSignError::from_std_error(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "storage was opened without a signing key",
+        ))
+    }
+}
+
+#[async_trait::async_trait]
+impl ed25519::Signer for PublicKeySigner {
+    type Error = BoxedSignError;
+
+    fn public_key(&self) -> ed25519::PublicKey {
+        self.key
+    }
+
+    async fn sign(&self, _data: &[u8]) -> Result<ed25519::Signature, Self::Error> {
+        Err(Self::error())
+    }
+}
+
+impl librad::Signer for PublicKeySigner {
+    fn sign_blocking(
+        &self,
+        _data: &[u8],
+    ) -> Result<librad::keystore::sign::Signature, <Self as ed25519::Signer>::Error> {
+        Err(Self::error())
+    }
+}
@@ -1,13 +1,21 @@
 mod push;
+mod verify;
 
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::TryInto;
+use std::fmt;
+use std::fs;
+use std::io;
 use std::net::SocketAddr;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time;
 
 use anyhow::anyhow;
+use fs2::FileExt as _;
 
 use librad::crypto::BoxedSigner;
+use librad::git::storage::ReadOnly;
 use librad::git::Urn;
 use librad::net::protocol::SendOnly;
 use librad::net::{
@@ -18,15 +26,18 @@ use librad::net::{
     replication, Network,
 };
 use librad::profile::Profile;
-use librad::Signer;
+use librad::{PeerId, Signer};
 use link_async::Spawner;
 use lnk_clib::seed::store::FileStore;
+use url::Url;
 
 pub use lnk_clib::seed::{Seed, Seeds};
 pub use lnk_sync::Mode;
+pub use verify::{verify, verify_all, verify_staged, Mismatch, Verification, VerifyError};
 
-use crate::config;
 use crate::nonempty::NonEmpty;
+use crate::seed::credentials;
+use crate::{config, git, offline, project, proxy};
 
 /// Sync result of a seed.
 #[derive(Debug)]
@@ -34,48 +45,207 @@ pub struct SyncResult {
     pub seed: Seed<Vec<SocketAddr>>,
     pub fetch: Option<Result<replication::Success, client::error::Replicate>>,
     pub push: Option<Result<push::Success, push::Error>>,
+    /// Whether the seed didn't respond in time.
+    pub timed_out: bool,
+    /// How many times an operation was retried after a network failure.
+    pub retries: u32,
 }
 
-/// Sync the given URN with the provided list of seeds.
+/// Outcome of syncing with a seed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    /// All operations succeeded.
+    Synced,
+    /// An operation failed, with the given error.
+    Failed(String),
+    /// The seed didn't respond in time.
+    TimedOut,
+}
+
+impl SyncResult {
+    /// Get the outcome of syncing with the seed.
+    pub fn status(&self) -> Status {
+        if self.timed_out {
+            return Status::TimedOut;
+        }
+        if let Some(Err(err)) = &self.fetch {
+            return Status::Failed(err.to_string());
+        }
+        match &self.push {
+            Some(Err(push::Error::NoResponse)) => Status::TimedOut,
+            Some(Err(err)) => Status::Failed(err.to_string()),
+            _ => Status::Synced,
+        }
+    }
+}
+
+/// How to retry operations with a seed that fail because of the network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retry {
+    /// Maximum number of retries per seed.
+    pub retries: u32,
+    /// Delay before the first retry. The delay is doubled on every subsequent retry.
+    pub backoff: time::Duration,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self {
+            retries: 2,
+            backoff: time::Duration::from_secs(1),
+        }
+    }
+}
+
+impl Retry {
+    /// Get the retry policy from the `sync.retries` and `sync.backoff` settings.
+    pub fn from_config() -> Self {
+        let default = Self::default();
+
+        Self {
+            retries: config::get_number("sync.retries")
+                .map(|n| n.try_into().unwrap_or(u32::MAX))
+                .unwrap_or(default.retries),
+            backoff: config::get_number("sync.backoff")
+                .map(time::Duration::from_secs)
+                .unwrap_or(default.backoff),
+        }
+    }
+
+    /// Get the delay before the given retry, starting at `1`.
+    pub fn delay(&self, retry: u32) -> time::Duration {
+        let factor = 1u32 << retry.saturating_sub(1).min(16);
+
+        self.backoff.saturating_mul(factor)
+    }
+}
+
+/// Wait before retrying an operation with a seed, reporting the retry.
+async fn backoff(retry: &Retry, attempt: u32, seed: &PeerId, progress: &impl Fn(&PeerId, &str)) {
+    let delay = retry.delay(attempt);
+
+    log::info!(
+        "Retrying sync with seed {} in {:?} ({}/{})",
+        seed,
+        delay,
+        attempt,
+        retry.retries
+    );
+    progress(
+        seed,
+        &format!(
+            "retrying in {}s ({}/{})",
+            delay.as_secs(),
+            attempt,
+            retry.retries
+        ),
+    );
+    tokio::time::sleep(delay).await;
+}
+
+/// Sync the given URN with the provided list of seeds. Seeds are synced with
+/// concurrently, and progress, eg. whether a seed is being fetched from, is reported
+/// through the given function, for each seed.
+///
+/// Operations that fail because the seed couldn't be reached, or didn't respond in
+/// time, are retried according to the given policy. Retried fetches resume where the
+/// previous attempt left off, in that refs and objects that were already fetched are
+/// not transferred again.
 pub async fn sync<S, E>(
     client: &Client<S, E>,
     urn: Urn,
     seeds: Seeds,
     mode: Mode,
     timeout: time::Duration,
+    retry: Retry,
+    progress: impl Fn(&PeerId, &str),
 ) -> Vec<SyncResult>
 where
     S: Signer + Clone,
     E: ConnectPeer + Clone + Send + Sync + 'static,
 {
-    let mut syncs = Vec::with_capacity(seeds.len());
     let is_push = mode.is_push();
     let is_fetch = mode.is_fetch();
+    let progress = &progress;
     let Seeds(seeds) = seeds;
 
-    for seed in seeds {
-        let fetch = if is_fetch {
-            match tokio::time::timeout(timeout, client.replicate(seed.clone(), urn.clone(), None))
-                .await
-            {
-                Ok(result) => Some(result),
-                Err(_) => Some(Err(client::error::Replicate::NoConnection(
-                    client::error::NoConnection(seed.peer),
-                ))),
-            }
-        } else {
-            None
-        };
+    let syncs = seeds.into_iter().map(|seed| {
+        let urn = urn.clone();
 
-        let push = if is_push {
-            Some(push::push(client, urn.clone(), seed.clone(), timeout).await)
-        } else {
-            None
-        };
+        async move {
+            log::info!("Syncing {} with seed {}", urn, seed.peer);
 
-        syncs.push(SyncResult { seed, fetch, push })
-    }
-    syncs
+            let mut timed_out = false;
+            let mut retries = 0;
+            let fetch = if is_fetch {
+                loop {
+                    progress(&seed.peer, "fetching");
+
+                    let result = match tokio::time::timeout(
+                        timeout,
+                        client.replicate(seed.clone(), urn.clone(), None),
+                    )
+                    .await
+                    {
+                        Ok(result) => {
+                            timed_out = false;
+                            result
+                        }
+                        Err(_) => {
+                            timed_out = true;
+                            Err(client::error::Replicate::NoConnection(
+                                client::error::NoConnection(seed.peer),
+                            ))
+                        }
+                    };
+                    let transient =
+                        matches!(result, Err(client::error::Replicate::NoConnection(_)));
+
+                    if transient && retries < retry.retries {
+                        retries += 1;
+                        backoff(&retry, retries, &seed.peer, progress).await;
+
+                        continue;
+                    }
+                    break Some(result);
+                }
+            } else {
+                None
+            };
+
+            let push = if is_push && !timed_out {
+                loop {
+                    progress(&seed.peer, "pushing");
+
+                    let result = push::push(client, urn.clone(), seed.clone(), timeout).await;
+                    let transient = matches!(
+                        result,
+                        Err(push::Error::NoResponse) | Err(push::Error::Client(_))
+                    );
+
+                    if transient && retries < retry.retries {
+                        retries += 1;
+                        backoff(&retry, retries, &seed.peer, progress).await;
+
+                        continue;
+                    }
+                    break Some(result);
+                }
+            } else {
+                None
+            };
+
+            SyncResult {
+                seed,
+                fetch,
+                push,
+                timed_out,
+                retries,
+            }
+        }
+    });
+
+    futures::future::join_all(syncs).await
 }
 
 /// Create a sync client.
@@ -83,6 +253,8 @@ pub async fn client(
     signer: BoxedSigner,
     profile: &Profile,
 ) -> anyhow::Result<Client<BoxedSigner, SendOnly>> {
+    offline::check("sync with seeds")?;
+
     let spawner = Spawner::from_current().ok_or(anyhow!("cannot create spawner"))?;
     let network = Network::default();
     let config = client::Config {
@@ -98,10 +270,48 @@ pub async fn client(
     Ok(client)
 }
 
-/// Get the seeds configured for the profile.
-/// First checks local (working copy) config, then global.
+/// Get the seeds configured for the profile. The first of these that sets any seeds is
+/// used:
+///
+/// 1. The seed set with [`crate::env::RAD_SEED`].
+/// 2. The working copy's seeds, set with `rad.seed` in its git configuration.
+/// 3. The seed of the `seed` setting, see [`config::get`].
+/// 4. The seeds of the working copy's `Radicle.toml`.
+/// 5. The user's named seeds, added with `rad seed add`.
+/// 6. The seeds of the profile's configuration file.
+/// 7. The profile's seeds file.
 pub fn seeds(profile: &Profile) -> anyhow::Result<NonEmpty<Seed<String>>> {
-    let config = config::Config::load(profile)?;
+    if let Some(seed) = crate::env::seed()? {
+        return Ok(NonEmpty::new(seed));
+    }
+    if let Ok(seeds) = crate::seed::get_project_seeds()?.try_into() {
+        return Ok(seeds);
+    }
+    if let Some(setting) = config::get("seed")? {
+        let seed = Seed::from_str(&setting.value).map_err(|_| {
+            anyhow!(
+                "invalid seed address '{}' in `seed` setting ({})",
+                setting.value,
+                setting.source
+            )
+        })?;
+        return Ok(NonEmpty::new(seed));
+    }
+    if let Ok(config) = config::Config::local() {
+        let seeds = config
+            .seeds()
+            .cloned()
+            .map(|s| s.try_into())
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Ok(seeds) = seeds.try_into() {
+            return Ok(seeds);
+        }
+    }
+    let seeds = config::seeds()?.into_values().collect::<Vec<_>>();
+    if let Ok(seeds) = seeds.try_into() {
+        return Ok(seeds);
+    }
+    let config = config::Config::profile(profile)?;
     let seeds = config
         .seeds()
         .cloned()
@@ -122,3 +332,432 @@ pub fn seeds(profile: &Profile) -> anyhow::Result<NonEmpty<Seed<String>>> {
         .try_into()
         .map_err(|_| anyhow!("No seeds configured for profile {}", profile.id()))
 }
+
+/// How a branch in local storage compares with the same branch on a seed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Divergence {
+    /// The branch points to the same commit.
+    UpToDate,
+    /// The seed is missing the given number of local commits, eg. they weren't pushed.
+    Ahead(usize),
+    /// Local storage is missing commits of the seed. Their number is only known if
+    /// they were already fetched.
+    Behind(Option<usize>),
+    /// Both have commits that the other doesn't have.
+    Diverged { ahead: usize, behind: usize },
+    /// The branch is only in local storage.
+    MissingOnSeed,
+    /// The branch is only on the seed.
+    MissingLocally,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UpToDate => write!(f, "up to date"),
+            Self::Ahead(n) => write!(f, "ahead by {} commit(s)", n),
+            Self::Behind(Some(n)) => write!(f, "behind by {} commit(s)", n),
+            Self::Behind(None) => write!(f, "behind"),
+            Self::Diverged { ahead, behind } => {
+                write!(f, "diverged, {} ahead and {} behind", ahead, behind)
+            }
+            Self::MissingOnSeed => write!(f, "missing on seed"),
+            Self::MissingLocally => write!(f, "missing locally"),
+        }
+    }
+}
+
+/// A peer's branch, compared between local storage and a seed.
+#[derive(Debug, Clone)]
+pub struct BranchStatus {
+    pub peer: PeerId,
+    pub branch: String,
+    /// Head of the branch in local storage.
+    pub local: Option<git::Oid>,
+    /// Head of the branch on the seed.
+    pub seed: Option<git::Oid>,
+    pub divergence: Divergence,
+}
+
+/// Compare the branches of a project in local storage with the branches on a seed,
+/// given the URL of the seed's git server. Only the seed's refs are listed: no objects
+/// are transferred.
+pub fn compare<S>(storage: &S, urn: &Urn, seed: &Url) -> anyhow::Result<Vec<BranchStatus>>
+where
+    S: AsRef<ReadOnly>,
+{
+    let repo = git::Repository::open_bare(storage.as_ref().path())?;
+    let mut heads = BTreeMap::new();
+
+    for (peer, branches) in project::list_local_heads(storage, urn)? {
+        for (branch, oid) in branches {
+            heads.entry((peer, branch)).or_insert((None, None)).0 = Some(oid);
+        }
+    }
+    for (peer, branches) in project::list_seed_heads(&repo, seed, urn)? {
+        for (branch, oid) in branches {
+            heads.entry((peer, branch)).or_insert((None, None)).1 = Some(oid);
+        }
+    }
+
+    heads
+        .into_iter()
+        .map(|((peer, branch), (local, seed))| {
+            let divergence = match (local, seed) {
+                (Some(l), Some(s)) if l == s => Divergence::UpToDate,
+                (Some(l), Some(s)) => {
+                    // Commits that weren't fetched can't be counted.
+                    if repo.find_commit(s).is_err() {
+                        Divergence::Behind(None)
+                    } else {
+                        match repo.graph_ahead_behind(l, s)? {
+                            (ahead, 0) => Divergence::Ahead(ahead),
+                            (0, behind) => Divergence::Behind(Some(behind)),
+                            (ahead, behind) => Divergence::Diverged { ahead, behind },
+                        }
+                    }
+                }
+                (Some(_), None) => Divergence::MissingOnSeed,
+                (None, _) => Divergence::MissingLocally,
+            };
+            Ok::<_, anyhow::Error>(BranchStatus {
+                peer,
+                branch,
+                local,
+                seed,
+                divergence,
+            })
+        })
+        .collect()
+}
+
+/// Verify the refs of every peer of a project in storage, other than the local peer.
+/// Returns the peers that failed verification.
+pub fn verify_peers<S>(storage: &S, urn: &Urn) -> anyhow::Result<BTreeMap<PeerId, Verification>>
+where
+    S: AsRef<ReadOnly>,
+{
+    let storage = storage.as_ref();
+    let peers = project::list_local_heads(storage, urn)?
+        .into_keys()
+        .filter(|peer| peer != storage.peer_id());
+
+    verify_all(storage, urn, peers)
+}
+
+/// Refs of a project to sync, when not syncing all of them.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Filter {
+    /// Only sync these branches, eg. `main`.
+    pub branches: Vec<String>,
+    /// Only sync identity refs, ie. `rad/*`, and no branches.
+    pub identity_only: bool,
+    /// Only sync the refs of these peers.
+    pub peers: Vec<PeerId>,
+}
+
+impl Filter {
+    /// Check whether the filter selects all refs.
+    pub fn is_empty(&self) -> bool {
+        self.branches.is_empty() && !self.identity_only && self.peers.is_empty()
+    }
+
+    /// Check whether a peer's ref is selected, given its name relative to the peer's
+    /// namespace, eg. `heads/main`. Identity refs are always selected, so that the
+    /// selected branches can be verified.
+    pub fn matches(&self, peer: &PeerId, name: &str) -> bool {
+        if !self.peers.is_empty() && !self.peers.contains(peer) {
+            return false;
+        }
+        if name.starts_with("rad/") {
+            return true;
+        }
+        if self.identity_only {
+            return false;
+        }
+        match name.strip_prefix("heads/") {
+            Some(branch) => self.branches.is_empty() || self.branches.iter().any(|b| b == branch),
+            None => self.branches.is_empty(),
+        }
+    }
+}
+
+/// Refs fetched from a seed's git server.
+#[derive(Debug, Default)]
+pub struct Fetched {
+    /// Names of the refs that were fetched.
+    pub refs: Vec<String>,
+    /// Peers whose refs don't match their signed refs, if verification wasn't required.
+    pub unverified: BTreeMap<PeerId, Verification>,
+}
+
+/// Prefix of the refs that fetched refs are staged under, before they are verified.
+pub const STAGING_REFS: &str = "refs/rad/staging";
+
+/// Fetch the refs of a project selected by the filter, from a seed's git server given
+/// its URL. Only the selected refs and the objects they need are transferred. The local
+/// peer's refs are never fetched.
+///
+/// The refs are fetched under [`STAGING_REFS`] first, and each peer's refs are verified
+/// there against the peer's signed refs. If `verify` is set and some peer's refs don't
+/// match, the project's refs are left untouched and a [`VerifyError`] is returned.
+/// Otherwise, the staged refs are moved into the project's namespace.
+pub fn fetch<S>(
+    storage: &S,
+    urn: &Urn,
+    seed: &Url,
+    filter: &Filter,
+    verify: bool,
+) -> anyhow::Result<Fetched>
+where
+    S: AsRef<ReadOnly>,
+{
+    let storage = storage.as_ref();
+    let repo = git::Repository::open_bare(storage.path())?;
+    let namespace = format!("refs/namespaces/{}/", urn.encode_id());
+    let staging = format!("{}/{}/", STAGING_REFS, urn.encode_id());
+    let refs = project::list_seed_refs(&repo, seed, urn)?
+        .into_iter()
+        .filter(|(name, _)| match git::parse_remote(name) {
+            Some((peer, r)) => &peer != storage.peer_id() && filter.matches(&peer, r),
+            None => false,
+        })
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+
+    if refs.is_empty() {
+        return Ok(Fetched::default());
+    }
+    // Refs left over from an interrupted fetch must not be mistaken for fetched ones.
+    clear_staging(&repo, &staging)?;
+
+    let result = fetch_staged(storage, urn, seed, &refs, &staging, verify).and_then(|unverified| {
+        for r in &refs {
+            let staged = repo.find_reference(&format!("{}{}", staging, r))?;
+
+            if let Some(oid) = staged.target() {
+                repo.reference(&format!("{}{}", namespace, r), oid, true, "rad sync: fetch")?;
+            }
+        }
+        Ok(unverified)
+    });
+    let cleared = clear_staging(&repo, &staging);
+    let unverified = result?;
+    cleared?;
+
+    Ok(Fetched { refs, unverified })
+}
+
+/// Fetch the given refs under the staging prefix, and verify them. Returns the peers
+/// whose refs failed verification, or a [`VerifyError`] if `verify` is set.
+fn fetch_staged(
+    storage: &ReadOnly,
+    urn: &Urn,
+    seed: &Url,
+    refs: &[String],
+    staging: &str,
+    verify: bool,
+) -> anyhow::Result<BTreeMap<PeerId, Verification>> {
+    let url = seed.join(&urn.encode_id())?;
+    let mut args = proxy::git_args(url.as_str())?;
+
+    args.extend(credentials::git_args());
+    args.extend(
+        ["fetch", "--quiet", "--no-tags", url.as_str()]
+            .iter()
+            .map(|a| a.to_string()),
+    );
+    args.extend(refs.iter().map(|r| format!("+{}:{}{}", r, staging, r)));
+    git::git(storage.path(), args)?;
+
+    let peers = refs
+        .iter()
+        .filter_map(|r| git::parse_remote(r).map(|(peer, _)| peer))
+        .collect::<BTreeSet<_>>();
+    let mut unverified = BTreeMap::new();
+
+    for peer in peers {
+        let verification = verify_staged(storage, urn, staging, &peer)?;
+
+        if !verification.is_verified() {
+            unverified.insert(peer, verification);
+        }
+    }
+    if verify && !unverified.is_empty() {
+        return Err(VerifyError(unverified).into());
+    }
+    Ok(unverified)
+}
+
+/// Delete the refs under the given staging prefix.
+fn clear_staging(repo: &git::Repository, staging: &str) -> anyhow::Result<()> {
+    let names = repo
+        .references_glob(&format!("{}*", staging))?
+        .names()
+        .map(|name| name.map(|n| n.to_owned()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for name in names {
+        repo.find_reference(&name)?.delete()?;
+    }
+    Ok(())
+}
+
+/// File under the profile's git directory, recording when each project was last synced
+/// with each seed.
+pub const SYNCS_FILE: &str = "rad-syncs.json";
+/// File under the profile's git directory, locked while syncs are recorded.
+pub const SYNCS_LOCK_FILE: &str = "rad-syncs.lock";
+
+/// Times of the last successful sync of each project with each seed, as seconds since
+/// the epoch, keyed by project URN and seed Peer ID.
+type Syncs = HashMap<String, BTreeMap<String, u64>>;
+
+fn read_syncs(profile: &Profile) -> io::Result<Syncs> {
+    match fs::read(profile.paths().git_dir().join(SYNCS_FILE)) {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Syncs::default()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Record that a project was successfully synced with the given seeds just now.
+pub fn record<'a>(
+    profile: &Profile,
+    urn: &Urn,
+    seeds: impl IntoIterator<Item = &'a PeerId>,
+) -> io::Result<()> {
+    let now = time::SystemTime::now()
+        .duration_since(time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let dir = profile.paths().git_dir();
+
+    // Nb. Syncs can be recorded concurrently, eg. by two `rad sync` commands, which
+    // would otherwise overwrite each other's records. The lock is held until the new
+    // records are in place, and these are written to a temporary file first, so that
+    // readers never see a partially written file.
+    let lock = fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .open(dir.join(SYNCS_LOCK_FILE))?;
+    lock.lock_exclusive()?;
+
+    let mut syncs = read_syncs(profile)?;
+    let entry = syncs.entry(urn.to_string()).or_default();
+
+    for seed in seeds {
+        entry.insert(seed.to_string(), now);
+    }
+    let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+    serde_json::to_writer_pretty(&mut tmp, &syncs)?;
+    tmp.persist(dir.join(SYNCS_FILE))?;
+
+    Ok(())
+}
+
+/// Get when a project was last synced with each seed, as seconds since the epoch.
+pub fn last_synced(profile: &Profile, urn: &Urn) -> io::Result<BTreeMap<PeerId, u64>> {
+    let mut syncs = read_syncs(profile)?;
+    let times = syncs
+        .remove(&urn.to_string())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(peer, time)| PeerId::from_str(&peer).ok().map(|peer| (peer, time)))
+        .collect();
+
+    Ok(times)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test;
+
+    #[test]
+    fn test_filter_matches() {
+        let peer =
+            PeerId::from_str("hyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa").unwrap();
+        let other =
+            PeerId::from_str("hyd7wpd8p5aqnm9htsfoatxkckmw6ingnsdudns9code5xq17h1rhw").unwrap();
+
+        let filter = Filter::default();
+        assert!(filter.is_empty());
+        assert!(filter.matches(&peer, "heads/main"));
+        assert!(filter.matches(&peer, "cobs/issues"));
+
+        let filter = Filter {
+            branches: vec![String::from("main")],
+            peers: vec![peer],
+            ..Filter::default()
+        };
+        assert!(filter.matches(&peer, "heads/main"));
+        assert!(filter.matches(&peer, "rad/signed_refs"));
+        assert!(!filter.matches(&peer, "heads/dev"));
+        assert!(!filter.matches(&peer, "cobs/issues"));
+        assert!(!filter.matches(&other, "heads/main"));
+
+        let filter = Filter {
+            identity_only: true,
+            ..Filter::default()
+        };
+        assert!(filter.matches(&other, "rad/id"));
+        assert!(!filter.matches(&other, "heads/main"));
+    }
+
+    #[test]
+    fn test_fetch_unverified() {
+        let (storage, urn, peer, head) = test::setup::tracking();
+        let monorepo = storage.as_ref().path();
+        let local = format!("refs/namespaces/{}/refs/", urn.encode_id());
+        let remote = format!("refs/remotes/{}/", peer);
+        let tracking = format!("{}{}heads/master", local, remote);
+
+        // A seed's git server, serving the peer's refs with a forged branch.
+        let dir = tempfile::tempdir().unwrap();
+        let seed = git::Repository::init_bare(dir.path().join(urn.encode_id())).unwrap();
+        git::git(
+            seed.path(),
+            [
+                String::from("fetch"),
+                monorepo.display().to_string(),
+                format!("+{}{}*:{}*", local, remote, remote),
+            ],
+        )
+        .unwrap();
+
+        let sig = git::Signature::now("anonymous", "anonymous@radicle.xyz").unwrap();
+        let tree = seed
+            .find_tree(seed.treebuilder(None).unwrap().write().unwrap())
+            .unwrap();
+        let forged = seed
+            .commit(None, &sig, &sig, "Forged commit", &tree, &[])
+            .unwrap();
+        seed.reference(&format!("{}heads/master", remote), forged, true, "")
+            .unwrap();
+
+        let url = Url::from_directory_path(dir.path()).unwrap();
+        let repo = git::Repository::open_bare(monorepo).unwrap();
+        let target = || repo.find_reference(&tracking).unwrap().target().unwrap();
+
+        let staged = || {
+            repo.references_glob(&format!("{}/*", STAGING_REFS))
+                .unwrap()
+                .count()
+        };
+
+        // The forged branch is refused, and the peer's refs are left untouched.
+        let err = fetch(&storage, &urn, &url, &Filter::default(), true).unwrap_err();
+        let err = err.downcast_ref::<VerifyError>().unwrap();
+        assert!(err.0.contains_key(&peer));
+        assert_eq!(target(), head);
+        assert_eq!(staged(), 0);
+
+        // Unless verification isn't required.
+        let fetched = fetch(&storage, &urn, &url, &Filter::default(), false).unwrap();
+        assert!(fetched.unverified.contains_key(&peer));
+        assert_eq!(target(), forged);
+        assert_eq!(staged(), 0);
+    }
+}
@@ -0,0 +1,384 @@
+//! Verification of peers' refs against their signed refs.
+//!
+//! Every peer signs the refs it publishes, in its `rad/signed_refs`. A seed serving a
+//! peer's refs could change them, so after fetching, the refs in storage are compared
+//! with what the peer signed.
+use std::collections::BTreeMap;
+use std::fmt;
+
+use librad::git::refs::{Refs, Signed};
+use librad::git::storage::ReadOnly;
+use librad::git::Urn;
+use librad::PeerId;
+
+use crate::git;
+
+/// A peer's ref that doesn't match the peer's signed refs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// Name of the ref, eg. `heads/main`.
+    pub name: String,
+    /// Target of the ref in storage.
+    pub actual: git::Oid,
+    /// Target of the ref in the peer's signed refs, if it was signed.
+    pub signed: Option<git::Oid>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.signed {
+            Some(signed) => write!(
+                f,
+                "{} is at {}, but was signed at {}",
+                self.name,
+                crate::fmt::oid(&self.actual),
+                crate::fmt::oid(&signed)
+            ),
+            None => write!(f, "{} was not signed", self.name),
+        }
+    }
+}
+
+/// Outcome of verifying a peer's refs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verification {
+    /// The refs match the peer's signed refs.
+    Verified,
+    /// The peer has refs, but no signed refs.
+    Unsigned,
+    /// The signed refs couldn't be loaded, eg. because their signature is invalid.
+    Invalid(String),
+    /// Some refs don't match the signed refs.
+    Mismatched(Vec<Mismatch>),
+}
+
+impl Verification {
+    pub fn is_verified(&self) -> bool {
+        matches!(self, Self::Verified)
+    }
+}
+
+impl fmt::Display for Verification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Verified => write!(f, "refs match their signatures"),
+            Self::Unsigned => write!(f, "refs are not signed"),
+            Self::Invalid(err) => write!(f, "signed refs are invalid: {}", err),
+            Self::Mismatched(mismatches) => write!(
+                f,
+                "{}",
+                mismatches
+                    .iter()
+                    .map(|m| m.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+/// Peers whose refs failed verification.
+#[derive(Debug, Clone)]
+pub struct VerifyError(pub BTreeMap<PeerId, Verification>);
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "refs of {} peer(s) don't match their signatures: ",
+            self.0.len()
+        )?;
+        let failures = self
+            .0
+            .iter()
+            .map(|(peer, v)| format!("{} ({})", crate::fmt::peer(peer), v))
+            .collect::<Vec<_>>();
+
+        write!(f, "{}", failures.join("; "))
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// Verify a peer's refs of a project in storage against the refs the peer signed.
+/// Only branches and tags are compared. Signed refs that are missing from storage, eg.
+/// because they weren't fetched, are not a failure.
+pub fn verify<S>(storage: &S, urn: &Urn, peer: &PeerId) -> anyhow::Result<Verification>
+where
+    S: AsRef<ReadOnly>,
+{
+    let storage = storage.as_ref();
+    let repo = git::Repository::open_bare(storage.path())?;
+    let prefix = format!("refs/namespaces/{}/refs/remotes/{}/", urn.encode_id(), peer);
+    let actual = branches(&repo, &prefix)?;
+    let signed = Refs::load(&storage, urn, Some(*peer))
+        .map(|refs| refs.map(|refs| signed_branches(&refs)))
+        .map_err(|err| err.to_string());
+
+    Ok(compare(actual, signed))
+}
+
+/// Verify a peer's refs of a project that were fetched under a staging prefix, ie.
+/// `<staging>refs/remotes/<peer>/`, before they are moved into the project's namespace.
+/// The peer's signed refs are taken from the staged refs if they were fetched, and
+/// from storage otherwise.
+pub fn verify_staged<S>(
+    storage: &S,
+    urn: &Urn,
+    staging: &str,
+    peer: &PeerId,
+) -> anyhow::Result<Verification>
+where
+    S: AsRef<ReadOnly>,
+{
+    let storage = storage.as_ref();
+    let repo = git::Repository::open_bare(storage.path())?;
+    let prefix = format!("{}refs/remotes/{}/", staging, peer);
+    let actual = branches(&repo, &prefix)?;
+
+    let signed = match repo.find_reference(&format!("{}rad/signed_refs", prefix)) {
+        Ok(reference) => {
+            let tree = reference.peel_to_commit()?.tree()?;
+
+            match tree.get_name(SIGNED_REFS_BLOB) {
+                Some(entry) => {
+                    let blob = repo.find_blob(entry.id())?;
+
+                    Signed::from_json(blob.content(), peer)
+                        .map(|refs| Some(signed_branches(&refs)))
+                        .map_err(|err| err.to_string())
+                }
+                None => Err(format!("missing `{}` blob", SIGNED_REFS_BLOB)),
+            }
+        }
+        Err(err) if err.code() == git::ErrorCode::NotFound => {
+            Refs::load(&storage, urn, Some(*peer))
+                .map(|refs| refs.map(|refs| signed_branches(&refs)))
+                .map_err(|err| err.to_string())
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    Ok(compare(actual, signed))
+}
+
+/// Name of the blob holding the signed refs, in the tree of `rad/signed_refs`.
+const SIGNED_REFS_BLOB: &str = "refs";
+
+/// Get the branches and tags under the given prefix, by name relative to the prefix.
+fn branches(repo: &git::Repository, prefix: &str) -> anyhow::Result<BTreeMap<String, git::Oid>> {
+    let mut branches = BTreeMap::new();
+
+    for category in ["heads", "tags"] {
+        for reference in repo.references_glob(&format!("{}{}/*", prefix, category))? {
+            let reference = reference?;
+
+            if let (Some(name), Some(oid)) = (reference.name(), reference.target()) {
+                branches.insert(name[prefix.len()..].to_owned(), oid);
+            }
+        }
+    }
+    Ok(branches)
+}
+
+/// Get the signed branches and tags, by name, eg. `heads/main`.
+fn signed_branches(refs: &Refs) -> BTreeMap<String, git::Oid> {
+    let mut signed = BTreeMap::new();
+
+    for (name, oid) in refs.heads() {
+        signed.insert(format!("heads/{}", name), git::Oid::from(*oid));
+    }
+    for (name, oid) in refs.tags() {
+        signed.insert(format!("tags/{}", name), git::Oid::from(*oid));
+    }
+    signed
+}
+
+/// Compare a peer's refs with its signed refs, if they could be loaded.
+fn compare(
+    actual: BTreeMap<String, git::Oid>,
+    signed: Result<Option<BTreeMap<String, git::Oid>>, String>,
+) -> Verification {
+    let signed = match signed {
+        Ok(Some(signed)) => signed,
+        Ok(None) if actual.is_empty() => return Verification::Verified,
+        Ok(None) => return Verification::Unsigned,
+        Err(err) => return Verification::Invalid(err),
+    };
+    let mismatches = actual
+        .into_iter()
+        .filter_map(|(name, actual)| {
+            let signed = signed.get(&name).copied();
+
+            if signed == Some(actual) {
+                None
+            } else {
+                Some(Mismatch {
+                    name,
+                    actual,
+                    signed,
+                })
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if mismatches.is_empty() {
+        Verification::Verified
+    } else {
+        Verification::Mismatched(mismatches)
+    }
+}
+
+/// Verify the refs of the given peers. Returns the peers that failed verification.
+pub fn verify_all<S>(
+    storage: &S,
+    urn: &Urn,
+    peers: impl IntoIterator<Item = PeerId>,
+) -> anyhow::Result<BTreeMap<PeerId, Verification>>
+where
+    S: AsRef<ReadOnly>,
+{
+    let mut failures = BTreeMap::new();
+
+    for peer in peers {
+        let verification = verify(storage, urn, &peer)?;
+
+        if !verification.is_verified() {
+            failures.insert(peer, verification);
+        }
+    }
+    Ok(failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test;
+
+    fn remote_ref(urn: &Urn, peer: &PeerId, name: &str) -> String {
+        format!(
+            "refs/namespaces/{}/refs/remotes/{}/{}",
+            urn.encode_id(),
+            peer,
+            name
+        )
+    }
+
+    #[test]
+    fn test_verified() {
+        let (storage, urn, peer, _) = test::setup::tracking();
+
+        assert_eq!(
+            verify(&storage, &urn, &peer).unwrap(),
+            Verification::Verified
+        );
+        assert!(verify_all(&storage, &urn, [peer]).unwrap().is_empty());
+
+        // A peer without refs has nothing to verify.
+        assert_eq!(
+            verify(&storage, &urn, storage.peer_id()).unwrap(),
+            Verification::Verified
+        );
+    }
+
+    #[test]
+    fn test_unsigned() {
+        let (storage, urn, peer, _) = test::setup::tracking();
+        let repo = git::Repository::open_bare(storage.as_ref().path()).unwrap();
+
+        repo.find_reference(&remote_ref(&urn, &peer, "rad/signed_refs"))
+            .unwrap()
+            .delete()
+            .unwrap();
+
+        assert_eq!(
+            verify(&storage, &urn, &peer).unwrap(),
+            Verification::Unsigned
+        );
+    }
+
+    #[test]
+    fn test_mismatched() {
+        let (storage, urn, peer, head) = test::setup::tracking();
+        let repo = git::Repository::open_bare(storage.as_ref().path()).unwrap();
+        let sig = git::Signature::now("anonymous", "anonymous@radicle.xyz").unwrap();
+        let tree = repo
+            .find_tree(repo.treebuilder(None).unwrap().write().unwrap())
+            .unwrap();
+        let forged = repo
+            .commit(None, &sig, &sig, "Forged commit", &tree, &[])
+            .unwrap();
+
+        repo.reference(&remote_ref(&urn, &peer, "heads/master"), forged, true, "")
+            .unwrap();
+        repo.reference(&remote_ref(&urn, &peer, "heads/forged"), forged, true, "")
+            .unwrap();
+
+        let verification = verify(&storage, &urn, &peer).unwrap();
+        assert_eq!(
+            verification,
+            Verification::Mismatched(vec![
+                Mismatch {
+                    name: String::from("heads/forged"),
+                    actual: forged,
+                    signed: None,
+                },
+                Mismatch {
+                    name: String::from("heads/master"),
+                    actual: forged,
+                    signed: Some(head),
+                },
+            ])
+        );
+        assert_eq!(
+            verify_all(&storage, &urn, [peer]).unwrap().get(&peer),
+            Some(&verification)
+        );
+    }
+
+    #[test]
+    fn test_staged() {
+        let (storage, urn, peer, head) = test::setup::tracking();
+        let repo = git::Repository::open_bare(storage.as_ref().path()).unwrap();
+        let staging = "refs/rad/staging/test/";
+        let staged = |name: &str| format!("{}refs/remotes/{}/{}", staging, peer, name);
+
+        for name in ["heads/master", "rad/signed_refs"] {
+            let oid = repo
+                .find_reference(&remote_ref(&urn, &peer, name))
+                .unwrap()
+                .target()
+                .unwrap();
+            repo.reference(&staged(name), oid, true, "").unwrap();
+        }
+        assert_eq!(
+            verify_staged(&storage, &urn, staging, &peer).unwrap(),
+            Verification::Verified
+        );
+
+        let sig = git::Signature::now("anonymous", "anonymous@radicle.xyz").unwrap();
+        let tree = repo
+            .find_tree(repo.treebuilder(None).unwrap().write().unwrap())
+            .unwrap();
+        let forged = repo
+            .commit(None, &sig, &sig, "Forged commit", &tree, &[])
+            .unwrap();
+
+        repo.reference(&staged("heads/master"), forged, true, "")
+            .unwrap();
+
+        assert_eq!(
+            verify_staged(&storage, &urn, staging, &peer).unwrap(),
+            Verification::Mismatched(vec![Mismatch {
+                name: String::from("heads/master"),
+                actual: forged,
+                signed: Some(head),
+            }])
+        );
+        // The refs in storage are untouched.
+        assert_eq!(
+            verify(&storage, &urn, &peer).unwrap(),
+            Verification::Verified
+        );
+    }
+}
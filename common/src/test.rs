@@ -11,14 +11,16 @@ use librad::crypto::keystore::FileStorage;
 use librad::crypto::BoxedSigner;
 use librad::git::identities::local::LocalIdentity;
 use librad::git::identities::Project;
+use librad::git::refs::Refs;
+use librad::git::Urn;
 
 use librad::git::Storage;
 use librad::keystore::crypto::Crypto;
 use librad::keystore::Keystore;
 use librad::profile::{Profile, LNK_HOME};
-use librad::PublicKey;
+use librad::{PeerId, PublicKey};
 
-use super::{keys, person, profile, project, signer, test};
+use super::{bundle, git, keys, person, profile, project, signer, test};
 
 pub type BoxedError = Box<dyn error::Error>;
 
@@ -55,10 +57,26 @@ pub mod setup {
             "Bitcoin light-client".to_owned(),
             "master".to_owned(),
         );
-        let project = project::create(payload, &storage).unwrap();
+        let project = project::create(payload, &[], &storage).unwrap();
 
         (storage, profile, whoami, project)
     }
+
+    /// Get a storage tracking another peer's project, with the peer's `master` branch
+    /// and signed refs, as well as the project, the peer and the branch head.
+    pub fn tracking() -> (Storage, Urn, PeerId, git::Oid) {
+        let (storage, _, _, project) = profile();
+        let (other, _, _, _) = profile();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nakamoto.bundle");
+        let urn = project.urn();
+        let head = test::commit(&storage, &urn, "master").unwrap();
+
+        bundle::export(&storage, &urn, &path).unwrap();
+        bundle::import(&other, &path).unwrap();
+
+        (other, urn, *storage.peer_id(), head)
+    }
 }
 
 pub mod teardown {
@@ -93,3 +111,30 @@ where
         keystore.secret_key,
     )))
 }
+
+/// Point a branch of a project in storage to a new commit, and sign the project's refs.
+pub fn commit(storage: &Storage, urn: &Urn, branch: &str) -> Result<git::Oid, anyhow::Error> {
+    let repo = git::Repository::open_bare(storage.as_ref().path())?;
+    let name = format!("refs/namespaces/{}/refs/heads/{}", urn.encode_id(), branch);
+    let parent = repo.find_reference(&name).ok().and_then(|r| r.target());
+    let parents = parent
+        .map(|p| repo.find_commit(p))
+        .transpose()?
+        .into_iter()
+        .collect::<Vec<_>>();
+    let sig = git::Signature::now("anonymous", "anonymous@radicle.xyz")?;
+    let tree = repo.find_tree(repo.treebuilder(None)?.write()?)?;
+    let oid = repo.commit(
+        None,
+        &sig,
+        &sig,
+        "Test commit",
+        &tree,
+        &parents.iter().collect::<Vec<_>>(),
+    )?;
+
+    repo.reference(&name, oid, true, "test commit")?;
+    Refs::update(storage, urn)?;
+
+    Ok(oid)
+}
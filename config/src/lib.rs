@@ -0,0 +1,223 @@
+use std::ffi::OsString;
+
+use anyhow::anyhow;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::{config, error};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "config",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad config [list]
+    rad config get <key>
+    rad config set <key> <value> [--project]
+    rad config unset <key> [--project]
+
+    Settings are written to the user configuration file, `radicle/config.toml`
+    under `$XDG_CONFIG_HOME`, or `~/.config` if it isn't set. With `--project`,
+    they are written to the `rad.<key>` git configuration of the working copy
+    instead, so that they only apply to the current project.
+
+    When reading a setting, its environment variable takes precedence, followed
+    by the `rad.<key>` git configuration of the working copy, the user
+    configuration file, and the `Radicle.toml` or profile configuration.
+
+Keys
+
+    seed                   Default seed, eg. <peer-id>@<host>:<port> (env: RAD_SEED)
+    clone.directory        Directory under which projects are cloned (env: RAD_CLONE_DIR)
+    terminal.color         Whether to output colors
+    terminal.emoji         Whether to output emoji and unicode glyphs
+    terminal.plain         Whether to output simple status lines instead of animations
+    terminal.timestamps    Timestamp format: relative, absolute or a strftime format
+    terminal.pager         Pager for long output, eg. `less -R` (env: RAD_PAGER)
+    network.offline        Whether to fail right away instead of using the network (env: RAD_OFFLINE)
+    network.proxy          Proxy for HTTP connections to seeds, eg. `socks5h://localhost:9050`
+    sync.retries           Times to retry syncing with a seed after a network failure
+    sync.backoff           Seconds to wait before the first sync retry, doubled on every retry
+    mirror.remote          Git remote or URL that `rad push --mirror-external` mirrors to
+    web.host               Host of the web client that `rad web` opens projects in
+
+Options
+
+    --project    Set or unset the setting for the current project only
+    --help       Print help
+"#,
+    ..Help::DEFAULT
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Operation {
+    List,
+    Get { key: String },
+    Set { key: String, value: String },
+    Unset { key: String },
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+    /// Whether to write to the working copy's git configuration.
+    pub project: bool,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut values: Vec<String> = Vec::new();
+        let mut project = false;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Long("project") => {
+                    project = true;
+                }
+                Value(val) if values.len() < 3 => {
+                    values.push(val.to_string_lossy().to_string());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let op = match values.as_slice() {
+            [] => Operation::List,
+            [op] if op == "list" => Operation::List,
+            [op, key] if op == "get" => Operation::Get { key: key.clone() },
+            [op, key, value] if op == "set" => Operation::Set {
+                key: key.clone(),
+                value: value.clone(),
+            },
+            [op, key] if op == "unset" => Operation::Unset { key: key.clone() },
+            [op, ..] if ["list", "get", "set", "unset"].contains(&op.as_str()) => {
+                return Err(Error::Usage.into());
+            }
+            [op, ..] => anyhow::bail!("unknown operation '{}'", op),
+        };
+
+        if project && !matches!(op, Operation::Set { .. } | Operation::Unset { .. }) {
+            anyhow::bail!("`--project` can only be used with `set` and `unset`");
+        }
+
+        Ok((Options { op, project }, vec![]))
+    }
+}
+
+pub fn run(options: Options, _ctx: impl term::Context) -> anyhow::Result<()> {
+    match options.op {
+        Operation::List => {
+            let mut table = term::Table::default();
+
+            for key in config::KEYS {
+                match config::get(key.name)? {
+                    Some(setting) => table.push([
+                        term::format::highlight(key.name),
+                        setting.value,
+                        term::format::dim(setting.source),
+                    ]),
+                    None => table.push([
+                        term::format::highlight(key.name),
+                        term::format::dim("-"),
+                        String::new(),
+                    ]),
+                }
+            }
+            table.render();
+        }
+        Operation::Get { key } => match config::get(&key)? {
+            Some(setting) => term::print(setting.value),
+            None => {
+                return Err(
+                    error::Error::new(error::Kind::NotFound, anyhow!("{} is not set", key)).into(),
+                )
+            }
+        },
+        Operation::Set { key, value } => {
+            let path = if options.project {
+                config::set_project(&key, &value)?
+            } else {
+                config::set(&key, &value)?
+            };
+
+            term::success!(
+                "Set {} to {} in {}",
+                term::format::highlight(&key),
+                term::format::tertiary(&value),
+                path.display()
+            );
+            warn_overridden(&key, options.project)?;
+        }
+        Operation::Unset { key } if options.project => {
+            if config::unset_project(&key)? {
+                term::success!("Unset {} for this project", term::format::highlight(&key));
+            } else {
+                term::info!("{} is not set in the git configuration", key);
+            }
+        }
+        Operation::Unset { key } => {
+            if config::unset(&key)? {
+                term::success!("Unset {}", term::format::highlight(&key));
+                warn_overridden(&key, false)?;
+            } else {
+                term::info!("{} is not set in the user configuration", key);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Warn if the setting is overridden by an environment variable or, unless it was
+/// set for the project, the working copy.
+fn warn_overridden(key: &str, project: bool) -> anyhow::Result<()> {
+    if let Some(setting) = config::get(key)? {
+        let overridden = match setting.source {
+            config::Source::Env(_) => true,
+            config::Source::Git => !project,
+            _ => false,
+        };
+        if overridden {
+            term::warning(&format!(
+                "{} is overridden by {}, with value {}",
+                key, setting.source, setting.value
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> anyhow::Result<Operation> {
+        let args = args.iter().map(OsString::from).collect();
+        let (options, _) = Options::from_args(args)?;
+
+        Ok(options.op)
+    }
+
+    #[test]
+    fn test_from_args() {
+        assert_eq!(parse(&[]).unwrap(), Operation::List);
+        assert_eq!(
+            parse(&["set", "terminal.emoji", "false"]).unwrap(),
+            Operation::Set {
+                key: "terminal.emoji".to_owned(),
+                value: "false".to_owned()
+            }
+        );
+        assert!(parse(&["get"]).is_err());
+        assert!(parse(&["get", "seed", "--project"]).is_err());
+        assert!(parse(&["frobnicate", "seed"]).is_err());
+    }
+}
@@ -0,0 +1,194 @@
+use std::ffi::OsString;
+
+use anyhow::anyhow;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "daemon",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad daemon [start] [--foreground]
+    rad daemon stop
+    rad daemon status
+
+    Starts a daemon that keeps your storage open, and serves radicle operations
+    to editors and other apps, so that they don't have to run `rad` for every
+    operation.
+
+    The daemon answers JSON-RPC 2.0 requests, one per line, on a socket in your
+    profile directory, or on the path set with the `RAD_DAEMON_SOCK` environment
+    variable. The following methods are supported:
+
+        projects.list     List the projects in local storage
+        projects.sync     Sync a `project` with seeds, optionally with a `mode`
+                          (fetch, push or all) and a `seed`
+        issues.list       List the issues of a `project`, optionally by `state`
+        patches.create    Propose the `head` commit as a patch to a `project`,
+                          with a `title`, and optionally a `description` and `base`
+        daemon.stop       Stop the daemon
+
+Options
+
+    --foreground    Run the daemon in the foreground, eg. under a service manager
+    --help          Print help
+"#,
+    examples: &[(
+        "List projects through a running daemon",
+        r#"echo '{"jsonrpc":"2.0","id":1,"method":"projects.list"}' | nc -U <socket>"#,
+    )],
+    environment: &[(
+        "RAD_DAEMON_SOCK",
+        "The path of the daemon's socket, instead of the default one.",
+    )],
+    ..Help::DEFAULT
+};
+
+#[derive(Debug)]
+pub enum Operation {
+    Start { foreground: bool },
+    Stop,
+    Status,
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut op: Option<String> = None;
+        let mut foreground = false;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("foreground") => {
+                    foreground = true;
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if op.is_none() => {
+                    op = Some(val.to_string_lossy().to_string());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let op = match op.as_deref() {
+            None | Some("start") => Operation::Start { foreground },
+            Some("stop") => Operation::Stop,
+            Some("status") => Operation::Status,
+            Some(unknown) => anyhow::bail!("unknown operation '{}'", unknown),
+        };
+        if foreground && !matches!(op, Operation::Start { .. }) {
+            return Err(Error::Usage.into());
+        }
+
+        Ok((Options { op }, vec![]))
+    }
+}
+
+#[cfg(unix)]
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    use std::io::Write as _;
+    use std::process::{Command, Stdio};
+    use std::{env, thread, time};
+
+    use radicle_common::daemon::{self, Daemon};
+    use radicle_common::keys::{self, agent};
+
+    let profile = ctx.profile()?;
+    let path = daemon::socket_path(&profile);
+
+    match options.op {
+        Operation::Start { foreground: true } => {
+            let signer = term::signer(&profile)?;
+            let daemon = Daemon::new(profile, signer)?;
+
+            term::info!(
+                "Daemon listening on {}",
+                term::format::highlight(path.display())
+            );
+            daemon::serve(&path, daemon)?;
+        }
+        Operation::Start { foreground: false } => {
+            if daemon::is_running(&path) {
+                anyhow::bail!("a daemon is already running on {}", path.display());
+            }
+            // Nb. Without a key agent, the daemon can't unseal the key on its own, so
+            // it is handed the passphrase, once checked.
+            let passphrase = if keys::ssh_auth_sock().is_ok()
+                || agent::AgentSigner::connect(&agent::socket_path(&profile)).is_ok()
+            {
+                None
+            } else {
+                let passphrase = term::secret_input();
+                let spinner = term::spinner("Unsealing key...");
+                keys::load_secret_key(&profile, passphrase.clone())?;
+                spinner.finish();
+
+                Some(passphrase)
+            };
+
+            let mut child = Command::new(env::current_exe()?)
+                .args(["daemon", "start", "--foreground"])
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()?;
+
+            if let (Some(mut stdin), Some(passphrase)) = (child.stdin.take(), passphrase) {
+                writeln!(stdin, "{}", passphrase.unsecure())?;
+            }
+            let spinner = term::spinner("Starting daemon...");
+
+            for _ in 0..50 {
+                if daemon::is_running(&path) {
+                    spinner.finish();
+                    term::success!(
+                        "Daemon started on {}",
+                        term::format::highlight(path.display())
+                    );
+                    return Ok(());
+                }
+                if child.try_wait()?.is_some() {
+                    break;
+                }
+                thread::sleep(time::Duration::from_millis(100));
+            }
+            spinner.failed();
+            anyhow::bail!("daemon failed to start, try running it with `--foreground`");
+        }
+        Operation::Stop => {
+            daemon::stop(&path)
+                .map_err(|_| anyhow!("no daemon is running on {}", path.display()))?;
+            term::success!("Daemon stopped");
+        }
+        Operation::Status => {
+            if daemon::is_running(&path) {
+                term::success!(
+                    "Daemon is running on {}",
+                    term::format::highlight(path.display())
+                );
+            } else {
+                term::info!("No daemon is running on {}", path.display());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn run(_options: Options, _ctx: impl term::Context) -> anyhow::Result<()> {
+    anyhow::bail!("`rad daemon` is not supported on this platform")
+}
@@ -0,0 +1,225 @@
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context as _};
+use either::Either;
+
+use librad::git::storage::ReadOnly;
+use librad::git::Urn;
+use librad::{PeerId, PublicKey};
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::{keys, profile, project};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "delegate",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad delegate [ls] [<option>...]
+    rad delegate add <urn | peer-id> [<option>...]
+    rad delegate rm <urn | peer-id> [<option>...]
+    rad delegate accept <peer-id> [<option>...]
+
+    Delegates are the maintainers of a project, who sign updates to its identity
+    document. A delegate is either a personal identity, given by URN, or a device
+    key, given by Peer ID. Personal identities must be in your storage.
+
+    Updates to the delegates must be signed by a majority of the current delegates
+    to take effect. After a delegate is added or removed, the other delegates can
+    sign the update with `rad delegate accept`, passing the Peer ID of the delegate
+    who made it.
+
+Options
+
+    --urn <urn>    Project to manage, instead of the project in the current directory
+    --wait         Wait for other commands to release the storage
+    --help         Print help
+"#,
+    ..Help::DEFAULT
+};
+
+#[derive(Debug)]
+pub enum Operation {
+    List,
+    Add { delegate: Either<PublicKey, Urn> },
+    Remove { delegate: Either<PublicKey, Urn> },
+    Accept { peer: PeerId },
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub urn: Option<Urn>,
+    pub op: Operation,
+    pub wait: bool,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut urn: Option<Urn> = None;
+        let mut op: Option<String> = None;
+        let mut value: Option<String> = None;
+        let mut wait = false;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("urn") if urn.is_none() => {
+                    let val = parser.value()?;
+                    let val = val.to_string_lossy();
+                    let val = Urn::from_str(&val).context(format!("invalid URN '{}'", val))?;
+
+                    urn = Some(val);
+                }
+                Long("wait") => {
+                    wait = true;
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if op.is_none() => {
+                    op = Some(val.to_string_lossy().to_string());
+                }
+                Value(val) if value.is_none() => {
+                    value = Some(val.to_string_lossy().to_string());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let op = match op.as_deref() {
+            None | Some("ls") => Operation::List,
+            Some("add") => Operation::Add {
+                delegate: project::parse_delegate(&value.ok_or(Error::Usage)?)?,
+            },
+            Some("rm") => Operation::Remove {
+                delegate: project::parse_delegate(&value.ok_or(Error::Usage)?)?,
+            },
+            Some("accept") => Operation::Accept {
+                peer: value
+                    .ok_or(Error::Usage)?
+                    .parse()
+                    .context("invalid value specified for peer")?,
+            },
+            Some(unknown) => anyhow::bail!("unknown operation '{}'", unknown),
+        };
+
+        Ok((Options { urn, op, wait }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let urn = match options.urn {
+        Some(urn) => urn,
+        None => project::cwd()
+            .map(|(urn, _)| urn)
+            .context("this command must be run within a project, or with `--urn`")?,
+    };
+
+    if let Operation::List = options.op {
+        let storage = profile::read_only(&profile)?;
+        let project = project::get(&storage, &urn)?
+            .ok_or_else(|| anyhow!("project {} was not found", urn))?;
+        let table = self::table(&storage, &project);
+
+        table.render();
+        term::blank();
+        term::info!(
+            "Updates must be signed by {} of {} delegate(s)",
+            project::quorum(project.delegates.len()),
+            project.delegates.len()
+        );
+
+        return Ok(());
+    }
+
+    let _lock = term::lock_storage(&profile, options.wait)?;
+    let signer = term::signer(&profile)?;
+    let storage = keys::storage(&profile, signer)?;
+    let before = project::get(&storage, &urn)?
+        .ok_or_else(|| anyhow!("project {} was not found", urn))?
+        .delegates
+        .len();
+
+    match options.op {
+        Operation::Add { delegate } => {
+            project::update_delegates(&storage, &urn, Some(&delegate), None)?;
+            term::success!("Delegate added to {}", term::format::highlight(&urn));
+        }
+        Operation::Remove { delegate } => {
+            project::update_delegates(&storage, &urn, None, Some(&delegate))?;
+            term::success!("Delegate removed from {}", term::format::highlight(&urn));
+        }
+        Operation::Accept { peer } => {
+            project::accept_update(&storage, &urn, peer)?;
+            term::success!(
+                "Signed the update of {} by {}",
+                term::format::highlight(&urn),
+                term::format::tertiary(peer)
+            );
+        }
+        Operation::List => unreachable!(),
+    }
+    let quorum = project::quorum(before);
+
+    if quorum > 1 {
+        term::info!(
+            "The update takes effect once it is signed by {} of {} delegates",
+            quorum,
+            before
+        );
+        term::tip!(
+            "Other delegates can sign it with {}",
+            term::format::secondary(format!(
+                "`rad delegate accept {} --urn {}`",
+                storage.peer_id(),
+                urn
+            ))
+        );
+    }
+    term::blank();
+    term::tip!(
+        "To publish the update, run {}",
+        term::format::secondary("`rad push`")
+    );
+
+    Ok(())
+}
+
+/// Get a table of the delegates of a project, with their keys.
+pub fn table<S: AsRef<ReadOnly>>(storage: &S, project: &project::Metadata) -> term::Table<3> {
+    let mut table = term::Table::default();
+
+    for delegate in &project.delegates {
+        match delegate {
+            project::Delegate::Direct { id } => {
+                table.push([
+                    term::format::tertiary(id),
+                    term::format::dim("key"),
+                    String::new(),
+                ]);
+            }
+            project::Delegate::Indirect { urn, ids } => {
+                table.push([
+                    term::format::highlight(urn),
+                    term::format::dim("person"),
+                    delegate.name(storage).unwrap_or_default(),
+                ]);
+                for id in ids {
+                    table.push([
+                        format!("└─ {}", term::format::tertiary(id)),
+                        term::format::dim("key"),
+                        String::new(),
+                    ]);
+                }
+            }
+        }
+    }
+    table
+}
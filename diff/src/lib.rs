@@ -0,0 +1,199 @@
+use std::ffi::OsString;
+use std::process::Command;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context as _};
+
+use librad::git::Urn;
+use librad::PeerId;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::{config, profile, project};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "diff",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad diff <peer> [<branch>] [<option>...]
+
+    Shows the changes a tracked peer made to a project branch since it diverged
+    from your view of it, straight from storage, without checking anything out. The peer is
+    given by Peer ID, or by name. The branch defaults to the project's default
+    branch.
+
+    By default, a diffstat is shown followed by the patch.
+
+Options
+
+    --urn <urn>    Project to diff, instead of the project in the current directory
+    --stat         Only show the diffstat
+    --patch        Only show the patch
+    --help         Print help
+"#,
+    ..Help::DEFAULT
+};
+
+/// What to output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    /// Diffstat followed by the patch.
+    All,
+    /// Only the diffstat.
+    Stat,
+    /// Only the patch.
+    Patch,
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Self::All
+    }
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub peer: String,
+    pub branch: Option<String>,
+    pub urn: Option<Urn>,
+    pub output: Output,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut peer: Option<String> = None;
+        let mut branch: Option<String> = None;
+        let mut urn: Option<Urn> = None;
+        let mut output = Output::default();
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("urn") if urn.is_none() => {
+                    let val = parser.value()?;
+                    let val = val.to_string_lossy();
+                    let val = Urn::from_str(&val).context(format!("invalid URN '{}'", val))?;
+
+                    urn = Some(val);
+                }
+                Long("stat") if output == Output::All => {
+                    output = Output::Stat;
+                }
+                Long("patch") if output == Output::All => {
+                    output = Output::Patch;
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if peer.is_none() => {
+                    peer = Some(val.to_string_lossy().to_string());
+                }
+                Value(val) if branch.is_none() => {
+                    branch = Some(val.to_string_lossy().to_string());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        Ok((
+            Options {
+                peer: peer.ok_or_else(|| anyhow!("a peer to diff against must be specified"))?,
+                branch,
+                urn,
+                output,
+            },
+            vec![],
+        ))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let storage = profile::read_only(&profile)?;
+    let urn = match options.urn {
+        Some(urn) => urn,
+        None => project::cwd()
+            .map(|(urn, _)| urn)
+            .context("this command must be run within a project, or with `--urn`")?,
+    };
+    let project = project::get(&storage, &urn)?
+        .ok_or_else(|| anyhow!("project {} was not found in local storage", urn))?;
+    let branch = options
+        .branch
+        .unwrap_or_else(|| project.default_branch.to_string());
+    let peer = find_peer(&options.peer, &project, &storage)?;
+
+    let ours = project::get_local_head(&storage, &urn, &branch)?
+        .ok_or_else(|| anyhow!("you don't have a `{}` branch in this project", branch))?;
+    let theirs = project::get_remote_head(&storage, &urn, &peer, &branch)
+        .ok()
+        .flatten()
+        .ok_or_else(|| Error::WithHint {
+            err: anyhow!("peer {} has no `{}` branch in local storage", peer, branch),
+            hint: "Fetch the peer's refs with `rad sync --fetch`.",
+        })?;
+
+    if ours == theirs {
+        term::info!(
+            "No changes, both views of `{}` are at {:.7}",
+            branch,
+            ours.to_string()
+        );
+        return Ok(());
+    }
+
+    let mut args = vec![String::from("diff")];
+    match options.output {
+        Output::All => args.extend([String::from("--stat"), String::from("--patch")]),
+        Output::Stat => args.push(String::from("--stat")),
+        Output::Patch => args.push(String::from("--patch")),
+    }
+    // Nb. Only the peer's changes since the views diverged are shown, as with a patch,
+    // and not the changes that are only in our view.
+    args.push(format!("{}...{}", ours, theirs));
+
+    let mut git = Command::new("git");
+    git.arg("--git-dir")
+        .arg(profile.paths().git_dir())
+        .args(&args);
+
+    // Use the configured pager, if any.
+    if let Some(pager) = config::get("terminal.pager")? {
+        git.env("GIT_PAGER", pager.value);
+    }
+    let status = git.status()?;
+    if !status.success() {
+        anyhow::bail!("`git diff` failed with {}", status);
+    }
+    Ok(())
+}
+
+/// Find a tracked peer by Peer ID or by name.
+fn find_peer(
+    peer: &str,
+    project: &project::Metadata,
+    storage: &librad::git::storage::ReadOnly,
+) -> anyhow::Result<PeerId> {
+    if let Ok(peer) = PeerId::from_str(peer) {
+        return Ok(peer);
+    }
+    let matches = project::tracked(project, storage)?
+        .into_iter()
+        .filter(|(_, info)| info.name() == peer)
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+
+    match matches.as_slice() {
+        [id] => Ok(*id),
+        [] => Err(anyhow!("no tracked peer named '{}' was found", peer)),
+        _ => Err(anyhow!(
+            "more than one tracked peer is named '{}', use its Peer ID instead",
+            peer
+        )),
+    }
+}
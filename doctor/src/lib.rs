@@ -0,0 +1,299 @@
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+
+use anyhow::anyhow;
+
+use librad::git::tracking;
+use librad::profile::Profile;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::{git, keys, profile};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "doctor",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad doctor [<option>...]
+
+    Checks your git installation, profile, storage and key agents for problems,
+    and suggests how to fix them. Exits with a non-zero status if any check fails.
+
+Options
+
+    --help    Print help
+"#,
+    ..Help::DEFAULT
+};
+
+#[derive(Debug)]
+pub struct Options {}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+
+        if let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        Ok((Options {}, vec![]))
+    }
+}
+
+/// Outcome of a check.
+enum Outcome {
+    /// The check passed.
+    Ok(String),
+    /// The check found a problem that doesn't prevent radicle from working.
+    Warn { problem: String, fix: String },
+    /// The check failed.
+    Fail { problem: String, fix: String },
+}
+
+impl Outcome {
+    fn warn(problem: impl ToString, fix: impl ToString) -> Self {
+        Self::Warn {
+            problem: problem.to_string(),
+            fix: fix.to_string(),
+        }
+    }
+
+    fn fail(problem: impl ToString, fix: impl ToString) -> Self {
+        Self::Fail {
+            problem: problem.to_string(),
+            fix: fix.to_string(),
+        }
+    }
+}
+
+/// Results of all checks.
+#[derive(Default)]
+struct Report {
+    failures: usize,
+}
+
+impl Report {
+    fn check(&mut self, name: &str, outcome: Outcome) {
+        match outcome {
+            Outcome::Ok(detail) => {
+                term::info!(
+                    "{} {} {}",
+                    term::format::positive(term::format::glyph("✓", "+")),
+                    name,
+                    term::format::dim(detail)
+                );
+            }
+            Outcome::Warn { problem, fix } => {
+                term::info!(
+                    "{} {} {}",
+                    term::format::yellow("!"),
+                    name,
+                    term::format::yellow(problem)
+                );
+                term::indented(&term::format::dim(format!("fix: {}", fix)));
+            }
+            Outcome::Fail { problem, fix } => {
+                self.failures += 1;
+
+                term::info!(
+                    "{} {} {}",
+                    term::format::negative(term::format::glyph("✗", "x")),
+                    name,
+                    term::format::negative(problem)
+                );
+                term::indented(&term::format::dim(format!("fix: {}", fix)));
+            }
+        }
+    }
+}
+
+pub fn run(_options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let mut report = Report::default();
+
+    report.check("git", check_git());
+
+    match ctx.profile() {
+        Ok(profile) => {
+            report.check("profile", Outcome::Ok(profile.id().to_string()));
+
+            if let Some(outcome) = check_layout(&profile) {
+                report.check("storage", outcome);
+            } else {
+                report.check("storage", check_integrity(&profile));
+                report.check("tracking", check_tracking(&profile));
+            }
+            report.check("ssh-agent", check_ssh_agent(&profile));
+            #[cfg(unix)]
+            report.check("rad agent", check_agent(&profile));
+        }
+        Err(err) => {
+            report.check(
+                "profile",
+                Outcome::fail(err, "Run `rad auth --init` to create a profile"),
+            );
+        }
+    }
+
+    match report.failures {
+        0 => Ok(()),
+        1 => Err(anyhow!("1 check failed")),
+        n => Err(anyhow!("{} checks failed", n)),
+    }
+}
+
+fn check_git() -> Outcome {
+    match git::check_version() {
+        Ok(version) => Outcome::Ok(format!("version {}", version)),
+        Err(err) => Outcome::fail(
+            err,
+            format!(
+                "Install git {} or later, see https://git-scm.com/downloads",
+                git::VERSION_REQUIRED
+            ),
+        ),
+    }
+}
+
+/// Check that the profile's storage and key exist. Returns `None` if they do.
+fn check_layout(profile: &Profile) -> Option<Outcome> {
+    let paths = profile.paths();
+    let fix = "Your profile is incomplete, run `rad auth --init` to create a new profile";
+
+    if !paths.git_dir().is_dir() {
+        return Some(Outcome::fail(
+            format!("{} is missing", paths.git_dir().display()),
+            fix,
+        ));
+    }
+    let key = paths.keys_dir().join(keys::KEY_FILE);
+    if !key.is_file() {
+        return Some(Outcome::fail(format!("{} is missing", key.display()), fix));
+    }
+    None
+}
+
+fn check_integrity(profile: &Profile) -> Outcome {
+    let git_dir = profile.paths().git_dir();
+    let spinner = term::spinner("Checking storage integrity...");
+    let result = git::git(
+        git_dir,
+        [
+            "fsck",
+            "--no-progress",
+            "--no-dangling",
+            "--connectivity-only",
+        ],
+    );
+    spinner.clear();
+
+    match result {
+        Ok(_) => Outcome::Ok(git_dir.display().to_string()),
+        Err(err) => {
+            let problem = err.to_string();
+            let problem = problem.lines().next().unwrap_or_default();
+
+            Outcome::fail(
+                format!("`git fsck` failed: {}", problem),
+                "Re-fetch the affected projects with `rad sync <urn>`, or restore the storage from a backup",
+            )
+        }
+    }
+}
+
+fn check_tracking(profile: &Profile) -> Outcome {
+    let dangling = profile::read_only(profile).and_then(|storage| {
+        let mut dangling = BTreeSet::new();
+
+        for tracked in tracking::tracked(&storage, None)? {
+            let urn = tracked?.urn().clone();
+
+            if !storage.has_urn(&urn)? {
+                dangling.insert(urn.to_string());
+            }
+        }
+        Ok(dangling)
+    });
+
+    match dangling {
+        Ok(dangling) if dangling.is_empty() => Outcome::Ok(String::from("no dangling entries")),
+        Ok(dangling) => {
+            let urns = dangling.into_iter().collect::<Vec<_>>();
+
+            Outcome::warn(
+                format!("tracked projects missing from storage: {}", urns.join(", ")),
+                format!(
+                    "Fetch the projects with `rad sync <urn>`, eg. `rad sync {}`",
+                    urns[0]
+                ),
+            )
+        }
+        Err(err) => Outcome::fail(
+            format!("could not read tracking entries: {}", err),
+            "Check that the storage is readable, and run `rad doctor` again",
+        ),
+    }
+}
+
+fn check_ssh_agent(profile: &Profile) -> Outcome {
+    let sock = match keys::ssh_auth_sock() {
+        Ok(sock) => sock,
+        Err(_) => {
+            return Outcome::warn(
+                "not running, you will be prompted for your passphrase",
+                "Start ssh-agent, eg. with `eval $(ssh-agent)`, or run `rad agent start`",
+            )
+        }
+    };
+    match keys::is_ready(profile, sock) {
+        Ok(true) => Outcome::Ok(String::from("key present")),
+        Ok(false) => Outcome::warn(
+            "radicle key is not in ssh-agent",
+            "Run `rad auth` to add your key to ssh-agent",
+        ),
+        Err(err) => Outcome::fail(
+            err,
+            "Check that `SSH_AUTH_SOCK` points to a running ssh-agent",
+        ),
+    }
+}
+
+#[cfg(unix)]
+fn check_agent(profile: &Profile) -> Outcome {
+    use librad::crypto::keystore::sign::ed25519::Signer as _;
+    use radicle_common::keys::agent;
+
+    let path = agent::socket_path(profile);
+    let agent = match agent::AgentSigner::connect(&path) {
+        Ok(agent) => agent,
+        Err(_) => return Outcome::Ok(String::from("not running")),
+    };
+    let storage = match profile::read_only(profile) {
+        Ok(storage) => storage,
+        Err(err) => {
+            return Outcome::fail(
+                format!("could not read profile key: {}", err),
+                "Check that the storage is readable, and run `rad doctor` again",
+            )
+        }
+    };
+
+    if agent.public_key().0[..] == *storage.peer_id().as_public_key().as_ref() {
+        Outcome::Ok(format!("running on {}", path.display()))
+    } else {
+        Outcome::fail(
+            format!("agent on {} holds a key of another profile", path.display()),
+            "Restart the agent with `rad agent stop` and `rad agent start`",
+        )
+    }
+}
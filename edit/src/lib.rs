@@ -1,12 +1,16 @@
+use std::convert::TryFrom;
 use std::ffi::OsString;
 use std::str::FromStr;
 
 use radicle_common::args::{Args, Error, Help};
-use radicle_common::keys;
+use radicle_common::{keys, sync, tokio};
 use radicle_terminal as term;
 
+use librad::canonical::Cstring;
 use librad::git::identities::{any, person, project, SomeIdentity};
+use librad::git::storage::Storage;
 use librad::git::Urn;
+use librad::git_ext::RefLike;
 
 use link_identities::payload::{PersonPayload, ProjectPayload};
 
@@ -24,15 +28,35 @@ Usage
     Edits the identity document pointed to by the URN. If it isn't specified,
     the current project is edited.
 
+    The document is opened in your editor, unless the project metadata to change
+    is given with options. The update is signed with your key, and published to
+    your seeds.
+
 Options
 
-    --help              Print help
+    --name <name>               Change the project name
+    --description <text>        Change the project description
+    --default-branch <branch>   Change the project default branch
+    --no-push                   Don't publish the update to the seeds
+    --help                      Print help
 "#,
+    ..Help::DEFAULT
 };
 
 #[derive(Default, Debug, Eq, PartialEq)]
 pub struct Options {
     pub urn: Option<Urn>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub branch: Option<String>,
+    pub push: bool,
+}
+
+impl Options {
+    /// Whether any project metadata to change was given with options.
+    fn has_changes(&self) -> bool {
+        self.name.is_some() || self.description.is_some() || self.branch.is_some()
+    }
 }
 
 impl Args for Options {
@@ -41,9 +65,25 @@ impl Args for Options {
 
         let mut parser = lexopt::Parser::from_args(args);
         let mut urn: Option<Urn> = None;
+        let mut name = None;
+        let mut description = None;
+        let mut branch = None;
+        let mut push = true;
 
         while let Some(arg) = parser.next()? {
             match arg {
+                Long("name") if name.is_none() => {
+                    name = Some(parser.value()?.to_string_lossy().to_string());
+                }
+                Long("description") if description.is_none() => {
+                    description = Some(parser.value()?.to_string_lossy().to_string());
+                }
+                Long("default-branch") if branch.is_none() => {
+                    branch = Some(parser.value()?.to_string_lossy().to_string());
+                }
+                Long("no-push") => {
+                    push = false;
+                }
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
@@ -60,17 +100,27 @@ impl Args for Options {
             }
         }
 
-        Ok((Options { urn }, vec![]))
+        Ok((
+            Options {
+                urn,
+                name,
+                description,
+                branch,
+                push,
+            },
+            vec![],
+        ))
     }
 }
 
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profile = ctx.profile()?;
     let signer = term::signer(&profile)?;
-    let storage = keys::storage(&profile, signer)?;
+    let storage = keys::storage(&profile, signer.clone())?;
 
     let urn = options
         .urn
+        .clone()
         .or_else(|| radicle_common::project::cwd().ok().map(|(urn, _)| urn))
         .ok_or_else(|| anyhow!("Couldn't get URN from either command line or cwd"))?;
 
@@ -79,18 +129,49 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
     match identity {
         SomeIdentity::Project(_) => {
-            let payload = serde_json::to_string_pretty(
-                project::verify(&storage, &urn)?
-                    .ok_or_else(|| anyhow!("Couldn't get project's identity doc"))?
-                    .payload(),
-            )?;
-            match term::Editor::new().edit(&payload)? {
-                Some(updated_payload) => {
-                    let payload: ProjectPayload = serde_json::from_str(&updated_payload)?;
-                    project::update(&storage, &urn, None, payload, None)?;
+            let current = project::verify(&storage, &urn)?
+                .ok_or_else(|| anyhow!("Couldn't get project's identity doc"))?
+                .payload()
+                .clone();
+
+            let payload = if options.has_changes() {
+                let mut payload = current.clone();
+
+                if let Some(name) = options.name {
+                    payload.subject.name = Cstring::from(name);
                 }
-                None => return Err(anyhow!("Operation aborted!")),
+                if let Some(description) = options.description {
+                    payload.subject.description = Some(Cstring::from(description));
+                }
+                if let Some(branch) = options.branch {
+                    payload.subject.default_branch = Some(Cstring::from(branch));
+                }
+                payload
+            } else {
+                match term::editor(&serde_json::to_string_pretty(&current)?, ".json")? {
+                    Some(updated_payload) => serde_json::from_str(&updated_payload)?,
+                    None => return Err(anyhow!("Operation aborted!")),
+                }
+            };
+            validate(&payload, &urn, &storage)?;
+
+            if serde_json::to_value(&payload)? == serde_json::to_value(&current)? {
+                term::info!("No changes to project metadata");
+                return Ok(());
             }
+            project::update(&storage, &urn, None, payload, None)?;
+            term::success!("Project {} updated", term::format::highlight(&urn));
+
+            if options.push {
+                let seeds = sync::seeds(&profile)?;
+                let rt = tokio::runtime::Runtime::new()?;
+
+                term::sync::sync(urn, seeds, sync::Mode::Push, &profile, signer, &rt)?;
+            }
+            return Ok(());
+        }
+        SomeIdentity::Person(_) if options.has_changes() => {
+            anyhow::bail!("Project metadata options can't be used with personal identities")
         }
         SomeIdentity::Person(_) => {
             let payload = serde_json::to_string_pretty(
@@ -98,7 +179,7 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                     .ok_or_else(|| anyhow!("Couldn't get person's identity doc"))?
                     .payload(),
             )?;
-            match term::Editor::new().edit(&payload)? {
+            match term::editor(&payload, ".json")? {
                 Some(updated_payload) => {
                     let payload: PersonPayload = serde_json::from_str(&updated_payload)?;
                     person::update(&storage, &urn, None, payload, None)?;
@@ -115,3 +196,28 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Validate updated project metadata.
+fn validate(payload: &ProjectPayload, urn: &Urn, storage: &Storage) -> anyhow::Result<()> {
+    let subject = &payload.subject;
+
+    if subject.name.to_string().trim().is_empty() {
+        anyhow::bail!("project name must not be empty");
+    }
+    let branch = subject
+        .default_branch
+        .as_ref()
+        .map(|b| b.to_string())
+        .ok_or_else(|| anyhow!("project default branch must be set"))?;
+
+    if RefLike::try_from(branch.as_str()).is_err() {
+        anyhow::bail!("invalid default branch name '{}'", branch);
+    }
+    if radicle_common::project::get_local_head(storage, urn, &branch)?.is_none() {
+        anyhow::bail!(
+            "default branch '{}' was not found in project, push it first",
+            branch
+        );
+    }
+    Ok(())
+}
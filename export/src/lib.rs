@@ -0,0 +1,109 @@
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context as _};
+
+use librad::git::Urn;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::{bundle, profile, project};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "export",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad export <urn> <file> [<option>...]
+
+    Exports a project from local storage to a bundle file, which can be moved to
+    another device and imported with `rad import`, without a network connection.
+
+    The bundle contains every peer's view of the project that is in local storage,
+    including yours, along with the identities needed to verify it.
+
+Options
+
+    --help    Print help
+"#,
+    ..Help::DEFAULT
+};
+
+#[derive(Debug)]
+pub struct Options {
+    pub urn: Urn,
+    pub file: PathBuf,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut urn: Option<Urn> = None;
+        let mut file: Option<PathBuf> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if urn.is_none() => {
+                    let val = val.to_string_lossy();
+                    let val = Urn::from_str(&val).context(format!("invalid URN '{}'", val))?;
+
+                    urn = Some(val);
+                }
+                Value(val) if file.is_none() => {
+                    file = Some(PathBuf::from(val));
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        Ok((
+            Options {
+                urn: urn.ok_or(Error::Usage)?,
+                file: file.ok_or(Error::Usage)?,
+            },
+            vec![],
+        ))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let storage = profile::read_only(&profile)?;
+    let project = project::get(&storage, &options.urn)?
+        .ok_or_else(|| anyhow!("project {} was not found in local storage", options.urn))?;
+
+    if options.file.exists() {
+        anyhow::bail!("file '{}' already exists", options.file.display());
+    }
+
+    let spinner = term::spinner(format!(
+        "Exporting {} to {}...",
+        term::format::highlight(&project.name),
+        term::format::tertiary(options.file.display())
+    ));
+    match bundle::export(&storage, &options.urn, &options.file) {
+        Ok(refs) => {
+            spinner.finish();
+            term::success!("Exported {} ref(s)", refs);
+        }
+        Err(err) => {
+            spinner.failed();
+            return Err(err);
+        }
+    }
+    term::blank();
+    term::tip!(
+        "To import the project on another device, run {}",
+        term::format::secondary(format!("`rad import {}`", options.file.display()))
+    );
+
+    Ok(())
+}
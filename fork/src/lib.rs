@@ -0,0 +1,161 @@
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context as _};
+
+use librad::git::Urn;
+use librad::PeerId;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::{keys, project};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "fork",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad fork [<urn>] [<option>...]
+
+    Creates a new project from a project you track, delegated to you, so that you
+    can publish your own changes to it. The new project records which project it
+    was forked from, and its default branch starts at your view of the upstream
+    project, or at the given peer's view with `--peer`.
+
+    If no URN is specified, the project of the current working copy is forked.
+
+Options
+
+    --peer <peer-id>    Fork the given peer's view of the project
+    --wait              Wait for other commands to release the storage
+    --help              Print help
+"#,
+    ..Help::DEFAULT
+};
+
+#[derive(Debug)]
+pub struct Options {
+    pub urn: Option<Urn>,
+    pub peer: Option<PeerId>,
+    pub wait: bool,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut urn: Option<Urn> = None;
+        let mut peer: Option<PeerId> = None;
+        let mut wait = false;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("peer") if peer.is_none() => {
+                    let val = parser.value()?;
+                    let val = val
+                        .to_string_lossy()
+                        .parse()
+                        .context("invalid value specified for peer")?;
+
+                    peer = Some(val);
+                }
+                Long("wait") => {
+                    wait = true;
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if urn.is_none() => {
+                    let val = val.to_string_lossy();
+                    let val = Urn::from_str(&val).context(format!("invalid URN '{}'", val))?;
+
+                    urn = Some(val);
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        Ok((Options { urn, peer, wait }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let urn = match options.urn {
+        Some(urn) => urn,
+        None => project::cwd()
+            .map(|(urn, _)| urn)
+            .context("this command must be run within a project, or with a URN")?,
+    };
+
+    let _lock = term::lock_storage(&profile, options.wait)?;
+    let signer = term::signer(&profile)?;
+    let storage = keys::storage(&profile, signer)?;
+    let upstream =
+        project::get(&storage, &urn)?.context("project could not be found in local storage")?;
+    let branch = upstream.default_branch.to_string();
+
+    // Fork our own view if we have one, otherwise the view of the only delegate.
+    let head = match options.peer {
+        Some(peer) => project::get_remote_head(&storage, &urn, &peer, &branch)
+            .ok()
+            .flatten()
+            .ok_or_else(|| anyhow!("{} branch of peer {} was not found", branch, peer))?,
+        None => match project::get_local_head(&storage, &urn, &branch)? {
+            Some(head) => head,
+            None if upstream.remotes.len() == 1 => {
+                let peer = upstream.remotes.iter().next().unwrap();
+
+                project::get_remote_head(&storage, &urn, peer, &branch)
+                    .ok()
+                    .flatten()
+                    .ok_or_else(|| {
+                        anyhow!("{} branch of delegate {} was not found", branch, peer)
+                    })?
+            }
+            None => {
+                return Err(Error::WithHint {
+                    err: anyhow!(
+                        "project has more than one delegate, and no local {} branch",
+                        branch
+                    ),
+                    hint: "Use `--peer` to specify which view of the project to fork.",
+                }
+                .into())
+            }
+        },
+    };
+
+    term::headline(&format!(
+        "Forking 🌱 {} ({}) at {}",
+        term::format::highlight(&urn),
+        upstream.name,
+        term::format::secondary(format!("{:.7}", head.to_string())),
+    ));
+
+    let spinner = term::spinner("Creating fork...");
+    let fork = match project::fork(&storage, &upstream, head) {
+        Ok(fork) => fork,
+        Err(err) => {
+            spinner.failed();
+            return Err(err);
+        }
+    };
+    spinner.finish();
+
+    term::success!("Fork {} created", term::format::highlight(&fork.urn()));
+    term::blank();
+    term::tip!(
+        "To work on your fork, run {}",
+        term::format::secondary(format!("`rad checkout {}`", fork.urn()))
+    );
+    term::tip!(
+        "To publish it, run {} from its working copy",
+        term::format::secondary("`rad push`")
+    );
+
+    Ok(())
+}
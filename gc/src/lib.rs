@@ -0,0 +1,144 @@
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use anyhow::anyhow;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::git;
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "gc",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad gc [<option>...]
+
+    Repacks the objects in the local storage and removes unreachable objects
+    that are older than two weeks, then shows how much space was reclaimed.
+
+    Refs are left unpacked, so that projects can still be removed with `rad rm`.
+    With `--prune`, unreachable objects are removed once they are an hour old, eg.
+    those that were only reachable from projects removed with `rad rm`. Objects
+    are never removed as soon as they are unreachable, since some commands, eg.
+    `git push rad`, write objects without locking the storage.
+
+Options
+
+    --prune    Remove unreachable objects older than an hour
+    --wait     Wait for other commands to release the storage
+    --help     Print help
+"#,
+    ..Help::DEFAULT
+};
+
+/// How long unreachable objects are kept for, by default.
+pub const PRUNE_EXPIRE: &str = "2.weeks.ago";
+/// How long unreachable objects are kept for, with `--prune`. Objects that were just
+/// written by a command that doesn't lock the storage aren't reachable yet.
+pub const PRUNE_EXPIRE_MIN: &str = "1.hour.ago";
+
+#[derive(Debug, Default)]
+pub struct Options {
+    pub prune: bool,
+    pub wait: bool,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut options = Options::default();
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("prune") => {
+                    options.prune = true;
+                }
+                Long("wait") => {
+                    options.wait = true;
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        Ok((options, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    // Nb. Commands that lock the storage may not write to it while objects are pruned,
+    // but others, eg. `git-remote-rad`, may, hence the grace period of pruning.
+    let _lock = term::lock_storage(&profile, options.wait)?;
+    let monorepo = profile.paths().git_dir();
+    let objects = monorepo.join("objects");
+    let before = size(&objects)?;
+    let expire = if options.prune {
+        PRUNE_EXPIRE_MIN
+    } else {
+        PRUNE_EXPIRE
+    };
+    let prune = format!("--prune={}", expire);
+
+    let spinner = term::spinner("Collecting garbage...");
+    let result = (|| {
+        if options.prune {
+            // Reflogs would otherwise keep objects of removed refs reachable.
+            let reflog = format!("--expire-unreachable={}", PRUNE_EXPIRE_MIN);
+            git::git(monorepo, ["reflog", "expire", reflog.as_str(), "--all"])?;
+        }
+        git::git(
+            monorepo,
+            [
+                "-c",
+                "gc.autoDetach=false",
+                "-c",
+                "gc.packRefs=false",
+                "gc",
+                "--quiet",
+                prune.as_str(),
+            ],
+        )
+    })();
+
+    if let Err(err) = result {
+        spinner.failed();
+        return Err(err);
+    }
+    spinner.finish();
+
+    let after = size(&objects)?;
+    term::success!(
+        "Storage is {} ({} reclaimed)",
+        term::format::highlight(term::format::bytes(after)),
+        term::format::positive(term::format::bytes(before.saturating_sub(after)))
+    );
+
+    Ok(())
+}
+
+/// Get the total size of the files in a directory, recursively.
+fn size(path: &Path) -> io::Result<u64> {
+    let mut total = 0;
+
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let meta = entry.metadata()?;
+
+        if meta.is_dir() {
+            total += size(&entry.path())?;
+        } else {
+            total += meta.len();
+        }
+    }
+    Ok(total)
+}
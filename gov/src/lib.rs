@@ -49,6 +49,7 @@ Commands
     queue   (q)  queue a proposal
     vote    (v)  vote on a proposal
 "#,
+    ..Help::DEFAULT
 };
 
 enum Command {
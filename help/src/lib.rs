@@ -1,88 +1,205 @@
 use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::anyhow;
 
 use radicle_common::args::{Args, Error, Help};
+use radicle_common::{man, plugin};
 use radicle_terminal as term;
 
 #[cfg(feature = "ethereum")]
 pub use rad_account;
+pub use rad_agent;
 pub use rad_auth;
 pub use rad_checkout;
 pub use rad_clone;
 pub use rad_comment;
+pub use rad_config;
+pub use rad_daemon;
+pub use rad_delegate;
+pub use rad_diff;
+pub use rad_doctor;
 pub use rad_edit;
 #[cfg(feature = "ethereum")]
 pub use rad_ens;
+pub use rad_export;
+pub use rad_fork;
+pub use rad_gc;
 #[cfg(feature = "ethereum")]
 pub use rad_gov;
+pub use rad_import;
 pub use rad_init;
 pub use rad_inspect;
 pub use rad_issue;
+pub use rad_log;
 pub use rad_ls;
 pub use rad_merge;
+pub use rad_notifications;
 pub use rad_patch;
 pub use rad_path;
 pub use rad_pull;
 pub use rad_push;
+pub use rad_react;
 pub use rad_remote;
 pub use rad_review;
 pub use rad_rm;
+pub use rad_seed;
 pub use rad_self;
+pub use rad_status;
 pub use rad_sync;
+pub use rad_tag;
 pub use rad_track;
 pub use rad_untrack;
+pub use rad_verify;
+pub use rad_web;
 
 pub const HELP: Help = Help {
     name: "help",
     description: env!("CARGO_PKG_DESCRIPTION"),
     version: env!("CARGO_PKG_VERSION"),
-    usage: "Usage: rad help [--help]",
+    usage: r#"
+Usage
+
+    rad help [--man <command> | --man-dir <dir>] [<option>...]
+
+    Without options, lists the common commands, and the external commands found
+    on your `PATH`. An external command `rad-<name>` is run as `rad <name>`, with
+    the same arguments and environment, and the profile in use set in `RAD_PROFILE`.
+
+    Hooks are executables in the `hooks` directory of the profile, run around
+    radicle operations: `pre-push`, `post-sync`, `post-clone` and `patch-received`.
+    They are given the context of the operation as JSON on their standard input.
+    A failing `pre-push` hook aborts the push.
+
+    With `--man`, prints the man page of a command as roff, eg. to view it with
+    `rad help --man sync | man -l -`. With `--man-dir`, the man pages of all
+    commands are written to a directory.
+
+Options
+
+    --man <command>     Print the man page of a command
+    --man-dir <dir>     Write the man pages of all commands to a directory
+    --help              Print help
+"#,
+    ..Help::DEFAULT
 };
 
 const COMMANDS: &[Help] = &[
     rad_auth::HELP,
+    rad_agent::HELP,
+    rad_daemon::HELP,
     rad_init::HELP,
+    rad_delegate::HELP,
     rad_self::HELP,
+    rad_config::HELP,
+    rad_doctor::HELP,
     rad_inspect::HELP,
     rad_clone::HELP,
+    rad_fork::HELP,
+    rad_export::HELP,
+    rad_import::HELP,
+    rad_log::HELP,
     rad_ls::HELP,
+    rad_status::HELP,
     rad_remote::HELP,
     rad_push::HELP,
     rad_pull::HELP,
     rad_checkout::HELP,
+    rad_diff::HELP,
+    rad_tag::HELP,
+    rad_verify::HELP,
     rad_track::HELP,
     rad_untrack::HELP,
     rad_sync::HELP,
+    rad_seed::HELP,
+    rad_web::HELP,
     #[cfg(feature = "ethereum")]
     rad_ens::HELP,
     #[cfg(feature = "ethereum")]
     rad_account::HELP,
     rad_rm::HELP,
+    rad_gc::HELP,
     rad_edit::HELP,
     crate::HELP,
 ];
 
+/// Commands that are not listed in the overview, but have man pages.
+const OTHER_COMMANDS: &[Help] = &[
+    rad_issue::HELP,
+    rad_patch::HELP,
+    rad_comment::HELP,
+    rad_review::HELP,
+    rad_merge::HELP,
+    rad_react::HELP,
+    rad_notifications::HELP,
+    rad_path::HELP,
+    #[cfg(feature = "ethereum")]
+    rad_gov::HELP,
+];
+
 #[derive(Default)]
-pub struct Options {}
+pub struct Options {
+    pub man: Option<String>,
+    pub man_dir: Option<PathBuf>,
+}
 
 impl Args for Options {
     fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
         use lexopt::prelude::*;
 
         let mut parser = lexopt::Parser::from_args(args);
+        let mut options = Options::default();
 
-        if let Some(arg) = parser.next()? {
+        while let Some(arg) = parser.next()? {
             match arg {
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
+                Long("man") => {
+                    options.man = Some(parser.value()?.to_string_lossy().into_owned());
+                }
+                Long("man-dir") => {
+                    options.man_dir = Some(parser.value()?.into());
+                }
                 _ => return Err(anyhow::anyhow!(arg.unexpected())),
             }
         }
-        Ok((Options {}, vec![]))
+        Ok((options, vec![]))
     }
 }
 
-pub fn run(_options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+/// Get the help of all built-in commands.
+pub fn commands() -> impl Iterator<Item = &'static Help> {
+    COMMANDS.iter().chain(OTHER_COMMANDS)
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    if let Some(name) = options.man {
+        let help = commands()
+            .find(|h| h.name == name)
+            .ok_or_else(|| anyhow!("unknown command `{}`", name))?;
+        print!("{}", man::render(help));
+
+        return Ok(());
+    }
+    if let Some(dir) = options.man_dir {
+        fs::create_dir_all(&dir)?;
+
+        for help in commands() {
+            fs::write(
+                dir.join(format!("{}.1", man::name(help))),
+                man::render(help),
+            )?;
+        }
+        term::success!(
+            "Wrote {} man pages to {}",
+            commands().count(),
+            dir.display()
+        );
+        return Ok(());
+    }
+
     println!("Usage: rad <command> [--help]");
 
     if ctx.profile().is_err() {
@@ -109,6 +226,26 @@ pub fn run(_options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
         );
     }
     println!();
+
+    // Nb. Built-in commands take precedence over external commands of the same name.
+    let external = plugin::list()
+        .into_iter()
+        .filter(|(name, _)| commands().all(|h| h.name != name))
+        .collect::<Vec<_>>();
+
+    if !external.is_empty() {
+        println!("External commands found on your `PATH`:");
+        println!();
+
+        for (name, path) in external {
+            println!(
+                "\t{} {}",
+                term::format::bold(format!("{:-12}", name)),
+                term::format::dim(path.display())
+            );
+        }
+        println!();
+    }
     println!("See `rad <command> --help` to learn about a specific command.");
     println!();
 
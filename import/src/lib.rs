@@ -0,0 +1,577 @@
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::{fs, thread, time};
+
+use anyhow::anyhow;
+
+use librad::git::{tracking, Urn};
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::identity::Origin;
+use radicle_common::sync::Mode;
+use radicle_common::{bundle, config, git, keys, offline, profile, project, Interactive};
+use radicle_terminal as term;
+
+/// Name of the remote of the upstream repository, when importing from a git URL.
+pub const UPSTREAM_REMOTE: &str = "origin";
+/// How often the upstream repository is fetched, with `--track-upstream`.
+pub const DEFAULT_INTERVAL: time::Duration = time::Duration::from_secs(10 * 60);
+
+pub const HELP: Help = Help {
+    name: "import",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad import <file> [--no-track] [<option>...]
+    rad import <url> [<path>] [--track-upstream [--interval <secs>]] [<option>...]
+
+    Imports a project from a bundle file created with `rad export`. The views of
+    the project in the bundle are stored as remote peers' views, and the project
+    is verified before the import is completed. Your own refs are never changed.
+
+    By default, the peers whose views were imported are tracked, so that the
+    project can be kept up to date and served to others.
+
+Mirroring
+
+    Imports a git repository from any git URL, eg. on GitHub, GitLab or a
+    self-hosted server, as a new project under your identity. The repository is
+    cloned into <path>, or a directory named after it, and initialized as with
+    `rad init`. All of its branches and tags are then pushed to the project, and
+    synced with seeds, unless `--no-sync` is used.
+
+    With `--track-upstream`, the command keeps running, and fetches the upstream
+    repository every `--interval` seconds (default: 600). New commits and tags are
+    pushed to the project and synced, and deleted branches and tags are deleted,
+    so that it stays a mirror of the upstream repository. Upstream is the `origin`
+    remote of the working copy.
+
+Options
+
+    --no-track              Don't track the peers in the bundle
+    --name <name>           Name of the project (default: name of the repository)
+    --description <text>    Description of the project
+    --track-upstream        Keep running, and mirror upstream changes
+    --interval <secs>       How often to fetch upstream, with `--track-upstream`
+    --[no-]sync             Sync the mirrored project with seeds (default: sync)
+    --wait                  Wait for other commands to release the storage
+    --help                  Print help
+"#,
+    examples: &[
+        ("Import a project from a bundle", "rad import acme.bundle"),
+        (
+            "Mirror a repository, and keep it up to date every hour",
+            "rad import https://github.com/acme/acme.git --track-upstream --interval 3600",
+        ),
+    ],
+    ..Help::DEFAULT
+};
+
+/// What to import from.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Source {
+    /// A bundle file created with `rad export`.
+    Bundle(PathBuf),
+    /// A git repository URL.
+    Git(String),
+}
+
+impl Source {
+    /// Parse a source. Existing files are taken as bundles, and anything that looks
+    /// like a git URL, eg. `https://host/repo.git` or `git@host:repo.git`, as URLs.
+    pub fn parse(source: &str) -> anyhow::Result<Self> {
+        if Path::new(source).exists() {
+            return Ok(Self::Bundle(PathBuf::from(source)));
+        }
+        if let Some((scheme, _)) = source.split_once("://") {
+            if scheme == "rad" {
+                return Err(Error::WithHint {
+                    err: anyhow!("'{}' is a radicle URL, not a git URL", source),
+                    hint: "To clone a radicle project, run `rad clone <url>`.",
+                }
+                .into());
+            }
+            return Ok(Self::Git(source.to_owned()));
+        }
+        // Nb. The scp-like syntax of git, eg. `git@host:path`.
+        if let Some((host, _)) = source.split_once(':') {
+            if host.contains('@') && !host.contains('/') {
+                return Ok(Self::Git(source.to_owned()));
+            }
+        }
+        Ok(Self::Bundle(PathBuf::from(source)))
+    }
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub source: Source,
+    pub path: Option<PathBuf>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub track: bool,
+    pub track_upstream: bool,
+    pub interval: time::Duration,
+    pub sync: bool,
+    pub wait: bool,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut source: Option<Source> = None;
+        let mut path: Option<PathBuf> = None;
+        let mut name = None;
+        let mut description = None;
+        let mut track = true;
+        let mut track_upstream = false;
+        let mut interval = None;
+        let mut sync = true;
+        let mut wait = false;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("no-track") => {
+                    track = false;
+                }
+                Long("name") if name.is_none() => {
+                    name = Some(parser.value()?.to_string_lossy().into_owned());
+                }
+                Long("description") if description.is_none() => {
+                    description = Some(parser.value()?.to_string_lossy().into_owned());
+                }
+                Long("track-upstream") => {
+                    track_upstream = true;
+                }
+                Long("interval") if interval.is_none() => {
+                    let value = parser.value()?;
+                    let secs = u64::from_str(&value.to_string_lossy())
+                        .ok()
+                        .filter(|secs| *secs > 0)
+                        .ok_or_else(|| {
+                            anyhow!(
+                                "invalid interval '{}', expected a number of seconds",
+                                value.to_string_lossy()
+                            )
+                        })?;
+
+                    interval = Some(time::Duration::from_secs(secs));
+                }
+                Long("sync") => {
+                    sync = true;
+                }
+                Long("no-sync") => {
+                    sync = false;
+                }
+                Long("wait") => {
+                    wait = true;
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if source.is_none() => {
+                    source = Some(Source::parse(&val.to_string_lossy())?);
+                }
+                Value(val) if path.is_none() => {
+                    path = Some(PathBuf::from(val));
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let source = source.ok_or(Error::Usage)?;
+        if let Source::Bundle(_) = source {
+            if path.is_some()
+                || name.is_some()
+                || description.is_some()
+                || track_upstream
+                || interval.is_some()
+            {
+                anyhow::bail!(
+                    "`<path>`, `--name`, `--description`, `--track-upstream` and `--interval` can only be used when importing from a git URL"
+                );
+            }
+        }
+        if interval.is_some() && !track_upstream {
+            anyhow::bail!("`--interval` can only be used with `--track-upstream`");
+        }
+
+        Ok((
+            Options {
+                source,
+                path,
+                name,
+                description,
+                track,
+                track_upstream,
+                interval: interval.unwrap_or(DEFAULT_INTERVAL),
+                sync,
+                wait,
+            },
+            vec![],
+        ))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+
+    match &options.source {
+        Source::Bundle(file) => import_bundle(file, &options, &profile),
+        Source::Git(url) => import_git(url, &options, &profile),
+    }
+}
+
+fn import_bundle(file: &Path, options: &Options, profile: &profile::Profile) -> anyhow::Result<()> {
+    let _lock = term::lock_storage(profile, options.wait)?;
+    let signer = term::signer(profile)?;
+    let storage = keys::storage(profile, signer)?;
+
+    if !file.exists() {
+        anyhow::bail!("file '{}' was not found", file.display());
+    }
+
+    let spinner = term::spinner(format!(
+        "Importing {}...",
+        term::format::tertiary(file.display())
+    ));
+    let imported = match bundle::import(&storage, file) {
+        Ok(imported) => {
+            spinner.finish();
+            imported
+        }
+        Err(err) => {
+            spinner.failed();
+            return Err(err);
+        }
+    };
+    let project = project::get(&storage, &imported.urn)?
+        .ok_or_else(|| anyhow!("project {} was not found after import", imported.urn))?;
+
+    term::success!(
+        "{} {} ({}), with {} peer view(s)",
+        if imported.new { "Imported" } else { "Updated" },
+        term::format::highlight(&project.name),
+        term::format::dim(&imported.urn),
+        imported.peers.len()
+    );
+
+    if options.track {
+        for peer in &imported.peers {
+            tracking::track(
+                &storage,
+                &imported.urn,
+                Some(*peer),
+                tracking::config::Config::default(),
+                tracking::policy::Track::Any,
+            )??;
+            term::success!("Tracking {}", term::format::tertiary(peer));
+        }
+    }
+    term::blank();
+    term::tip!(
+        "To checkout the project, run {}",
+        term::format::secondary(format!("`rad checkout {}`", imported.urn))
+    );
+
+    Ok(())
+}
+
+fn import_git(url: &str, options: &Options, profile: &profile::Profile) -> anyhow::Result<()> {
+    let name = url
+        .trim_end_matches('/')
+        .rsplit(|c| c == '/' || c == ':')
+        .next()
+        .map(|name| name.strip_suffix(".git").unwrap_or(name))
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| anyhow!("couldn't get the repository name from URL '{}'", url))?;
+    let destination = match (&options.path, config::get_path("clone.directory")?) {
+        (Some(path), _) => path.clone(),
+        (None, Some(dir)) => dir.join(name),
+        (None, None) => std::env::current_dir()?.join(name),
+    };
+    if destination.exists() {
+        anyhow::bail!("destination '{}' already exists", destination.display());
+    }
+
+    let spinner = term::spinner(&format!(
+        "Cloning git repository {}...",
+        term::format::highlight(url)
+    ));
+    match git::clone(url, &destination) {
+        Ok(_) => spinner.finish(),
+        Err(err) => {
+            spinner.failed();
+            return Err(err);
+        }
+    }
+
+    let init = rad_init::init(
+        rad_init::Options {
+            path: Some(destination.clone()),
+            name: Some(options.name.clone().unwrap_or_else(|| name.to_owned())),
+            description: options.description.clone(),
+            interactive: Interactive::No,
+            wait: options.wait,
+            ..rad_init::Options::default()
+        },
+        profile,
+    );
+    // Nb. The clone is removed, so that the import can be retried.
+    if let Err(err) = init {
+        if let Err(e) = fs::remove_dir_all(&destination) {
+            term::warning(&format!(
+                "Failed to remove '{}': {}",
+                destination.display(),
+                e
+            ));
+        }
+        return Err(err);
+    }
+
+    let repo = git::Repository::open(&destination)?;
+    let urn = git::rad_remote(&repo)?.url.urn;
+
+    mirror(&destination, &repo, &[])?;
+    sync(&urn, options, profile)?;
+
+    if options.track_upstream {
+        track_upstream(&destination, &urn, options, profile)?;
+    }
+    Ok(())
+}
+
+/// Push all branches and tags of the upstream repository to storage, as our own.
+/// They are force-pushed, so that the project follows upstream, even when its
+/// history is rewritten.
+fn mirror(path: &Path, repo: &git::Repository, deleted: &[String]) -> anyhow::Result<()> {
+    let mut refspecs = Vec::new();
+
+    for branch in repo.branches(Some(git::BranchType::Remote))? {
+        let (branch, _) = branch?;
+        let name = match branch.name()?.and_then(|n| n.split_once('/')) {
+            Some((UPSTREAM_REMOTE, "HEAD")) => continue,
+            Some((UPSTREAM_REMOTE, name)) => name,
+            _ => continue,
+        };
+        refspecs.push(format!(
+            "+refs/remotes/{}/{}:refs/heads/{}",
+            UPSTREAM_REMOTE, name, name
+        ));
+    }
+    let branches = refspecs.len();
+    refspecs.push(String::from("+refs/tags/*:refs/tags/*"));
+
+    let spinner = term::spinner(if deleted.is_empty() {
+        format!(
+            "Pushing {} branch(es) and tags to remote `rad`...",
+            branches
+        )
+    } else {
+        format!(
+            "Pushing {} branch(es) and tags to remote `rad`, deleting {} ref(s)...",
+            branches,
+            deleted.len()
+        )
+    });
+    let args = ["push", "rad"]
+        .iter()
+        .map(|a| a.to_string())
+        .chain(refspecs)
+        .chain(deleted.iter().map(|r| format!(":{}", r)))
+        .collect::<Vec<_>>();
+
+    match git::git(path, args) {
+        Ok(_) => {
+            spinner.finish();
+            Ok(())
+        }
+        Err(err) => {
+            spinner.failed();
+            Err(err)
+        }
+    }
+}
+
+/// Sync the project with seeds, unless disabled, or offline.
+fn sync(urn: &Urn, options: &Options, profile: &profile::Profile) -> anyhow::Result<()> {
+    if !options.sync {
+        return Ok(());
+    }
+    if offline::is_enabled() {
+        term::warning("Offline mode is enabled, the project was not synced with seeds");
+        return Ok(());
+    }
+    rad_sync::run(
+        rad_sync::Options {
+            origin: Some(Origin::from_urn(urn.clone())),
+            mode: Mode::Push,
+            wait: true,
+            ..rad_sync::Options::default()
+        },
+        profile.clone(),
+    )
+}
+
+/// Keep fetching the upstream repository, and mirror it whenever it changes.
+fn track_upstream(
+    path: &Path,
+    urn: &Urn,
+    options: &Options,
+    profile: &profile::Profile,
+) -> anyhow::Result<()> {
+    offline::check("track upstream repository")?;
+
+    let repo = git::Repository::open(path)?;
+    let mut mirrored = upstream_refs(path)?;
+    let mut unsynced = false;
+
+    term::blank();
+    term::info!(
+        "Mirroring upstream every {} second(s). Press Ctrl-C to stop.",
+        term::format::highlight(options.interval.as_secs())
+    );
+
+    loop {
+        thread::sleep(options.interval);
+
+        // Nb. Upstream may be temporarily unreachable, in which case we try again later.
+        if let Err(err) = git::git(path, ["fetch", "--prune", "--tags", UPSTREAM_REMOTE]) {
+            term::warning(&format!(
+                "Failed to fetch upstream: {}",
+                err.to_string().trim()
+            ));
+            continue;
+        }
+        let refs = match upstream_refs(path) {
+            Ok(refs) => refs,
+            Err(err) => {
+                term::warning(&format!(
+                    "Failed to read upstream refs: {}",
+                    err.to_string().trim()
+                ));
+                continue;
+            }
+        };
+        if refs != mirrored {
+            term::blank();
+            term::headline(&format!(
+                "Mirroring 🌱 upstream changes to {}",
+                term::format::highlight(urn)
+            ));
+            // Nb. A failed push is retried on the next round, since the refs are only
+            // considered mirrored once they were pushed.
+            let removed = deleted(&mirrored, &refs);
+            if let Err(err) = mirror(path, &repo, &removed) {
+                term::warning(&format!(
+                    "Failed to mirror upstream: {}",
+                    err.to_string().trim()
+                ));
+                continue;
+            }
+            mirrored = refs;
+            unsynced = true;
+        }
+        if unsynced {
+            match sync(urn, options, profile) {
+                Ok(()) => unsynced = false,
+                Err(err) => {
+                    term::warning(&format!("Failed to sync: {}", err.to_string().trim()));
+                }
+            }
+        }
+    }
+}
+
+/// Get the project refs to delete, given the upstream refs that were mirrored and the
+/// current ones, as output by [`upstream_refs`].
+fn deleted(mirrored: &str, refs: &str) -> Vec<String> {
+    let names = |refs: &str| {
+        refs.lines()
+            .filter_map(|line| line.split_once(' ').map(|(_, name)| name.to_owned()))
+            .collect::<BTreeSet<_>>()
+    };
+    let branches = format!("refs/remotes/{}/", UPSTREAM_REMOTE);
+    let current = names(refs);
+
+    names(mirrored)
+        .difference(&current)
+        .filter_map(|name| match name.strip_prefix(&branches) {
+            Some("HEAD") => None,
+            Some(branch) => Some(format!("refs/heads/{}", branch)),
+            None => Some(name.to_owned()),
+        })
+        .collect()
+}
+
+/// Get the upstream branches and tags, as `<oid> <ref>` lines.
+fn upstream_refs(path: &Path) -> anyhow::Result<String> {
+    let branches = format!("refs/remotes/{}", UPSTREAM_REMOTE);
+
+    git::git(
+        path,
+        [
+            "for-each-ref",
+            "--format=%(objectname) %(refname)",
+            branches.as_str(),
+            "refs/tags",
+        ],
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_deleted() {
+        let mirrored = "\
+            a1 refs/remotes/origin/HEAD\n\
+            a1 refs/remotes/origin/master\n\
+            b2 refs/remotes/origin/feature/old\n\
+            c3 refs/tags/v1.0\n\
+            d4 refs/tags/v1.1\n";
+        let refs = "\
+            e5 refs/remotes/origin/master\n\
+            d4 refs/tags/v1.1\n";
+
+        assert_eq!(
+            deleted(mirrored, refs),
+            vec![
+                String::from("refs/heads/feature/old"),
+                String::from("refs/tags/v1.0")
+            ]
+        );
+        assert!(deleted(refs, mirrored).is_empty());
+    }
+
+    #[test]
+    fn test_source() {
+        assert_eq!(
+            Source::parse("https://github.com/acme/acme.git").unwrap(),
+            Source::Git("https://github.com/acme/acme.git".to_owned())
+        );
+        assert_eq!(
+            Source::parse("git@gitlab.com:acme/acme.git").unwrap(),
+            Source::Git("git@gitlab.com:acme/acme.git".to_owned())
+        );
+        assert_eq!(
+            Source::parse("acme.bundle").unwrap(),
+            Source::Bundle(PathBuf::from("acme.bundle"))
+        );
+        assert_eq!(
+            Source::parse("./acme@1:0.bundle").unwrap(),
+            Source::Bundle(PathBuf::from("./acme@1:0.bundle"))
+        );
+        assert!(
+            Source::parse("rad://pine.radicle.garden/hnrkfbrd7y9674d8ow8uioki16fniwcyoz67y")
+                .is_err()
+        );
+    }
+}
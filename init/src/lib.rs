@@ -1,17 +1,22 @@
 #![allow(clippy::or_fun_call)]
+pub mod template;
+
 use std::convert::TryFrom;
 use std::env;
 use std::ffi::OsString;
 use std::path::PathBuf;
 
 use anyhow::{anyhow, bail, Context as _};
+use chrono::Datelike as _;
+use either::Either;
 
-use librad::PeerId;
+use librad::git::{Storage, Urn};
+use librad::{PeerId, PublicKey};
 
 use radicle_common::args::{Args, Error, Help};
 use radicle_common::json;
 use radicle_common::Interactive;
-use radicle_common::{git, keys, profile, project};
+use radicle_common::{git, keys, person, profile, project};
 use radicle_terminal as term;
 
 pub const HELP: Help = Help {
@@ -29,9 +34,34 @@ Options
     --description        Description of the project
     --default-branch     The default branch of the project
     --set-upstream, -u   Setup the upstream of the default branch
-    --no-confirm         Don't ask for confirmation during setup
+    --template <name>    Scaffold a `.gitignore` from a template, eg. `rust`
+    --license <spdx>     Scaffold a `LICENSE` and record it in the project, eg. `MIT`
+    --delegate <id>      Add a delegate, by personal URN or Peer ID (may be repeated)
+    --no-confirm         Don't prompt for anything, use defaults instead
+    --wait               Wait for other commands to release the storage
     --help               Print help
+
+    With `--no-confirm`, the project name defaults to the name of the directory,
+    the description to an empty one, and the default branch to the current branch.
+    This allows projects to be created non-interactively, eg. by scripts.
+
+Delegates
+
+    The project is delegated to your identity, and any identity or key given with
+    `--delegate`. Personal identities must be in your storage, eg. from a previous
+    `rad track` or `rad clone`. Updates to the project's identity document must be
+    signed by a majority of its delegates.
+
+Templates
+
+    If `--template` or `--license` is used, the respective files are created in the
+    working copy, unless they already exist. If the repository has no commits yet,
+    they are committed to the default branch, so that the project can be published.
+
+    Available templates are `c`, `go`, `node`, `python` and `rust`. Available
+    licenses are `MIT`, `ISC`, `BSD-2-Clause` and `BSD-3-Clause`.
 "#,
+    ..Help::DEFAULT
 };
 
 #[derive(Default)]
@@ -42,6 +72,10 @@ pub struct Options {
     pub branch: Option<String>,
     pub interactive: Interactive,
     pub set_upstream: bool,
+    pub template: Option<&'static template::Template>,
+    pub license: Option<&'static template::License>,
+    pub delegates: Vec<Either<PublicKey, Urn>>,
+    pub wait: bool,
 }
 
 impl Args for Options {
@@ -54,8 +88,12 @@ impl Args for Options {
         let mut name = None;
         let mut description = None;
         let mut branch = None;
-        let mut interactive = Interactive::Yes;
+        let interactive = term::interactive();
         let mut set_upstream = false;
+        let mut template = None;
+        let mut license = None;
+        let mut delegates = Vec::new();
+        let mut wait = false;
 
         while let Some(arg) = parser.next()? {
             match arg {
@@ -94,8 +132,46 @@ impl Args for Options {
                 Long("set-upstream") | Short('u') => {
                     set_upstream = true;
                 }
-                Long("no-confirm") => {
-                    interactive = Interactive::No;
+                Long("template") if template.is_none() => {
+                    let value = parser.value()?;
+                    let value = value.to_string_lossy();
+
+                    template = Some(template::template(&value).ok_or_else(|| {
+                        anyhow!(
+                            "unknown template '{}', available templates are: {}",
+                            value,
+                            template::TEMPLATES
+                                .iter()
+                                .map(|t| t.name)
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )
+                    })?);
+                }
+                Long("license") if license.is_none() => {
+                    let value = parser.value()?;
+                    let value = value.to_string_lossy();
+
+                    license = Some(template::license(&value).ok_or_else(|| {
+                        anyhow!(
+                            "unknown license '{}', available licenses are: {}",
+                            value,
+                            template::LICENSES
+                                .iter()
+                                .map(|l| l.id)
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )
+                    })?);
+                }
+                Long("delegate") => {
+                    let value = parser.value()?;
+                    let delegate = project::parse_delegate(&value.to_string_lossy())?;
+
+                    delegates.push(delegate);
+                }
+                Long("wait") => {
+                    wait = true;
                 }
                 Long("help") => {
                     return Err(Error::Help.into());
@@ -115,6 +191,10 @@ impl Args for Options {
                 branch,
                 interactive,
                 set_upstream,
+                template,
+                license,
+                delegates,
+                wait,
             },
             vec![],
         ))
@@ -157,34 +237,80 @@ pub fn init(options: Options, profile: &profile::Profile) -> anyhow::Result<()>
         );
     }
 
-    let signer = term::signer(profile)?;
-    let storage = keys::storage(profile, signer.clone())?;
-
-    let head: String = repo
+    let scaffold = options.template.is_some() || options.license.is_some();
+    let head: Option<String> = repo
         .head()
         .ok()
-        .and_then(|head| head.shorthand().map(|h| h.to_owned()))
-        .ok_or_else(|| anyhow!("error: repository head does not point to any commits"))?;
-
-    let name = options.name.unwrap_or_else(|| {
-        let default = path.file_name().map(|f| f.to_string_lossy().to_string());
-        term::text_input("Name", default).unwrap()
-    });
-    let description = options
-        .description
-        .unwrap_or_else(|| term::text_input("Description", None).unwrap());
-    let branch = options.branch.unwrap_or_else(|| {
-        if interactive.yes() {
-            term::text_input("Default branch", Some(head)).unwrap()
-        } else {
-            head
+        .and_then(|head| head.shorthand().map(|h| h.to_owned()));
+    let unborn = head.is_none();
+    let head = match head {
+        Some(head) => head,
+        // An empty repository can be initialized if the files to commit are scaffolded.
+        None if scaffold => self::unborn_branch(&repo).unwrap_or_else(|| "master".to_owned()),
+        None => bail!("error: repository head does not point to any commits"),
+    };
+
+    let default_name = path.file_name().map(|f| f.to_string_lossy().to_string());
+    let name = match options.name {
+        Some(name) => name,
+        None if interactive.yes() => {
+            term::text_input("Name", default_name).map_err(|err| Error::WithHint {
+                err,
+                hint: "Use `--name` to specify the project name.",
+            })?
+        }
+        None => default_name.ok_or(Error::WithHint {
+            err: anyhow!("project name could not be determined from the path"),
+            hint: "Use `--name` to specify the project name.",
+        })?,
+    };
+    let description = match options.description {
+        Some(description) => description,
+        None if interactive.yes() => {
+            term::text_input("Description", None).map_err(|err| Error::WithHint {
+                err,
+                hint: "Use `--description` to specify the project description.",
+            })?
+        }
+        None => String::new(),
+    };
+    let branch = match options.branch {
+        Some(branch) => branch,
+        None if interactive.yes() => {
+            term::text_input("Default branch", Some(head)).map_err(|err| Error::WithHint {
+                err,
+                hint: "Use `--default-branch` to specify the default branch.",
+            })?
         }
-    });
+        None => head,
+    };
+
+    // Nb. The storage is only locked once all input is collected, so that other
+    // commands aren't kept waiting on the prompts.
+    let _lock = term::lock_storage(profile, options.wait)?;
+    let signer = term::signer(profile)?;
+    let storage = keys::storage(profile, signer.clone())?;
+
+    if scaffold {
+        self::scaffold(
+            &repo,
+            options.template,
+            options.license,
+            &storage,
+            &branch,
+            unborn,
+        )?;
+    }
 
     let mut spinner = term::spinner("Initializing...");
-    let payload = project::payload(name, description, branch.clone());
+    let mut payload = project::payload(name, description, branch.clone());
+    if let Some(license) = options.license {
+        payload = payload.with_ext(project::License {
+            id: license.id.to_owned(),
+        })?;
+    }
 
-    match project::create(payload, &storage).and_then(|proj| {
+    match project::create(payload, &options.delegates, &storage).and_then(|proj| {
         project::init(&proj, &repo, &storage, profile.paths(), signer).map(|_| proj)
     }) {
         Ok(proj) => {
@@ -211,6 +337,7 @@ pub fn init(options: Options, profile: &profile::Profile) -> anyhow::Result<()>
 
             // Setup radicle signing key.
             self::setup_signing(storage.peer_id(), &repo, interactive)?;
+            self::setup_profile(profile, &repo)?;
 
             term::blank();
             term::info!(
@@ -250,6 +377,87 @@ pub fn init(options: Options, profile: &profile::Profile) -> anyhow::Result<()>
     Ok(())
 }
 
+/// Get the branch HEAD points to in a repository without commits.
+fn unborn_branch(repo: &git::Repository) -> Option<String> {
+    let head = repo.find_reference("HEAD").ok()?;
+    let target = head.symbolic_target()?;
+
+    target.strip_prefix("refs/heads/").map(|b| b.to_owned())
+}
+
+/// Create the template and license files in the working copy. If the repository has
+/// no commits, they are committed to the given branch.
+fn scaffold(
+    repo: &git::Repository,
+    template: Option<&template::Template>,
+    license: Option<&template::License>,
+    storage: &Storage,
+    branch: &str,
+    unborn: bool,
+) -> anyhow::Result<()> {
+    let workdir = repo
+        .workdir()
+        .ok_or(anyhow!("cannot scaffold files in bare repository"))?;
+    let mut files = Vec::new();
+
+    if let Some(template) = template {
+        files.push((".gitignore", template.gitignore.to_owned()));
+    }
+    if let Some(license) = license {
+        let holder = person::local(storage)?.subject().name.to_string();
+        let year = chrono::Utc::now().year();
+
+        files.push(("LICENSE", license.text(year, &holder)));
+    }
+
+    let mut written = Vec::new();
+    for (name, contents) in files {
+        if template::write(workdir, name, &contents)?.is_some() {
+            term::success!("Created {} file", term::format::tertiary(name));
+            written.push(name);
+        } else {
+            term::warning(&format!("Found existing {} file, skipping", name));
+        }
+    }
+
+    if unborn {
+        if written.is_empty() {
+            bail!("repository has no commits, and there are no files to commit");
+        }
+        git::git(
+            workdir,
+            ["symbolic-ref", "HEAD", &format!("refs/heads/{}", branch)],
+        )?;
+        git::git(workdir, ["add", "--"].iter().chain(written.iter()))?;
+        git::git(workdir, ["commit", "-m", "Initial commit"])?;
+
+        term::success!(
+            "Created initial commit on {}",
+            term::format::highlight(branch)
+        );
+    } else if !written.is_empty() {
+        term::tip!(
+            "Commit the new files with {}",
+            term::format::secondary("`git commit`")
+        );
+    }
+    Ok(())
+}
+
+/// Pin the repository to the given profile, if there is more than one profile.
+/// This prevents the repository from being used with the wrong identity.
+pub fn setup_profile(profile: &profile::Profile, repo: &git::Repository) -> anyhow::Result<()> {
+    if profile::count()? > 1 {
+        profile::pin(repo, profile.id())?;
+
+        term::success!(
+            "Working copy pinned to profile {}",
+            term::format::highlight(profile.id())
+        );
+    }
+    Ok(())
+}
+
 /// Setup radicle key as commit signing key in repository.
 pub fn setup_signing(
     peer_id: &PeerId,
@@ -292,7 +500,9 @@ pub fn setup_signing(
 
                 if ssh_keys.contains(&ssh_key) {
                     term::success!("Signing key is already in {} file", gitsigners);
-                } else if term::confirm(&format!("Add signing key to {}?", gitsigners)) {
+                } else if interactive.no()
+                    || term::confirm(&format!("Add signing key to {}?", gitsigners))
+                {
                     git::add_gitsigners(repo, [peer_id])?;
                 }
             }
@@ -309,3 +519,18 @@ pub fn setup_signing(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_args_no_confirm() {
+        let args = vec!["--no-confirm".into(), "--name".into(), "acme".into()];
+        let opts = term::parse_args::<Options>(args).unwrap();
+
+        assert!(opts.interactive.no());
+        assert_eq!(opts.name.as_deref(), Some("acme"));
+        assert_eq!(opts.description, None);
+    }
+}
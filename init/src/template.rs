@@ -0,0 +1,194 @@
+//! Templates to scaffold new projects with.
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A project template, for a language or ecosystem.
+#[derive(Debug)]
+pub struct Template {
+    /// Name of the template, eg. `rust`.
+    pub name: &'static str,
+    /// Contents of the template's `.gitignore` file.
+    pub gitignore: &'static str,
+}
+
+/// Available project templates.
+pub const TEMPLATES: &[Template] = &[
+    Template {
+        name: "c",
+        gitignore: "*.o\n*.a\n*.so\n*.dylib\n*.exe\n*.out\n/build/\n",
+    },
+    Template {
+        name: "go",
+        gitignore: "*.exe\n*.test\n*.out\n/vendor/\n/bin/\n",
+    },
+    Template {
+        name: "node",
+        gitignore: "node_modules/\nnpm-debug.log*\nyarn-error.log*\n/dist/\n/coverage/\n.env\n",
+    },
+    Template {
+        name: "python",
+        gitignore: "__pycache__/\n*.py[cod]\n*.egg-info/\n/build/\n/dist/\n.venv/\n.env\n",
+    },
+    Template {
+        name: "rust",
+        gitignore: "/target/\n**/*.rs.bk\n",
+    },
+];
+
+/// An open source license.
+#[derive(Debug)]
+pub struct License {
+    /// SPDX identifier of the license, eg. `MIT`.
+    pub id: &'static str,
+    /// License text, with `{year}` and `{holder}` placeholders.
+    text: &'static str,
+}
+
+impl License {
+    /// Get the license text for the given year and copyright holder.
+    pub fn text(&self, year: i32, holder: &str) -> String {
+        self.text
+            .replace("{year}", &year.to_string())
+            .replace("{holder}", holder)
+    }
+}
+
+/// Available licenses.
+pub const LICENSES: &[License] = &[
+    License {
+        id: "MIT",
+        text: MIT,
+    },
+    License {
+        id: "ISC",
+        text: ISC,
+    },
+    License {
+        id: "BSD-2-Clause",
+        text: BSD_2_CLAUSE,
+    },
+    License {
+        id: "BSD-3-Clause",
+        text: BSD_3_CLAUSE,
+    },
+];
+
+/// Find a template by name.
+pub fn template(name: &str) -> Option<&'static Template> {
+    TEMPLATES.iter().find(|t| t.name.eq_ignore_ascii_case(name))
+}
+
+/// Find a license by SPDX identifier. The identifier is case-insensitive.
+pub fn license(id: &str) -> Option<&'static License> {
+    LICENSES.iter().find(|l| l.id.eq_ignore_ascii_case(id))
+}
+
+/// Write a file to the working copy, unless it already exists.
+/// Returns the path of the file if it was written.
+pub fn write(workdir: &Path, name: &str, contents: &str) -> io::Result<Option<PathBuf>> {
+    let path = workdir.join(name);
+
+    if path.exists() {
+        return Ok(None);
+    }
+    fs::write(&path, contents)?;
+
+    Ok(Some(path))
+}
+
+const MIT: &str = "MIT License
+
+Copyright (c) {year} {holder}
+
+Permission is hereby granted, free of charge, to any person obtaining a copy
+of this software and associated documentation files (the \"Software\"), to deal
+in the Software without restriction, including without limitation the rights
+to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+copies of the Software, and to permit persons to whom the Software is
+furnished to do so, subject to the following conditions:
+
+The above copyright notice and this permission notice shall be included in all
+copies or substantial portions of the Software.
+
+THE SOFTWARE IS PROVIDED \"AS IS\", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+SOFTWARE.
+";
+
+const ISC: &str = "ISC License
+
+Copyright (c) {year} {holder}
+
+Permission to use, copy, modify, and/or distribute this software for any
+purpose with or without fee is hereby granted, provided that the above
+copyright notice and this permission notice appear in all copies.
+
+THE SOFTWARE IS PROVIDED \"AS IS\" AND THE AUTHOR DISCLAIMS ALL WARRANTIES
+WITH REGARD TO THIS SOFTWARE INCLUDING ALL IMPLIED WARRANTIES OF
+MERCHANTABILITY AND FITNESS. IN NO EVENT SHALL THE AUTHOR BE LIABLE FOR
+ANY SPECIAL, DIRECT, INDIRECT, OR CONSEQUENTIAL DAMAGES OR ANY DAMAGES
+WHATSOEVER RESULTING FROM LOSS OF USE, DATA OR PROFITS, WHETHER IN AN
+ACTION OF CONTRACT, NEGLIGENCE OR OTHER TORTIOUS ACTION, ARISING OUT OF
+OR IN CONNECTION WITH THE USE OR PERFORMANCE OF THIS SOFTWARE.
+";
+
+const BSD_2_CLAUSE: &str = "BSD 2-Clause License
+
+Copyright (c) {year}, {holder}
+
+Redistribution and use in source and binary forms, with or without
+modification, are permitted provided that the following conditions are met:
+
+1. Redistributions of source code must retain the above copyright notice, this
+   list of conditions and the following disclaimer.
+
+2. Redistributions in binary form must reproduce the above copyright notice,
+   this list of conditions and the following disclaimer in the documentation
+   and/or other materials provided with the distribution.
+
+THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS \"AS IS\"
+AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+";
+
+const BSD_3_CLAUSE: &str = "BSD 3-Clause License
+
+Copyright (c) {year}, {holder}
+
+Redistribution and use in source and binary forms, with or without
+modification, are permitted provided that the following conditions are met:
+
+1. Redistributions of source code must retain the above copyright notice, this
+   list of conditions and the following disclaimer.
+
+2. Redistributions in binary form must reproduce the above copyright notice,
+   this list of conditions and the following disclaimer in the documentation
+   and/or other materials provided with the distribution.
+
+3. Neither the name of the copyright holder nor the names of its
+   contributors may be used to endorse or promote products derived from
+   this software without specific prior written permission.
+
+THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS \"AS IS\"
+AND ANY EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE
+IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE
+FOR ANY DIRECT, INDIRECT, INCIDENTAL, SPECIAL, EXEMPLARY, OR CONSEQUENTIAL
+DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER
+CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT, STRICT LIABILITY,
+OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE USE
+OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+";
@@ -1,12 +1,12 @@
 #![allow(clippy::or_fun_call)]
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::ffi::OsString;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
 use std::str::FromStr;
 
 use radicle_common::args::{Args, Error, Help};
-use radicle_common::{git, profile};
+use radicle_common::{git, profile, project};
 use radicle_terminal as term;
 
 use librad::git::identities::any;
@@ -33,14 +33,18 @@ Usage
     Inspects the given path or URN. If neither is specified,
     the current project is inspected.
 
+    Only local storage is read, so this works in offline mode.
+
 Options
 
-    --id        Return the ID without the URN scheme
-    --payload   Inspect the object's payload
-    --refs      Inspect the object's refs on the local device (requires `tree`)
-    --history   Show object's history
-    --help      Print help
+    --id          Return the ID without the URN scheme
+    --payload     Inspect the object's payload
+    --delegates   Show the project's delegates
+    --refs        Show the object's refs on the local device, per peer
+    --history     Show object's history
+    --help        Print help
 "#,
+    ..Help::DEFAULT
 };
 
 #[derive(Default, Debug, Eq, PartialEq)]
@@ -50,6 +54,7 @@ pub struct Options {
     pub refs: bool,
     pub payload: bool,
     pub history: bool,
+    pub delegates: bool,
     pub id: bool,
 }
 
@@ -63,6 +68,7 @@ impl Args for Options {
         let mut refs = false;
         let mut payload = false;
         let mut history = false;
+        let mut delegates = false;
         let mut id = false;
 
         while let Some(arg) = parser.next()? {
@@ -79,6 +85,9 @@ impl Args for Options {
                 Long("history") => {
                     history = true;
                 }
+                Long("delegates") => {
+                    delegates = true;
+                }
                 Long("id") => {
                     id = true;
                 }
@@ -105,6 +114,7 @@ impl Args for Options {
                 path,
                 payload,
                 history,
+                delegates,
                 refs,
                 urn,
             },
@@ -127,15 +137,9 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     };
 
     if options.refs {
-        let path = profile.paths().git_dir().join("refs").join("namespaces");
-
-        Command::new("tree")
-            .current_dir(path)
-            .args([&urn.encode_id(), "--noreport", "--prune"])
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .spawn()?
-            .wait()?;
+        refs(&urn, &profile)?;
+    } else if options.delegates {
+        delegates(&urn, &storage)?;
     } else if options.payload {
         let payload = any::get(&storage, &urn)
             .map(|o| o.map(|p| p.payload()))
@@ -212,3 +216,64 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Print the refs of an object in the local monorepo, grouped by peer.
+fn refs(urn: &Urn, profile: &profile::Profile) -> anyhow::Result<()> {
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    let namespace = format!("refs/namespaces/{}/", urn.encode_id());
+    let mut peers: BTreeMap<Option<String>, Vec<(String, String)>> = BTreeMap::new();
+
+    for reference in monorepo.references_glob(&format!("{}*", namespace))? {
+        let reference = reference?;
+        let name = match reference.name() {
+            Some(name) => name.trim_start_matches(&namespace),
+            None => continue,
+        };
+        let oid = reference
+            .target()
+            .map(|oid| format!("{:.7}", oid.to_string()))
+            .unwrap_or_default();
+
+        // Refs of other peers are under `refs/remotes/<peer>/`.
+        let (peer, name) = match name.strip_prefix("refs/remotes/") {
+            Some(rest) => match rest.split_once('/') {
+                Some((peer, name)) => (Some(peer.to_owned()), format!("refs/{}", name)),
+                None => continue,
+            },
+            None => (None, name.to_owned()),
+        };
+        peers.entry(peer).or_default().push((name, oid));
+    }
+    if peers.is_empty() {
+        anyhow::bail!("No refs found for {} in storage", urn);
+    }
+
+    for (peer, refs) in peers {
+        match peer {
+            Some(peer) => term::info!("{}", term::format::highlight(peer)),
+            None => term::info!(
+                "{} {}",
+                term::format::highlight("local"),
+                term::format::dim("(you)")
+            ),
+        }
+        let mut table = term::Table::default();
+        for (name, oid) in refs {
+            table.push([term::format::secondary(oid), name]);
+        }
+        table.render_tree();
+        term::blank();
+    }
+    Ok(())
+}
+
+/// Print the delegates of a project.
+fn delegates(urn: &Urn, storage: &librad::git::storage::ReadOnly) -> anyhow::Result<()> {
+    let project =
+        project::get(storage, urn)?.ok_or_else(|| anyhow!("No project found for this URN"))?;
+    let table = rad_delegate::table(storage, &project);
+
+    table.render();
+
+    Ok(())
+}
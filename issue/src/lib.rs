@@ -1,12 +1,16 @@
 #![allow(clippy::or_fun_call)]
 use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
 use std::str::FromStr;
 
 use anyhow::{anyhow, Context};
 
+use radicle_common as common;
 use radicle_common::args::{Args, Error, Help};
 use radicle_common::cobs::issue::*;
-use radicle_common::{cobs, keys, project};
+use radicle_common::cobs::patch::{Patch, PatchId};
+use radicle_common::{cobs, keys, offline, project, sync, tokio};
 use radicle_terminal as term;
 
 pub const HELP: Help = Help {
@@ -17,15 +21,53 @@ pub const HELP: Help = Help {
 Usage
 
     rad issue new [--title <title>] [--description <text>]
+    rad issue show <id>
+    rad issue close <id> [--solved]
     rad issue state <id> [--closed | --open | --solved]
     rad issue delete <id>
     rad issue react <id> [--emoji <char>]
-    rad issue list
+    rad issue list [--state <state>] [--porcelain]
+    rad issue export [--output <file>]
+
+    Issues are stored in storage as collaborative objects, and are synced with
+    seeds like other refs. To specify an issue, use the fully qualified issue id
+    or an unambiguous prefix of it.
+
+    `rad issue new` (or `create`) opens an issue, with its title and description
+    written in your editor, unless given as options. `rad issue close` closes an
+    issue, as solved with `--solved`.
+
+    `rad issue list` lists open issues, or with `--state`, closed issues or all of
+    them. With `--porcelain`, one issue is printed per line, as tab-separated id,
+    state and title.
+
+    `rad issue export` exports all issues of the project as JSON, with their
+    comments and the signatures of their changes, eg. for migrations, backups or
+    dashboards. The JSON schema is versioned, with a top-level `version` field.
 
 Options
 
-    --help      Print help
+    --state <state>     List issues in this state: open, closed or all (default: open)
+    --porcelain         Print issues in a stable format, for scripts
+    --output <file>     Export issues to a file instead of printing them
+    --[no-]sync         Sync changes to issues with seeds (default: sync)
+    --help              Print help
 "#,
+    examples: &[
+        (
+            "Open an issue, with its description written in your editor",
+            "rad issue new --title \"Crash on startup\"",
+        ),
+        (
+            "List all issues, open or closed",
+            "rad issue list --state all",
+        ),
+        (
+            "Export issues to a file",
+            "rad issue export --output issues.json",
+        ),
+    ],
+    ..Help::DEFAULT
 };
 
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
@@ -37,10 +79,13 @@ pub struct Metadata {
 #[derive(Debug, PartialEq, Eq)]
 pub enum OperationName {
     Create,
+    Show,
+    Close,
     State,
     React,
     Delete,
     List,
+    Export,
 }
 
 impl Default for OperationName {
@@ -55,24 +100,34 @@ pub enum Operation {
         title: Option<String>,
         description: Option<String>,
     },
+    Show {
+        id: cobs::Identifier,
+    },
     State {
-        id: cobs::issue::IssueId,
+        id: cobs::Identifier,
         state: cobs::issue::State,
     },
     Delete {
-        id: cobs::issue::IssueId,
+        id: cobs::Identifier,
     },
     React {
-        id: cobs::issue::IssueId,
+        id: cobs::Identifier,
         reaction: cobs::Reaction,
     },
-    List,
+    List {
+        state: cobs::StateFilter,
+        porcelain: bool,
+    },
+    Export {
+        output: Option<PathBuf>,
+    },
 }
 
 /// Tool options.
 #[derive(Debug)]
 pub struct Options {
     pub op: Operation,
+    pub sync: bool,
 }
 
 impl Args for Options {
@@ -81,17 +136,41 @@ impl Args for Options {
 
         let mut parser = lexopt::Parser::from_args(args);
         let mut op: Option<OperationName> = None;
-        let mut id: Option<cobs::issue::IssueId> = None;
+        let mut id: Option<cobs::Identifier> = None;
         let mut title: Option<String> = None;
         let mut reaction: Option<cobs::Reaction> = None;
         let mut description: Option<String> = None;
         let mut state: Option<cobs::issue::State> = None;
+        let mut sync = true;
+        let mut filter = cobs::StateFilter::default();
+        let mut porcelain = false;
+        let mut output: Option<PathBuf> = None;
 
         while let Some(arg) = parser.next()? {
             match arg {
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
+                Long("sync") => {
+                    sync = true;
+                }
+                Long("no-sync") => {
+                    sync = false;
+                }
+                Long("state") if matches!(op, None | Some(OperationName::List)) => {
+                    let val = parser.value()?;
+
+                    filter = cobs::StateFilter::from_str(&val.to_string_lossy())?;
+                    if filter == cobs::StateFilter::Merged {
+                        anyhow::bail!("issues can't be merged, use one of: open, closed, all");
+                    }
+                }
+                Long("porcelain") if matches!(op, None | Some(OperationName::List)) => {
+                    porcelain = true;
+                }
+                Long("output") if op == Some(OperationName::Export) => {
+                    output = Some(parser.value()?.into());
+                }
                 Long("title") if op == Some(OperationName::Create) => {
                     title = Some(parser.value()?.to_string_lossy().into());
                 }
@@ -103,7 +182,9 @@ impl Args for Options {
                 Long("open") if op == Some(OperationName::State) => {
                     state = Some(cobs::issue::State::Open);
                 }
-                Long("solved") if op == Some(OperationName::State) => {
+                Long("solved")
+                    if matches!(op, Some(OperationName::State | OperationName::Close)) =>
+                {
                     state = Some(cobs::issue::State::Closed {
                         reason: CloseReason::Solved,
                     });
@@ -120,11 +201,14 @@ impl Args for Options {
                     description = Some(parser.value()?.to_string_lossy().into());
                 }
                 Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
-                    "n" | "new" => op = Some(OperationName::Create),
+                    "n" | "new" | "create" => op = Some(OperationName::Create),
+                    "show" => op = Some(OperationName::Show),
+                    "close" => op = Some(OperationName::Close),
                     "s" | "state" => op = Some(OperationName::State),
                     "d" | "delete" => op = Some(OperationName::Delete),
                     "l" | "list" => op = Some(OperationName::List),
                     "r" | "react" => op = Some(OperationName::React),
+                    "export" => op = Some(OperationName::Export),
 
                     unknown => anyhow::bail!("unknown operation '{}'", unknown),
                 },
@@ -134,7 +218,7 @@ impl Args for Options {
                         .ok_or_else(|| anyhow!("issue id specified is not UTF-8"))?;
 
                     id = Some(
-                        IssueId::from_str(val)
+                        cobs::Identifier::from_str(val)
                             .map_err(|_| anyhow!("invalid issue id '{}'", val))?,
                     );
                 }
@@ -146,6 +230,15 @@ impl Args for Options {
 
         let op = match op.unwrap_or_default() {
             OperationName::Create => Operation::Create { title, description },
+            OperationName::Show => Operation::Show {
+                id: id.ok_or_else(|| anyhow!("an issue id must be provided"))?,
+            },
+            OperationName::Close => Operation::State {
+                id: id.ok_or_else(|| anyhow!("an issue id must be provided"))?,
+                state: state.unwrap_or(cobs::issue::State::Closed {
+                    reason: CloseReason::Other,
+                }),
+            },
             OperationName::State => Operation::State {
                 id: id.ok_or_else(|| anyhow!("an issue id must be provided"))?,
                 state: state.ok_or_else(|| anyhow!("a state operation must be provided"))?,
@@ -157,10 +250,14 @@ impl Args for Options {
             OperationName::Delete => Operation::Delete {
                 id: id.ok_or_else(|| anyhow!("an issue id to remove must be provided"))?,
             },
-            OperationName::List => Operation::List,
+            OperationName::List => Operation::List {
+                state: filter,
+                porcelain,
+            },
+            OperationName::Export => Operation::Export { output },
         };
 
-        Ok((Options { op }, vec![]))
+        Ok((Options { op, sync }, vec![]))
     }
 }
 
@@ -171,18 +268,54 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let (project, _) = project::cwd()?;
     let cobs = cobs::store(&profile, &storage)?;
     let issues = cobs.issues();
+    let resolve = |id: &cobs::Identifier| -> anyhow::Result<IssueId> {
+        issues
+            .resolve_id::<Issue>(&project, id)?
+            .ok_or_else(|| anyhow!("couldn't find issue {} locally", id))
+    };
+    let mut changed = true;
 
     match options.op {
         Operation::Create {
             title: Some(title),
             description: Some(description),
         } => {
-            issues.create(&project, &title, &description, &[])?;
+            let id = issues.create(&project, &title, &description, &[])?;
+            term::success!("Issue {} created", term::format::highlight(id));
+        }
+        Operation::Show { id } => {
+            let id = resolve(&id)?;
+            let mut issue = issues
+                .get(&project, &id)?
+                .ok_or_else(|| anyhow!("couldn't find issue {} locally", id))?;
+
+            // Patches referencing this issue, eg. with `fixes #<id>`.
+            let patches = cobs
+                .patches()
+                .all(&project)?
+                .into_iter()
+                .filter_map(|(patch_id, patch)| {
+                    let reference = patch.references().into_iter().find(|r| r.id.matches(&id))?;
+                    Some((patch_id, patch, reference.closes))
+                })
+                .collect::<Vec<_>>();
+
+            issue.resolve(&storage).ok();
+            show(&id, &issue, &patches);
+            changed = false;
         }
         Operation::State { id, state } => {
+            let id = resolve(&id)?;
             issues.lifecycle(&project, &id, state)?;
+
+            term::success!(
+                "Issue {} is now {}",
+                term::format::highlight(common::fmt::cob(&id)),
+                pretty_state(state)
+            );
         }
         Operation::React { id, reaction } => {
+            let id = resolve(&id)?;
             if let Some(issue) = issues.get(&project, &id)? {
                 let comment_id = term::comment_select(&issue).unwrap();
                 issues.react(&project, &id, comment_id, reaction)?;
@@ -200,7 +333,7 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                 description.unwrap_or("Enter a description...".to_owned())
             );
 
-            if let Some(text) = term::Editor::new().edit(&doc)? {
+            if let Some(text) = term::editor(&doc, ".markdown")? {
                 let mut meta = String::new();
                 let mut frontmatter = false;
                 let mut lines = text.lines();
@@ -224,18 +357,162 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                 let meta: Metadata =
                     serde_yaml::from_str(&meta).context("failed to parse yaml front-matter")?;
 
-                issues.create(&project, &meta.title, description.trim(), &meta.labels)?;
+                let id = issues.create(&project, &meta.title, description.trim(), &meta.labels)?;
+                term::success!("Issue {} created", term::format::highlight(id));
+            } else {
+                changed = false;
             }
         }
-        Operation::List => {
-            for (id, issue) in issues.all(&project)? {
-                println!("{} {}", id, issue.title());
+        Operation::List { state, porcelain } => {
+            let listed = issues
+                .all(&project)?
+                .into_iter()
+                .filter(|(_, i)| i.matches(state));
+            changed = false;
+
+            if porcelain {
+                for (id, issue) in listed {
+                    let state = match issue.state() {
+                        State::Open => "open",
+                        State::Closed {
+                            reason: CloseReason::Solved,
+                        } => "solved",
+                        State::Closed {
+                            reason: CloseReason::Other,
+                        } => "closed",
+                    };
+                    println!("{}\t{}\t{}", id, state, issue.title().replace('\t', " "));
+                }
+                return Ok(());
+            }
+            let mut table = term::Table::default();
+
+            for (id, issue) in listed {
+                table.push([
+                    id.to_string(),
+                    pretty_state(issue.state()),
+                    issue.title().to_owned(),
+                    term::format::dim(term::format::timestamp(&issue.timestamp())),
+                ]);
             }
+            table.render();
         }
         Operation::Delete { id } => {
+            let id = resolve(&id)?;
             issues.remove(&project, &id)?;
         }
+        Operation::Export { output } => {
+            let export = cobs::export::issues(&cobs, &storage, &project)?;
+            let json = common::json::to_string_pretty(&export)?;
+            changed = false;
+
+            if let Some(path) = output {
+                fs::write(&path, json + "\n")?;
+                term::success!(
+                    "Exported {} issue(s) to {}",
+                    export.issues.len(),
+                    path.display()
+                );
+            } else {
+                println!("{}", json);
+            }
+        }
+    }
+
+    if changed && options.sync {
+        if offline::is_enabled() {
+            term::warning("Offline mode is enabled, use `rad sync` to publish changes when online");
+        } else {
+            let rt = tokio::runtime::Runtime::new()?;
+
+            term::sync::sync(
+                project,
+                sync::seeds(&profile)?,
+                sync::Mode::Push,
+                &profile,
+                term::signer(&profile)?,
+                &rt,
+            )?;
+        }
     }
 
     Ok(())
 }
+
+/// Print an issue, with its description, the patches referencing it, and its discussion.
+fn show(id: &IssueId, issue: &Issue, patches: &[(PatchId, Patch, bool)]) {
+    term::blank();
+    term::info!(
+        "{} {} {}",
+        term::format::bold(issue.title()),
+        term::format::highlight(common::fmt::cob(id)),
+        pretty_state(issue.state()),
+    );
+    term::info!(
+        "└─ opened by {} {}",
+        term::format::tertiary(issue.author().name()),
+        term::format::dim(term::format::timestamp(&issue.timestamp())),
+    );
+    if !issue.labels().is_empty() {
+        let mut labels = issue
+            .labels()
+            .iter()
+            .map(|l| l.name().to_owned())
+            .collect::<Vec<_>>();
+        labels.sort();
+
+        term::info!(
+            "   {} {}",
+            term::format::dim("labels"),
+            term::format::yellow(labels.join(", "))
+        );
+    }
+    term::blank();
+
+    let description = issue.description().trim();
+    if description.is_empty() {
+        term::print(term::format::italic("No description provided."));
+    } else {
+        term::markdown(description);
+    }
+    if !issue.reactions().is_empty() {
+        term::blank();
+        term::print(term::format::reactions(issue.reactions()));
+    }
+    if !patches.is_empty() {
+        term::blank();
+    }
+    for (patch_id, patch, closes) in patches {
+        let verb = if *closes { "fixed by" } else { "mentioned in" };
+        let state = if patch.is_merged() {
+            term::format::secondary("merged")
+        } else if patch.is_archived() {
+            term::format::dim("archived")
+        } else {
+            term::format::positive("open")
+        };
+
+        term::info!(
+            "{} patch {} {} {}",
+            term::format::dim(verb),
+            term::format::highlight(common::fmt::cob(patch_id)),
+            term::format::bold(&patch.title),
+            state,
+        );
+    }
+    term::blank();
+    term::discussion(issue.comments());
+}
+
+/// Make a human friendly string for an issue state.
+fn pretty_state(state: State) -> String {
+    match state {
+        State::Open => term::format::positive("open"),
+        State::Closed {
+            reason: CloseReason::Solved,
+        } => term::format::secondary("solved"),
+        State::Closed {
+            reason: CloseReason::Other,
+        } => term::format::dim("closed"),
+    }
+}
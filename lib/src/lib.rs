@@ -0,0 +1,57 @@
+//! Radicle client operations, for use as a library.
+//!
+//! This crate gives GUI frontends, bots and other programs access to radicle
+//! operations, without running `rad` and scraping its output. Operations go through a
+//! [`Client`], which keeps the profile's storage open. They take and return plain
+//! data, which can be serialized, eg. to JSON, and never read from or write to the
+//! terminal.
+//!
+//! ```no_run
+//! use radicle_client_lib as radicle;
+//!
+//! let client = radicle::open(None)?;
+//!
+//! for project in client.projects()? {
+//!     let issues = client.issues(&project.metadata.urn, radicle::StateFilter::Open)?;
+//!
+//!     println!("{} has {} open issue(s)", project.metadata.name, issues.len());
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! Only the operations of [`Client`] are covered so far: listing projects, issues and
+//! patches, syncing, and proposing patches. Other operations are still only available
+//! as `rad` commands.
+//!
+//! Nb. The types used by these operations are re-exported from the crates this one is
+//! built on, and change along with them. There is no stability guarantee yet.
+pub use librad::crypto::keystore::pinentry::SecUtf8;
+pub use librad::crypto::BoxedSigner;
+pub use librad::profile::Profile;
+pub use librad::PeerId;
+
+pub use radicle_common::client::{signer, Client, NewPatch, Object, Project};
+pub use radicle_common::cobs::issue::{Issue, IssueId, State as IssueState};
+pub use radicle_common::cobs::patch::{Patch, PatchId, Revision, State as PatchState};
+pub use radicle_common::cobs::{Author, Comment, Label, StateFilter, Timestamp};
+pub use radicle_common::git::Oid;
+pub use radicle_common::hooks::SeedResult;
+pub use radicle_common::nonempty::NonEmpty;
+pub use radicle_common::project::Metadata;
+pub use radicle_common::sync::{Mode, Seed};
+pub use radicle_common::Urn;
+
+/// Get the profile set with `RAD_PROFILE`, or else the active profile.
+pub fn profile() -> anyhow::Result<Profile> {
+    Ok(radicle_common::profile::default()?)
+}
+
+/// Open a client of the profile set with `RAD_PROFILE`, or else of the active profile.
+/// If its signing key isn't in an agent, it is unsealed with the given passphrase,
+/// or the one set with `RAD_PASSPHRASE`.
+pub fn open(passphrase: Option<SecUtf8>) -> anyhow::Result<Client> {
+    let profile = profile()?;
+    let signer = signer(&profile, passphrase)?;
+
+    Client::new(profile, signer)
+}
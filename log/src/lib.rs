@@ -0,0 +1,185 @@
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context as _};
+
+use librad::git::Urn;
+use librad::PeerId;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::identity::{self, Change};
+use radicle_common::{fmt, person, profile, project};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "log",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad log --identity [--urn <urn>] [<option>...]
+
+    Shows the history of the identity document of a project or person, newest
+    revision first. For each revision, the keys that signed it are listed, along
+    with what changed: delegates added or removed, and payload fields edited.
+
+    If no URN is given, the project in the current directory is used.
+
+Options
+
+    --identity     Show the identity document history
+    --urn <urn>    Project or person to show the history of
+    --help         Print help
+"#,
+    ..Help::DEFAULT
+};
+
+#[derive(Debug)]
+pub struct Options {
+    pub urn: Option<Urn>,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut urn: Option<Urn> = None;
+        let mut identity = false;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("identity") => {
+                    identity = true;
+                }
+                Long("urn") if urn.is_none() => {
+                    let val = parser.value()?;
+                    let val = val.to_string_lossy();
+                    let val = Urn::from_str(&val).context(format!("invalid URN '{}'", val))?;
+
+                    urn = Some(val);
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        if !identity {
+            return Err(Error::WithHint {
+                err: anyhow!("a history to show must be specified"),
+                hint: "To show the identity document history, run `rad log --identity`.",
+            }
+            .into());
+        }
+
+        Ok((Options { urn }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let storage = profile::read_only(&profile)?;
+    let urn = match options.urn {
+        Some(urn) => urn,
+        None => project::cwd()
+            .map(|(urn, _)| urn)
+            .context("this command must be run within a project, or with `--urn`")?,
+    };
+    let revisions = identity::history(&storage, &urn)?;
+    let mut previous = serde_json::Value::Null;
+    let mut entries = Vec::new();
+
+    for revision in revisions {
+        let changes = identity::changes(&previous, &revision.doc);
+        previous = revision.doc.clone();
+        entries.push((revision, changes));
+    }
+
+    term::blank();
+    for (i, (revision, changes)) in entries.iter().enumerate().rev() {
+        let signers = revision
+            .signers
+            .iter()
+            .map(|s| key(s))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        term::info!(
+            "{} {}",
+            term::format::yellow(format!("revision {}", fmt::oid(&revision.id))),
+            term::format::dim(format!("(commit {})", fmt::oid(&revision.commit)))
+        );
+        term::info!("Date:    {}", term::format::timestamp(&revision.timestamp));
+        term::info!("Signers: {}", signers);
+        term::blank();
+
+        if i == 0 {
+            term::info!("    {}", term::format::positive("Identity created"));
+        } else if changes.is_empty() {
+            term::info!("    {}", term::format::dim("No changes"));
+        }
+        for change in changes {
+            match change {
+                Change::DelegateAdded(d) => {
+                    term::info!(
+                        "    {} delegate {}",
+                        term::format::positive("+"),
+                        delegate(&storage, d)
+                    );
+                }
+                Change::DelegateRemoved(d) => {
+                    term::info!(
+                        "    {} delegate {}",
+                        term::format::negative("-"),
+                        delegate(&storage, d)
+                    );
+                }
+                Change::Payload { field, old, new } => {
+                    let value = |v: &Option<serde_json::Value>| {
+                        v.as_ref()
+                            .map(|v| v.to_string())
+                            .unwrap_or_else(|| String::from("(unset)"))
+                    };
+                    term::info!(
+                        "    {} {}: {} → {}",
+                        term::format::yellow("~"),
+                        term::format::bold(field),
+                        term::format::dim(value(old)),
+                        value(new)
+                    );
+                }
+            }
+        }
+        term::blank();
+    }
+
+    Ok(())
+}
+
+/// Format a signing key, compactly if it's a valid Peer ID.
+fn key(key: &str) -> String {
+    match PeerId::from_str(key) {
+        Ok(peer) => term::format::tertiary(fmt::peer(&peer)),
+        Err(_) => term::format::tertiary(key),
+    }
+}
+
+/// Format a delegate, which is either a key or a person URN. Persons are shown with
+/// their name, if they are in storage.
+fn delegate(storage: &librad::git::storage::ReadOnly, delegate: &str) -> String {
+    match Urn::from_str(delegate) {
+        Ok(urn) => {
+            let name = person::get(storage, &urn)
+                .ok()
+                .flatten()
+                .map(|p| format!(" ({})", p.subject().name))
+                .unwrap_or_default();
+
+            format!("{}{}", term::format::highlight(urn), name)
+        }
+        Err(_) => key(delegate),
+    }
+}
@@ -1,7 +1,13 @@
 use std::ffi::OsString;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use librad::git::tracking;
 
 use radicle_common::args::{Args, Error, Help};
-use radicle_common::{profile, project};
+use radicle_common::cobs::Timestamp;
+use radicle_common::{git, profile, project};
 use radicle_terminal as term;
 
 pub const HELP: Help = Help {
@@ -13,22 +19,77 @@ Usage
 
     rad ls [<option>...]
 
+    Lists the projects in local storage. Seeds are not contacted, so this works
+    in offline mode.
+
 Options
 
-    --help    Print help
+    --sort <field>         Sort projects by `name`, or by `updated` with the most
+                           recently updated first
+    --filter <text>        Only list projects whose name, description or URN
+                           contains the given text, ignoring case
+    --format <template>    Print each project using the given template, eg. "%urn %name"
+                           Placeholders: %urn, %name, %head, %description, %peers
+    --help                 Print help
 "#,
+    ..Help::DEFAULT
 };
 
-pub struct Options {}
+/// Placeholders supported by `--format`.
+pub const FORMAT_FIELDS: &[&str] = &["urn", "name", "head", "description", "peers"];
+
+/// Order of listed projects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sort {
+    /// Sort by name.
+    Name,
+    /// Sort by the time of the head commit, most recent first.
+    Updated,
+}
+
+impl FromStr for Sort {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "name" => Ok(Self::Name),
+            "updated" => Ok(Self::Updated),
+            _ => Err(anyhow!(
+                "invalid sort field '{}', expected `name` or `updated`",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Options {
+    pub format: Option<term::Template>,
+    pub sort: Option<Sort>,
+    pub filter: Option<String>,
+}
 
 impl Args for Options {
     fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
         use lexopt::prelude::*;
 
         let mut parser = lexopt::Parser::from_args(args);
+        let mut options = Options::default();
 
-        if let Some(arg) = parser.next()? {
+        while let Some(arg) = parser.next()? {
             match arg {
+                Long("format") => {
+                    let template: term::Template = parser.value()?.to_string_lossy().parse()?;
+                    template.check(FORMAT_FIELDS)?;
+
+                    options.format = Some(template);
+                }
+                Long("sort") => {
+                    options.sort = Some(parser.value()?.to_string_lossy().parse()?);
+                }
+                Long("filter") => {
+                    options.filter = Some(parser.value()?.to_string_lossy().to_lowercase());
+                }
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
@@ -36,25 +97,78 @@ impl Args for Options {
             }
         }
 
-        Ok((Options {}, vec![]))
+        Ok((options, vec![]))
     }
 }
 
-pub fn run(_options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profile = ctx.profile()?;
     let storage = profile::read_only(&profile)?;
-    let projs = project::list(&storage)?;
-    let mut table = term::Table::default();
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+    let mut projs = Vec::new();
+
+    for (urn, meta, head) in project::list(&storage)? {
+        if let Some(filter) = &options.filter {
+            if ![&meta.name, &meta.description, &urn.to_string()]
+                .iter()
+                .any(|s| s.to_lowercase().contains(filter))
+            {
+                continue;
+            }
+        }
+        let time = head
+            .and_then(|h| monorepo.find_commit(h).ok())
+            .map(|c| c.time().seconds());
+        let peers = tracking::tracked(&storage, Some(&urn))?
+            .filter_map(|t| t.ok().and_then(|t| t.peer_id()))
+            .count();
+
+        projs.push((urn, meta, head, time, peers));
+    }
+
+    match options.sort {
+        Some(Sort::Name) => projs.sort_by(|a, b| a.1.name.cmp(&b.1.name)),
+        Some(Sort::Updated) => projs.sort_by(|a, b| b.3.cmp(&a.3)),
+        None => {}
+    }
 
-    for (urn, meta, head) in projs {
+    if let Some(template) = options.format {
+        for (urn, meta, head, _, peers) in projs {
+            println!(
+                "{}",
+                template.render(|field| match field {
+                    "urn" => Some(urn.to_string()),
+                    "name" => Some(meta.name.clone()),
+                    "head" => head.map(|h| h.to_string()),
+                    "description" => Some(meta.description.clone()),
+                    "peers" => Some(peers.to_string()),
+                    _ => None,
+                })
+            );
+        }
+        return Ok(());
+    }
+
+    let mut table = term::Table::default();
+    for (urn, meta, head, time, peers) in projs {
+        let updated = time
+            .map(|t| term::format::timestamp(&Timestamp::new(t as u64)))
+            .unwrap_or_default();
         let head = head
             .map(|h| format!("{:.7}", h.to_string()))
             .unwrap_or_else(String::new);
+        let peers = match peers {
+            0 => String::new(),
+            1 => String::from("1 peer"),
+            n => format!("{} peers", n),
+        };
 
         table.push([
             term::format::bold(meta.name),
             term::format::tertiary(urn),
             term::format::secondary(head),
+            term::format::dim(updated),
+            term::format::dim(peers),
             term::format::italic(meta.description),
         ]);
     }
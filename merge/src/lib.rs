@@ -6,9 +6,10 @@ use anyhow::{anyhow, Context};
 
 use radicle_common as common;
 use radicle_common::args::{Args, Error, Help};
+use radicle_common::cobs::issue::{CloseReason, Issue, State as IssueState};
 use radicle_common::cobs::patch::{Patch, PatchId};
 use radicle_common::patch::MergeStyle;
-use radicle_common::{cobs, git, keys, project};
+use radicle_common::{cobs, git, keys, offline, project, sync, tokio};
 use radicle_terminal as term;
 
 use cobs::patch::RevisionIx;
@@ -22,6 +23,14 @@ Usage
 
     rad merge [<id>] [<option>...]
 
+    Merges a patch into the project's default branch, which must be checked out
+    in the working copy. When possible, the branch is fast-forwarded, otherwise
+    a merge commit is created. The merge is recorded in the patch, and the branch
+    and patch are then pushed to storage and synced with seeds.
+
+    Open issues that the patch author references with a closing keyword, eg.
+    `fixes #<issue-id>` in the patch description, are closed as solved.
+
     To specify a patch to merge, use the fully qualified patch id
     or an unambiguous prefix of it.
 
@@ -29,8 +38,11 @@ Options
 
     -i, --interactive         Ask for confirmations
     -r, --revision <number>   Revision number to merge, defaults to the latest
+        --[no-]push           Push the merged branch to storage (default: true)
+        --[no-]sync           Sync the merge with seeds (default: true)
         --help                Print help
 "#,
+    ..Help::DEFAULT
 };
 
 /// Merge commit help message.
@@ -48,6 +60,8 @@ pub struct Options {
     pub id: cobs::Identifier,
     pub interactive: bool,
     pub revision: Option<RevisionIx>,
+    pub push: bool,
+    pub sync: bool,
 }
 
 impl Args for Options {
@@ -58,6 +72,8 @@ impl Args for Options {
         let mut id: Option<cobs::Identifier> = None;
         let mut revision: Option<RevisionIx> = None;
         let mut interactive = false;
+        let mut push = true;
+        let mut sync = true;
 
         while let Some(arg) = parser.next()? {
             match arg {
@@ -67,6 +83,18 @@ impl Args for Options {
                 Long("interactive") | Short('i') => {
                     interactive = true;
                 }
+                Long("push") => {
+                    push = true;
+                }
+                Long("no-push") => {
+                    push = false;
+                }
+                Long("sync") => {
+                    sync = true;
+                }
+                Long("no-sync") => {
+                    sync = false;
+                }
                 Long("revision") | Short('r') => {
                     let value = parser.value()?;
                     let id =
@@ -94,6 +122,8 @@ impl Args for Options {
                 id: id.ok_or_else(|| anyhow!("a patch id to merge must be provided"))?,
                 interactive,
                 revision,
+                push,
+                sync,
             },
             vec![],
         ))
@@ -108,11 +138,12 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
         .map_err(|_| anyhow!("this command must be run in the context of a project"))?;
     let profile = ctx.profile()?;
     let signer = term::signer(&profile)?;
-    let storage = keys::storage(&profile, signer)?;
-    let _project = project::get(&storage, &urn)?
+    let storage = keys::storage(&profile, signer.clone())?;
+    let project = project::get(&storage, &urn)?
         .ok_or_else(|| anyhow!("couldn't load project {} from local state", urn))?;
     let cobs = cobs::store(&profile, &storage)?;
     let patches = cobs.patches();
+    let issues = cobs.issues();
 
     if repo.head_detached()? {
         anyhow::bail!("HEAD is in a detached state; can't merge");
@@ -139,6 +170,26 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
         .get(revision_id)
         .ok_or_else(|| anyhow!("revision R{} does not exist", revision_id))?;
 
+    if branch != project.default_branch.to_string() {
+        return Err(Error::WithHint {
+            err: anyhow!(
+                "patches can only be merged into the default branch ({}), but {} is checked out",
+                project.default_branch,
+                branch
+            ),
+            hint: "hint: check out the project's default branch and try again",
+        }
+        .into());
+    }
+    if revision.merges.iter().any(|m| m.peer == *storage.peer_id()) {
+        term::info!(
+            "✓ Patch {} {} was already merged",
+            term::format::tertiary(common::fmt::cob(&patch_id)),
+            term::format::dim(format!("R{}", revision_id)),
+        );
+        return Ok(());
+    }
+
     //
     // Analyze merge
     //
@@ -239,13 +290,78 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     //
     // Update patch COB
     //
-    // TODO: Don't allow merging the same revision twice?
-    patches.merge(&urn, &patch_id, revision_id, head_oid.into())?;
+    // Nb. The merge is recorded with the branch head that now contains the revision.
+    let merged_oid = repo
+        .head()?
+        .target()
+        .ok_or_else(|| anyhow!("invalid HEAD ref after merge"))?;
+    patches.merge(&urn, &patch_id, revision_id, merged_oid.into())?;
+
+    term::success!("Patch state updated");
+
+    // Close the issues fixed by this patch, eg. with `fixes #<id>` in its description.
+    for reference in patch.references().into_iter().filter(|r| r.closes) {
+        match cobs.resolve::<Issue>(&urn, &reference.id) {
+            Ok(Some((issue_id, issue))) if issue.state() == IssueState::Open => {
+                issues.lifecycle(
+                    &urn,
+                    &issue_id,
+                    IssueState::Closed {
+                        reason: CloseReason::Solved,
+                    },
+                )?;
+                term::success!(
+                    "Issue {} closed",
+                    term::format::highlight(common::fmt::cob(&issue_id))
+                );
+            }
+            Ok(_) => {}
+            Err(err) => {
+                term::warning(&format!("Couldn't close issue #{}: {}", reference.id, err));
+            }
+        }
+    }
+
+    //
+    // Publish
+    //
+    if options.push {
+        let mut spinner = term::spinner(format!(
+            "Pushing {} to storage...",
+            term::format::highlight(branch)
+        ));
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| anyhow!("cannot push from bare repo"))?;
+
+        match git::git(workdir, ["push", "rad", branch]) {
+            Ok(_) => spinner.finish(),
+            Err(err) => {
+                spinner.failed();
+                return Err(err.context("failed to push merged branch to storage"));
+            }
+        }
+    }
 
-    term::success!(
-        "Patch state updated, use {} to publish",
-        term::format::secondary("`rad push`")
-    );
+    if !options.sync {
+        term::info!(
+            "Merge not synced, use {} to publish",
+            term::format::secondary("`rad sync`")
+        );
+    } else if offline::is_enabled() {
+        term::warning("Offline mode is enabled, use `rad sync` to publish the merge when online");
+    } else {
+        let rt = tokio::runtime::Runtime::new()?;
+
+        term::sync::sync(
+            urn.clone(),
+            sync::seeds(&profile)?,
+            sync::Mode::Push,
+            &profile,
+            signer,
+            &rt,
+        )?;
+    }
 
     Ok(())
 }
@@ -289,25 +405,8 @@ fn merge_commit(
     writeln!(&mut merge_msg, "{}", MERGE_HELP_MSG.join("\n").as_str())?;
 
     // Offer user the chance to edit the message before committing.
-    let merge_msg = match term::Editor::new()
-        .require_save(true)
-        .trim_newlines(true)
-        .extension(".git-commit")
-        .edit(&merge_msg)
-        .unwrap()
-    {
-        Some(s) => s
-            .lines()
-            .filter(|l| !l.starts_with('#'))
-            .collect::<Vec<_>>()
-            .join("\n"),
-        None => anyhow::bail!("user aborted merge"),
-    };
-
-    // Empty message aborts merge.
-    if merge_msg.trim().is_empty() {
-        anyhow::bail!("user aborted merge");
-    }
+    // Nb. An empty message aborts merge.
+    let merge_msg = term::editor_input(&merge_msg).context("user aborted merge")?;
 
     // Perform merge (nb. this does not commit).
     repo.merge(&[patch_commit], Some(merge_opts.patience(true)), None)
@@ -0,0 +1,244 @@
+use std::ffi::OsString;
+
+use anyhow::anyhow;
+
+use librad::git::Storage;
+use librad::PeerId;
+
+use radicle_common as common;
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::cobs::patch::Verdict;
+use radicle_common::cobs::{self, Timestamp};
+use radicle_common::notifications::{self, Checkpoint, Kind, Notification};
+use radicle_common::{keys, project};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "notifications",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad notifications [--mark-read] [<option>...]
+
+    Lists what is new in the current project since it was last marked as read:
+    issues and patches opened by others, comments, reviews and merges on your
+    own issues and patches, and updates to the identity and branches of tracked
+    peers. Only what was already fetched is considered, so run `rad sync --fetch`
+    first to get the latest.
+
+    With `--mark-read`, everything listed is marked as read, and won't be listed
+    again. This state is stored locally, next to your user configuration.
+
+Options
+
+    --mark-read     Mark everything listed as read
+    --help          Print help
+"#,
+    ..Help::DEFAULT
+};
+
+#[derive(Default, Debug)]
+pub struct Options {
+    pub mark_read: bool,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut mark_read = false;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Long("mark-read") => {
+                    mark_read = true;
+                }
+                _ => {
+                    return Err(anyhow!(arg.unexpected()));
+                }
+            }
+        }
+
+        Ok((Options { mark_read }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let (urn, _) = project::cwd()
+        .map_err(|_| anyhow!("this command must be run in the context of a project"))?;
+    let profile = ctx.profile()?;
+    let signer = term::signer(&profile)?;
+    let storage = keys::storage(&profile, signer)?;
+    let project = project::get(&storage, &urn)?
+        .ok_or_else(|| anyhow!("couldn't load project {} from local state", urn))?;
+    let cobs = cobs::store(&profile, &storage)?;
+
+    // Nb. The time of the check is taken before scanning, so that anything arriving
+    // while we scan is listed again next time.
+    let now = Timestamp::now();
+    let checkpoint = notifications::checkpoint(&urn)?;
+    let (since, previous) = match checkpoint {
+        Some(Checkpoint { timestamp, refs }) => (timestamp, refs),
+        None => (Timestamp::new(0), Default::default()),
+    };
+    let current = notifications::refs(&storage, &urn)?;
+    let mut inbox = notifications::cobs(&cobs, &urn, since)?;
+
+    inbox.extend(notifications::ref_changes(&previous, &current));
+
+    if inbox.is_empty() {
+        term::print(&term::format::italic("Nothing new."));
+    } else {
+        for notification in &mut inbox {
+            print(notification, &project, &storage);
+        }
+    }
+
+    if options.mark_read {
+        notifications::mark_read(
+            &urn,
+            Checkpoint {
+                timestamp: now,
+                refs: current,
+            },
+        )?;
+        if !inbox.is_empty() {
+            term::blank();
+            term::success!("Marked {} notification(s) as read", inbox.len());
+        }
+    } else if !inbox.is_empty() {
+        term::blank();
+        term::info!("To mark these as read, run `rad notifications --mark-read`");
+    }
+
+    Ok(())
+}
+
+fn print(notification: &mut Notification, project: &project::Metadata, storage: &Storage) {
+    let peer = |peer: &PeerId| project::PeerInfo::get(peer, project, storage).name();
+
+    let (what, id, title, by, timestamp) = match notification {
+        Notification::Issue {
+            id,
+            title,
+            author,
+            timestamp,
+        } => {
+            author.resolve(storage).ok();
+            (
+                "issue",
+                Some(*id),
+                title.clone(),
+                format!("opened by {}", author.name()),
+                Some(*timestamp),
+            )
+        }
+        Notification::Patch {
+            id,
+            title,
+            author,
+            timestamp,
+        } => {
+            author.resolve(storage).ok();
+            (
+                "patch",
+                Some(*id),
+                title.clone(),
+                format!("proposed by {}", author.name()),
+                Some(*timestamp),
+            )
+        }
+        Notification::Comment {
+            kind,
+            id,
+            title,
+            author,
+            timestamp,
+        } => {
+            let what = match kind {
+                Kind::Issue => "issue",
+                Kind::Patch => "patch",
+            };
+            author.resolve(storage).ok();
+            (
+                what,
+                Some(*id),
+                title.clone(),
+                format!("commented on by {}", author.name()),
+                Some(*timestamp),
+            )
+        }
+        Notification::Review {
+            id,
+            title,
+            author,
+            verdict,
+            timestamp,
+        } => {
+            let verb = match verdict {
+                Some(Verdict::Accept) => "accepted",
+                Some(Verdict::Reject) => "rejected",
+                None => "reviewed",
+            };
+            author.resolve(storage).ok();
+            (
+                "patch",
+                Some(*id),
+                title.clone(),
+                format!("{} by {}", verb, author.name()),
+                Some(*timestamp),
+            )
+        }
+        Notification::Merge {
+            id,
+            title,
+            peer: merged_by,
+            timestamp,
+        } => (
+            "patch",
+            Some(*id),
+            title.clone(),
+            format!("merged by {}", peer(merged_by)),
+            Some(*timestamp),
+        ),
+        Notification::Identity {
+            peer: updated_by,
+            oid,
+        } => (
+            "identity",
+            None,
+            format!("{:.7}", oid),
+            format!("updated by {}", peer(updated_by)),
+            None,
+        ),
+        Notification::Branch {
+            peer: updated_by,
+            name,
+            oid,
+        } => (
+            "branch",
+            None,
+            format!("{} {}", name, term::format::dim(format!("{:.7}", oid))),
+            format!("updated by {}", peer(updated_by)),
+            None,
+        ),
+    };
+
+    term::info!(
+        "{} {}{} {} {}",
+        term::format::secondary(format!("{:<8}", what)),
+        id.map(|id| format!("{} ", term::format::highlight(common::fmt::cob(&id))))
+            .unwrap_or_default(),
+        term::format::bold(title),
+        term::format::tertiary(by),
+        timestamp
+            .map(|t| term::format::dim(term::format::timestamp(&t)))
+            .unwrap_or_default(),
+    );
+}
@@ -3,11 +3,13 @@
 #![allow(clippy::for_kv_map)]
 use std::convert::TryFrom;
 use std::ffi::OsString;
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use anyhow::anyhow;
 
+use common::cobs::issue::{Issue, State as IssueState};
 use common::cobs::patch::Verdict;
 use librad::git::identities::local::LocalIdentity;
 use librad::git::storage::ReadOnlyStorage;
@@ -17,7 +19,7 @@ use librad::profile::Profile;
 
 use radicle_common as common;
 use radicle_common::args::{Args, Error, Help};
-use radicle_common::cobs::patch::{MergeTarget, Patch, PatchId, PatchStore};
+use radicle_common::cobs::patch::{Patch, PatchId, PatchStore, RevisionIx};
 use radicle_common::tokio;
 use radicle_common::{cobs, git, keys, patch, project, sync};
 use radicle_terminal as term;
@@ -30,21 +32,71 @@ pub const HELP: Help = Help {
     usage: r#"
 Usage
 
-    rad patch [<option>...]
+    rad patch [create] [<option>...]
+    rad patch update [<id>] [<option>...]
+    rad patch list [--state <state>] [--porcelain]
+    rad patch show <id> [--diff]
+    rad patch diff <id> [--revision <n>]
+    rad patch export [--output <file>]
+
+    Without an operation, the current branch is proposed as a patch. The patch
+    title and description are written in your editor, unless given with `--message`.
+    Issues are referenced with `#<issue-id>`, and with eg. `fixes #<issue-id>`, the
+    issue is closed when the patch is merged with `rad merge`.
+
+    `rad patch update` publishes the current branch as a new revision of an
+    existing patch, eg. after review. Without an id, the patch to update is found
+    by its base. Earlier revisions are kept, so that reviewers can compare them.
+
+    `rad patch list` lists the patches of the project, by you and by the peers
+    you track, with their reviews and merges. By default, only open patches are
+    listed, ie. patches that are neither merged nor archived. `rad patch show` prints a
+    patch's description, the issues it references, its revisions with their reviews,
+    and the diffstat of its latest revision, or with `--diff`, the full diff with the
+    code comments left on it with `rad comment`. Patches are reviewed with `rad review`.
+    `rad patch diff` shows the changes between a revision and the one before it,
+    as a range diff, so that only what changed since the last review is shown.
+
+    `rad patch export` exports all patches of the project as JSON, with their
+    revisions, comments, reviews, merges and the signatures of their changes, eg.
+    for migrations, backups or dashboards. The JSON schema is versioned, with a
+    top-level `version` field.
+
+    Patches are stored in storage as collaborative objects, and are synced with
+    seeds like other refs.
 
 Create options
 
-    -u, --update [<id>]        Update an existing patch (default: no)
+    -u, --update [<id>]        Update an existing patch, same as `rad patch update`
         --[no-]sync            Sync patch to seed (default: sync)
         --[no-]push            Push patch head to storage (default: true)
     -m, --message [<string>]   Provide a comment message to the patch or revision (default: prompt)
         --no-message           Leave the patch or revision comment message blank
 
+List options
+
+        --state <state>        Only list patches in this state: open, closed, merged or all (default: open)
+        --porcelain            Print one patch per line, as tab-separated id, state, revision,
+                               head and title, without syncing first
+
+Show options
+
+        --diff                 Show the diff of the latest revision, with code comments
+
+Diff options
+
+    -r, --revision <n>         Revision to compare with its previous revision (default: latest)
+
+Export options
+
+        --output <file>        Export patches to a file instead of printing them
+
 Options
 
-    -l, --list                 List all patches (default: false)
+    -l, --list                 List all patches, same as `rad patch list`
         --help                 Print help
 "#,
+    ..Help::DEFAULT
 };
 
 pub const PATCH_MSG: &str = r#"
@@ -79,9 +131,50 @@ impl Default for Update {
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum OperationName {
+    Create,
+    Update,
+    List,
+    Show,
+    Diff,
+    Export,
+}
+
+impl Default for OperationName {
+    fn default() -> Self {
+        Self::Create
+    }
+}
+
+#[derive(Debug)]
+pub enum Operation {
+    Create,
+    List,
+    Show {
+        id: cobs::Identifier,
+        diff: bool,
+    },
+    Diff {
+        id: cobs::Identifier,
+        revision: Option<RevisionIx>,
+    },
+    Export {
+        output: Option<PathBuf>,
+    },
+}
+
+impl Default for Operation {
+    fn default() -> Self {
+        Self::Create
+    }
+}
+
 #[derive(Default, Debug)]
 pub struct Options {
-    pub list: bool,
+    pub op: Operation,
+    pub state: cobs::StateFilter,
+    pub porcelain: bool,
     pub verbose: bool,
     pub sync: bool,
     pub push: bool,
@@ -94,17 +187,23 @@ impl Args for Options {
         use lexopt::prelude::*;
 
         let mut parser = lexopt::Parser::from_args(args);
-        let mut list = false;
+        let mut op: Option<OperationName> = None;
+        let mut id: Option<cobs::Identifier> = None;
+        let mut revision: Option<RevisionIx> = None;
+        let mut diff = false;
+        let mut state = cobs::StateFilter::default();
+        let mut porcelain = false;
         let mut verbose = false;
         let mut sync = true;
         let mut message = Comment::default();
         let mut push = true;
         let mut update = Update::default();
+        let mut output: Option<PathBuf> = None;
 
         while let Some(arg) = parser.next()? {
             match arg {
                 Long("list") | Short('l') => {
-                    list = true;
+                    op = Some(OperationName::List);
                 }
                 Long("verbose") | Short('v') => {
                     verbose = true;
@@ -129,6 +228,32 @@ impl Args for Options {
                         update = Update::Any;
                     }
                 }
+                Long("revision") | Short('r') => {
+                    let val = parser.value()?;
+                    let val = val
+                        .to_str()
+                        .ok_or_else(|| anyhow!("revision specified is not UTF-8"))?;
+                    let ix = val
+                        .trim_start_matches(&['R', 'r'][..])
+                        .parse()
+                        .map_err(|_| anyhow!("invalid revision '{}'", val))?;
+
+                    revision = Some(ix);
+                }
+                Long("output") if op == Some(OperationName::Export) => {
+                    output = Some(parser.value()?.into());
+                }
+                Long("diff") => {
+                    diff = true;
+                }
+                Long("state") => {
+                    let val = parser.value()?;
+
+                    state = cobs::StateFilter::from_str(&val.to_string_lossy())?;
+                }
+                Long("porcelain") => {
+                    porcelain = true;
+                }
                 Long("sync") => {
                     sync = true;
                 }
@@ -144,13 +269,61 @@ impl Args for Options {
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
+                Value(val) if op.is_none() => match val.to_string_lossy().as_ref() {
+                    "c" | "create" => op = Some(OperationName::Create),
+                    "u" | "update" => op = Some(OperationName::Update),
+                    "l" | "list" => op = Some(OperationName::List),
+                    "s" | "show" => op = Some(OperationName::Show),
+                    "d" | "diff" => op = Some(OperationName::Diff),
+                    "export" => op = Some(OperationName::Export),
+
+                    unknown => anyhow::bail!("unknown operation '{}'", unknown),
+                },
+                Value(val)
+                    if matches!(
+                        op,
+                        Some(OperationName::Update | OperationName::Show | OperationName::Diff)
+                    ) && id.is_none() =>
+                {
+                    let val = val
+                        .to_str()
+                        .ok_or_else(|| anyhow!("patch id specified is not UTF-8"))?;
+
+                    id = Some(
+                        cobs::Identifier::from_str(val)
+                            .map_err(|_| anyhow!("invalid patch id '{}'", val))?,
+                    );
+                }
                 _ => return Err(anyhow::anyhow!(arg.unexpected())),
             }
         }
 
+        let op = match op.unwrap_or_default() {
+            OperationName::Create => Operation::Create,
+            OperationName::Update => {
+                update = match id {
+                    Some(id) => Update::Patch(id),
+                    None => Update::Any,
+                };
+                Operation::Create
+            }
+            OperationName::List => Operation::List,
+            OperationName::Show => Operation::Show {
+                id: id.ok_or_else(|| anyhow!("a patch id must be provided"))?,
+                diff,
+            },
+            OperationName::Diff => Operation::Diff {
+                id: id.ok_or_else(|| anyhow!("a patch id must be provided"))?,
+                revision,
+            },
+            OperationName::Export => Operation::Export { output },
+        };
+
         Ok((
             Options {
-                list,
+                op,
+                state,
+                porcelain,
                 sync,
                 message,
                 push,
@@ -172,10 +345,14 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let project = project::get(&storage, &urn)?
         .ok_or_else(|| anyhow!("couldn't load project {} from local state", urn))?;
 
-    if options.list {
-        list(&storage, Some(repo), &profile, &project, options)?;
-    } else {
-        create(&storage, &profile, &project, &repo, options)?;
+    match options.op {
+        Operation::List => list(&storage, Some(repo), &profile, &project, options)?,
+        Operation::Show { ref id, diff } => {
+            show(&storage, Some(repo), &profile, &project, id, diff)?
+        }
+        Operation::Diff { ref id, revision } => diff(&storage, &profile, &project, id, revision)?,
+        Operation::Create => create(&storage, &profile, &project, &repo, options)?,
+        Operation::Export { ref output } => export(&storage, &profile, &project, output)?,
     }
 
     Ok(())
@@ -188,7 +365,8 @@ fn list(
     project: &project::Metadata,
     options: Options,
 ) -> anyhow::Result<()> {
-    if options.sync {
+    // Nb. Porcelain output is meant to be fast, so there is no sync.
+    if options.sync && !options.porcelain {
         let rt = tokio::runtime::Runtime::new()?;
 
         term::sync::sync(
@@ -203,7 +381,35 @@ fn list(
 
     let cobs = cobs::store(profile, storage)?;
     let patches = cobs.patches();
-    let proposed = patches.proposed(&project.urn)?;
+    let listed = patches
+        .all(&project.urn)?
+        .into_iter()
+        .filter(|(_, p)| p.matches(options.state));
+
+    if options.porcelain {
+        for (id, patch) in listed {
+            let (version, revision) = patch.latest();
+            let state = if patch.is_merged() {
+                "merged"
+            } else if patch.is_archived() {
+                "archived"
+            } else if patch.is_proposed() {
+                "open"
+            } else {
+                "draft"
+            };
+
+            println!(
+                "{}\t{}\tR{}\t{}\t{}",
+                id,
+                state,
+                version,
+                *revision.oid,
+                patch.title.replace('\t', " ")
+            );
+        }
+        return Ok(());
+    }
     let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
 
     // Patches the user authored.
@@ -211,7 +417,7 @@ fn list(
     // Patches other users authored.
     let mut other = Vec::new();
 
-    for (id, patch) in proposed {
+    for (id, patch) in listed {
         if *patch.author.urn() == cobs.whoami.urn() {
             own.push((id, patch));
         } else {
@@ -249,6 +455,240 @@ fn list(
     Ok(())
 }
 
+fn show(
+    storage: &Storage,
+    repo: Option<git::Repository>,
+    profile: &Profile,
+    project: &project::Metadata,
+    id: &cobs::Identifier,
+    diff: bool,
+) -> anyhow::Result<()> {
+    let cobs = cobs::store(profile, storage)?;
+    let patches = cobs.patches();
+    let (id, mut patch) = patches
+        .resolve(&project.urn, id)?
+        .ok_or_else(|| anyhow!("Patch '{}' not found", id))?;
+    let monorepo = git::Repository::open_bare(profile.paths().git_dir())?;
+
+    // Resolve the authors of reviews and comments, for display.
+    patch.resolve(storage).ok();
+
+    term::blank();
+    print(
+        &cobs.whoami,
+        &id,
+        &mut patch,
+        project,
+        &monorepo,
+        &repo,
+        storage,
+    )?;
+    term::blank();
+
+    let description = patch.description().trim();
+    if description.is_empty() {
+        term::print(term::format::italic("No description provided."));
+    } else {
+        term::markdown(description);
+    }
+    let (_, latest) = patch.latest();
+    if !latest.comment.reactions.is_empty() {
+        term::blank();
+        term::print(term::format::reactions(&latest.comment.reactions));
+    }
+
+    let references = patch.references();
+    if !references.is_empty() {
+        term::blank();
+    }
+    for reference in references {
+        let verb = if reference.closes {
+            "fixes"
+        } else {
+            "mentions"
+        };
+
+        // Nb. References that are ambiguous are shown as not found.
+        if let Some((issue_id, issue)) = cobs
+            .resolve::<Issue>(&project.urn, &reference.id)
+            .ok()
+            .flatten()
+        {
+            let state = match issue.state() {
+                IssueState::Open => term::format::positive("open"),
+                IssueState::Closed { .. } => term::format::dim("closed"),
+            };
+            term::info!(
+                "{} issue {} {} {}",
+                term::format::dim(verb),
+                term::format::highlight(common::fmt::cob(&issue_id)),
+                term::format::bold(issue.title()),
+                state,
+            );
+        } else if let Some((patch_id, other)) = cobs
+            .resolve::<Patch>(&project.urn, &reference.id)
+            .ok()
+            .flatten()
+        {
+            term::info!(
+                "{} patch {} {}",
+                term::format::dim(verb),
+                term::format::highlight(common::fmt::cob(&patch_id)),
+                term::format::bold(&other.title),
+            );
+        } else {
+            term::info!(
+                "{} {} {}",
+                term::format::dim(verb),
+                term::format::highlight(format!("#{}", reference.id)),
+                term::format::italic("not found"),
+            );
+        }
+    }
+    term::blank();
+    term::discussion(&latest.discussion);
+
+    for (ix, revision) in patch.revisions.iter().enumerate() {
+        let comment = revision.description().lines().next().unwrap_or_default();
+
+        term::info!(
+            "{} {} {} {}",
+            term::format::dim(format!("R{}", ix)),
+            term::format::secondary(common::fmt::oid(&revision.oid)),
+            term::format::italic(comment),
+            term::format::dim(term::format::timestamp(&revision.timestamp)),
+        );
+
+        let mut reviews = revision.reviews.values().collect::<Vec<_>>();
+        reviews.sort_by_key(|r| r.timestamp);
+
+        for review in reviews {
+            let mut badges = Vec::new();
+            if review.author.urn() == &cobs.whoami.urn() {
+                badges.push(term::format::secondary("(you)"));
+            }
+            term::info!(
+                "   {} by {} {} {}",
+                pretty_verdict(review.verdict),
+                term::format::tertiary(review.author.name()),
+                badges.join(" "),
+                term::format::dim(term::format::timestamp(&review.timestamp)),
+            );
+
+            let comment = review.comment.body.trim();
+            if !comment.is_empty() {
+                for line in comment.lines() {
+                    term::info!("     {}", term::format::italic(line));
+                }
+            }
+        }
+    }
+    term::blank();
+
+    let (_, revision) = patch.latest();
+    if diff {
+        let mut comments = revision
+            .reviews
+            .values()
+            .flat_map(|r| r.inline.iter())
+            .collect::<Vec<_>>();
+        comments.sort_by_key(|c| c.comment.timestamp);
+
+        term::patch::print_diff(&monorepo, *revision.base, *revision.oid, &comments)?;
+    } else {
+        term::patch::print_diffstat(&monorepo, *revision.base, *revision.oid)?;
+        term::blank();
+    }
+
+    Ok(())
+}
+
+fn export(
+    storage: &Storage,
+    profile: &Profile,
+    project: &project::Metadata,
+    output: &Option<PathBuf>,
+) -> anyhow::Result<()> {
+    let cobs = cobs::store(profile, storage)?;
+    let export = cobs::export::patches(&cobs, storage, &project.urn)?;
+    let json = common::json::to_string_pretty(&export)?;
+
+    if let Some(path) = output {
+        fs::write(path, json + "\n")?;
+        term::success!(
+            "Exported {} patch(es) to {}",
+            export.patches.len(),
+            path.display()
+        );
+    } else {
+        println!("{}", json);
+    }
+
+    Ok(())
+}
+
+fn diff(
+    storage: &Storage,
+    profile: &Profile,
+    project: &project::Metadata,
+    id: &cobs::Identifier,
+    revision: Option<RevisionIx>,
+) -> anyhow::Result<()> {
+    let cobs = cobs::store(profile, storage)?;
+    let patches = cobs.patches();
+    let (id, patch) = patches
+        .resolve(&project.urn, id)?
+        .ok_or_else(|| anyhow!("Patch '{}' not found", id))?;
+    let ix = revision.unwrap_or_else(|| patch.version());
+    let current = patch
+        .revisions
+        .get(ix)
+        .ok_or_else(|| anyhow!("Patch {} has no revision R{}", common::fmt::cob(&id), ix))?;
+    let git_dir = profile.paths().git_dir();
+
+    // The first revision has nothing to compare with, so its changes are shown.
+    let output = match ix.checked_sub(1).and_then(|ix| patch.revisions.get(ix)) {
+        Some(previous) => {
+            term::info!(
+                "{} {} ({}) -> {} ({})",
+                term::format::tertiary(common::fmt::cob(&id)),
+                term::format::dim(format!("R{}", ix - 1)),
+                term::format::secondary(common::fmt::oid(&previous.oid)),
+                term::format::dim(format!("R{}", ix)),
+                term::format::secondary(common::fmt::oid(&current.oid)),
+            );
+            git::git(
+                git_dir,
+                [
+                    String::from("range-diff"),
+                    format!("{}..{}", *previous.base, *previous.oid),
+                    format!("{}..{}", *current.base, *current.oid),
+                ],
+            )?
+        }
+        None => {
+            term::info!(
+                "{} {} ({})",
+                term::format::tertiary(common::fmt::cob(&id)),
+                term::format::dim(format!("R{}", ix)),
+                term::format::secondary(common::fmt::oid(&current.oid)),
+            );
+            git::git(
+                git_dir,
+                [
+                    String::from("diff"),
+                    format!("{}..{}", *current.base, *current.oid),
+                ],
+            )?
+        }
+    };
+    term::blank();
+    term::blob(output.trim_end());
+    term::blank();
+
+    Ok(())
+}
+
 fn update(
     patch: Patch,
     patch_id: PatchId,
@@ -290,6 +730,10 @@ fn update(
 
     term::blank();
     term::success!("Patch {} updated 🌱", term::format::highlight(patch_id));
+    term::info!(
+        "To compare with the previous revision, run `rad patch diff {}`",
+        term::format::highlight(&patch_id)
+    );
     term::blank();
 
     if options.sync {
@@ -361,18 +805,18 @@ fn create(
     // Determine the merge target for this patch. This can ben any tracked remote's "default"
     // branch, as well as your own (eg. `rad/master`).
     let mut spinner = term::spinner("Analyzing remotes...");
-    let targets = patch::find_merge_targets(&head_oid, storage, project)?;
 
     // eg. `refs/namespaces/<proj>/refs/remotes/<peer>/heads/master`
-    let (target_peer, target_oid) = match targets.not_merged.as_slice() {
-        [] => {
+    // TODO: Let user select which branch to use as a target, if there's more than one.
+    let (target_peer, target_oid) = match patch::find_merge_target(&head_oid, storage, project) {
+        Ok(Some(target)) => target,
+        Ok(None) => {
             spinner.message("All tracked peers are up to date.");
             return Ok(());
         }
-        [target] => target,
-        _ => {
-            // TODO: Let user select which branch to use as a target.
-            todo!();
+        Err(err) => {
+            spinner.failed();
+            return Err(err);
         }
     };
     // TODO: Tell user how many peers don't have this change.
@@ -384,7 +828,7 @@ fn create(
     // base.
 
     // The merge base is basically the commit at which the histories diverge.
-    let base_oid = repo.merge_base((*target_oid).into(), head_oid)?;
+    let base_oid = repo.merge_base(target_oid.into(), head_oid)?;
     let commits = patch::patch_commits(repo, &base_oid, &head_oid)?;
 
     let patch = match &options.update {
@@ -393,7 +837,7 @@ fn create(
             let mut spinner = term::spinner("Finding patches to update...");
             let mut result = find_unmerged_with_base(
                 head_oid,
-                **target_oid,
+                *target_oid,
                 base_oid,
                 &patches,
                 &project.urn,
@@ -414,7 +858,7 @@ fn create(
                 } else {
                     spinner.failed();
                     term::blank();
-                    anyhow::bail!("More than one patch available to update, please specify an id with `rad patch --update <id>`");
+                    anyhow::bail!("More than one patch available to update, please specify an id with `rad patch update <id>`");
                 }
             } else {
                 spinner.failed();
@@ -451,7 +895,7 @@ fn create(
         "{}/{} ({}) <- {}/{} ({})",
         target_peer.name(),
         term::format::highlight(&project.default_branch.to_string()),
-        term::format::secondary(&common::fmt::oid(target_oid)),
+        term::format::secondary(&common::fmt::oid(&target_oid)),
         user_name,
         term::format::highlight(&head_branch.to_string()),
         term::format::secondary(&common::fmt::oid(&head_oid)),
@@ -460,7 +904,7 @@ fn create(
     // TODO: Test case where the target branch has been re-written passed the merge-base, since the fork was created
     // This can also happen *after* the patch is created.
 
-    term::patch::print_commits_ahead_behind(repo, head_oid, (*target_oid).into())?;
+    term::patch::print_commits_ahead_behind(repo, head_oid, target_oid.into())?;
 
     // List commits in patch that aren't in the target branch.
     term::blank();
@@ -506,14 +950,14 @@ fn create(
         anyhow::bail!("patch proposal aborted by user");
     }
 
-    let id = patches.create(
+    let id = patch::create(
+        &patches,
+        storage,
         &project.urn,
         title,
         &description,
-        MergeTarget::default(),
         base_oid,
         head_oid,
-        &[],
     )?;
 
     term::blank();
@@ -552,6 +996,15 @@ fn pretty_sync_status(
     Ok(format!("ahead {}, behind {}", ahead, behind))
 }
 
+/// Make a human friendly string for a review verdict.
+fn pretty_verdict(verdict: Option<Verdict>) -> String {
+    match verdict {
+        Some(Verdict::Accept) => term::format::positive(term::format::dim("✓ accepted")),
+        Some(Verdict::Reject) => term::format::negative(term::format::dim("✗ rejected")),
+        None => term::format::negative(term::format::dim("⋄ reviewed")),
+    }
+}
+
 /// Make a human friendly string for commit version information.
 ///
 /// For example '<oid> (branch1[, branch2])'.
@@ -616,7 +1069,7 @@ pub fn print(
     if you {
         author_info.push(term::format::secondary("(you)"));
     }
-    author_info.push(term::format::dim(patch.timestamp));
+    author_info.push(term::format::dim(term::format::timestamp(&patch.timestamp)));
 
     let revision = patch.revisions.last();
     term::info!(
@@ -653,11 +1106,7 @@ pub fn print(
         ));
     }
     for (_, review) in &revision.reviews {
-        let verdict = match review.verdict {
-            Some(Verdict::Accept) => term::format::positive(term::format::dim("✓ accepted")),
-            Some(Verdict::Reject) => term::format::negative(term::format::dim("✗ rejected")),
-            None => term::format::negative(term::format::dim("⋄ reviewed")),
-        };
+        let verdict = pretty_verdict(review.verdict);
         let peer = project::PeerInfo::get(&review.author.peer, project, storage);
         let mut badges = Vec::new();
 
@@ -682,7 +1131,11 @@ pub fn print(
     timeline.sort_by_key(|(t, _)| *t);
 
     for (time, event) in timeline.iter().rev() {
-        term::info!("{} {}", event, term::format::dim(time));
+        term::info!(
+            "{} {}",
+            event,
+            term::format::dim(term::format::timestamp(time))
+        );
     }
 
     Ok(())
@@ -12,6 +12,7 @@ Usage
 
     rad path [--help]
 "#,
+    ..Help::DEFAULT
 };
 
 #[derive(Default, Eq, PartialEq)]
@@ -22,17 +22,23 @@ Usage
 
     Pulls changes into the current branch after optionally syncing.
 
+    Unless seeds are given with `--seed`, the project's seeds are synced with, as
+    set with `rad seed default --project`.
+
 Options
 
     --seed <addr>   Seed to sync from (may be specified multiple times)
+    --quiet, -q     Don't show transfer progress
     --help          Print help
 
 "#,
+    ..Help::DEFAULT
 };
 
 #[derive(Debug)]
 pub struct Options {
     seeds: Vec<sync::Seed<String>>,
+    quiet: bool,
 }
 
 impl Args for Options {
@@ -41,13 +47,17 @@ impl Args for Options {
 
         let mut parser = lexopt::Parser::from_args(args);
         let mut seeds = Vec::new();
+        let mut quiet = false;
 
-        if let Some(arg) = parser.next()? {
+        while let Some(arg) = parser.next()? {
             match arg {
                 Long("seed") => {
                     let seed = seed::parse_value(&mut parser)?;
                     seeds.push(seed);
                 }
+                Long("quiet") | Short('q') => {
+                    quiet = true;
+                }
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
@@ -55,7 +65,7 @@ impl Args for Options {
             }
         }
 
-        Ok((Options { seeds }, vec![]))
+        Ok((Options { seeds, quiet }, vec![]))
     }
 }
 
@@ -74,6 +84,7 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
             origin: Some(identity::Origin::from_urn(urn)),
             seeds: options.seeds,
             mode: sync::Mode::Fetch,
+            wait: true,
             ..rad_sync::Options::default()
         },
         ctx,
@@ -82,7 +93,13 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     term::blank();
     term::subcommand("git pull");
 
-    let output = git::pull(std::path::Path::new("."), true)?;
+    let spinner = term::spinner("Pulling...");
+    let output = git::pull(std::path::Path::new("."), true, |progress| {
+        if !options.quiet {
+            spinner.progress(progress.to_string());
+        }
+    })?;
+    spinner.clear();
 
     term::info!("{}", output);
 
@@ -0,0 +1,171 @@
+use std::ffi::OsString;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use radicle_common as common;
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::cobs::{self, issue, patch, CommentId, Reaction};
+use radicle_common::{keys, project};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "react",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad react <id> <emoji> [--comment <index>] [<option>...]
+
+    Reacts to an issue or patch, by id or an unambiguous prefix of it, or with
+    `--comment`, to one of its comments. Reactions are signed by your identity,
+    and are counted in `rad issue show` and `rad patch show`. Reacting again
+    with the same emoji has no effect.
+
+    Comments are referred to by their index, as shown by `rad issue show` and
+    `rad patch show`. On patches, the comments are those of a revision, by
+    default the latest.
+
+Options
+
+        --comment <index>       React to a comment instead
+    -r, --revision <number>     Patch revision to react to (default: latest)
+        --help                  Print help
+"#,
+    ..Help::DEFAULT
+};
+
+#[derive(Debug)]
+pub struct Options {
+    pub id: cobs::Identifier,
+    pub reaction: Reaction,
+    pub comment: Option<usize>,
+    pub revision: Option<patch::RevisionIx>,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut id: Option<cobs::Identifier> = None;
+        let mut reaction: Option<Reaction> = None;
+        let mut comment: Option<usize> = None;
+        let mut revision: Option<patch::RevisionIx> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Long("comment") => {
+                    let value = parser.value()?;
+                    let ix = value
+                        .to_str()
+                        .unwrap_or_default()
+                        .trim_start_matches('#')
+                        .parse()
+                        .map_err(|_| {
+                            anyhow!("invalid comment index `{}`", value.to_string_lossy())
+                        })?;
+
+                    comment = Some(ix);
+                }
+                Long("revision") | Short('r') => {
+                    let value = parser.value()?;
+                    let ix = value.to_str().unwrap_or_default().parse().map_err(|_| {
+                        anyhow!("invalid revision number `{}`", value.to_string_lossy())
+                    })?;
+
+                    revision = Some(ix);
+                }
+                Value(val) if id.is_none() => {
+                    let val = val
+                        .to_str()
+                        .ok_or_else(|| anyhow!("object id specified is not UTF-8"))?;
+
+                    id = Some(
+                        cobs::Identifier::from_str(val)
+                            .map_err(|_| anyhow!("invalid object id '{}'", val))?,
+                    );
+                }
+                Value(val) if reaction.is_none() => {
+                    let val = val.to_string_lossy();
+
+                    reaction =
+                        Some(Reaction::from_str(&val).map_err(|_| {
+                            anyhow!("invalid reaction '{}', expected an emoji", val)
+                        })?);
+                }
+                _ => {
+                    return Err(anyhow!(arg.unexpected()));
+                }
+            }
+        }
+
+        Ok((
+            Options {
+                id: id.ok_or_else(|| anyhow!("an object id must be provided"))?,
+                reaction: reaction.ok_or_else(|| anyhow!("a reaction emoji must be provided"))?,
+                comment,
+                revision,
+            },
+            vec![],
+        ))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let signer = term::signer(&profile)?;
+    let storage = keys::storage(&profile, signer)?;
+    let (project, _) = project::cwd()?;
+    let cobs = cobs::store(&profile, &storage)?;
+    let cob_id = options.id;
+    // Nb. The root comment is the object's own comment, eg. an issue's description,
+    // and the comments of its discussion follow.
+    let comment_id = |comments: usize| -> anyhow::Result<CommentId> {
+        match options.comment {
+            None => Ok(CommentId::root()),
+            Some(ix) if ix < comments => Ok(CommentId::from(ix + 1)),
+            Some(ix) => Err(anyhow!(
+                "comment #{} does not exist, there are {} comment(s)",
+                ix,
+                comments
+            )),
+        }
+    };
+
+    if let Some((id, issue)) = cobs.resolve::<issue::Issue>(&project, &cob_id)? {
+        let comment_id = comment_id(issue.comments().len())?;
+
+        cobs.issues()
+            .react(&project, &id, comment_id, options.reaction)?;
+        term::success!(
+            "Reacted {} to issue {}",
+            options.reaction.emoji,
+            term::format::highlight(common::fmt::cob(&id))
+        );
+    } else if let Some((id, patch)) = cobs.resolve::<patch::Patch>(&project, &cob_id)? {
+        let revision_ix = options.revision.unwrap_or_else(|| patch.version());
+        let revision = patch
+            .revisions
+            .get(revision_ix)
+            .ok_or_else(|| anyhow!("revision R{} does not exist", revision_ix))?;
+        let comment_id = comment_id(revision.discussion.len())?;
+
+        cobs.patches()
+            .react(&project, &id, revision_ix, comment_id, options.reaction)?;
+        term::success!(
+            "Reacted {} to patch {} {}",
+            options.reaction.emoji,
+            term::format::highlight(common::fmt::cob(&id)),
+            term::format::dim(format!("R{}", revision_ix))
+        );
+    } else {
+        anyhow::bail!("Couldn't find issue or patch {}", cob_id);
+    }
+
+    Ok(())
+}
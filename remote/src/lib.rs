@@ -20,34 +20,48 @@ pub const HELP: Help = Help {
     usage: r#"
 Usage
 
-    rad remote add <name> <peer-id> [-f | --fetch]
+    rad remote add [<name>] <peer-id> [-f | --fetch]
     rad remote rm <name | peer-id>
-    rad remote ls
+    rad remote ls [--format <template>]
+    rad remote list [--format <template>]
+
+    Remotes fetch the refs of a peer's view of the project into the working copy.
+    The peer is tracked when its remote is added. If no name is given, the remote
+    is named after the peer's handle, if its identity is known, or else its Peer ID.
 
 Examples
 
+    rad remote add hyn9diwfnytahjq8u3iw63h9jte1ydcatxax3saymwdxqu1zo645pe --fetch
     rad remote add cloudhead hyn9diwfnytahjq8u3iw63h9jte1ydcatxax3saymwdxqu1zo645pe
 
 Options
 
-    -f, --fetch     Fetch the remote immediately after it is setup
-        --help      Print help
+    -f, --fetch                Fetch the remote immediately after it is setup
+        --format <template>    Print each remote using the given template, eg. "%peer %name"
+                               Placeholders: %peer, %name, %remote, %delegate
+        --help                 Print help
 "#,
+    ..Help::DEFAULT
 };
 
 #[derive(Debug)]
 pub enum Operation {
     Add {
-        name: String,
+        name: Option<String>,
         peer: PeerId,
         fetch: bool,
     },
     Remove {
         remote: String,
     },
-    List,
+    List {
+        format: Option<term::Template>,
+    },
 }
 
+/// Placeholders supported by `--format`.
+pub const FORMAT_FIELDS: &[&str] = &["peer", "name", "remote", "delegate"];
+
 /// Tool options.
 #[derive(Debug)]
 pub struct Options {
@@ -59,10 +73,10 @@ impl Args for Options {
         use lexopt::prelude::*;
 
         let mut parser = lexopt::Parser::from_args(args);
-        let mut peer: Option<PeerId> = None;
-        let mut remote: Option<String> = None;
+        let mut values: Vec<String> = Vec::new();
         let mut op: Option<String> = None;
         let mut fetch = false;
+        let mut format: Option<term::Template> = None;
 
         while let Some(arg) = parser.next()? {
             match arg {
@@ -72,14 +86,17 @@ impl Args for Options {
                 Long("fetch") | Short('f') if op.is_some() => {
                     fetch = true;
                 }
+                Long("format") if matches!(op.as_deref(), None | Some("ls") | Some("list")) => {
+                    let template: term::Template = parser.value()?.to_string_lossy().parse()?;
+                    template.check(FORMAT_FIELDS)?;
+
+                    format = Some(template);
+                }
                 Value(val) if op.is_none() => {
                     op = Some(val.to_string_lossy().to_string());
                 }
-                Value(val) if remote.is_none() => {
-                    remote = Some(val.to_string_lossy().to_string());
-                }
-                Value(val) if peer.is_none() => {
-                    peer = Some(val.parse().context("invalid value specified for peer")?);
+                Value(val) if values.len() < 2 => {
+                    values.push(val.to_string_lossy().to_string());
                 }
                 _ => {
                     return Err(anyhow!(arg.unexpected()));
@@ -89,19 +106,31 @@ impl Args for Options {
 
         let op = match op {
             Some(op) => match op.as_str() {
-                "add" => Operation::Add {
-                    name: remote.ok_or(Error::Usage)?,
-                    peer: peer.ok_or(Error::Usage)?,
-                    fetch,
-                },
-                "rm" => Operation::Remove {
-                    remote: remote.ok_or_else(|| anyhow!("a remote name must be specified"))?,
+                "add" => {
+                    let (name, peer) = match values.as_slice() {
+                        [peer] => (None, peer),
+                        [name, peer] => (Some(name.clone()), peer),
+                        _ => return Err(Error::Usage.into()),
+                    };
+                    Operation::Add {
+                        name,
+                        peer: peer.parse().context("invalid value specified for peer")?,
+                        fetch,
+                    }
+                }
+                "rm" => match values.as_slice() {
+                    [remote] => Operation::Remove {
+                        remote: remote.clone(),
+                    },
+                    [] => anyhow::bail!("a remote name must be specified"),
+                    _ => return Err(Error::Usage.into()),
                 },
-                "ls" => Operation::List,
+                "ls" | "list" if values.is_empty() => Operation::List { format },
+                "ls" | "list" => return Err(Error::Usage.into()),
 
                 unknown => anyhow::bail!("unknown operation '{}'", unknown),
             },
-            None => Operation::List,
+            None => Operation::List { format },
         };
 
         Ok((Options { op }, vec![]))
@@ -143,9 +172,6 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
 
     match options.op {
         Operation::Add { name, peer, fetch } => {
-            let mut remote = project::remote(&urn, &peer, &name)?;
-            remote.save(&repo)?;
-
             tracking::track(
                 &storage,
                 &urn,
@@ -164,7 +190,27 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                 let rt = tokio::runtime::Runtime::new()?;
                 let seeds = sync::seeds(&profile)?;
 
-                term::sync::sync(urn, seeds, sync::Mode::Fetch, &profile, signer.clone(), &rt)?;
+                term::sync::sync(
+                    urn.clone(),
+                    seeds,
+                    sync::Mode::Fetch,
+                    &profile,
+                    signer.clone(),
+                    &rt,
+                )?;
+            }
+            // Name the remote after the peer's handle, which is known once it's fetched.
+            let name = match name {
+                Some(name) => name,
+                None => match project::person(&storage, urn.clone(), &peer)? {
+                    Some(person) => person.subject().name.to_string(),
+                    None => peer.default_encoding(),
+                },
+            };
+            let mut remote = project::remote(&urn, &peer, &name)?;
+            remote.save(&repo)?;
+
+            if fetch {
                 git::fetch_remote(&mut remote, &repo, signer, &profile)?;
             }
             term::success!(
@@ -185,18 +231,33 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                 anyhow::bail!("remote '{}' not found", remote)
             }
         },
-        Operation::List => {
+        Operation::List { format } => {
             let mut table = term::Table::default();
             let proj = project::get(&storage, &urn)?
                 .ok_or_else(|| anyhow!("project {} not found on local device", urn))?;
             let mut peers = HashSet::new();
 
-            for (_, peer) in git::remotes(&repo)? {
+            for (remote, peer) in git::remotes(&repo)? {
                 if !peers.insert(peer) {
                     // Don't show duplicate peers.
                     continue;
                 }
 
+                if let Some(template) = &format {
+                    let person = project::person(&storage, urn.clone(), &peer)?;
+                    println!(
+                        "{}",
+                        template.render(|field| match field {
+                            "peer" => Some(peer.to_string()),
+                            "name" => person.as_ref().map(|p| p.subject().name.to_string()),
+                            "remote" => Some(remote.clone()),
+                            "delegate" => Some(proj.remotes.contains(&peer).to_string()),
+                            _ => None,
+                        })
+                    );
+                    continue;
+                }
+
                 let delegate = if proj.remotes.contains(&peer) {
                     term::format::badge_primary("delegate")
                 } else {
@@ -213,7 +274,9 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                     table.push([String::new(), term::format::tertiary(peer), delegate]);
                 }
             }
-            table.render();
+            if format.is_none() {
+                table.render();
+            }
         }
     }
 
@@ -26,14 +26,21 @@ Usage
     To specify a patch to review, use the fully qualified patch id
     or an unambiguous prefix of it.
 
+    The review is signed by your identity and recorded on a revision of the patch,
+    with an optional verdict: `--accept` to sign off on the revision, or `--reject`
+    to request changes. Reviews are shown in `rad patch show`.
+
 Options
 
+        --accept              Accept the revision
+        --reject              Reject the revision
     -r, --revision <number>   Revision number to review, defaults to the latest
         --[no-]sync           Sync review to seed (default: sync)
     -m, --message [<string>]  Provide a comment with the review (default: prompt)
         --no-message          Don't provide a comment with the review
         --help                Print help
 "#,
+    ..Help::DEFAULT
 };
 
 /// Review help message.
@@ -97,10 +104,13 @@ impl Args for Options {
                 Long("verbose") | Short('v') => {
                     verbose = true;
                 }
-                Long("accept") if verdict.is_none() => {
+                Long("accept") | Long("reject") if verdict.is_some() => {
+                    return Err(Error::Usage.into());
+                }
+                Long("accept") => {
                     verdict = Some(Verdict::Accept);
                 }
-                Long("reject") if verdict.is_none() => {
+                Long("reject") => {
                     verdict = Some(Verdict::Reject);
                 }
                 Value(val) => {
@@ -57,6 +57,7 @@ Environment variables
     ETH_RPC_URL  Ethereum JSON-RPC URL (overwrite with '--rpc-url')
     ETH_HDPATH   Hardware wallet derivation path (overwrite with '--ledger-hdpath')
 "#,
+    ..Help::DEFAULT
 };
 
 #[derive(Debug, Eq, PartialEq)]
@@ -22,13 +22,16 @@ Usage
 Options
 
     -i        Prompt before removal
+    --wait    Wait for other commands to release the storage
     --help    Print help
 "#,
+    ..Help::DEFAULT
 };
 
 pub struct Options {
     urn: Urn,
     prompt: bool,
+    wait: bool,
 }
 
 impl Args for Options {
@@ -38,12 +41,16 @@ impl Args for Options {
         let mut parser = lexopt::Parser::from_args(args);
         let mut urn: Option<Urn> = None;
         let mut prompt = false;
+        let mut wait = false;
 
         while let Some(arg) = parser.next()? {
             match arg {
                 Short('i') => {
                     prompt = true;
                 }
+                Long("wait") => {
+                    wait = true;
+                }
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
@@ -63,6 +70,7 @@ impl Args for Options {
                     anyhow!("a URN to remove must be provided; see `rad rm --help`")
                 })?,
                 prompt,
+                wait,
             },
             vec![],
         ))
@@ -71,6 +79,7 @@ impl Args for Options {
 
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profile = ctx.profile()?;
+    let _lock = term::lock_storage(&profile, options.wait)?;
     let storage = profile::read_only(&profile)?;
 
     if project::get(&storage, &options.urn)?.is_none() {
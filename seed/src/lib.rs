@@ -0,0 +1,587 @@
+use std::ffi::OsString;
+use std::net::ToSocketAddrs as _;
+use std::str::FromStr;
+use std::time;
+
+use anyhow::{anyhow, Context as _};
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::seed::credentials::{self, Credentials};
+use radicle_common::seed::discovery;
+use radicle_common::sync::Seed;
+use radicle_common::{config, error, fmt, project, seed, Url, Urn};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "seed",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad seed [list]
+    rad seed add <name> <address | domain>
+    rad seed rm <name>
+    rad seed default <name> [--project]
+    rad seed default <name>... --project
+    rad seed login <name | address | domain> [--username <name>]
+    rad seed logout <name | address | domain>
+    rad seed ping <name | address | domain> [<urn>]
+
+    Seeds are added to the user configuration file under a name, and can be given
+    by name wherever a seed address is expected, eg. `rad sync --seed <name>`.
+
+    Instead of an address, the domain name of a seed may be given, eg.
+    `pine.radicle.garden`. The seed's peer ID and address are then discovered from
+    `https://<domain>/.well-known/radicle`, the DNS TXT record of `_radicle.<domain>`,
+    or the seed's API.
+
+    When no seed is specified, `rad sync` and `rad clone` sync with the default
+    seed, if one is set. Otherwise, the seeds of the working copy's `Radicle.toml`
+    are used, then all named seeds, then the seeds of the profile.
+
+    With `--project`, the default seeds are only set for the working copy, in its
+    git configuration. A project may have several default seeds, which are all
+    synced with by `rad sync` and `rad pull` in the working copy, unless a seed is
+    given with `--seed`.
+
+    Seeds that require authentication, eg. to push over HTTP, are logged in to with
+    `rad seed login`, which prompts for a token and stores it for the seed's host,
+    in a file that only you can read. The token is then used by git whenever it
+    connects to the seed. Credentials can also be provided by git's own credential
+    helpers, eg. an OS keychain.
+
+    `rad seed ping` checks that a seed is reachable, showing the round-trip time
+    to its API, its version and capabilities, and whether its peer ID matches the
+    seed's address. If a project is given, or in a working copy, it also checks
+    whether the seed hosts the project. This helps tell connectivity problems
+    apart from other problems with `rad sync`.
+
+Options
+
+    --project             Set the default seeds of the current project only
+    --username <name>     Username to log in with (default: rad)
+    --help                Print help
+"#,
+    ..Help::DEFAULT
+};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Operation {
+    List,
+    Add {
+        name: String,
+        address: String,
+    },
+    Remove {
+        name: String,
+    },
+    Default {
+        names: Vec<String>,
+        project: bool,
+    },
+    Login {
+        seed: String,
+        username: Option<String>,
+    },
+    Logout {
+        seed: String,
+    },
+    Ping {
+        seed: String,
+        urn: Option<Urn>,
+    },
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut values: Vec<String> = Vec::new();
+        let mut project = false;
+        let mut username = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Long("project") => {
+                    project = true;
+                }
+                Long("username") => {
+                    username = Some(parser.value()?.to_string_lossy().to_string());
+                }
+                Value(val) => {
+                    values.push(val.to_string_lossy().to_string());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let op = match values.as_slice() {
+            [] => Operation::List,
+            [op] if op == "list" => Operation::List,
+            [op, name, addr] if op == "add" => {
+                if Seed::from_str(addr).is_err() && !discovery::is_domain(addr) {
+                    return Err(Error::WithHint {
+                        err: anyhow!("invalid seed address specified: '{}'", addr),
+                        hint: "hint: valid seed addresses have the format <peer-id>@<addr>, eg. hyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa@pine.radicle.garden:8776, or are domain names of seeds",
+                    }
+                    .into());
+                }
+                Operation::Add {
+                    name: name.clone(),
+                    address: addr.clone(),
+                }
+            }
+            [op, name] if op == "rm" => Operation::Remove { name: name.clone() },
+            [op, names @ ..] if op == "default" && (names.len() == 1 || project) => {
+                Operation::Default {
+                    names: names.to_vec(),
+                    project,
+                }
+            }
+            [op, seed] if op == "login" => Operation::Login {
+                seed: seed.clone(),
+                username: username.clone(),
+            },
+            [op, seed] if op == "logout" => Operation::Logout { seed: seed.clone() },
+            [op, seed] if op == "ping" => Operation::Ping {
+                seed: seed.clone(),
+                urn: None,
+            },
+            [op, seed, urn] if op == "ping" => Operation::Ping {
+                seed: seed.clone(),
+                urn: Some(Urn::from_str(urn).map_err(|_| anyhow!("invalid URN '{}'", urn))?),
+            },
+            [op, ..]
+                if ["list", "add", "rm", "default", "login", "logout", "ping"]
+                    .contains(&op.as_str()) =>
+            {
+                return Err(Error::Usage.into());
+            }
+            [op, ..] => anyhow::bail!("unknown operation '{}'", op),
+        };
+        if project && !matches!(op, Operation::Default { .. }) {
+            anyhow::bail!("`--project` can only be used with `rad seed default`");
+        }
+        if username.is_some() && !matches!(op, Operation::Login { .. }) {
+            anyhow::bail!("`--username` can only be used with `rad seed login`");
+        }
+
+        Ok((Options { op }, vec![]))
+    }
+}
+
+pub fn run(options: Options, _ctx: impl term::Context) -> anyhow::Result<()> {
+    match options.op {
+        Operation::List => {
+            let seeds = config::seeds()?;
+            let project = seed::get_project_seeds().unwrap_or_default();
+            let default = default()?;
+
+            if seeds.is_empty() {
+                term::info!(
+                    "No seeds added. Add one with `rad seed add <name> <address>`, eg. {}",
+                    term::format::secondary(
+                        "rad seed add pine hyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa@pine.radicle.garden:8776"
+                    )
+                );
+            }
+            let mut table = term::Table::default();
+
+            for (name, seed) in &seeds {
+                let (marker, source) = match &default {
+                    _ if project.iter().any(|s| is_same(s, seed)) => (
+                        term::format::positive("*"),
+                        term::format::dim(config::Source::Git),
+                    ),
+                    Some((s, source)) if project.is_empty() && is_same(s, seed) => {
+                        (term::format::positive("*"), term::format::dim(source))
+                    }
+                    _ => (String::from(" "), String::new()),
+                };
+                table.push([marker, term::format::highlight(name), address(seed), source]);
+            }
+            table.render();
+        }
+        Operation::Add {
+            name,
+            address: addr,
+        } => {
+            if name.is_empty() {
+                anyhow::bail!("seed name must not be empty");
+            }
+            let seed = match Seed::from_str(&addr) {
+                Ok(seed) => seed,
+                Err(_) => {
+                    let spinner = term::spinner(format!(
+                        "Discovering seed at {}...",
+                        term::format::highlight(&addr)
+                    ));
+                    match discovery::discover(&addr) {
+                        Ok(discovered) => {
+                            spinner.finish();
+                            discovered.seed(&name)
+                        }
+                        Err(err) => return Err(spinner.error(err)),
+                    }
+                }
+            };
+            let path = config::add_seed(&name, &seed)?;
+
+            term::success!(
+                "Added seed {} ({}) to {}",
+                term::format::highlight(&name),
+                term::format::tertiary(address(&seed)),
+                path.display()
+            );
+        }
+        Operation::Remove { name } => {
+            let seed = config::remove_seed(&name)?.ok_or_else(|| not_found(&name))?;
+
+            term::success!("Removed seed {}", term::format::highlight(&name));
+
+            // Don't leave a default seed behind that can no longer be named.
+            if let Some((default, config::Source::User(_))) = default()? {
+                if is_same(&default, &seed) {
+                    config::unset("seed")?;
+                    term::success!("Unset default seed");
+                }
+            }
+        }
+        Operation::Default { names, project } => {
+            let mut named = config::seeds()?;
+            let seeds = names
+                .iter()
+                .map(|name| named.remove(name).ok_or_else(|| not_found(name)))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            if project {
+                project::cwd().context("`--project` must be used inside a project")?;
+                seed::set_project_seeds(&seeds)?;
+
+                term::success!(
+                    "Set default seed(s) of this project to {}",
+                    names
+                        .iter()
+                        .map(term::format::highlight)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            } else {
+                let path = config::set("seed", &address(&seeds[0]))?;
+
+                term::success!(
+                    "Set default seed to {} in {}",
+                    term::format::highlight(&names[0]),
+                    path.display()
+                );
+                if let Some((_, source)) = default()? {
+                    if matches!(source, config::Source::Env(_) | config::Source::Git) {
+                        term::warning(&format!("The default seed is overridden by {}", source));
+                    }
+                }
+            }
+        }
+        Operation::Login { seed, username } => {
+            let (host, _) = resolve(&seed)?;
+            let username = match username {
+                Some(username) => username,
+                None => {
+                    term::text_input("Username", Some(credentials::DEFAULT_USERNAME.to_owned()))?
+                }
+            };
+            let token = term::secret_input_with_prompt("Token");
+
+            if token.unsecure().is_empty() {
+                anyhow::bail!("token must not be empty");
+            }
+            let path = credentials::set(
+                &host,
+                &Credentials {
+                    username,
+                    token: token.unsecure().to_owned(),
+                },
+            )?;
+
+            term::success!(
+                "Stored credentials for {} in {}",
+                term::format::highlight(&host),
+                path.display()
+            );
+        }
+        Operation::Logout { seed } => {
+            let (host, _) = resolve(&seed)?;
+
+            if !credentials::remove(&host)? {
+                return Err(error::Error::new(
+                    error::Kind::NotFound,
+                    anyhow!("no credentials are stored for {}", host),
+                )
+                .into());
+            }
+            term::success!("Removed credentials for {}", term::format::highlight(&host));
+        }
+        Operation::Ping { seed, urn } => {
+            let urn = urn.or_else(|| project::cwd().ok().map(|(urn, _)| urn));
+
+            ping(&seed, urn.as_ref())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Check a seed's API, peer ID and address, and whether it hosts the given project.
+fn ping(value: &str, urn: Option<&Urn>) -> anyhow::Result<()> {
+    let (host, seed) = resolve(value)?;
+    let url = Url::parse(&format!("https://{}", host))?;
+    let mut failed = false;
+
+    let mut spinner = term::spinner(format!(
+        "Connecting to {}...",
+        term::format::highlight(&host)
+    ));
+    let start = time::Instant::now();
+
+    match seed::get_seed_info(url.clone()) {
+        Ok(info) => {
+            let rtt = start.elapsed();
+            let version = match (&info.service, &info.version) {
+                (Some(service), Some(version)) => format!("{} {}", service, version),
+                (None, Some(version)) => version.clone(),
+                _ => String::from("unknown version"),
+            };
+            spinner.message(format!(
+                "API of {} is reachable in {}ms {}",
+                term::format::highlight(&host),
+                rtt.as_millis(),
+                term::format::dim(format!("({})", version))
+            ));
+            spinner.finish();
+
+            if !info.links.is_empty() {
+                term::indented(&format!(
+                    "{} {}",
+                    term::format::dim("Capabilities:"),
+                    info.links
+                        .iter()
+                        .map(|l| l.rel.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            }
+        }
+        Err(err) => {
+            spinner.failed();
+            term::error(format!("API is unreachable: {}", err));
+
+            return Err(error::Error::new(
+                error::Kind::Network,
+                anyhow!("seed {} is unreachable", host),
+            )
+            .into());
+        }
+    }
+
+    let mut spinner = term::spinner("Checking peer ID...");
+    match seed::get_seed_id(url.clone()) {
+        Ok(id) if seed.as_ref().map_or(false, |s| s.peer != id) => {
+            spinner.failed();
+            term::error(format!(
+                "Peer ID {} doesn't match the seed's address, which has {}",
+                fmt::peer(&id),
+                seed.as_ref()
+                    .map(|s| fmt::peer(&s.peer))
+                    .unwrap_or_default()
+            ));
+            failed = true;
+        }
+        Ok(id) => {
+            spinner.message(format!("Peer ID is {}", term::format::tertiary(id)));
+            spinner.finish();
+        }
+        Err(err) => {
+            spinner.failed();
+            term::error(format!("Failed to get peer ID: {}", err));
+            failed = true;
+        }
+    }
+
+    if let Some(seed) = &seed {
+        // Nb. The peer-to-peer protocol runs over UDP, so the address can only be resolved.
+        match seed.addrs.to_socket_addrs() {
+            Ok(mut addrs) => {
+                if let Some(addr) = addrs.next() {
+                    term::success!(
+                        "Peer-to-peer address {} resolves to {}",
+                        term::format::highlight(&seed.addrs),
+                        addr
+                    );
+                }
+            }
+            Err(err) => {
+                term::error(format!(
+                    "Peer-to-peer address {} doesn't resolve: {}",
+                    seed.addrs, err
+                ));
+                failed = true;
+            }
+        }
+    }
+
+    if let Some(urn) = urn {
+        let mut spinner = term::spinner(format!(
+            "Looking up project {}...",
+            term::format::highlight(urn)
+        ));
+        match seed::has_project(url, urn) {
+            Ok(true) => {
+                spinner.message(format!(
+                    "Seed hosts project {}",
+                    term::format::highlight(urn)
+                ));
+                spinner.finish();
+            }
+            Ok(false) => {
+                spinner.failed();
+                term::error(format!("Seed doesn't host project {}", urn));
+                failed = true;
+            }
+            Err(err) => {
+                spinner.failed();
+                term::error(format!("Failed to look up project: {}", err));
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        anyhow::bail!("seed {} failed some checks", host);
+    }
+    Ok(())
+}
+
+/// Resolve a seed given by name, address or domain, to its host, which is what
+/// credentials are stored for, and its address, if known.
+fn resolve(value: &str) -> anyhow::Result<(String, Option<Seed<String>>)> {
+    if let Some(seed) = config::seeds()?.remove(value) {
+        return Ok((seed_host(&seed)?, Some(seed)));
+    }
+    if let Ok(seed) = Seed::from_str(value) {
+        return Ok((seed_host(&seed)?, Some(seed)));
+    }
+    if discovery::is_domain(value) {
+        return Ok((value.to_owned(), None));
+    }
+    Err(Error::WithHint {
+        err: anyhow!("invalid seed specified: '{}'", value),
+        hint: "hint: seeds are given by name, as listed by `rad seed list`, by address, or by domain name",
+    }
+    .into())
+}
+
+fn seed_host(seed: &Seed<String>) -> anyhow::Result<String> {
+    seed::git_url(seed)?
+        .host_str()
+        .map(|h| h.to_owned())
+        .ok_or_else(|| anyhow!("seed address '{}' has no host", seed.addrs))
+}
+
+/// Get the default seed, and where it's set.
+fn default() -> anyhow::Result<Option<(Seed<String>, config::Source)>> {
+    let default = config::get("seed")?
+        .and_then(|s| Seed::from_str(&s.value).ok().map(|seed| (seed, s.source)));
+
+    Ok(default)
+}
+
+/// Get the address of a seed, without its label.
+fn address(seed: &Seed<String>) -> String {
+    Seed {
+        label: None,
+        ..seed.clone()
+    }
+    .to_string()
+}
+
+/// Check whether two seeds have the same address.
+fn is_same(a: &Seed<String>, b: &Seed<String>) -> bool {
+    a.peer == b.peer && a.addrs == b.addrs
+}
+
+fn not_found(name: &str) -> anyhow::Error {
+    error::Error::new(
+        error::Kind::NotFound,
+        anyhow!("seed '{}' was not found, see `rad seed list`", name),
+    )
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> anyhow::Result<Operation> {
+        let args = args.iter().map(OsString::from).collect();
+        let (options, _) = Options::from_args(args)?;
+
+        Ok(options.op)
+    }
+
+    #[test]
+    fn test_from_args() {
+        assert_eq!(parse(&[]).unwrap(), Operation::List);
+        assert_eq!(
+            parse(&["default", "pine", "--project"]).unwrap(),
+            Operation::Default {
+                names: vec!["pine".to_owned()],
+                project: true
+            }
+        );
+        assert_eq!(
+            parse(&["default", "--project", "pine", "willow"]).unwrap(),
+            Operation::Default {
+                names: vec!["pine".to_owned(), "willow".to_owned()],
+                project: true
+            }
+        );
+        assert!(parse(&["default", "pine", "willow"]).is_err());
+        assert!(parse(&["default"]).is_err());
+        assert!(matches!(
+            parse(&[
+                "add",
+                "pine",
+                "hyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa@pine.radicle.garden:8776"
+            ])
+            .unwrap(),
+            Operation::Add { name, .. } if name == "pine"
+        ));
+        assert!(parse(&["add", "pine", "pine.radicle.garden"]).is_ok());
+        assert!(parse(&["add", "pine", "pine.radicle.garden:8776"]).is_err());
+        assert!(parse(&["rm", "pine", "--project"]).is_err());
+        assert!(parse(&["rm"]).is_err());
+        assert_eq!(
+            parse(&["login", "pine", "--username", "alice"]).unwrap(),
+            Operation::Login {
+                seed: "pine".to_owned(),
+                username: Some("alice".to_owned())
+            }
+        );
+        assert!(parse(&["logout", "pine", "--username", "alice"]).is_err());
+        assert!(matches!(
+            parse(&["ping", "pine"]).unwrap(),
+            Operation::Ping { urn: None, .. }
+        ));
+        assert!(parse(&["ping", "pine", "not-a-urn"]).is_err());
+        assert!(parse(&["frobnicate"]).is_err());
+    }
+}
@@ -1,9 +1,12 @@
 use std::ffi::OsString;
+use std::str::FromStr;
 
-use anyhow::anyhow;
+use anyhow::{anyhow, Context as _};
+
+use librad::identities::payload::PersonPayload;
 
 use radicle_common::args::{Args, Error, Help};
-use radicle_common::{keys, person, profile};
+use radicle_common::{keys, person, profile, sync, tokio};
 use radicle_terminal as term;
 
 pub const HELP: Help = Help {
@@ -14,6 +17,12 @@ pub const HELP: Help = Help {
 Usage
 
     rad self [<option>...]
+    rad self --edit
+
+    Shows information about your identity and device. With `--edit`, the
+    identity document is opened in your editor, to change eg. your name or
+    add extension fields such as a website. The updated document is signed
+    with your device key, and may be published to your seeds.
 
 Options
 
@@ -21,8 +30,12 @@ Options
     --urn        Show URN
     --peer       Show Peer ID
     --profile    Show Profile ID
+    --ssh-key    Show the device key, eg. for `authorized_keys` files
+    --format     Format of the device key (openssh, pem, fingerprint) (default: openssh)
+    --edit       Edit your identity document
     --help       Show help
 "#,
+    ..Help::DEFAULT
 };
 
 #[derive(Debug)]
@@ -31,12 +44,47 @@ enum Show {
     Urn,
     Peer,
     Profile,
+    SshKey(KeyFormat),
     All,
 }
 
+/// Output format of the device key.
+#[derive(Debug, Clone, Copy)]
+pub enum KeyFormat {
+    /// OpenSSH public key, as in `authorized_keys` files.
+    OpenSsh,
+    /// PEM-encoded public key.
+    Pem,
+    /// SHA-256 key fingerprint.
+    Fingerprint,
+}
+
+impl Default for KeyFormat {
+    fn default() -> Self {
+        Self::OpenSsh
+    }
+}
+
+impl FromStr for KeyFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "openssh" => Ok(Self::OpenSsh),
+            "pem" => Ok(Self::Pem),
+            "fingerprint" => Ok(Self::Fingerprint),
+            _ => Err(anyhow!(
+                "invalid key format '{}', expected one of: openssh, pem, fingerprint",
+                s
+            )),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Options {
     show: Show,
+    edit: bool,
 }
 
 impl Args for Options {
@@ -45,6 +93,8 @@ impl Args for Options {
 
         let mut parser = lexopt::Parser::from_args(args);
         let mut show: Option<Show> = None;
+        let mut format: Option<KeyFormat> = None;
+        let mut edit = false;
 
         while let Some(arg) = parser.next()? {
             match arg {
@@ -60,6 +110,18 @@ impl Args for Options {
                 Long("profile") if show.is_none() => {
                     show = Some(Show::Profile);
                 }
+                Long("ssh-key") if show.is_none() => {
+                    show = Some(Show::SshKey(KeyFormat::default()));
+                }
+                Long("format") if format.is_none() => {
+                    let val = parser.value()?;
+                    let val = val.to_string_lossy();
+
+                    format = Some(val.parse()?);
+                }
+                Long("edit") => {
+                    edit = true;
+                }
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
@@ -67,17 +129,25 @@ impl Args for Options {
             }
         }
 
-        Ok((
-            Options {
-                show: show.unwrap_or(Show::All),
-            },
-            vec![],
-        ))
+        if edit && (show.is_some() || format.is_some()) {
+            anyhow::bail!("`--edit` can't be combined with other options");
+        }
+        let show = match (show, format) {
+            (Some(Show::SshKey(_)), Some(format)) => Show::SshKey(format),
+            (_, Some(_)) => anyhow::bail!("`--format` can only be used with `--ssh-key`"),
+            (show, None) => show.unwrap_or(Show::All),
+        };
+
+        Ok((Options { show, edit }, vec![]))
     }
 }
 
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
     let profile = ctx.profile()?;
+
+    if options.edit {
+        return edit(&profile);
+    }
     let storage = profile::read_only(&profile)?;
 
     match options.show {
@@ -102,6 +172,15 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
                     .ok_or_else(|| anyhow!("no user found"))?,
             );
         }
+        Show::SshKey(format) => {
+            let peer_id = storage.peer_id();
+            let key = match format {
+                KeyFormat::OpenSsh => keys::to_ssh_key(peer_id)?,
+                KeyFormat::Pem => keys::to_pem(peer_id),
+                KeyFormat::Fingerprint => keys::to_ssh_fingerprint(peer_id)?,
+            };
+            term::print(key);
+        }
         Show::All => all(&profile)?,
     }
 
@@ -152,3 +231,47 @@ fn all(profile: &profile::Profile) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+fn edit(profile: &profile::Profile) -> anyhow::Result<()> {
+    let signer = term::signer(profile)?;
+    let storage = keys::storage(profile, signer.clone())?;
+    let id = person::local(&storage)?;
+    let urn = id.urn();
+
+    let current = serde_json::to_value(id.payload())?;
+    let template = format!(
+        "# Edit the identity document of {}.\n\
+         # Lines starting with '#' are ignored.\n{}",
+        urn,
+        serde_json::to_string_pretty(&current)?
+    );
+    let input = term::editor_input(&template)?;
+    let payload: PersonPayload =
+        serde_json::from_str(&input).context("invalid identity document")?;
+
+    let name = payload.subject.name.to_string();
+    if let Err(err) = person::validate_name(&name) {
+        match person::suggest_name(&name) {
+            Some(suggestion) => {
+                anyhow::bail!("invalid name '{}': {}, try '{}'", name, err, suggestion)
+            }
+            None => anyhow::bail!("invalid name '{}': {}", name, err),
+        }
+    }
+    if serde_json::to_value(&payload)? == current {
+        term::info!("No changes to identity document");
+        return Ok(());
+    }
+
+    let person = person::set_payload(payload, &storage)?;
+    term::success!("Local identity {} updated", term::format::highlight(&urn));
+    term::blob(serde_json::to_string(&person.payload())?);
+
+    if term::confirm("Publish the updated identity to your seeds?") {
+        let seeds = sync::seeds(profile)?;
+        let rt = tokio::runtime::Runtime::new()?;
+
+        term::sync::sync(urn, seeds, sync::Mode::Push, profile, signer, &rt)?;
+    }
+    Ok(())
+}
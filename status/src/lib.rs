@@ -0,0 +1,168 @@
+use std::ffi::OsString;
+
+use anyhow::{anyhow, Context as _};
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::cobs::Timestamp;
+use radicle_common::{cobs, git, person, profile, project, sync};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "status",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad status [<option>...]
+
+    Shows an overview of the project in the current working copy: the profile
+    and peer it is published as, how local branches compare to the published
+    ones, when the project was last synced with each seed, and the number of
+    open patches.
+
+Options
+
+    --help    Print help
+"#,
+    ..Help::DEFAULT
+};
+
+#[derive(Default, Debug)]
+pub struct Options {}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+
+        if let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+        Ok((Options {}, vec![]))
+    }
+}
+
+pub fn run(_options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let (urn, repo) =
+        project::cwd().context("this command must be run from within a project working copy")?;
+    let storage = profile::read_storage(&profile)?;
+    let project = project::get(&storage, &urn)?
+        .ok_or_else(|| anyhow!("project {} was not found in local storage", urn))?;
+
+    // Project and identity.
+    let mut table = term::Table::default();
+    let name = person::local(&storage)
+        .map(|p| p.subject().name.to_string())
+        .unwrap_or_default();
+    let pinned = if profile::pinned()?.as_ref() == Some(profile.id()) {
+        term::format::dim("(pinned)")
+    } else {
+        String::new()
+    };
+
+    table.push([
+        String::from("Project"),
+        format!(
+            "{} {}",
+            term::format::bold(&project.name),
+            term::format::tertiary(&urn)
+        ),
+    ]);
+    table.push([
+        String::from("Profile"),
+        format!("{} {}", term::format::highlight(profile.id()), pinned),
+    ]);
+    table.push([
+        String::from("Peer"),
+        format!("{} {}", term::format::tertiary(storage.peer_id()), name),
+    ]);
+    table.render();
+    term::blank();
+
+    // Local branches compared to the published ones.
+    term::info!("{}", term::format::bold("Branches"));
+    let mut table = term::Table::default();
+    for branch in repo.branches(Some(git::BranchType::Local))? {
+        let (branch, _) = branch?;
+        let name = match branch.name()? {
+            Some(name) => name.to_owned(),
+            None => continue,
+        };
+        let local = match branch.get().target() {
+            Some(oid) => oid,
+            None => continue,
+        };
+        let status = match repo
+            .find_reference(&format!("refs/remotes/rad/{}", name))
+            .ok()
+            .and_then(|r| r.target())
+        {
+            Some(published) if published == local => term::format::positive("up to date"),
+            Some(published) => {
+                let (ahead, behind) = repo.graph_ahead_behind(local, published)?;
+                let mut status = Vec::new();
+
+                if ahead > 0 {
+                    status.push(term::format::yellow(format!("{} ahead", ahead)));
+                }
+                if behind > 0 {
+                    status.push(term::format::yellow(format!("{} behind", behind)));
+                }
+                status.join(", ")
+            }
+            None => term::format::dim("not published"),
+        };
+        let default = if name == project.default_branch.to_string() {
+            term::format::badge_primary("default")
+        } else {
+            String::new()
+        };
+        table.push([term::format::highlight(&name), status, default]);
+    }
+    table.render_tree();
+    term::blank();
+
+    // Last sync with each seed.
+    term::info!("{}", term::format::bold("Seeds"));
+    let synced = sync::last_synced(&profile, &urn)?;
+    match sync::seeds(&profile) {
+        Ok(seeds) => {
+            let mut table = term::Table::default();
+            for seed in &seeds {
+                let last = match synced.get(&seed.peer) {
+                    Some(time) => {
+                        format!("synced {}", term::format::timestamp(&Timestamp::new(*time)))
+                    }
+                    None => String::from("never synced"),
+                };
+                table.push([
+                    term::format::highlight(&seed.addrs),
+                    term::format::tertiary(seed.peer),
+                    term::format::dim(last),
+                ]);
+            }
+            table.render_tree();
+        }
+        Err(_) => term::indented(&term::format::dim("No seeds configured")),
+    }
+    term::blank();
+
+    // Open patches.
+    let cobs = cobs::store(&profile, &storage)?;
+    let open = cobs.patches().proposed(&urn)?.count();
+    term::info!(
+        "{} {}",
+        term::format::bold("Patches"),
+        term::format::dim(format!("{} open", open))
+    );
+
+    Ok(())
+}
@@ -1,24 +1,36 @@
 #![allow(clippy::or_fun_call)]
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryInto;
 use std::ffi::OsString;
 use std::iter;
 use std::str::FromStr;
+use std::{thread, time};
 
 use librad::git::Storage;
 use librad::git::Urn;
 use librad::profile::Profile;
+use librad::PeerId;
 
 use radicle_common::args;
 use radicle_common::args::{Args, Error, Help};
+use radicle_common::hooks;
 use radicle_common::nonempty::NonEmpty;
 use radicle_common::sync::Mode;
-use radicle_common::{identity, keys, person, project, sync, tokio};
+use radicle_common::{
+    env, fmt, git, identity, keys, lock, offline, person, profile, project, seed, sync, tokio,
+};
 use radicle_terminal as term;
 
 use anyhow::anyhow;
 use url::Url;
 
-pub const GATEWAY_HOST: &str = "app.radicle.network";
+pub use term::format::GATEWAY_HOST;
+
+/// How often refs are checked for changes, when watching.
+pub const WATCH_INTERVAL: time::Duration = time::Duration::from_secs(2);
+/// How long refs must stay the same before changes are synced, when watching.
+pub const WATCH_DEBOUNCE: time::Duration = time::Duration::from_secs(5);
+
 pub const HELP: Help = Help {
     name: "sync",
     description: env!("CARGO_PKG_DESCRIPTION"),
@@ -28,16 +40,56 @@ Usage
 
     rad sync [<urn> | <url>] [--seed <address>]... [<options>...]
     rad sync --self [--seed <address>]...
+    rad sync --status [<urn>] [--seed <address>]...
+    rad sync --watch [<urn> | --all] [--seed <address>]...
+    rad sync [<urn>] [--branch <name>]... [--peer <id>]... [--identity-only]
 
     If a <urn> is specified, seeds may be given via the `--seed` option.
     If a <url> is specified, the seed is implied.
-    If neither is specified, the URN and seed of the current project is used.
+    If neither is specified, the URN and seeds of the current project are used.
+    A project's seeds are set with `rad seed default --project <name>...`.
     If the project has no configured seed, the active profile's default seed list is used.
 
+    All seeds are synced with at the same time, with one line of progress per seed,
+    followed by a summary of the seeds that were synced, failed or timed out.
+
+    Seeds that can't be reached, or don't respond in time, are retried with an
+    increasing delay, as configured by the `sync.retries` and `sync.backoff` settings.
+    Retried fetches don't transfer again what was already fetched. Use `--verbose`
+    to see how many times each seed was retried.
+
+    With `--status`, nothing is synced. Instead, the branches of every peer on the
+    seeds are listed and compared with local storage, showing which branches are
+    ahead, behind or missing. Only refs are listed, no objects are transferred.
+
+    With `--watch`, the project, or with `--all` every local project, is watched
+    for changes to your refs, eg. after `git push rad`, which are then pushed to
+    the seeds. Changes are synced once refs have stopped changing for a few
+    seconds. Watching continues until interrupted, eg. with Ctrl-C.
+
+    With `--branch`, `--peer` or `--identity-only`, only the selected refs are
+    fetched from the seeds' git servers, which transfers less for large projects.
+    Identity refs, ie. `rad/*`, are always fetched, so that the selected branches
+    can be verified. Your own refs are not fetched nor pushed: to publish them, use
+    `rad push`, eg. `rad push --identity-only` to publish an identity update only.
+
+    Fetched refs are verified against the refs each peer signed, so that refs
+    changed by a seed are noticed. When refs don't match, the command fails and
+    shows which peer and ref failed. Refs fetched with `--branch`, `--peer` or
+    `--identity-only` are then not updated. Use `--no-verify` to only warn.
+
 Options
 
     --seed <address>    Sync to the given seed (may be specified multiple times)
     --self              Sync your local identity only
+    --status            Compare local branches with the seeds, without syncing
+    --watch             Keep running, and sync whenever your refs change
+    --all               Watch all local projects (with `--watch`)
+    --branch <name>     Only fetch the given branch (may be specified multiple times)
+    --peer <id>         Only fetch the refs of the given peer (may be specified multiple times)
+    --identity-only     Only fetch identity refs, and no branches
+    --no-verify         Only warn when fetched refs don't match their signatures
+    --wait              Wait for other commands to release the storage
     --help              Print help
 
 Seed addresses
@@ -47,7 +99,21 @@ Seed addresses
     The `<port>` component can often be omitted, in which case the default port will be used.
 
     Example: hyb5to4rshftx4apgmu9s6wnsp4ddmp1mz6ijh4qqey7fb8wrpawxa@pine.radicle.garden:8776
+
+    The name of a seed added with `rad seed add` may be given instead of an address.
 "#,
+    examples: &[
+        ("Sync the current project with its seeds", "rad sync"),
+        (
+            "Compare the branches on the seeds with local storage",
+            "rad sync --status",
+        ),
+    ],
+    environment: &[(
+        env::RAD_SEED,
+        "The seed to sync with, instead of the configured seeds.",
+    )],
+    ..Help::DEFAULT
 };
 
 #[derive(Default, Debug)]
@@ -57,6 +123,12 @@ pub struct Options {
     pub mode: Mode,
     pub verbose: bool,
     pub sync_self: bool,
+    pub status: bool,
+    pub watch: bool,
+    pub all: bool,
+    pub wait: bool,
+    pub filter: sync::Filter,
+    pub no_verify: bool,
 }
 
 impl Args for Options {
@@ -67,6 +139,12 @@ impl Args for Options {
         let mut verbose = false;
         let mut origin = None;
         let mut sync_self = false;
+        let mut status = false;
+        let mut watch = false;
+        let mut all = false;
+        let mut wait = false;
+        let mut filter = sync::Filter::default();
+        let mut no_verify = false;
         let mut unparsed = Vec::new();
         let mut seeds = Vec::new();
 
@@ -81,14 +159,36 @@ impl Args for Options {
                 Long("self") => {
                     sync_self = true;
                 }
+                Long("status") => {
+                    status = true;
+                }
+                Long("watch") => {
+                    watch = true;
+                }
+                Long("all") => {
+                    all = true;
+                }
+                Long("wait") => {
+                    wait = true;
+                }
+                Long("branch") => {
+                    let name = parser.value()?.to_string_lossy().to_string();
+
+                    filter.branches.push(name);
+                }
+                Long("peer") => {
+                    let peer = args::parse_value("peer", parser.value()?)?;
+
+                    filter.peers.push(peer);
+                }
+                Long("identity-only") => {
+                    filter.identity_only = true;
+                }
+                Long("no-verify") => {
+                    no_verify = true;
+                }
                 Long("seed") => {
-                    let value = parser.value()?;
-                    let value = value.to_string_lossy();
-                    let value = value.as_ref();
-                    let addr = sync::Seed::from_str(value).map_err(|_| Error::WithHint {
-                        err: anyhow!("invalid seed address specified: '{}'", value),
-                        hint: "hint: valid seed addresses have the format <peer-id>@<host>:<port>, see `rad sync --help` for more information",
-                    })?;
+                    let addr = seed::parse_value(&mut parser)?;
 
                     seeds.push(addr);
                 }
@@ -121,13 +221,40 @@ impl Args for Options {
             );
         }
 
+        if status && sync_self {
+            anyhow::bail!("`--status` cannot be used with `--self`");
+        }
+        if watch && (status || sync_self) {
+            anyhow::bail!("`--watch` cannot be used with `--status` or `--self`");
+        }
+        if all && !watch {
+            anyhow::bail!("`--all` can only be used with `--watch`");
+        }
+        if all && origin.is_some() {
+            anyhow::bail!("`--all` cannot be used when a project is specified");
+        }
+        if filter.identity_only && !filter.branches.is_empty() {
+            anyhow::bail!("`--identity-only` cannot be used with `--branch`");
+        }
+        if !filter.is_empty() && (status || watch || sync_self) {
+            anyhow::bail!(
+                "`--branch`, `--peer` and `--identity-only` cannot be used with `--status`, `--watch` or `--self`"
+            );
+        }
+
         Ok((
             Options {
                 origin,
                 seeds,
                 mode: Mode::default(),
                 sync_self,
+                status,
+                watch,
+                all,
                 verbose,
+                wait,
+                filter,
+                no_verify,
             },
             unparsed,
         ))
@@ -135,16 +262,9 @@ impl Args for Options {
 }
 
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
-    let profile = ctx.profile()?;
-    let signer = term::signer(&profile)?;
-    let storage = keys::storage(&profile, signer)?;
-    let rt = tokio::runtime::Runtime::new()?;
-    let urn = if let Some(origin) = &options.origin {
-        origin.urn.clone()
-    } else {
-        project::cwd().map(|(urn, _)| urn)?
-    };
+    offline::check("sync with seeds")?;
 
+    let profile = ctx.profile()?;
     let seeds = if let Some(seed) = options.origin.as_ref().and_then(|o| o.seed.clone()) {
         NonEmpty::new(seed)
     } else if let Ok(seeds) = options.seeds.clone().try_into() {
@@ -153,13 +273,316 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
         sync::seeds(&profile)?
     };
 
+    if options.watch && options.all {
+        let storage = profile::read_only(&profile)?;
+        let urns = project::list(&storage)?
+            .into_iter()
+            .map(|(urn, _, _)| urn)
+            .collect();
+
+        return watch(urns, &profile, seeds);
+    }
+    let urn = if let Some(origin) = &options.origin {
+        origin.urn.clone()
+    } else {
+        project::cwd().map(|(urn, _)| urn)?
+    };
+
+    if options.watch {
+        return watch(vec![urn], &profile, seeds);
+    }
+
+    if options.status {
+        return status(urn, &profile, seeds);
+    }
+
+    let lock = term::lock_storage(&profile, options.wait)?;
+
+    if options.verbose && term::verbosity() < term::Verbosity::Verbose {
+        term::set_verbosity(term::Verbosity::Verbose);
+    }
+    if !options.filter.is_empty() {
+        return fetch(urn, &profile, seeds, &options.filter, !options.no_verify);
+    }
+    let signer = term::signer(&profile)?;
+    let storage = keys::storage(&profile, signer)?;
+
+    let rt = tokio::runtime::Runtime::new()?;
+
     if options.sync_self {
         sync_self(&profile, seeds, storage, options, rt)
     } else {
-        sync(urn, &profile, seeds, storage, options, rt)
+        sync(urn, &profile, seeds, storage, options, rt, lock)
     }
 }
 
+/// Watch projects for changes to the local peer's refs, and push them to the seeds.
+/// Changes are only synced once refs haven't changed for [`WATCH_DEBOUNCE`], so that a
+/// series of updates, eg. pushing several branches, is synced once.
+pub fn watch(
+    urns: Vec<Urn>,
+    profile: &Profile,
+    seeds: NonEmpty<sync::Seed<String>>,
+) -> anyhow::Result<()> {
+    let signer = term::signer(profile)?;
+    let storage = profile::read_only(profile)?;
+    let repo = git::Repository::open_bare(storage.path())?;
+    let rt = tokio::runtime::Runtime::new()?;
+    let mut synced = HashMap::new();
+    let mut pending: HashMap<&Urn, (Refs, time::Instant)> = HashMap::new();
+    let mut failed = HashMap::new();
+
+    if urns.is_empty() {
+        anyhow::bail!("no projects to watch");
+    }
+    for urn in &urns {
+        synced.insert(urn, own_refs(&repo, urn)?);
+    }
+    term::info!(
+        "Watching {} project(s) for changes, syncing with {} seed(s). Press Ctrl-C to stop.",
+        term::format::highlight(urns.len()),
+        term::format::highlight(seeds.len())
+    );
+
+    loop {
+        thread::sleep(WATCH_INTERVAL);
+
+        for urn in &urns {
+            let refs = own_refs(&repo, urn)?;
+
+            if synced.get(urn) == Some(&refs) || failed.get(urn) == Some(&refs) {
+                pending.remove(urn);
+                continue;
+            }
+            match pending.get(urn) {
+                Some((last, since)) if last == &refs => {
+                    if since.elapsed() < WATCH_DEBOUNCE {
+                        continue;
+                    }
+                }
+                _ => {
+                    pending.insert(urn, (refs, time::Instant::now()));
+                    continue;
+                }
+            }
+
+            term::blank();
+            term::headline(&format!(
+                "Syncing 🌱 changes to {} with {} seed(s)",
+                term::format::highlight(urn),
+                term::format::dim(seeds.len())
+            ));
+
+            let result = term::lock_storage(profile, true).and_then(|_lock| {
+                term::sync::sync(
+                    urn.clone(),
+                    seeds.clone(),
+                    Mode::Push,
+                    profile,
+                    signer.clone(),
+                    &rt,
+                )
+            });
+            // If no seed was synced with, try again on the next change.
+            match result {
+                Ok(results) if results.iter().any(|r| r.status() == sync::Status::Synced) => {
+                    synced.insert(urn, refs);
+                    failed.remove(urn);
+                }
+                Ok(_) => {
+                    failed.insert(urn, refs);
+                }
+                Err(err) => {
+                    term::error(format!("Sync failed: {}", err));
+                    failed.insert(urn, refs);
+                }
+            }
+            pending.remove(urn);
+        }
+    }
+}
+
+/// The local peer's refs of a project, excluding other peers' refs.
+type Refs = BTreeMap<String, git::Oid>;
+
+/// Get the local peer's refs of a project.
+fn own_refs(repo: &git::Repository, urn: &Urn) -> anyhow::Result<Refs> {
+    let namespace = format!("refs/namespaces/{}/refs/", urn.encode_id());
+    let mut refs = Refs::new();
+
+    for reference in repo.references_glob(&format!("{}*", namespace))? {
+        let reference = reference?;
+        let (name, oid) = match (reference.name(), reference.target()) {
+            (Some(name), Some(oid)) => (&name[namespace.len()..], oid),
+            _ => continue,
+        };
+        if !name.starts_with("remotes/") {
+            refs.insert(name.to_owned(), oid);
+        }
+    }
+    Ok(refs)
+}
+
+/// Fetch the project's refs selected by the filter from each seed's git server.
+pub fn fetch(
+    urn: Urn,
+    profile: &Profile,
+    seeds: NonEmpty<sync::Seed<String>>,
+    filter: &sync::Filter,
+    verify: bool,
+) -> anyhow::Result<()> {
+    let storage = profile::read_only(profile)?;
+    let mut failed = 0;
+    let mut refused = false;
+
+    term::headline(&format!(
+        "Fetching 🌱 selected refs of {} from {} seed(s)",
+        term::format::highlight(&urn),
+        term::format::dim(seeds.len())
+    ));
+
+    for seed in &seeds {
+        let label = seed.label.clone().unwrap_or_else(|| fmt::peer(&seed.peer));
+        let mut spinner = term::spinner(format!(
+            "Fetching from {} {}",
+            label,
+            term::format::dim(&seed.addrs)
+        ));
+        match seed::git_url(seed).and_then(|url| sync::fetch(&storage, &urn, &url, filter, verify))
+        {
+            Ok(fetched) => {
+                spinner.message(format!(
+                    "Fetched {} ref(s) from {} {}",
+                    term::format::highlight(fetched.refs.len()),
+                    label,
+                    term::format::dim(&seed.addrs)
+                ));
+                spinner.finish();
+
+                if term::verbosity() >= term::Verbosity::Verbose {
+                    for r in fetched.refs {
+                        term::indented(&term::format::dim(r));
+                    }
+                }
+                report(&fetched.unverified, false);
+            }
+            Err(err) if err.is::<sync::VerifyError>() => {
+                spinner.failed();
+
+                if let Some(sync::VerifyError(failures)) = err.downcast_ref::<sync::VerifyError>() {
+                    report(failures, true);
+                }
+                term::error(format!("Refs fetched from {} were not updated", label));
+                failed += 1;
+                refused = true;
+            }
+            Err(err) => {
+                spinner.failed();
+                term::error(format!("Failed to fetch: {}", err));
+                failed += 1;
+            }
+        }
+    }
+    if refused {
+        return Err(unverified_error());
+    }
+    if failed == seeds.len() {
+        anyhow::bail!("couldn't fetch from any seed");
+    }
+    Ok(())
+}
+
+/// Report peers whose refs don't match their signed refs, as errors if the refs were
+/// refused, or else as warnings.
+fn report(failures: &BTreeMap<PeerId, sync::Verification>, refused: bool) {
+    for (peer, verification) in failures {
+        let msg = format!(
+            "Refs of peer {} failed verification: {}",
+            fmt::peer(peer),
+            verification
+        );
+        if refused {
+            term::error(msg);
+        } else {
+            term::warning(&msg);
+        }
+    }
+}
+
+fn unverified_error() -> anyhow::Error {
+    Error::WithHint {
+        err: anyhow!("fetched refs don't match their signatures"),
+        hint: "hint: use `--no-verify` to accept refs that don't match their signatures, at your own risk",
+    }
+    .into()
+}
+
+/// Compare the project's branches in local storage with the branches on each seed.
+pub fn status(
+    urn: Urn,
+    profile: &Profile,
+    seeds: NonEmpty<sync::Seed<String>>,
+) -> anyhow::Result<()> {
+    let storage = profile::read_only(profile)?;
+    let proj = project::get(&storage, &urn)?
+        .ok_or_else(|| anyhow!("project {} was not found in local storage", urn))?;
+    let mut failed = 0;
+
+    for seed in &seeds {
+        let label = seed.label.clone().unwrap_or_else(|| fmt::peer(&seed.peer));
+        let spinner = term::spinner(format!(
+            "Listing branches of {} on {} {}",
+            term::format::highlight(&proj.name),
+            label,
+            term::format::dim(&seed.addrs)
+        ));
+        let branches = match seed::git_url(seed).and_then(|url| sync::compare(&storage, &urn, &url))
+        {
+            Ok(branches) => {
+                spinner.finish();
+                branches
+            }
+            Err(err) => {
+                spinner.failed();
+                term::error(format!("Failed to list branches: {}", err));
+                failed += 1;
+                continue;
+            }
+        };
+        let mut table = term::Table::default();
+
+        for b in branches {
+            let peer = if &b.peer == storage.peer_id() {
+                format!("{} {}", fmt::peer(&b.peer), term::format::dim("(you)"))
+            } else {
+                fmt::peer(&b.peer)
+            };
+            let head = b
+                .local
+                .or(b.seed)
+                .map(|oid| term::format::secondary(fmt::oid(&oid)))
+                .unwrap_or_default();
+            let divergence = match b.divergence {
+                sync::Divergence::UpToDate => term::format::positive(b.divergence),
+                sync::Divergence::Diverged { .. } => term::format::negative(b.divergence),
+                _ => term::format::yellow(b.divergence),
+            };
+            table.push([
+                term::format::tertiary(peer),
+                term::format::highlight(b.branch),
+                head,
+                divergence,
+            ]);
+        }
+        table.render();
+        term::blank();
+    }
+    if failed == seeds.len() {
+        anyhow::bail!("couldn't list branches on any seed");
+    }
+    Ok(())
+}
+
 pub fn sync_self(
     profile: &Profile,
     seeds: NonEmpty<sync::Seed<String>>,
@@ -192,6 +615,7 @@ pub fn sync(
     storage: Storage,
     options: Options,
     rt: tokio::runtime::Runtime,
+    lock: lock::StorageLock,
 ) -> anyhow::Result<()> {
     term::headline(&format!(
         "Syncing 🌱 identity {} with {} seed(s)",
@@ -199,9 +623,9 @@ pub fn sync(
         term::format::dim(seeds.len())
     ));
 
-    let storage = storage.read_only();
+    let patch_heads = hooks::before_sync(profile, &storage, &urn, options.mode)?;
     let signer = term::signer(profile)?;
-    let _result = term::sync::sync(
+    let results = term::sync::sync(
         urn.clone(),
         seeds.clone(),
         options.mode,
@@ -211,6 +635,27 @@ pub fn sync(
     )?;
     term::blank();
 
+    let failures = if options.mode.is_fetch() {
+        sync::verify_peers(&storage, &urn)?
+    } else {
+        BTreeMap::new()
+    };
+    report(&failures, !options.no_verify);
+
+    // Nb. Hooks may run `rad` commands that lock the storage themselves. They only run
+    // once the fetched refs were verified, and are not told about unverified patches.
+    drop(lock);
+
+    let context = hooks::PostSync::new(&urn, options.mode, results.iter());
+
+    for err in hooks::after_sync(profile, &storage, &urn, patch_heads, &failures, &context)? {
+        term::warning(&err.to_string());
+    }
+    if !failures.is_empty() && !options.no_verify {
+        return Err(unverified_error());
+    }
+    let storage = storage.read_only();
+
     if options.verbose {
         // TODO: When sync result is usable, output should go here.
         // TODO: Depending on the result, we can show `~` as in partial success, `ok` as in total
@@ -245,7 +690,7 @@ pub fn sync(
                             term::indented(&format!(
                                 "{} {}",
                                 term::format::dim("(web)"),
-                                term::format::highlight(format!(
+                                term::format::url(format!(
                                     "https://{}/seeds/{}/{}",
                                     GATEWAY_HOST, host, urn
                                 ))
@@ -254,7 +699,7 @@ pub fn sync(
                         term::indented(&format!(
                             "{} {}",
                             term::format::dim("(web)"),
-                            term::format::highlight(format!(
+                            term::format::url(format!(
                                 "https://{}/seeds/{}/{}/remotes/{}",
                                 GATEWAY_HOST, host, urn, peer_id
                             ))
@@ -269,7 +714,7 @@ pub fn sync(
                     term::indented(&format!(
                         "{} {}",
                         term::format::dim("(git)"),
-                        term::format::highlight(format!("{}.git", git_url)),
+                        term::format::url(format!("{}.git", git_url)),
                     ));
                     term::blank();
                 }
@@ -0,0 +1,282 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::Path;
+
+use anyhow::anyhow;
+use anyhow::Context as _;
+
+use librad::PeerId;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::sync::Mode;
+use radicle_common::{fmt, git, keys, profile, project};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "tag",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad tag <name> [<commit>] [--message <notes>] [--no-sync]
+    rad tag --list
+
+    Creates an annotated tag for a release, signed with your radicle key, and
+    publishes it. The tag points to the given commit, or `HEAD`. The release notes
+    are taken from `--message`, or entered in your editor.
+
+    With `--list`, the tags of every peer in local storage are listed, and each
+    tag's signature is verified against the project delegates.
+
+Options
+
+    --message, -m <notes>    Release notes for the tag
+    --no-sync                Don't sync the tag after publishing it
+    --list                   List the project's tags and verify their signatures
+    --help                   Print help
+"#,
+    ..Help::DEFAULT
+};
+
+#[derive(Debug)]
+pub enum Operation {
+    Create {
+        name: String,
+        commit: Option<String>,
+        message: Option<String>,
+    },
+    List,
+}
+
+#[derive(Debug)]
+pub struct Options {
+    pub op: Operation,
+    pub sync: bool,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut name: Option<String> = None;
+        let mut commit: Option<String> = None;
+        let mut message: Option<String> = None;
+        let mut list = false;
+        let mut sync = true;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("message") | Short('m') if message.is_none() => {
+                    message = Some(parser.value()?.to_string_lossy().to_string());
+                }
+                Long("list") => {
+                    list = true;
+                }
+                Long("no-sync") => {
+                    sync = false;
+                }
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if name.is_none() => {
+                    name = Some(val.to_string_lossy().to_string());
+                }
+                Value(val) if commit.is_none() => {
+                    commit = Some(val.to_string_lossy().to_string());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        let op = match (list, name) {
+            (true, None) => Operation::List,
+            (true, Some(_)) => anyhow::bail!("`--list` can't be used when creating a tag"),
+            (false, Some(name)) => Operation::Create {
+                name,
+                commit,
+                message,
+            },
+            (false, None) => return Err(Error::Usage.into()),
+        };
+
+        Ok((Options { op, sync }, vec![]))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    match options.op {
+        Operation::Create {
+            name,
+            commit,
+            message,
+        } => create(name, commit, message, options.sync, ctx),
+        Operation::List => list(ctx),
+    }
+}
+
+/// Create a signed tag and publish it.
+fn create(
+    name: String,
+    commit: Option<String>,
+    message: Option<String>,
+    sync: bool,
+    ctx: impl term::Context,
+) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let storage = profile::read_only(&profile)?;
+    let (urn, _) =
+        project::cwd().context("this command must be run in the context of a project")?;
+    let peer_id = *storage.peer_id();
+
+    // Signing is done by `ssh-keygen`, through the agent.
+    let ready = keys::ssh_auth_sock()
+        .ok()
+        .map(|sock| keys::is_ready(&profile, sock))
+        .transpose()?
+        .unwrap_or(false);
+    if !ready {
+        return Err(Error::WithHint {
+            err: anyhow!("your radicle key is not in ssh-agent"),
+            hint: "To add it, run `rad auth`.",
+        }
+        .into());
+    }
+
+    let message = match message {
+        Some(message) => message,
+        None => term::editor_input(&format!(
+            "\n# Release notes for tag `{}` of {}.\n# Lines starting with '#' are ignored.\n",
+            name, urn
+        ))?,
+    };
+    let key = keys::to_ssh_key(&peer_id)?;
+    let signing_key = format!("{}={}", git::CONFIG_SIGNING_KEY, key);
+    let format = format!("{}=ssh", git::CONFIG_GPG_FORMAT);
+    let mut args = vec![
+        "-c",
+        signing_key.as_str(),
+        "-c",
+        format.as_str(),
+        "tag",
+        "--sign",
+        "--message",
+        message.as_str(),
+        name.as_str(),
+    ];
+    if let Some(commit) = &commit {
+        args.push(commit);
+    }
+    git::git(Path::new("."), args).context("failed to create tag")?;
+
+    term::success!(
+        "Created signed tag {} for {}",
+        term::format::highlight(&name),
+        term::format::tertiary(commit.as_deref().unwrap_or("HEAD"))
+    );
+
+    let refspec = format!("refs/tags/{}", name);
+    term::subcommand(&format!("git push rad {}", refspec));
+    git::git(Path::new("."), ["push", "rad", &refspec])?;
+
+    if sync {
+        rad_sync::run(
+            rad_sync::Options {
+                seeds: vec![],
+                verbose: false,
+                mode: Mode::Push,
+                origin: None,
+                sync_self: false,
+                wait: true,
+                ..rad_sync::Options::default()
+            },
+            ctx,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// List the tags of every peer, and verify their signatures.
+fn list(ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let storage = profile::read_only(&profile)?;
+    let (urn, _) =
+        project::cwd().context("this command must be run in the context of a project")?;
+    let project = project::get(&storage, &urn)?
+        .ok_or_else(|| anyhow!("project {} was not found in local storage", urn))?;
+
+    // Delegate keys, and how to show them.
+    let delegates = project::delegate_keys(&storage, &project);
+
+    // Tags by name and target, with the peers that have them.
+    let repo = git::Repository::open_bare(storage.path())?;
+    let namespace = format!("refs/namespaces/{}/refs/", urn.encode_id());
+    let mut tags: BTreeMap<(String, git::Oid), Vec<PeerId>> = BTreeMap::new();
+
+    for reference in repo.references_glob(&format!("{}*", namespace))? {
+        let reference = reference?;
+        let (name, oid) = match (reference.name(), reference.target()) {
+            (Some(name), Some(oid)) => (&name[namespace.len()..], oid),
+            _ => continue,
+        };
+        let (peer, tag) = if let Some(tag) = name.strip_prefix("tags/") {
+            (*storage.peer_id(), tag)
+        } else if let Some((peer, tag)) = name
+            .strip_prefix("remotes/")
+            .and_then(|r| r.split_once("/tags/"))
+        {
+            match peer.parse() {
+                Ok(peer) => (peer, tag),
+                Err(_) => continue,
+            }
+        } else {
+            continue;
+        };
+        tags.entry((tag.to_owned(), oid)).or_default().push(peer);
+    }
+
+    if tags.is_empty() {
+        term::info!(
+            "No tags found for {}",
+            term::format::highlight(&project.name)
+        );
+        return Ok(());
+    }
+
+    let mut table = term::Table::default();
+    for ((name, oid), peers) in tags {
+        let signer = git::verify_tag(storage.path(), &oid.to_string(), delegates.keys())?;
+        let status = match signer {
+            Some(signer) => format!(
+                "{} {}",
+                term::format::positive(term::format::glyph("✓", "+")),
+                delegates
+                    .get(&signer)
+                    .cloned()
+                    .unwrap_or_else(|| fmt::peer(&signer))
+            ),
+            None => format!(
+                "{} {}",
+                term::format::negative(term::format::glyph("✗", "x")),
+                term::format::dim("not signed by a delegate")
+            ),
+        };
+        let target = repo
+            .find_object(oid, None)
+            .and_then(|o| o.peel_to_commit())
+            .map(|c| fmt::oid(&c.id()))
+            .unwrap_or_default();
+
+        table.push([
+            term::format::highlight(name),
+            term::format::secondary(target),
+            status,
+            term::format::dim(format!("{} peer(s)", peers.len())),
+        ]);
+    }
+    table.render();
+
+    Ok(())
+}
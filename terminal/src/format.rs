@@ -1,5 +1,53 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::env;
+use std::str::FromStr;
+use std::time::Duration;
+
+use unicode_segmentation::UnicodeSegmentation as _;
+use unicode_width::UnicodeWidthStr as _;
+
 pub use dialoguer::console::style;
 
+use radicle_common::cobs::{Reaction, Timestamp};
+
+/// Host of the web client that URNs and Peer IDs are linked to.
+pub const GATEWAY_HOST: &str = "app.radicle.network";
+
+/// Format of absolute timestamps, unless configured otherwise.
+pub const DEFAULT_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Age after which relative timestamps are shown as a date instead.
+pub const RELATIVE_TIME_MAX: Duration = Duration::from_secs(60 * 60 * 24 * 30);
+
+/// How timestamps are formatted by [`timestamp`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimeFormat {
+    /// Relative to now, eg. "2 hours ago". Older timestamps are shown as a date.
+    Relative,
+    /// Absolute, with the given `strftime`-style format.
+    Absolute(String),
+}
+
+impl Default for TimeFormat {
+    fn default() -> Self {
+        Self::Relative
+    }
+}
+
+impl FromStr for TimeFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "relative" => Ok(Self::Relative),
+            "absolute" => Ok(Self::Absolute(DEFAULT_TIME_FORMAT.to_owned())),
+            fmt if Timestamp::new(0).format(fmt).is_some() => Ok(Self::Absolute(fmt.to_owned())),
+            _ => Err(anyhow::anyhow!("invalid time format '{}'", s)),
+        }
+    }
+}
+
 pub fn negative<D: std::fmt::Display>(msg: D) -> String {
     style(msg).red().bright().to_string()
 }
@@ -58,3 +106,375 @@ pub fn dim<D: std::fmt::Display>(input: D) -> String {
 pub fn italic<D: std::fmt::Display>(input: D) -> String {
     style(input).italic().dim().to_string()
 }
+
+/// Format text as a clickable hyperlink to the given URL, if the terminal supports
+/// OSC 8 hyperlinks. Otherwise, the text is returned as-is.
+pub fn hyperlink<D: std::fmt::Display>(text: D, url: impl std::fmt::Display) -> String {
+    if hyperlinks() {
+        format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Format a Peer ID, linking to the peer's view of a project on the web client, via
+/// the given seed host.
+pub fn peer_link<D: std::fmt::Display>(peer: D, urn: impl std::fmt::Display, seed: &str) -> String {
+    let url = format!(
+        "https://{}/seeds/{}/{}/remotes/{}",
+        GATEWAY_HOST, seed, urn, peer
+    );
+
+    hyperlink(bold(peer), url)
+}
+
+/// Format a URL, linking to itself.
+pub fn url<D: std::fmt::Display>(url: D) -> String {
+    hyperlink(highlight(&url), &url)
+}
+
+/// Check whether the terminal supports OSC 8 hyperlinks. This can be overridden
+/// by setting `FORCE_HYPERLINK` to `1` or `0`.
+pub fn hyperlinks() -> bool {
+    if let Ok(force) = env::var("FORCE_HYPERLINK") {
+        return force != "0";
+    }
+    // Nb. Colors are disabled when the output isn't a terminal.
+    if !console::colors_enabled() {
+        return false;
+    }
+    if env::var_os("WT_SESSION").is_some()
+        || env::var_os("KITTY_WINDOW_ID").is_some()
+        || env::var_os("KONSOLE_VERSION").is_some()
+        || env::var_os("DOMTERM").is_some()
+    {
+        return true;
+    }
+    if let Ok(program) = env::var("TERM_PROGRAM") {
+        if matches!(
+            program.as_str(),
+            "iTerm.app" | "WezTerm" | "vscode" | "Hyper" | "ghostty"
+        ) {
+            return true;
+        }
+    }
+    if let Ok(version) = env::var("VTE_VERSION") {
+        // VTE-based terminals, eg. GNOME Terminal, support hyperlinks since 0.50.
+        return version.parse::<u32>().map_or(false, |v| v >= 5000);
+    }
+    false
+}
+
+/// Pick between a unicode glyph and its ASCII equivalent, depending on whether
+/// ASCII output is enabled, eg. with `--no-emoji`.
+pub fn glyph(unicode: &'static str, ascii: &'static str) -> &'static str {
+    if crate::io::is_ascii() {
+        ascii
+    } else {
+        unicode
+    }
+}
+
+/// Replace emoji and unicode glyphs in the given text with ASCII equivalents, if ASCII
+/// output is enabled. Emoji without an equivalent are removed. Other text is kept as-is.
+pub fn ascii(text: &str) -> Cow<str> {
+    if !crate::io::is_ascii() || text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+    Cow::Owned(to_ascii(text))
+}
+
+fn to_ascii(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut removed = false;
+
+    for c in text.chars() {
+        let replacement = match c {
+            '✓' | '✔' => "+",
+            '✗' | '✘' => "x",
+            '⋄' => "*",
+            '▲' => "^",
+            '▼' => "v",
+            '⤷' => "->",
+            '⤹' => "<-",
+            '·' => ".",
+            '…' => "...",
+            '—' | '–' | '─' | '━' => "-",
+            '│' | '┃' | '├' => "|",
+            '└' => "`",
+            '┌' | '┐' | '┘' | '┴' | '┬' | '┤' | '┼' | '╭' | '╮' | '╯' | '╰' => {
+                "+"
+            }
+            ' ' if removed && (output.is_empty() || output.ends_with(' ')) => {
+                // Don't leave a double space where an emoji was removed.
+                removed = false;
+                continue;
+            }
+            c if is_emoji(c) => {
+                removed = true;
+                continue;
+            }
+            c => {
+                removed = false;
+                output.push(c);
+                continue;
+            }
+        };
+        removed = false;
+        output.push_str(replacement);
+    }
+    if removed && output.ends_with(' ') {
+        output.pop();
+    }
+    output
+}
+
+/// Check whether a character is an emoji, or part of an emoji sequence.
+fn is_emoji(c: char) -> bool {
+    matches!(c,
+        '\u{1f000}'..='\u{1faff}'
+        | '\u{2600}'..='\u{27bf}'
+        | '\u{200d}'
+        | '\u{fe0f}')
+}
+
+/// Format a timestamp, according to the configured [`TimeFormat`]. Relative timestamps
+/// older than [`RELATIVE_TIME_MAX`], or in the future, are shown as a date.
+pub fn timestamp(time: &Timestamp) -> String {
+    format_timestamp(time, Timestamp::now(), &crate::io::time_format())
+}
+
+fn format_timestamp(time: &Timestamp, now: Timestamp, format: &TimeFormat) -> String {
+    match format {
+        TimeFormat::Relative => match now.as_secs().checked_sub(time.as_secs()) {
+            Some(ago) if ago <= RELATIVE_TIME_MAX.as_secs() => {
+                timeago::Formatter::new().convert(Duration::from_secs(ago))
+            }
+            _ => time.format("%Y-%m-%d").unwrap_or_default(),
+        },
+        TimeFormat::Absolute(fmt) => time.format(fmt).unwrap_or_default(),
+    }
+}
+
+/// Format reactions with their counts, most frequent first, eg. `👍 3  🎉 1`.
+pub fn reactions(reactions: &HashMap<Reaction, usize>) -> String {
+    let mut reactions = reactions.iter().collect::<Vec<_>>();
+    reactions.sort_by(|(a, m), (b, n)| n.cmp(m).then(a.emoji.cmp(&b.emoji)));
+
+    reactions
+        .into_iter()
+        .map(|(r, n)| format!("{} {}", r.emoji, dim(n)))
+        .collect::<Vec<_>>()
+        .join("  ")
+}
+
+/// Format a size in bytes, eg. `12.3 MiB`.
+pub fn bytes(size: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB"];
+
+    if size < 1024 {
+        return format!("{} B", size);
+    }
+    let mut size = size as f64 / 1024.;
+    let mut unit = UNITS[0];
+
+    for u in &UNITS[1..] {
+        if size < 1024. {
+            break;
+        }
+        size /= 1024.;
+        unit = u;
+    }
+    format!("{:.1} {}", size, unit)
+}
+
+/// Get the display width of some text, in terminal columns. Styling is ignored, and
+/// wide characters such as emoji and CJK count for two columns.
+pub fn text_width(text: &str) -> usize {
+    console::AnsiCodeIterator::new(text)
+        .filter(|(_, is_ansi)| !is_ansi)
+        .flat_map(|(s, _)| s.graphemes(true))
+        .map(grapheme_width)
+        .sum()
+}
+
+/// Truncate text to the given display width, ending it with an ellipsis if it was
+/// truncated. Never splits a grapheme cluster, eg. an emoji with modifiers.
+pub fn truncate(text: &str, width: usize) -> String {
+    if text_width(text) <= width {
+        return text.to_owned();
+    }
+    let ellipsis = glyph("…", "...");
+    let max = width.saturating_sub(ellipsis.width());
+    let mut output = String::new();
+    let mut current = 0;
+    let mut styled = false;
+
+    'outer: for (s, is_ansi) in console::AnsiCodeIterator::new(text) {
+        if is_ansi {
+            output.push_str(s);
+            styled = true;
+            continue;
+        }
+        for g in s.graphemes(true) {
+            let w = grapheme_width(g);
+            if current + w > max {
+                break 'outer;
+            }
+            output.push_str(g);
+            current += w;
+        }
+    }
+    output.push_str(ellipsis);
+
+    if styled {
+        output.push_str("\x1b[0m");
+    }
+    output
+}
+
+/// Wrap text to lines of at most the given display width. Lines are broken at
+/// whitespace where possible; words wider than a line are split between grapheme
+/// clusters.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        let mut current = 0;
+
+        for word in paragraph.split_whitespace() {
+            let w = text_width(word);
+
+            if current > 0 && current + 1 + w <= width {
+                line.push(' ');
+                line.push_str(word);
+                current += 1 + w;
+                continue;
+            }
+            if current > 0 {
+                lines.push(std::mem::take(&mut line));
+                current = 0;
+            }
+            if w <= width {
+                line.push_str(word);
+                current = w;
+                continue;
+            }
+            // The word doesn't fit on a line by itself.
+            for g in word.graphemes(true) {
+                let gw = grapheme_width(g);
+
+                if current + gw > width && current > 0 {
+                    lines.push(std::mem::take(&mut line));
+                    current = 0;
+                }
+                line.push_str(g);
+                current += gw;
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Display width of a single grapheme cluster.
+fn grapheme_width(g: &str) -> usize {
+    // Emoji sequences, eg. with skin tone modifiers or joined with ZWJ, as well as
+    // characters with emoji presentation selectors, render as a single wide glyph.
+    if g.chars().count() > 1
+        && g.chars()
+            .any(|c| c == '\u{200d}' || c == '\u{fe0f}' || ('\u{1f3fb}'..='\u{1f3ff}').contains(&c))
+    {
+        return 2;
+    }
+    g.width()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_width() {
+        assert_eq!(text_width("hello"), 5);
+        assert_eq!(text_width("🌱 seed"), 7);
+        assert_eq!(text_width("日本語"), 6);
+        assert_eq!(text_width("👩‍💻"), 2);
+        assert_eq!(text_width("\x1b[31mred\x1b[0m"), 3);
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("hello", 5), "hello");
+        assert_eq!(truncate("hello world", 6), "hello…");
+        assert_eq!(truncate("日本語です", 5), "日本…");
+        assert_eq!(truncate("👩‍💻👩‍💻👩‍💻", 5), "👩‍💻👩‍💻…");
+    }
+
+    #[test]
+    fn test_to_ascii() {
+        assert_eq!(
+            to_ascii("Initializing your 🌱 profile"),
+            "Initializing your profile"
+        );
+        assert_eq!(to_ascii("🌱 Project cloned"), "Project cloned");
+        assert_eq!(to_ascii("Patch 42 created 🌱"), "Patch 42 created");
+        assert_eq!(to_ascii("├── a └── b"), "|-- a `-- b");
+        assert_eq!(to_ascii("✓ merged…"), "+ merged...");
+        assert_eq!(to_ascii("Ünïcödé 日本"), "Ünïcödé 日本");
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        let now = Timestamp::new(1_600_000_000);
+        let ago = |secs| Timestamp::new(now.as_secs() - secs);
+
+        assert_eq!(
+            format_timestamp(&ago(2 * 60 * 60), now, &TimeFormat::Relative),
+            "2 hours ago"
+        );
+        assert_eq!(
+            format_timestamp(&ago(90 * 24 * 60 * 60), now, &TimeFormat::Relative).len(),
+            "2020-06-15".len()
+        );
+        assert_eq!(
+            format_timestamp(&now, now, &TimeFormat::Absolute("%Y".to_owned())),
+            "2020"
+        );
+    }
+
+    #[test]
+    fn test_time_format() {
+        assert_eq!(
+            "relative".parse::<TimeFormat>().unwrap(),
+            TimeFormat::Relative
+        );
+        assert_eq!(
+            "%d.%m.%Y".parse::<TimeFormat>().unwrap(),
+            TimeFormat::Absolute("%d.%m.%Y".to_owned())
+        );
+        assert!("%Q".parse::<TimeFormat>().is_err());
+    }
+
+    #[test]
+    fn test_bytes() {
+        assert_eq!(bytes(0), "0 B");
+        assert_eq!(bytes(1023), "1023 B");
+        assert_eq!(bytes(1024), "1.0 KiB");
+        assert_eq!(bytes(1536), "1.5 KiB");
+        assert_eq!(bytes(5 * 1024 * 1024 * 1024), "5.0 GiB");
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            wrap("the quick brown fox jumps", 10),
+            vec!["the quick", "brown fox", "jumps"]
+        );
+        assert_eq!(wrap("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+        assert_eq!(wrap("日本語 です", 4), vec!["日本", "語", "です"]);
+        assert_eq!(wrap("", 4), Vec::<String>::new());
+    }
+}
@@ -1,15 +1,22 @@
 use std::fmt;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::Mutex;
 
 use librad::crypto::keystore::pinentry::SecUtf8;
 use librad::crypto::BoxedSigner;
 use librad::profile::Profile;
 
-use dialoguer::{console::style, console::Style, theme::ColorfulTheme, Input, Password};
+use dialoguer::{console::style, console::Style, theme::ColorfulTheme, Editor, Input, Password};
+use zeroize::Zeroizing;
 
 use radicle_common::cobs::issue::Issue;
-use radicle_common::cobs::shared::CommentId;
+use radicle_common::cobs::shared::{Comment, CommentId, Replies};
+use radicle_common::error;
+use radicle_common::lock;
+use radicle_common::logger;
 use radicle_common::signer::ToSigner;
+use radicle_common::{config, env, Interactive};
 
 use super::command;
 use super::format;
@@ -22,10 +29,199 @@ pub use super::ethereum;
 
 pub const TAB: &str = "   ";
 
+/// Number of options above which select prompts can be filtered by typing.
+pub const FUZZY_SELECT_THRESHOLD: usize = 8;
+
+/// Environment variable that disables all colors when set to a non-empty value.
+/// See <https://no-color.org>.
+pub const NO_COLOR: &str = "NO_COLOR";
+
+/// Environment variable that replaces emoji and unicode glyphs with ASCII equivalents
+/// when set to a non-empty value.
+pub const RAD_NO_EMOJI: &str = "RAD_NO_EMOJI";
+
+/// Environment variable that enables plain, line-oriented output when set to a
+/// non-empty value.
+pub const RAD_PLAIN: &str = "RAD_PLAIN";
+
+/// Whether emoji and unicode glyphs are replaced with ASCII equivalents.
+static ASCII: AtomicBool = AtomicBool::new(false);
+
+/// Whether output is plain and line-oriented, eg. for screen readers.
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+/// How timestamps are formatted. Defaults to relative timestamps.
+static TIME_FORMAT: Mutex<Option<format::TimeFormat>> = Mutex::new(None);
+
+/// Whether errors are reported as JSON objects.
+static JSON: AtomicBool = AtomicBool::new(false);
+
+/// Whether confirmation prompts are answered with "yes" without asking.
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+
+/// Answer all confirmation prompts with "yes", eg. when `--yes` is passed.
+pub fn set_assume_yes(enabled: bool) {
+    ASSUME_YES.store(enabled, Ordering::Relaxed);
+}
+
+/// Check whether confirmation prompts are answered with "yes" without asking.
+pub fn is_assume_yes() -> bool {
+    ASSUME_YES.load(Ordering::Relaxed)
+}
+
+/// Whether commands should prompt for input, ie. unless `--yes` or `--no-confirm` was passed.
+pub fn interactive() -> Interactive {
+    if is_assume_yes() {
+        Interactive::No
+    } else {
+        Interactive::Yes
+    }
+}
+
+/// Report errors as JSON objects instead of human-readable text.
+pub fn set_json(enabled: bool) {
+    JSON.store(enabled, Ordering::Relaxed);
+}
+
+/// Check whether errors are reported as JSON objects.
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+/// Output verbosity.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Copy, Clone)]
+pub enum Verbosity {
+    /// Only errors are output.
+    Quiet,
+    /// Errors, warnings and progress are output.
+    Normal,
+    /// Underlying operations are also logged.
+    Verbose,
+    /// Everything is logged.
+    Debug,
+}
+
+impl Verbosity {
+    /// The log level for this verbosity.
+    pub fn level(&self) -> logger::Level {
+        match self {
+            Self::Quiet => logger::Level::Error,
+            Self::Normal => logger::Level::Warn,
+            Self::Verbose => logger::Level::Info,
+            Self::Debug => logger::Level::Debug,
+        }
+    }
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+
+/// Set the output verbosity.
+pub fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+}
+
+/// Get the output verbosity.
+pub fn verbosity() -> Verbosity {
+    match VERBOSITY.load(Ordering::Relaxed) {
+        0 => Verbosity::Quiet,
+        1 => Verbosity::Normal,
+        2 => Verbosity::Verbose,
+        _ => Verbosity::Debug,
+    }
+}
+
+/// Check whether everything but errors should be suppressed.
+pub fn is_quiet() -> bool {
+    verbosity() == Verbosity::Quiet
+}
+
+/// Enable or disable ANSI styling of all terminal output, including formatted text,
+/// prompts and spinners.
+pub fn set_colors(enabled: bool) {
+    console::set_colors_enabled(enabled);
+    console::set_colors_enabled_stderr(enabled);
+}
+
+/// Disable colors if `NO_COLOR` is set, or the `terminal.color` setting is `false`.
+/// Output that isn't a terminal is never colored.
+pub fn init_colors() {
+    if std::env::var_os(NO_COLOR).map_or(false, |v| !v.is_empty())
+        || config::get_bool("terminal.color") == Some(false)
+    {
+        set_colors(false);
+    }
+}
+
+/// Replace emoji and unicode glyphs in all output with ASCII equivalents.
+pub fn set_ascii(enabled: bool) {
+    ASCII.store(enabled, Ordering::Relaxed);
+}
+
+/// Check whether output is restricted to ASCII.
+pub fn is_ascii() -> bool {
+    ASCII.load(Ordering::Relaxed)
+}
+
+/// Output simple status lines instead of spinners, progress bars and other output that
+/// is rewritten in place. Emoji and box-drawing characters are also replaced.
+pub fn set_plain(enabled: bool) {
+    PLAIN.store(enabled, Ordering::Relaxed);
+
+    if enabled {
+        set_ascii(true);
+    }
+}
+
+/// Check whether output is plain and line-oriented.
+pub fn is_plain() -> bool {
+    PLAIN.load(Ordering::Relaxed)
+}
+
+/// Set how timestamps are formatted.
+pub fn set_time_format(format: format::TimeFormat) {
+    if let Ok(mut current) = TIME_FORMAT.lock() {
+        *current = Some(format);
+    }
+}
+
+/// Get how timestamps are formatted.
+pub fn time_format() -> format::TimeFormat {
+    TIME_FORMAT
+        .lock()
+        .ok()
+        .and_then(|f| f.clone())
+        .unwrap_or_default()
+}
+
+/// Apply output settings. Output is restricted to ASCII if `RAD_NO_EMOJI` is set, and
+/// plain if `RAD_PLAIN` is set. Otherwise, the `terminal.emoji` and `terminal.plain`
+/// settings are used, as well as the `terminal.timestamps` setting.
+pub fn init_output() {
+    let is_set = |var: &str| std::env::var_os(var).map_or(false, |v| !v.is_empty());
+
+    if is_set(RAD_PLAIN) {
+        set_plain(true);
+    }
+    if is_set(RAD_NO_EMOJI) {
+        set_ascii(true);
+    }
+    if config::get_bool("terminal.plain") == Some(true) {
+        set_plain(true);
+    } else if config::get_bool("terminal.emoji") == Some(false) {
+        set_ascii(true);
+    }
+    if let Ok(Some(timestamps)) = config::get("terminal.timestamps") {
+        match timestamps.value.parse() {
+            Ok(format) => set_time_format(format),
+            Err(err) => warning(&format!("Ignoring configuration: {}", err)),
+        }
+    }
+}
+
 #[macro_export]
 macro_rules! info {
     ($($arg:tt)*) => ({
-        println!("{}", format_args!($($arg)*));
+        $crate::io::info_args(format_args!($($arg)*));
     })
 }
 
@@ -43,18 +239,41 @@ macro_rules! tip {
     })
 }
 
-pub fn success_args(args: fmt::Arguments) {
-    println!("{} {}", style("ok").green().reverse(), args);
+pub fn info_args(args: fmt::Arguments) {
+    if is_quiet() {
+        return;
+    }
+    println!("{}", format::ascii(&args.to_string()));
 }
 
-pub fn tip_args(args: fmt::Arguments) {
+pub fn success_args(args: fmt::Arguments) {
+    if is_quiet() {
+        return;
+    }
     println!(
         "{} {}",
-        style("=>").blue(),
-        style(format!("{}", args)).dim()
+        style("ok").green().reverse(),
+        format::ascii(&args.to_string())
     );
 }
 
+pub fn tip_args(args: fmt::Arguments) {
+    if is_quiet() {
+        return;
+    }
+    let tip = format::ascii(&args.to_string()).into_owned();
+    let lines = if is_terminal() {
+        format::wrap(&tip, width().saturating_sub(3))
+    } else {
+        vec![tip]
+    };
+
+    for (i, line) in lines.iter().enumerate() {
+        let prefix = if i == 0 { "=>" } else { "  " };
+        println!("{} {}", style(prefix).blue(), style(line).dim());
+    }
+}
+
 pub use info;
 pub use success;
 pub use tip;
@@ -64,7 +283,21 @@ pub fn width() -> usize {
     rows as usize
 }
 
+/// Check whether standard output is a terminal.
+pub fn is_terminal() -> bool {
+    console::Term::stdout().is_term()
+}
+
 pub fn headline(headline: &str) {
+    if is_quiet() {
+        return;
+    }
+    let headline = format::ascii(headline);
+    let headline = if is_terminal() {
+        format::truncate(&headline, width().saturating_sub(1))
+    } else {
+        headline.into_owned()
+    };
     println!();
     println!("{}", style(headline).bold());
     println!();
@@ -102,7 +335,7 @@ pub fn usage(name: &str, usage: &str) {
 }
 
 pub fn eprintln(prefix: impl fmt::Display, msg: impl fmt::Display) {
-    eprintln!("{} {}", prefix, msg);
+    eprintln!("{} {}", prefix, format::ascii(&msg.to_string()));
 }
 
 pub fn indented(msg: &str) {
@@ -110,24 +343,37 @@ pub fn indented(msg: &str) {
 }
 
 pub fn subcommand(msg: impl fmt::Display) {
+    if is_quiet() {
+        return;
+    }
     println!("{} {}", style("$").dim(), style(msg).dim());
 }
 
 pub fn warning(warning: &str) {
+    if is_quiet() {
+        return;
+    }
     eprintln!(
         "{} {} {}",
         style("**").yellow(),
         style("Warning:").yellow().bold(),
-        style(warning).yellow()
+        style(format::ascii(warning)).yellow()
     );
 }
 
 pub fn error(error: impl fmt::Display) {
-    eprintln!("{} {}", style("==").red(), style(error).red());
+    eprintln!(
+        "{} {}",
+        style("==").red(),
+        style(format::ascii(&error.to_string())).red()
+    );
 }
 
 pub fn fail(header: &str, error: &anyhow::Error) {
-    let err = error.to_string();
+    if is_json() {
+        return error_json(error, error::kind(error));
+    }
+    let err = format::ascii(&error.to_string()).into_owned();
     let err = err.trim_end();
     let separator = if err.len() > 160 || err.contains('\n') {
         "\n"
@@ -140,7 +386,7 @@ pub fn fail(header: &str, error: &anyhow::Error) {
         style("==").red(),
         style(header).red().reverse(),
         separator,
-        style(error).red().bold(),
+        style(err).red().bold(),
     );
 
     let cause = error.root_cause();
@@ -159,9 +405,88 @@ pub fn fail(header: &str, error: &anyhow::Error) {
     }
 }
 
+/// Print an error of the given kind as a machine-readable JSON object.
+pub fn error_json(error: &anyhow::Error, kind: error::Kind) {
+    let hint = match error.downcast_ref::<Error>() {
+        Some(Error::WithHint { hint, .. }) => Some(*hint),
+        _ => None,
+    };
+    let causes = error
+        .chain()
+        .skip(1)
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>();
+
+    eprintln!(
+        "{}",
+        radicle_common::json::json!({
+            "error": {
+                "kind": kind.as_str(),
+                "code": kind.exit_code(),
+                "message": error.to_string(),
+                "causes": causes,
+                "hint": hint,
+            }
+        })
+    );
+}
+
+/// Check whether the user can be prompted for input, ie. whether standard input and
+/// standard error are terminals. When they aren't, prompts fall back to reading lines
+/// from standard input.
+pub fn is_interactive() -> bool {
+    atty::is(atty::Stream::Stdin) && atty::is(atty::Stream::Stderr)
+}
+
+/// Read a line of input for a prompt that can't be shown interactively. The prompt is
+/// echoed to standard error. Returns `None` at the end of input.
+fn read_line(prompt: impl fmt::Display) -> Option<Zeroizing<String>> {
+    let mut line = Zeroizing::new(String::new());
+
+    eprintln!("{} {}", style(format::glyph(" ⤷", " ->")).cyan(), prompt);
+
+    match std::io::stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => {
+            let len = line.trim_end_matches(&['\r', '\n'][..]).len();
+            line.truncate(len);
+
+            Some(line)
+        }
+    }
+}
+
+/// Ask a yes/no question. With `--yes`, the prompt is echoed and answered with "yes".
+/// When input is not a terminal, the answer is read from standard input; an empty line
+/// selects the default, and the end of input is taken as "no".
 pub fn ask<D: fmt::Display>(prompt: D, default: bool) -> bool {
+    let prompt = format::ascii(&prompt.to_string()).into_owned();
+
+    if is_assume_yes() {
+        eprintln!(
+            "{} {} {}",
+            style(format::glyph(" ⤷", " ->")).cyan(),
+            prompt,
+            style("yes (--yes)").dim()
+        );
+        return true;
+    }
+    if !is_interactive() {
+        return match read_line(&prompt) {
+            Some(answer) => match answer.trim().to_lowercase().as_str() {
+                "y" | "yes" => true,
+                "n" | "no" => false,
+                _ => default,
+            },
+            None => false,
+        };
+    }
     dialoguer::Confirm::new()
-        .with_prompt(format!("{} {}", style(" ⤷".to_owned()).cyan(), prompt))
+        .with_prompt(format!(
+            "{} {}",
+            style(format::glyph(" ⤷", " ->")).cyan(),
+            prompt
+        ))
         .wait_for_newline(false)
         .default(true)
         .default(default)
@@ -177,29 +502,67 @@ pub fn abort<D: fmt::Display>(prompt: D) -> bool {
     ask(format::tertiary(prompt), false)
 }
 
-/// Get the signer. First we try getting it from ssh-agent, otherwise we prompt the user.
+/// Get the signer. First we try getting it from ssh-agent, then from `rad agent`,
+/// otherwise we prompt the user.
 pub fn signer(profile: &Profile) -> anyhow::Result<BoxedSigner> {
-    let signer = if let Ok(sock) = keys::ssh_auth_sock() {
-        sock.to_signer(profile)?
-    } else {
-        secret_key(profile)?.to_signer(profile)?
-    };
-    Ok(signer)
+    if let Ok(sock) = keys::ssh_auth_sock() {
+        if keys::is_ready(profile, sock.clone()).unwrap_or(false) {
+            return Ok(sock.to_signer(profile)?);
+        }
+    }
+    if let Some(signer) = keys::agent_signer(profile) {
+        return Ok(signer);
+    }
+    Ok(secret_key(profile)?.to_signer(profile)?)
+}
+
+/// Lock the profile's storage for modification. If another command holds the lock, fail
+/// with a "storage is busy" error, or wait for it to be released if `wait` is set.
+pub fn lock_storage(profile: &Profile, wait: bool) -> anyhow::Result<lock::StorageLock> {
+    if let Some(lock) = lock::try_storage(profile)? {
+        return Ok(lock);
+    }
+    if !wait {
+        return Err(Error::WithHint {
+            err: error::Error::new(
+                error::Kind::Conflict,
+                anyhow::anyhow!("storage is busy, another command is modifying it"),
+            )
+            .into(),
+            hint: "Try again once it has finished, or use `--wait` to wait for it.",
+        }
+        .into());
+    }
+    let spinner = spinner("Waiting for another command to release the storage...");
+    let lock = lock::storage(profile)?;
+    spinner.clear();
+
+    Ok(lock)
 }
 
 pub fn theme() -> ColorfulTheme {
     ColorfulTheme {
         success_prefix: style("ok".to_owned()).for_stderr().green().reverse(),
-        prompt_prefix: style(" ⤷".to_owned()).cyan().dim().for_stderr(),
-        prompt_suffix: style("·".to_owned()).cyan().for_stderr(),
+        prompt_prefix: style(format::glyph(" ⤷", " ->").to_owned())
+            .cyan()
+            .dim()
+            .for_stderr(),
+        prompt_suffix: style(format::glyph("·", ":").to_owned())
+            .cyan()
+            .for_stderr(),
         prompt_style: Style::new().cyan().bold().for_stderr(),
         active_item_style: Style::new().for_stderr().yellow().reverse(),
         active_item_prefix: style("*".to_owned()).yellow().for_stderr(),
         picked_item_prefix: style("*".to_owned()).yellow().for_stderr(),
         inactive_item_prefix: style(" ".to_string()).for_stderr(),
         inactive_item_style: Style::new().yellow().for_stderr(),
-        error_prefix: style("⤹  Error:".to_owned()).red().for_stderr(),
-        success_suffix: style("·".to_owned()).cyan().for_stderr(),
+        fuzzy_match_highlight_style: Style::new().for_stderr().yellow().bold().underlined(),
+        error_prefix: style(format::glyph("⤹  Error:", "<- Error:").to_owned())
+            .red()
+            .for_stderr(),
+        success_suffix: style(format::glyph("·", ":").to_owned())
+            .cyan()
+            .for_stderr(),
 
         ..ColorfulTheme::default()
     }
@@ -210,6 +573,19 @@ where
     S: fmt::Display + std::str::FromStr<Err = E> + Clone,
     E: fmt::Debug + fmt::Display,
 {
+    if !is_interactive() {
+        return match read_line(message) {
+            Some(line) if !line.is_empty() => line
+                .parse()
+                .map_err(|e| anyhow::anyhow!("invalid value for '{}': {}", message, e)),
+            _ => default.ok_or_else(|| {
+                anyhow::anyhow!(
+                    "a value for '{}' is required, but input is not a terminal",
+                    message
+                )
+            }),
+        };
+    }
     let theme = theme();
     let mut input: Input<S> = Input::with_theme(&theme);
 
@@ -259,6 +635,15 @@ where
     S: fmt::Display + fmt::Debug + FromStr<Err = E> + Clone,
     E: fmt::Debug + fmt::Display,
 {
+    if !is_interactive() {
+        return match read_line(message) {
+            Some(line) if !line.is_empty() => line
+                .parse()
+                .map(Some)
+                .map_err(|e| anyhow::anyhow!("invalid value for '{}': {}", message, e)),
+            _ => Ok(None),
+        };
+    }
     let theme = theme();
     let mut input: Input<Optional<S>> = Input::with_theme(&theme);
 
@@ -273,7 +658,54 @@ where
     Ok(value.option)
 }
 
+/// Open the user's editor with the given text, and return the edited text, or `None` if
+/// the editor was closed without saving. The file opened has the given extension, eg.
+/// `.markdown`, so that the editor can highlight it. If input is not a terminal, it is
+/// read from standard input instead.
+pub fn editor(text: &str, extension: &str) -> anyhow::Result<Option<String>> {
+    if is_interactive() {
+        let text = Editor::new()
+            .require_save(true)
+            .trim_newlines(true)
+            .extension(extension)
+            .edit(text)?;
+
+        Ok(text)
+    } else {
+        let mut text = String::new();
+        std::io::Read::read_to_string(&mut std::io::stdin(), &mut text)?;
+
+        Ok(Some(text))
+    }
+}
+
+/// Get multi-line input from the user's editor, eg. for a patch description.
+///
+/// The editor is opened with the given template, like for a git commit message. Lines
+/// starting with `#` are comments and are stripped from the result. Fails if the editor
+/// is closed without saving, or if the input is empty once comments are stripped.
+pub fn editor_input(template: &str) -> anyhow::Result<String> {
+    let text = editor(template, ".git-commit")?
+        .ok_or_else(|| anyhow::anyhow!("editor was closed without saving"))?;
+    let text = text
+        .lines()
+        .filter(|l| !l.starts_with('#'))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let text = text.trim();
+
+    if text.is_empty() {
+        anyhow::bail!("input is empty");
+    }
+    Ok(text.to_owned())
+}
+
+/// Get the passphrase of the signing key. If `RAD_PASSPHRASE` is set, it is used
+/// instead of prompting.
 pub fn secret_input() -> SecUtf8 {
+    if let Some(passphrase) = env::passphrase() {
+        return passphrase;
+    }
     secret_input_with_prompt("Passphrase")
 }
 
@@ -291,6 +723,11 @@ pub fn secret_key(profile: &Profile) -> Result<keys::signer::ZeroizingSecretKey,
 // even if the password is later found out to be wrong.
 // We should handle this differently.
 pub fn secret_input_with_prompt(prompt: &str) -> SecUtf8 {
+    if !is_interactive() {
+        return read_line(prompt)
+            .map(|line| SecUtf8::from(line.as_str()))
+            .unwrap_or_else(|| SecUtf8::from(""));
+    }
     SecUtf8::from(
         Password::with_theme(&theme())
             .allow_empty_password(true)
@@ -301,6 +738,9 @@ pub fn secret_input_with_prompt(prompt: &str) -> SecUtf8 {
 }
 
 pub fn secret_input_with_confirmation() -> SecUtf8 {
+    if !is_interactive() || env::passphrase().is_some() {
+        return secret_input();
+    }
     SecUtf8::from(
         Password::with_theme(&theme())
             .with_prompt("Passphrase")
@@ -314,53 +754,87 @@ pub fn select<'a, T>(options: &'a [T], active: &'a T) -> Option<&'a T>
 where
     T: fmt::Display + Eq + PartialEq,
 {
-    let theme = theme();
     let active = options.iter().position(|o| o == active);
-    let mut selection = dialoguer::Select::with_theme(&theme);
+    let items = options.iter().map(|p| p.to_string()).collect::<Vec<_>>();
 
-    if let Some(active) = active {
-        selection.default(active);
-    }
-    let result = selection
-        .items(&options.iter().map(|p| p.to_string()).collect::<Vec<_>>())
-        .interact_opt()
-        .unwrap();
-
-    result.map(|i| &options[i])
+    select_index(None, &items, active).map(|i| &options[i])
 }
 
 pub fn select_with_prompt<'a, T>(prompt: &str, options: &'a [T], active: &'a T) -> Option<&'a T>
 where
     T: fmt::Display + Eq + PartialEq,
 {
-    let theme = theme();
     let active = options.iter().position(|o| o == active);
-    let mut selection = dialoguer::Select::with_theme(&theme);
-    selection.with_prompt(prompt);
-
-    if let Some(active) = active {
-        selection.default(active);
+    let items = options.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+
+    select_index(Some(prompt), &items, active).map(|i| &options[i])
+}
+
+/// Let the user pick one of the given items, and return its index. If there are more
+/// than [`FUZZY_SELECT_THRESHOLD`] items, the user can type to filter them.
+pub fn select_index(
+    prompt: Option<&str>,
+    items: &[String],
+    active: Option<usize>,
+) -> Option<usize> {
+    if !is_interactive() {
+        // Nb. Without a terminal, the default item is selected.
+        if let Some(item) = active.and_then(|i| items.get(i)) {
+            eprintln!(
+                "{} {} {}",
+                style(format::glyph(" ⤷", " ->")).cyan(),
+                prompt.unwrap_or("Select"),
+                console::strip_ansi_codes(item)
+            );
+        }
+        return active;
     }
-    let result = selection
-        .items(&options.iter().map(|p| p.to_string()).collect::<Vec<_>>())
-        .interact_opt()
-        .unwrap();
+    let theme = theme();
 
-    result.map(|i| &options[i])
+    if items.len() > FUZZY_SELECT_THRESHOLD {
+        // Nb. Matches are highlighted by the prompt, so existing styling is removed.
+        let items = items
+            .iter()
+            .map(|i| console::strip_ansi_codes(i).to_string())
+            .collect::<Vec<_>>();
+        let mut selection = dialoguer::FuzzySelect::with_theme(&theme);
+
+        if let Some(prompt) = prompt {
+            selection.with_prompt(prompt);
+        }
+        if let Some(active) = active {
+            selection.default(active);
+        }
+        selection.items(&items).interact_opt().unwrap()
+    } else {
+        let mut selection = dialoguer::Select::with_theme(&theme);
+
+        if let Some(prompt) = prompt {
+            selection.with_prompt(prompt);
+        }
+        if let Some(active) = active {
+            selection.default(active);
+        }
+        selection.items(items).interact_opt().unwrap()
+    }
 }
 
+/// Let the user pick a profile, by id. Nb. Profiles' storage isn't opened here, so that
+/// listing many profiles stays cheap: load the details of the selected profile only.
 pub fn profile_select<'a>(profiles: &'a [Profile], active: &Profile) -> Option<&'a Profile> {
     let active = profiles.iter().position(|p| p.id() == active.id()).unwrap();
-    let selection = dialoguer::Select::with_theme(&theme())
-        .items(&profiles.iter().map(|p| p.id()).collect::<Vec<_>>())
-        .default(active)
-        .interact_opt()
-        .unwrap();
+    let items = profiles
+        .iter()
+        .map(|p| p.id().to_string())
+        .collect::<Vec<_>>();
 
-    selection.map(|i| &profiles[i])
+    select_index(Some("Select a profile"), &items, Some(active)).map(|i| &profiles[i])
 }
 
 pub fn comment_select(issue: &Issue) -> Option<CommentId> {
+    if !is_interactive() {
+        return Some(CommentId::root());
+    }
     let selection = dialoguer::Select::with_theme(&theme())
         .with_prompt("Which comment do you want to react to?")
         .item(&issue.description().to_string())
@@ -378,6 +852,36 @@ pub fn comment_select(issue: &Issue) -> Option<CommentId> {
     selection.map(CommentId::from)
 }
 
+/// Print the comments of a discussion, with their replies and reactions. Comments are
+/// shown with their index, eg. `#0`, which is used to reply or react to them.
+pub fn discussion(comments: &[Comment<Replies>]) {
+    for (ix, comment) in comments.iter().enumerate() {
+        info!(
+            "{} {} {}",
+            format::dim(format!("#{}", ix)),
+            format::tertiary(comment.author.name()),
+            format::dim(format::timestamp(&comment.timestamp)),
+        );
+        markdown(comment.body.trim());
+
+        if !comment.reactions.is_empty() {
+            indented(&format::reactions(&comment.reactions));
+        }
+        for reply in &comment.replies {
+            info!(
+                "   {} {} {}",
+                format::dim("↳"),
+                format::tertiary(reply.author.name()),
+                format::dim(format::timestamp(&reply.timestamp)),
+            );
+            for line in reply.body.trim().lines() {
+                indented(line);
+            }
+        }
+        blank();
+    }
+}
+
 pub fn markdown(content: &str) {
     if !content.is_empty() {
         if command::bat(["-p", "-l", "md"], content).is_err() {
@@ -7,9 +7,11 @@ pub mod format;
 pub mod io;
 pub mod keys;
 pub mod patch;
+pub mod progress;
 pub mod spinner;
 pub mod sync;
 pub mod table;
+pub mod template;
 pub mod textbox;
 
 use std::ffi::OsString;
@@ -17,14 +19,17 @@ use std::process;
 
 use dialoguer::console::style;
 use radicle_common::args::{Args, Error, Help};
+use radicle_common::error::{self, Kind};
 use radicle_common::profile;
 use radicle_common::profile::Profile;
 
-pub use console::measure_text_width as text_width;
 pub use dialoguer::Editor;
+pub use format::text_width;
 pub use io::*;
+pub use progress::MultiSpinner;
 pub use spinner::{spinner, Spinner};
 pub use table::Table;
+pub use template::Template;
 pub use textbox::TextBox;
 
 /// Context passed to all commands.
@@ -73,6 +78,18 @@ where
     run_command_args(help, action, cmd, args)
 }
 
+/// Parse the arguments of a command, including the flags accepted by all commands.
+pub fn parse_args<A: Args>(args: Vec<OsString>) -> anyhow::Result<A> {
+    let mut args = args;
+    if radicle_common::args::assume_yes(&mut args) {
+        io::set_assume_yes(true);
+    }
+    let (opts, unparsed) = A::from_args(args)?;
+    radicle_common::args::finish(unparsed)?;
+
+    Ok(opts)
+}
+
 pub fn run_command_args<A, C>(help: Help, action: &str, cmd: C, args: Vec<OsString>) -> !
 where
     A: Args,
@@ -80,26 +97,34 @@ where
 {
     use crate::io as term;
 
-    let options = match A::from_args(args) {
-        Ok((opts, unparsed)) => {
-            if let Err(err) = radicle_common::args::finish(unparsed) {
-                term::error(err);
-                process::exit(1);
-            }
-            opts
-        }
+    term::init_colors();
+    term::init_output();
+    // Nb. Fails if a logger was already setup, eg. by `rad`.
+    radicle_common::logger::init(term::verbosity().level()).ok();
+
+    let options = match parse_args::<A>(args) {
+        Ok(opts) => opts,
         Err(err) => {
             match err.downcast_ref::<Error>() {
                 Some(Error::Help) => {
                     term::help(help.name, help.version, help.description, help.usage);
                     process::exit(0);
                 }
-                Some(Error::Usage) => {
+                Some(Error::Usage) if !term::is_json() => {
                     term::usage(help.name, help.usage);
-                    process::exit(1);
+                    process::exit(Kind::Usage.exit_code());
                 }
                 _ => {}
             };
+            // Argument parsing errors are usage errors, unless they say otherwise.
+            let kind = match error::kind(&err) {
+                Kind::Other => Kind::Usage,
+                kind => kind,
+            };
+            if term::is_json() {
+                term::error_json(&err, kind);
+                process::exit(kind.exit_code());
+            }
             eprintln!(
                 "{} {} {} {}",
                 style("==").red(),
@@ -112,7 +137,7 @@ where
                 eprintln!("{}", style(hint).yellow());
             }
 
-            process::exit(1);
+            process::exit(kind.exit_code());
         }
     };
 
@@ -120,7 +145,7 @@ where
         Ok(()) => process::exit(0),
         Err(err) => {
             term::fail(&format!("{} failed", action), &err);
-            process::exit(1);
+            process::exit(error::kind(&err).exit_code());
         }
     }
 }
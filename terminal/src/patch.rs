@@ -1,4 +1,8 @@
+use std::collections::HashSet;
+use std::path::Path;
+
 use radicle_common as common;
+use radicle_common::cobs::patch::CodeComment;
 use radicle_common::git;
 
 use crate as term;
@@ -18,12 +22,7 @@ impl Comment {
     /// Get the comment as a string according to the method.
     pub fn get(self, help: &str) -> String {
         let comment = match self {
-            Comment::Edit => term::Editor::new()
-                .require_save(true)
-                .trim_newlines(true)
-                .extension(".markdown")
-                .edit(help)
-                .unwrap(),
+            Comment::Edit => term::editor(help, ".markdown").unwrap(),
             Comment::Blank => None,
             Comment::Text(c) => Some(c),
         };
@@ -85,3 +84,128 @@ pub fn print_commits_ahead_behind(
     );
     Ok(())
 }
+
+/// Print the diffstat of the changes between two commits, eg. of a patch revision.
+pub fn print_diffstat(
+    repo: &git::Repository,
+    base: git::Oid,
+    head: git::Oid,
+) -> anyhow::Result<()> {
+    let base = repo.find_commit(base)?.tree()?;
+    let head = repo.find_commit(head)?.tree()?;
+    let diff = repo.diff_tree_to_tree(Some(&base), Some(&head), None)?;
+    let stats = diff.stats()?.to_buf(git::DiffStatsFormat::FULL, 80)?;
+
+    term::blob(String::from_utf8_lossy(&stats).trim_end());
+
+    Ok(())
+}
+
+/// Print the diff between two commits, eg. of a patch revision, with the given code
+/// comments shown under the lines they refer to. Comments on lines that aren't part
+/// of the diff are shown after the changes of their file.
+pub fn print_diff(
+    repo: &git::Repository,
+    base: git::Oid,
+    head: git::Oid,
+    comments: &[&CodeComment],
+) -> anyhow::Result<()> {
+    let base = repo.find_commit(base)?.tree()?;
+    let head = repo.find_commit(head)?.tree()?;
+    let diff = repo.diff_tree_to_tree(Some(&base), Some(&head), None)?;
+    let mut paths = HashSet::new();
+
+    for ix in 0..diff.deltas().len() {
+        let patch = match git::Patch::from_diff(&diff, ix)? {
+            Some(patch) => patch,
+            None => continue,
+        };
+        let delta = patch.delta();
+        let path = match delta.new_file().path().or_else(|| delta.old_file().path()) {
+            Some(path) => path.to_path_buf(),
+            None => continue,
+        };
+        let mut pending = comments
+            .iter()
+            .filter(|c| c.location.path == path)
+            .collect::<Vec<_>>();
+
+        term::info!("{}", term::format::bold(path.display()));
+
+        for h in 0..patch.num_hunks() {
+            let (hunk, lines) = patch.hunk(h)?;
+
+            term::info!(
+                "{}",
+                term::format::tertiary(String::from_utf8_lossy(hunk.header()).trim_end())
+            );
+            for l in 0..lines {
+                let line = patch.line_in_hunk(h, l)?;
+                let content = String::from_utf8_lossy(line.content());
+                let content = content.trim_end_matches(&['\n', '\r'][..]);
+
+                match line.origin() {
+                    '+' => term::info!("{}", term::format::positive(format!("+{}", content))),
+                    '-' => term::info!("{}", term::format::negative(format!("-{}", content))),
+                    ' ' => term::info!(" {}", content),
+                    _ => term::info!("{}", term::format::dim(content)),
+                }
+
+                if let Some(lineno) = line.new_lineno() {
+                    pending.retain(|c| {
+                        if *c.location.lines.end() == lineno as usize {
+                            print_code_comment(c);
+                            false
+                        } else {
+                            true
+                        }
+                    });
+                }
+            }
+        }
+        for comment in pending {
+            print_code_comment(comment);
+        }
+        term::blank();
+        paths.insert(path);
+    }
+
+    // Comments on files that weren't changed.
+    let mut rest = comments
+        .iter()
+        .filter(|c| !paths.contains(&c.location.path))
+        .collect::<Vec<_>>();
+    rest.sort_by_key(|c| (c.location.path.clone(), *c.location.lines.start()));
+
+    let mut current: Option<&Path> = None;
+    for comment in rest {
+        if current != Some(comment.location.path.as_path()) {
+            term::info!("{}", term::format::bold(comment.location.path.display()));
+            current = Some(comment.location.path.as_path());
+        }
+        print_code_comment(comment);
+    }
+
+    Ok(())
+}
+
+/// Print a code comment, under the lines it refers to.
+fn print_code_comment(comment: &CodeComment) {
+    let lines = &comment.location.lines;
+    let lines = if lines.start() == lines.end() {
+        format!("L{}", lines.start())
+    } else {
+        format!("L{}-{}", lines.start(), lines.end())
+    };
+
+    term::info!(
+        "  {} {} on {} {}",
+        term::format::yellow("┃"),
+        term::format::tertiary(comment.comment.author.name()),
+        term::format::dim(lines),
+        term::format::dim(term::format::timestamp(&comment.comment.timestamp)),
+    );
+    for line in comment.comment.body.lines() {
+        term::info!("  {} {}", term::format::yellow("┃"), line);
+    }
+}
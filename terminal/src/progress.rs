@@ -0,0 +1,160 @@
+use std::sync::Arc;
+use std::thread;
+
+use dialoguer::console::style;
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+use crate as term;
+
+/// Renders several labeled tasks at once, eg. syncing with multiple seeds in parallel.
+/// Each task is shown on its own line with a spinner, or a progress bar if its length
+/// is known, and is finalized with success or failure.
+///
+/// Tasks must be added before rendering is started with [`MultiSpinner::start`].
+pub struct MultiSpinner {
+    multi: Arc<MultiProgress>,
+    tasks: Vec<(ProgressBar, String)>,
+}
+
+impl Default for MultiSpinner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MultiSpinner {
+    pub fn new() -> Self {
+        let multi = MultiProgress::new();
+
+        // Nb. In plain mode, only the outcome of each task is output.
+        if term::is_quiet() || term::is_plain() {
+            multi.set_draw_target(ProgressDrawTarget::hidden());
+        }
+
+        Self {
+            multi: Arc::new(multi),
+            tasks: Vec::new(),
+        }
+    }
+
+    /// Add a task with the given label.
+    pub fn add(&mut self, label: impl ToString) -> Task {
+        let label = term::format::ascii(&label.to_string()).into_owned();
+        let progress = self.multi.add(ProgressBar::new(!0));
+
+        progress.set_style(spinner_style());
+        self.tasks.push((progress.clone(), label.clone()));
+
+        Task { progress, label }
+    }
+
+    /// Start rendering the tasks added so far.
+    pub fn start(self) -> Rendering {
+        // Align task labels.
+        let width = self
+            .tasks
+            .iter()
+            .map(|(_, label)| term::text_width(label))
+            .max()
+            .unwrap_or_default();
+
+        for (progress, label) in &self.tasks {
+            progress.set_prefix(
+                console::pad_str(label, width, console::Alignment::Left, None).to_string(),
+            );
+            progress.enable_steady_tick(99);
+        }
+
+        let multi = self.multi.clone();
+        let handle = thread::spawn(move || {
+            multi.join().ok();
+        });
+
+        Rendering { handle }
+    }
+}
+
+/// Handle on a running [`MultiSpinner`].
+pub struct Rendering {
+    handle: thread::JoinHandle<()>,
+}
+
+impl Rendering {
+    /// Wait for all tasks to be finalized.
+    pub fn join(self) {
+        self.handle.join().ok();
+    }
+}
+
+/// A single task of a [`MultiSpinner`].
+pub struct Task {
+    progress: ProgressBar,
+    label: String,
+}
+
+impl Drop for Task {
+    fn drop(&mut self) {
+        if !self.progress.is_finished() {
+            self.finish(style("!!").red().reverse().to_string(), String::new());
+        }
+    }
+}
+
+impl Task {
+    /// Set the task's status message.
+    pub fn message(&self, msg: impl Into<String>) {
+        self.progress
+            .set_message(term::format::ascii(&msg.into()).into_owned());
+    }
+
+    /// Show the task as a progress bar, with the given position and length.
+    pub fn progress(&self, pos: u64, len: u64) {
+        if self.progress.length() != len {
+            self.progress.set_style(bar_style());
+            self.progress.set_length(len);
+        }
+        self.progress.set_position(pos);
+    }
+
+    /// Finalize the task as successful.
+    pub fn success(self, msg: impl Into<String>) {
+        self.finish(style("ok").green().reverse().to_string(), msg.into());
+    }
+
+    /// Finalize the task as failed.
+    pub fn failed(self, msg: impl Into<String>) {
+        self.finish(
+            style("!!").red().reverse().to_string(),
+            term::format::negative(msg.into()),
+        );
+    }
+
+    fn finish(&self, symbol: String, msg: String) {
+        if term::is_plain() {
+            term::info!("{} {} {}", symbol, self.label, msg);
+        }
+        self.progress.set_style(
+            ProgressStyle::default_spinner()
+                .template(&format!("{} {{prefix:.bold}} {{msg}}", symbol)),
+        );
+        self.progress.finish_with_message(msg);
+    }
+}
+
+fn spinner_style() -> ProgressStyle {
+    ProgressStyle::default_spinner()
+        .tick_strings(&[
+            &style("\\ ").yellow().to_string(),
+            &style("| ").yellow().to_string(),
+            &style("/ ").yellow().to_string(),
+            &style("| ").yellow().to_string(),
+            &style("  ").to_string(),
+        ])
+        .template("{spinner}{prefix:.bold} {msg:.dim}")
+}
+
+fn bar_style() -> ProgressStyle {
+    ProgressStyle::default_bar()
+        .template("{spinner}{prefix:.bold} [{bar:24.yellow}] {pos}/{len} {msg:.dim}")
+        .progress_chars("=> ")
+}
@@ -1,5 +1,5 @@
 use dialoguer::console::style;
-use indicatif::{ProgressBar, ProgressFinish, ProgressStyle};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressFinish, ProgressStyle};
 
 use crate as term;
 
@@ -44,12 +44,25 @@ impl Spinner {
     }
 
     pub fn message(&mut self, msg: impl Into<String>) {
-        let msg = msg.into();
+        let msg = term::format::ascii(&msg.into()).into_owned();
+
+        if term::is_plain() {
+            term::info!("{}", msg);
+        }
 
         self.progress.set_message(msg.clone());
         self.message = msg;
     }
 
+    /// Show transient progress next to the message, eg. of a transfer. Progress isn't
+    /// output in plain mode, and isn't kept once the spinner is finished.
+    pub fn progress(&self, progress: impl AsRef<str>) {
+        let progress = term::format::ascii(progress.as_ref());
+
+        self.progress
+            .set_message(format!("{} {}", self.message, term::format::dim(progress)));
+    }
+
     pub fn set_failed(&mut self) {
         self.progress.finish_and_clear();
         term::eprintln(style("!!").red().reverse(), &self.message);
@@ -57,7 +70,7 @@ impl Spinner {
 }
 
 pub fn spinner(message: impl ToString) -> Spinner {
-    let message = message.to_string();
+    let message = term::format::ascii(&message.to_string()).into_owned();
     let style = ProgressStyle::default_spinner()
         .tick_strings(&[
             &style("\\ ").yellow().to_string(),
@@ -70,7 +83,16 @@ pub fn spinner(message: impl ToString) -> Spinner {
 
     let progress = ProgressBar::new(!0);
     progress.set_style(style);
-    progress.enable_steady_tick(99);
+
+    if term::is_quiet() {
+        progress.set_draw_target(ProgressDrawTarget::hidden());
+    } else if term::is_plain() {
+        // Nb. Without animation, the message is output once, as a status line.
+        progress.set_draw_target(ProgressDrawTarget::hidden());
+        term::info!("{}", message);
+    } else {
+        progress.enable_steady_tick(99);
+    }
     progress.set_message(message.clone());
 
     Spinner { message, progress }
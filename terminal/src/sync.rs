@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::convert::TryInto;
 use std::time;
 
@@ -20,17 +21,89 @@ pub fn sync(
     signer: impl ToSigner,
     rt: &common::tokio::runtime::Runtime,
 ) -> anyhow::Result<NonEmpty<SyncResult>> {
+    common::offline::check("sync with seeds")?;
+
     let signer = signer.to_signer(profile)?;
+
+    if let Ok(Some(proxy)) = common::proxy::url() {
+        term::warning(&format!(
+            "Proxy {} is not used to sync with seeds: the peer-to-peer protocol can't be proxied",
+            proxy
+        ));
+    }
     let timeout = time::Duration::from_secs(9);
-    let spinner = term::spinner("Syncing...");
+    let retry = sync::Retry::from_config();
+
+    // One line per seed, since seeds are synced with concurrently.
+    let mut spinner = term::MultiSpinner::new();
+    let mut tasks = seeds
+        .iter()
+        .map(|seed| {
+            let label = seed
+                .label
+                .clone()
+                .unwrap_or_else(|| common::fmt::peer(&seed.peer));
+            let task = spinner.add(format!("{} {}", label, term::format::dim(&seed.addrs)));
+
+            (seed.peer, task)
+        })
+        .collect::<HashMap<_, _>>();
+    let rendering = spinner.start();
+
     let result = rt.block_on(async {
         let (seeds, _errors) = sync::Seeds::resolve(seeds.iter()).await;
         let client = sync::client(signer, profile).await?;
-        let result = sync::sync(&client, urn, seeds, mode, timeout).await;
+        let result = sync::sync(
+            &client,
+            urn.clone(),
+            seeds,
+            mode,
+            timeout,
+            retry,
+            |peer, msg| {
+                if let Some(task) = tasks.get(peer) {
+                    task.message(msg);
+                }
+            },
+        )
+        .await;
 
         Ok::<Vec<SyncResult>, anyhow::Error>(result)
     })?;
 
+    let (mut synced, mut failed, mut timed_out) = (0, 0, 0);
+    for result in &result {
+        if let Some(task) = tasks.remove(&result.seed.peer) {
+            // Retries are only worth mentioning when asked for details.
+            let retries = if result.retries > 0 && term::verbosity() >= term::Verbosity::Verbose {
+                format!(" after {} retry(s)", result.retries)
+            } else {
+                String::new()
+            };
+
+            match result.status() {
+                sync::Status::Synced => {
+                    synced += 1;
+                    task.success(format!("synced{}", retries));
+                }
+                sync::Status::Failed(err) => {
+                    failed += 1;
+                    task.failed(format!("{}{}", err, retries));
+                }
+                sync::Status::TimedOut => {
+                    timed_out += 1;
+                    task.failed(format!("timed out{}", retries));
+                }
+            }
+        }
+    }
+    // Seeds that are left couldn't be resolved.
+    for (_, task) in tasks.drain() {
+        failed += 1;
+        task.failed("address could not be resolved");
+    }
+    rendering.join();
+
     let results = if let Ok(results) = result.try_into() {
         results
     } else {
@@ -39,10 +112,25 @@ pub fn sync(
         ));
     };
 
-    match mode {
-        sync::Mode::Push | sync::Mode::All => spinner.finish(),
-        sync::Mode::Fetch => spinner.clear(),
+    let summary = format!(
+        "Synced with {} of {} seed(s), {} failed, {} timed out",
+        synced,
+        synced + failed + timed_out,
+        failed,
+        timed_out
+    );
+    if synced > 0 {
+        term::success!("{}", summary);
+    } else {
+        term::warning(&summary);
     }
 
+    let synced = results
+        .iter()
+        .filter(|r| r.status() == sync::Status::Synced)
+        .map(|r| &r.seed.peer);
+    // Failing to record the sync time shouldn't fail the sync.
+    sync::record(profile, &urn, synced).ok();
+
     Ok(results)
 }
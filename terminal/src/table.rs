@@ -2,79 +2,148 @@ use std::fmt::Write;
 
 use crate as term;
 
+pub use console::Alignment;
+
 #[derive(Debug, Default)]
 pub struct TableOptions {
     pub overflow: bool,
+    /// Maximum width of a rendered row. Defaults to the terminal width.
+    pub max_width: Option<usize>,
 }
 
 #[derive(Debug)]
 pub struct Table<const W: usize> {
+    header: Option<[String; W]>,
     rows: Vec<[String; W]>,
     widths: [usize; W],
+    alignments: [Alignment; W],
+    limits: [Option<usize>; W],
     opts: TableOptions,
 }
 
 impl<const W: usize> Table<W> {
     pub fn new(opts: TableOptions) -> Self {
         Self {
+            header: None,
             rows: Vec::new(),
             widths: [0; W],
+            alignments: [Alignment::Left; W],
+            limits: [None; W],
             opts,
         }
     }
 
     pub fn default() -> Self {
-        Self {
-            rows: Vec::new(),
-            widths: [0; W],
-            opts: TableOptions::default(),
-        }
+        Self::new(TableOptions::default())
+    }
+
+    /// Set the column titles, rendered above the rows.
+    pub fn header(&mut self, header: [String; W]) -> &mut Self {
+        self.measure(&header);
+        self.header = Some(header);
+        self
+    }
+
+    /// Set the alignment of a column.
+    pub fn align(&mut self, column: usize, alignment: Alignment) -> &mut Self {
+        self.alignments[column] = alignment;
+        self
+    }
+
+    /// Limit the width of a column. Longer cells are truncated with an ellipsis.
+    pub fn truncate(&mut self, column: usize, max: usize) -> &mut Self {
+        self.limits[column] = Some(max);
+        self
     }
 
     pub fn push(&mut self, row: [String; W]) {
-        for (i, cell) in row.iter().enumerate() {
-            self.widths[i] = self.widths[i].max(console::measure_text_width(cell));
-        }
+        self.measure(&row);
         self.rows.push(row);
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
     pub fn render(self) {
-        let width = term::width(); // Terminal width.
+        let width = self.opts.max_width.unwrap_or_else(term::width); // Terminal width.
+        let ellipsis = term::format::glyph("…", "...");
 
+        if let Some(header) = &self.header {
+            let header = header.iter().map(term::format::bold).collect::<Vec<_>>();
+            let header = term::format::ascii(&self.format(&header)).into_owned();
+            println!(
+                "{}",
+                console::truncate_str(&header, width.saturating_sub(1), ellipsis)
+            );
+        }
         for row in &self.rows {
-            let mut output = String::new();
-            let cells = row.len();
-
-            for (i, cell) in row.iter().enumerate() {
-                if i == cells - 1 || self.opts.overflow {
-                    write!(output, "{}", cell).ok();
-                } else {
-                    write!(
-                        output,
-                        "{} ",
-                        console::pad_str(cell, self.widths[i], console::Alignment::Left, None)
-                    )
-                    .ok();
-                }
-            }
-            println!("{}", console::truncate_str(&output, width - 1, "…"));
+            let row = term::format::ascii(&self.format(row)).into_owned();
+            println!(
+                "{}",
+                console::truncate_str(&row, width.saturating_sub(1), ellipsis)
+            );
         }
     }
 
     pub fn render_tree(self) {
         for (r, row) in self.rows.iter().enumerate() {
             if r != self.rows.len() - 1 {
-                print!("├── ");
+                print!("{}", term::format::glyph("├── ", "|-- "));
             } else {
-                print!("└── ");
+                print!("{}", term::format::glyph("└── ", "`-- "));
             }
             for (i, cell) in row.iter().enumerate() {
-                print!(
-                    "{} ",
-                    console::pad_str(cell, self.widths[i], console::Alignment::Left, None)
-                );
+                print!("{} ", term::format::ascii(&self.pad(i, cell)));
             }
             println!();
         }
     }
+
+    fn measure(&mut self, row: &[String; W]) {
+        for (i, cell) in row.iter().enumerate() {
+            self.widths[i] = self.widths[i].max(console::measure_text_width(cell));
+        }
+    }
+
+    /// Format a row, padding all cells but the last one, unless the table overflows.
+    fn format(&self, row: &[String]) -> String {
+        let mut output = String::new();
+        let cells = row.len();
+
+        for (i, cell) in row.iter().enumerate() {
+            if (i == cells - 1 && self.alignments[i] == Alignment::Left) || self.opts.overflow {
+                write!(output, "{}", self.clip(i, cell)).ok();
+            } else {
+                write!(output, "{}", self.pad(i, cell)).ok();
+            }
+            if i != cells - 1 {
+                output.push(' ');
+            }
+        }
+        output
+    }
+
+    /// Truncate a cell to the column's width limit, if any.
+    fn clip<'a>(&self, column: usize, cell: &'a str) -> std::borrow::Cow<'a, str> {
+        match self.limits[column] {
+            Some(max) => console::truncate_str(cell, max, term::format::glyph("…", "...")),
+            None => cell.into(),
+        }
+    }
+
+    /// Truncate and pad a cell to the column width.
+    fn pad(&self, column: usize, cell: &str) -> String {
+        let width = match self.limits[column] {
+            Some(max) => self.widths[column].min(max),
+            None => self.widths[column],
+        };
+        console::pad_str(
+            &self.clip(column, cell),
+            width,
+            self.alignments[column],
+            None,
+        )
+        .to_string()
+    }
 }
@@ -0,0 +1,153 @@
+//! Output templates, for printing selected fields of listed items, eg. with `--format`.
+//!
+//! A template is text with placeholders of the form `%<field>`, where `<field>` is
+//! a lower-case word, eg. `%urn %name`. Use `%%` for a literal `%`, `%n` for a newline
+//! and `%t` for a tab.
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    Field(String),
+}
+
+/// A parsed output template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    /// Fields used by this template, in order of appearance.
+    pub fn fields(&self) -> impl Iterator<Item = &str> {
+        self.segments.iter().filter_map(|s| match s {
+            Segment::Field(f) => Some(f.as_str()),
+            Segment::Text(_) => None,
+        })
+    }
+
+    /// Check that the template only uses the given fields.
+    pub fn check(&self, known: &[&str]) -> anyhow::Result<()> {
+        for field in self.fields() {
+            if !known.contains(&field) {
+                let known = known
+                    .iter()
+                    .map(|f| format!("%{}", f))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                anyhow::bail!(
+                    "unknown placeholder '%{}' in format, expected one of: {}",
+                    field,
+                    known
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Render the template, substituting fields with the values returned by `value`.
+    /// Fields without a value are substituted with an empty string.
+    pub fn render<F>(&self, value: F) -> String
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut output = String::new();
+
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => output.push_str(text),
+                Segment::Field(field) => {
+                    if let Some(v) = value(field) {
+                        write!(output, "{}", v).ok();
+                    }
+                }
+            }
+        }
+        output
+    }
+}
+
+impl FromStr for Template {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                text.push(c);
+                continue;
+            }
+            let mut field = String::new();
+            while let Some(c) = chars.peek().filter(|c| c.is_ascii_lowercase()) {
+                field.push(*c);
+                chars.next();
+            }
+
+            match field.as_str() {
+                "" => match chars.next() {
+                    Some('%') => text.push('%'),
+                    Some(c) => return Err(anyhow!("invalid placeholder '%{}' in format", c)),
+                    None => return Err(anyhow!("format must not end with '%'")),
+                },
+                "n" => text.push('\n'),
+                "t" => text.push('\t'),
+                _ => {
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    segments.push(Segment::Field(field));
+                }
+            }
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Ok(Self { segments })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let tmpl: Template = "%name (%urn)%t100%%%n".parse().unwrap();
+        let output = tmpl.render(|field| match field {
+            "name" => Some("acme".to_owned()),
+            "urn" => Some("rad:git:hnrk".to_owned()),
+            _ => None,
+        });
+
+        assert_eq!(output, "acme (rad:git:hnrk)\t100%\n");
+        assert_eq!(tmpl.fields().collect::<Vec<_>>(), vec!["name", "urn"]);
+    }
+
+    #[test]
+    fn test_missing_field() {
+        let tmpl: Template = "%head-%peer".parse().unwrap();
+
+        assert_eq!(tmpl.render(|_| None), "-");
+    }
+
+    #[test]
+    fn test_check() {
+        let tmpl: Template = "%urn %nmae".parse().unwrap();
+
+        assert!(tmpl.check(&["urn", "name"]).is_err());
+        assert!(tmpl.check(&["urn", "nmae"]).is_ok());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!("100%".parse::<Template>().is_err());
+        assert!("%X".parse::<Template>().is_err());
+    }
+}
@@ -32,6 +32,15 @@ impl TextBox {
 
 impl fmt::Display for TextBox {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut output = String::new();
+        self.draw(&mut output)?;
+
+        write!(f, "{}", term::format::ascii(&output))
+    }
+}
+
+impl TextBox {
+    fn draw(&self, f: &mut impl fmt::Write) -> fmt::Result {
         let mut width = self
             .body
             .lines()
@@ -54,7 +63,12 @@ impl fmt::Display for TextBox {
             writeln!(
                 f,
                 "│ {}│",
-                console::pad_str(l, width - 1, console::Alignment::Left, Some("…"))
+                console::pad_str(
+                    l,
+                    width - 1,
+                    console::Alignment::Left,
+                    Some(term::format::glyph("…", "..."))
+                )
             )?;
         }
 
@@ -62,8 +62,10 @@ Options
     --no-sync              Don't sync the peer's refs
     --no-fetch             Don't fetch the peer's refs into the working copy
     --verbose, -v          Verbose output
+    --wait                 Wait for other commands to release the storage
     --help                 Print help
 "#,
+    ..Help::DEFAULT
 };
 
 pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
@@ -77,6 +79,8 @@ pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
         .ok_or_else(|| anyhow!("project {} not found in local storage", &urn))?;
 
     if let Some(peer) = options.peer {
+        let _lock = term::lock_storage(&profile, options.wait)?;
+
         // Track peer.
         track(peer, proj, repo, storage, profile, signer, options)?;
     } else {
@@ -235,7 +239,14 @@ pub fn show(
     // TODO: Deterministic ordering of peers when printed.
     for (i, peer) in peers.iter().enumerate() {
         let you = &peer.id == storage.peer_id();
-        let mut header = vec![term::format::bold(peer.id)];
+        let mut header = match &options.seed {
+            Some(seed) if !options.local => vec![term::format::peer_link(
+                peer.id,
+                &project.urn,
+                &seed.host.to_string(),
+            )],
+            _ => vec![term::format::bold(peer.id)],
+        };
 
         if let Some(meta) = &peer.meta {
             if let Some(name) = meta.person.as_ref().map(|p| &p.name) {
@@ -19,6 +19,7 @@ pub struct Options {
     pub local: bool,
     pub seed: Option<seed::Address>,
     pub verbose: bool,
+    pub wait: bool,
 }
 
 impl Args for Options {
@@ -32,6 +33,7 @@ impl Args for Options {
         let mut sync = true;
         let mut fetch = true;
         let mut verbose = false;
+        let mut wait = false;
         let mut seed = None;
 
         while let Some(arg) = parser.next()? {
@@ -59,6 +61,7 @@ impl Args for Options {
                 Long("no-fetch") => fetch = false,
 
                 Long("verbose") | Short('v') => verbose = true,
+                Long("wait") => wait = true,
                 Long("help") => {
                     return Err(Error::Help.into());
                 }
@@ -94,6 +97,7 @@ impl Args for Options {
                 local,
                 seed,
                 verbose,
+                wait,
             },
             vec![],
         ))
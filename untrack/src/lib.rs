@@ -28,6 +28,7 @@ Options
 
     --help   Print help
 "#,
+    ..Help::DEFAULT
 };
 
 /// Tool options.
@@ -0,0 +1,151 @@
+use std::ffi::OsString;
+use std::path::Path;
+
+use anyhow::anyhow;
+use anyhow::Context as _;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::git::CommitSignature;
+use radicle_common::{fmt, git, profile, project};
+use radicle_terminal as term;
+
+pub const HELP: Help = Help {
+    name: "verify",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad verify <rev-range> [<option>...]
+
+    Checks that the commits in the given revision range, eg. `master..feature`,
+    are signed by keys of the project delegates. Delegate keys are resolved from
+    the project and personal identity documents in local storage.
+
+    Unsigned commits, and commits signed by other keys, are reported, and the
+    command fails if there are any. This is useful before merging a patch.
+
+Options
+
+    --help    Print help
+"#,
+    ..Help::DEFAULT
+};
+
+#[derive(Debug)]
+pub struct Options {
+    pub range: String,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut range: Option<String> = None;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Value(val) if range.is_none() => {
+                    range = Some(val.to_string_lossy().to_string());
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        Ok((
+            Options {
+                range: range.ok_or(Error::Usage)?,
+            },
+            vec![],
+        ))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let storage = profile::read_only(&profile)?;
+    let (urn, _) =
+        project::cwd().context("this command must be run in the context of a project")?;
+    let project = project::get(&storage, &urn)?
+        .ok_or_else(|| anyhow!("project {} was not found in local storage", urn))?;
+
+    // Delegate keys, with the name to show them by.
+    let delegates = project::delegate_keys(&storage, &project);
+
+    let commits = git::verify_commits(Path::new("."), &options.range, delegates.keys())?;
+    if commits.is_empty() {
+        term::info!("No commits in {}", term::format::highlight(&options.range));
+        return Ok(());
+    }
+
+    let mut table = term::Table::default();
+    let mut failures = 0;
+
+    for commit in &commits {
+        let status = match &commit.signature {
+            CommitSignature::Signer(peer) => {
+                let name = delegates
+                    .get(peer)
+                    .cloned()
+                    .unwrap_or_else(|| fmt::peer(peer));
+
+                format!(
+                    "{} {}",
+                    term::format::positive(term::format::glyph("✓", "+")),
+                    name
+                )
+            }
+            CommitSignature::Foreign(fingerprint) => {
+                failures += 1;
+                format!(
+                    "{} {} {}",
+                    term::format::negative(term::format::glyph("✗", "x")),
+                    term::format::negative("not a delegate"),
+                    term::format::dim(fingerprint.as_deref().unwrap_or_default())
+                )
+            }
+            CommitSignature::Bad => {
+                failures += 1;
+                format!(
+                    "{} {}",
+                    term::format::negative(term::format::glyph("✗", "x")),
+                    term::format::negative("bad signature")
+                )
+            }
+            CommitSignature::Unsigned => {
+                failures += 1;
+                format!(
+                    "{} {}",
+                    term::format::negative(term::format::glyph("✗", "x")),
+                    term::format::negative("unsigned")
+                )
+            }
+        };
+        table.push([
+            term::format::secondary(fmt::oid(&commit.oid)),
+            term::format::italic(&commit.summary),
+            status,
+        ]);
+    }
+    table.render();
+    term::blank();
+
+    if failures > 0 {
+        anyhow::bail!(
+            "{} of {} commit(s) are not signed by a delegate",
+            failures,
+            commits.len()
+        );
+    }
+    term::success!(
+        "All {} commit(s) are signed by delegates of {}",
+        commits.len(),
+        term::format::highlight(&project.name)
+    );
+
+    Ok(())
+}
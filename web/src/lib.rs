@@ -0,0 +1,252 @@
+use std::ffi::OsString;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use url::Url;
+
+use librad::collaborative_objects::ObjectId;
+use librad::git::Urn;
+
+use radicle_common::args::{Args, Error, Help};
+use radicle_common::cobs::issue::Issue;
+use radicle_common::cobs::patch::Patch;
+use radicle_common::cobs::{self, Cob, Identifier};
+use radicle_common::{config, profile, project, sync};
+use radicle_terminal as term;
+
+/// Setting of the web client host.
+pub const WEB_HOST_KEY: &str = "web.host";
+
+pub const HELP: Help = Help {
+    name: "web",
+    description: env!("CARGO_PKG_DESCRIPTION"),
+    version: env!("CARGO_PKG_VERSION"),
+    usage: r#"
+Usage
+
+    rad web [<urn>] [--issue <id> | --patch <id>] [--seed <host>] [--url]
+
+    Opens the current project, or the project with the given URN, in the web
+    client, in your browser. With `--issue` or `--patch`, an issue or patch of
+    the project is opened instead. With `--url`, the URL is only printed, eg. to
+    share it.
+
+    The project is shown as it is on a seed: the seed given with `--seed`, or
+    else the first of the project's seeds, or of your default seeds. The web
+    client is set with the `web.host` setting, eg. with
+    `rad config set web.host <host>`.
+
+    The browser is the one set in the `BROWSER` environment variable, or else
+    the default browser of your system.
+
+Options
+
+    --issue <id>    Open an issue of the project
+    --patch <id>    Open a patch of the project
+    --seed <host>   Show the project as it is on this seed
+    --url           Only print the URL
+    --help          Print help
+"#,
+    examples: &[
+        ("Open the current project in the browser", "rad web"),
+        (
+            "Print the URL of a patch, to share it",
+            "rad web --patch 2c8d1f0 --url",
+        ),
+    ],
+    environment: &[(
+        "BROWSER",
+        "The browser to open URLs with, instead of the default browser.",
+    )],
+    ..Help::DEFAULT
+};
+
+/// What to open.
+#[derive(Debug)]
+pub enum Target {
+    Project,
+    Issue(Identifier),
+    Patch(Identifier),
+}
+
+impl Default for Target {
+    fn default() -> Self {
+        Self::Project
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct Options {
+    pub urn: Option<Urn>,
+    pub target: Target,
+    pub seed: Option<String>,
+    pub url: bool,
+}
+
+impl Args for Options {
+    fn from_args(args: Vec<OsString>) -> anyhow::Result<(Self, Vec<OsString>)> {
+        use lexopt::prelude::*;
+
+        let mut parser = lexopt::Parser::from_args(args);
+        let mut urn = None;
+        let mut target = Target::Project;
+        let mut seed = None;
+        let mut url = false;
+
+        while let Some(arg) = parser.next()? {
+            match arg {
+                Long("help") => {
+                    return Err(Error::Help.into());
+                }
+                Long("issue") if matches!(target, Target::Project) => {
+                    let id = parser.value()?.to_string_lossy().into_owned();
+                    let id = Identifier::from_str(&id)
+                        .map_err(|_| anyhow!("invalid issue id '{}'", id))?;
+
+                    target = Target::Issue(id);
+                }
+                Long("patch") if matches!(target, Target::Project) => {
+                    let id = parser.value()?.to_string_lossy().into_owned();
+                    let id = Identifier::from_str(&id)
+                        .map_err(|_| anyhow!("invalid patch id '{}'", id))?;
+
+                    target = Target::Patch(id);
+                }
+                Long("seed") if seed.is_none() => {
+                    seed = Some(parser.value()?.to_string_lossy().into_owned());
+                }
+                Long("url") => {
+                    url = true;
+                }
+                Value(val) if urn.is_none() => {
+                    let val = val.to_string_lossy();
+                    let val = Urn::from_str(&val).map_err(|_| anyhow!("invalid URN '{}'", val))?;
+
+                    urn = Some(val);
+                }
+                _ => return Err(anyhow!(arg.unexpected())),
+            }
+        }
+
+        Ok((
+            Options {
+                urn,
+                target,
+                seed,
+                url,
+            },
+            vec![],
+        ))
+    }
+}
+
+pub fn run(options: Options, ctx: impl term::Context) -> anyhow::Result<()> {
+    let profile = ctx.profile()?;
+    let urn = match options.urn {
+        Some(urn) => urn,
+        None => project::cwd()
+            .map(|(urn, _)| urn)
+            .map_err(|_| Error::WithHint {
+                err: anyhow!("this command must be run in the context of a project"),
+                hint: "To open another project, specify its URN, eg. `rad web <urn>`.",
+            })?,
+    };
+    let host = match config::get(WEB_HOST_KEY)? {
+        Some(setting) => setting.value,
+        None => term::format::GATEWAY_HOST.to_owned(),
+    };
+    let seed = match options.seed {
+        Some(seed) => seed,
+        None => seed_host(&sync::seeds(&profile)?.first().addrs)?,
+    };
+
+    let mut url = format!("https://{}/seeds/{}/{}", host, seed, urn);
+    match &options.target {
+        Target::Project => {}
+        Target::Issue(id) => {
+            let id = resolve::<Issue>(&profile, &urn, id, "issue")?;
+            url.push_str(&format!("/issues/{}", id));
+        }
+        Target::Patch(id) => {
+            let id = resolve::<Patch>(&profile, &urn, id, "patch")?;
+            url.push_str(&format!("/patches/{}", id));
+        }
+    }
+
+    if options.url {
+        term::print(url);
+        return Ok(());
+    }
+    term::info!("Opening {}", term::format::url(&url));
+
+    if let Err(err) = open(&url) {
+        term::warning(&format!("Couldn't open the browser: {}", err));
+    }
+    Ok(())
+}
+
+/// Get the host of a seed, from its address, eg. `pine.radicle.garden:8776`.
+fn seed_host(addr: &str) -> anyhow::Result<String> {
+    Url::from_str(&format!("https://{}", addr))
+        .ok()
+        .and_then(|url| url.host_str().map(ToOwned::to_owned))
+        .ok_or_else(|| anyhow!("invalid seed address '{}'", addr))
+}
+
+/// Resolve an issue or patch identifier to a full id. Only prefixes need local storage.
+fn resolve<T: Cob>(
+    profile: &profile::Profile,
+    urn: &Urn,
+    id: &Identifier,
+    what: &str,
+) -> anyhow::Result<ObjectId> {
+    if let Identifier::Full(id) = id {
+        return Ok(*id);
+    }
+    let storage = profile::read_storage(profile)?;
+    let store = cobs::store(profile, &storage)?;
+
+    store
+        .resolve_id::<T>(urn, id)?
+        .ok_or_else(|| anyhow!("couldn't find {} {} locally", what, id))
+}
+
+/// Open a URL in the browser.
+fn open(url: &str) -> anyhow::Result<()> {
+    let mut cmd = if let Some(browser) = radicle_common::env::var("BROWSER") {
+        Command::new(browser)
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", "start", ""]);
+        cmd
+    } else {
+        Command::new("xdg-open")
+    };
+    let status = cmd
+        .arg(url)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+
+    if !status.success() {
+        anyhow::bail!("browser exited with {}", status);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_seed_host() {
+        assert_eq!(
+            seed_host("pine.radicle.garden:8776").unwrap(),
+            "pine.radicle.garden"
+        );
+        assert_eq!(seed_host("127.0.0.1:8776").unwrap(), "127.0.0.1");
+    }
+}